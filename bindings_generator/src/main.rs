@@ -18,7 +18,9 @@ fn compiler_bindings() {
         .rust_target(msrv)
         .parse_callbacks(Box::new(RenameCallback))
         .clang_args(&["-I", include_dir.to_str().unwrap()])
-        .dynamic_link_require_all(true)
+        // Load functions that are missing from older or newer releases of the library as
+        // `Err`, `saxaboom` decides which ones it requires
+        .dynamic_link_require_all(false)
         .dynamic_library_name("metal_irconverter")
         .layout_tests(false)
         .default_enum_style(bindgen::EnumVariation::Rust {
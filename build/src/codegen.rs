@@ -0,0 +1,367 @@
+//! Generates Rust constants from [`IRShaderReflection`] and
+//! [`IRRootSignature::resource_locations()`], so that resource offsets, function constants and
+//! thread group sizes never have to be copied by hand.
+//!
+//! ```no_run
+//! # fn example(reflection: &saxaboom::IRShaderReflection) {
+//! use saxaboom_build::codegen::{Bindings, Module};
+//!
+//! let mut module = Module::new();
+//! module.begin_mod("blur", "Bindings of `blur.hlsl`.");
+//! module.bindings(&Bindings::from_reflection(reflection));
+//! module.end_mod();
+//! std::fs::write("bindings.rs", module.finish()).unwrap();
+//! # }
+//! ```
+//!
+//! The generated source refers to the [`Module::PRELUDE`] types at the top of the file, and can
+//! be `include!`d into any module:
+//!
+//! ```ignore
+//! encoder.set_buffer(blur::resources::CONSTANTS.top_level_offset as u64, ...);
+//! let [x, y, z] = blur::THREADGROUP_SIZE;
+//! ```
+use std::{collections::HashSet, ffi::CStr, fmt::Write as _};
+
+use saxaboom::{ffi, FunctionConstant, IRRootSignature, IRShaderReflection};
+
+/// Owned counterpart of [`ffi::IRResourceLocation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub name: Option<String>,
+    pub resource_type: ffi::IRResourceType,
+    pub space: u32,
+    pub slot: u32,
+    pub top_level_offset: u32,
+    pub size_bytes: u64,
+}
+
+impl Resource {
+    /// # Safety
+    /// The name of `location` must be null or point to a live string.
+    unsafe fn from_location(location: &ffi::IRResourceLocation) -> Self {
+        Self {
+            name: (!location.resourceName.is_null()).then(|| {
+                unsafe { CStr::from_ptr(location.resourceName) }
+                    .to_string_lossy()
+                    .into_owned()
+            }),
+            resource_type: location.resourceType,
+            space: location.space,
+            slot: location.slot,
+            top_level_offset: location.topLevelOffset,
+            size_bytes: location.sizeBytes,
+        }
+    }
+}
+
+/// Everything that [`Module::bindings()`] generates constants for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bindings {
+    /// Of compute, mesh and amplification shaders.
+    pub threadgroup_size: Option<[u32; 3]>,
+    pub resources: Vec<Resource>,
+    pub function_constants: Vec<FunctionConstant>,
+}
+
+impl Bindings {
+    pub fn from_reflection(reflection: &IRShaderReflection) -> Self {
+        let version = ffi::IRReflectionVersion::_1_0;
+        let threadgroup_size = reflection
+            .compute_info(version)
+            .map(|info| unsafe { info.u_1.info_1_0 }.tg_size)
+            .or_else(|| {
+                reflection
+                    .mesh_info(version)
+                    .map(|info| unsafe { info.u_1.info_1_0 }.num_threads)
+            })
+            .or_else(|| {
+                reflection
+                    .amplification_info(version)
+                    .map(|info| unsafe { info.u_1.info_1_0 }.num_threads)
+            });
+        Self {
+            threadgroup_size,
+            // Names point into `reflection`
+            resources: reflection
+                .resource_locations()
+                .iter()
+                .map(|location| unsafe { Resource::from_location(location) })
+                .collect(),
+            function_constants: reflection.function_constants(),
+        }
+    }
+
+    /// The resources of every entry point converted with `root_signature`.
+    pub fn from_root_signature(root_signature: &IRRootSignature) -> Self {
+        Self {
+            // Names point into `root_signature`
+            resources: root_signature
+                .resource_locations()
+                .iter()
+                .map(|location| unsafe { Resource::from_location(location) })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Rust source file being generated.
+#[derive(Debug)]
+pub struct Module {
+    source: String,
+    depth: usize,
+}
+
+impl Default for Module {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module {
+    /// Types of the generated constants, at the top of every file.
+    pub const PRELUDE: &'static str = r#"/// Location of a resource in the top-level argument buffer.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceLocation {
+    pub resource_type: ResourceType,
+    pub space: u32,
+    pub slot: u32,
+    /// Offset in bytes into the top-level argument buffer.
+    pub top_level_offset: u32,
+    /// Size of the entry in the top-level argument buffer in bytes.
+    pub size_bytes: u64,
+}
+
+#[allow(dead_code, clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceType {
+    Table,
+    Constant,
+    CBV,
+    SRV,
+    UAV,
+    Sampler,
+    Invalid,
+}
+
+/// Function constant that must be specialized before creating a pipeline.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionConstant {
+    pub name: &'static str,
+    pub r#type: FunctionConstantType,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FunctionConstantType {
+    Bool,
+    Int,
+    Float,
+}
+"#;
+
+    /// Starts a file with the [`Self::PRELUDE`].
+    pub fn new() -> Self {
+        Self {
+            source: Self::PRELUDE.to_owned(),
+            depth: 0,
+        }
+    }
+
+    /// Opens `pub mod {name}`, documented with `doc`.  `name` must be a valid identifier, see
+    /// [`module_name()`].
+    pub fn begin_mod(&mut self, name: &str, doc: &str) -> &mut Self {
+        self.line("");
+        self.doc(doc);
+        self.line("#[allow(dead_code)]");
+        self.line(&format!("pub mod {name} {{"));
+        self.depth += 1;
+        self
+    }
+
+    /// Closes the module opened by the last [`Self::begin_mod()`].
+    pub fn end_mod(&mut self) -> &mut Self {
+        assert!(self.depth > 0, "no module to end");
+        self.depth -= 1;
+        self.line("}");
+        self
+    }
+
+    /// Adds a `pub const`, where `value` is a Rust expression of `ty`.
+    pub fn constant(&mut self, doc: &str, name: &str, ty: &str, value: &str) -> &mut Self {
+        self.doc(doc);
+        self.line(&format!("pub const {name}: {ty} = {value};"));
+        self
+    }
+
+    /// Adds `THREADGROUP_SIZE`, and `resources` and `function_constants` modules holding a
+    /// constant for every resource and function constant.
+    pub fn bindings(&mut self, bindings: &Bindings) -> &mut Self {
+        if let Some(threadgroup_size) = bindings.threadgroup_size {
+            self.constant(
+                "Threads per thread group.",
+                "THREADGROUP_SIZE",
+                "[u32; 3]",
+                &format!("{threadgroup_size:?}"),
+            );
+        }
+
+        if !bindings.resources.is_empty() {
+            self.begin_mod("resources", "Resources in the top-level argument buffer.");
+            self.line(&format!(
+                "use {}{{ResourceLocation, ResourceType}};",
+                self.root()
+            ));
+            let mut names = Names::default();
+            for resource in &bindings.resources {
+                let name = names.unique(match &resource.name {
+                    Some(name) => constant_name(name),
+                    None => format!(
+                        "{:?}_SPACE{}_SLOT{}",
+                        resource.resource_type, resource.space, resource.slot
+                    )
+                    .to_ascii_uppercase(),
+                });
+                let doc = match &resource.name {
+                    Some(name) => format!("`{name}`"),
+                    None => "Unnamed resource".to_owned(),
+                };
+                self.constant(
+                    &doc,
+                    &name,
+                    "ResourceLocation",
+                    &format!(
+                        "ResourceLocation {{ resource_type: ResourceType::{:?}, space: {}, slot: {}, top_level_offset: {}, size_bytes: {} }}",
+                        resource.resource_type,
+                        resource.space,
+                        resource.slot,
+                        resource.top_level_offset,
+                        resource.size_bytes
+                    ),
+                );
+            }
+            self.end_mod();
+        }
+
+        if !bindings.function_constants.is_empty() {
+            self.begin_mod(
+                "function_constants",
+                "Function constants to specialize before creating a pipeline.",
+            );
+            self.line(&format!(
+                "use {}{{FunctionConstant, FunctionConstantType}};",
+                self.root()
+            ));
+            let mut names = Names::default();
+            for constant in &bindings.function_constants {
+                let name = names.unique(constant_name(&constant.name));
+                self.constant(
+                    &format!("`{}`", constant.name),
+                    &name,
+                    "FunctionConstant",
+                    &format!(
+                        "FunctionConstant {{ name: {:?}, r#type: FunctionConstantType::{:?} }}",
+                        constant.name, constant.r#type
+                    ),
+                );
+            }
+            self.end_mod();
+        }
+        self
+    }
+
+    /// The generated source, with every module closed.
+    pub fn finish(mut self) -> String {
+        while self.depth > 0 {
+            self.end_mod();
+        }
+        self.source
+    }
+
+    /// Path from the current module to the top of the file.
+    fn root(&self) -> String {
+        "super::".repeat(self.depth)
+    }
+
+    fn doc(&mut self, doc: &str) {
+        for line in doc.lines() {
+            self.line(format!("/// {line}").trim_end());
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        if !line.is_empty() {
+            for _ in 0..self.depth {
+                self.source.push_str("    ");
+            }
+        }
+        // `fmt::Write` for `String` never fails
+        let _ = writeln!(self.source, "{line}");
+    }
+}
+
+/// Makes identifiers unique within one module by appending the first counter that does not clash
+/// with an identifier that was already used, such as a resource named `Tex_2`.
+#[derive(Default)]
+struct Names(HashSet<String>);
+
+impl Names {
+    fn unique(&mut self, name: String) -> String {
+        let name = if self.0.contains(&name) {
+            (2..)
+                .map(|n| format!("{name}_{n}"))
+                .find(|candidate| !self.0.contains(candidate))
+                .unwrap_or_default()
+        } else {
+            name
+        };
+        self.0.insert(name.clone());
+        name
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield",
+];
+
+/// `snake_case` module identifier for a name such as `Blur-H.cs`.
+pub fn module_name(name: &str) -> String {
+    let mut name = identifier(name).to_ascii_lowercase();
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+/// `SCREAMING_SNAKE_CASE` constant identifier for a name such as `g_BlurWeights`.
+pub fn constant_name(name: &str) -> String {
+    identifier(name).to_ascii_uppercase()
+}
+
+/// Replaces characters that cannot appear in identifiers with `_`, and separates `camelCase`
+/// words with `_`.
+fn identifier(name: &str) -> String {
+    let mut identifier = String::with_capacity(name.len());
+    let mut previous = '_';
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && (previous.is_ascii_lowercase() || previous.is_ascii_digit()) {
+            identifier.push('_');
+        }
+        identifier.push(if c.is_ascii_alphanumeric() { c } else { '_' });
+        previous = c;
+    }
+    if identifier.chars().all(|c| c == '_') {
+        return "unnamed".to_owned();
+    }
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
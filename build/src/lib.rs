@@ -0,0 +1,378 @@
+//! Converts a directory of DXIL shaders to `metallib`s with [Metal Shader Converter] from a cargo
+//! build script, and generates a Rust module that embeds them.
+//!
+//! ```no_run
+//! // In `fn main()` of build.rs
+//! saxaboom_build::Builder::new("shaders").build().unwrap();
+//! ```
+//!
+//! Every `.dxil` file in `shaders/` becomes a module of `$OUT_DIR/shaders.rs`, named after the
+//! file:
+//!
+//! ```ignore
+//! mod shaders {
+//!     include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+//! }
+//!
+//! let library = device.newLibraryWithData_error(shaders::blur::METALLIB)?;
+//! let [x, y, z] = shaders::blur::THREADGROUP_SIZE;
+//! let offset = shaders::blur::resources::CONSTANTS.top_level_offset;
+//! ```
+//!
+//! [Metal Shader Converter]: https://developer.apple.com/metal/shader-converter/
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use codegen::{module_name, Bindings, Module};
+use saxaboom::{ffi, IRCompiler, IRRootSignature, MetalIrConverter};
+
+pub mod codegen;
+
+/// Module holding the resources of [`Builder::root_signature()`].
+const ROOT_SIGNATURE_MODULE: &str = "root_signature";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(
+        "`OUT_DIR` is not set, call `Builder::out_dir()` when not running from a build script"
+    )]
+    OutDir,
+    #[error("Failed to read `{}`: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to write `{}`: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("`{}` contains a NUL byte", path.display())]
+    Nul { path: PathBuf },
+    #[error("`{}` and `{}` would both generate the module `{name}`", first.display(), second.display())]
+    DuplicateName {
+        name: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+    #[error(transparent)]
+    Saxaboom(#[from] saxaboom::Error),
+}
+
+/// A converted shader, see [`Output::shaders`].
+#[derive(Clone, Debug)]
+pub struct Shader {
+    /// Name of the generated module, derived from the file name of [`Self::input`].
+    pub name: String,
+    pub input: PathBuf,
+    pub entry_point: String,
+    pub stage: ffi::IRShaderStage,
+    pub metallib: PathBuf,
+    /// Reflection JSON, when the loaded library supports serializing it.
+    pub reflection: Option<PathBuf>,
+    pub bindings: Bindings,
+}
+
+/// Returned by [`Builder::build()`].
+#[derive(Clone, Debug)]
+pub struct Output {
+    /// The generated Rust module.
+    pub module: PathBuf,
+    /// In file name order.
+    pub shaders: Vec<Shader>,
+}
+
+type Configure = dyn Fn(&mut IRCompiler);
+
+/// Converts every `.dxil` file in a directory, see the [crate] documentation.
+pub struct Builder {
+    input_dir: PathBuf,
+    out_dir: Option<PathBuf>,
+    library: Option<PathBuf>,
+    entry_point: Option<CString>,
+    root_signature: Option<PathBuf>,
+    configure: Option<Box<Configure>>,
+    module_name: String,
+    cargo_metadata: bool,
+}
+
+impl Builder {
+    /// Converts the `.dxil` files in `input_dir`, which is relative to the package root in build
+    /// scripts.
+    pub fn new(input_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            input_dir: input_dir.into(),
+            out_dir: None,
+            library: None,
+            entry_point: None,
+            root_signature: None,
+            configure: None,
+            module_name: "shaders".to_owned(),
+            cargo_metadata: true,
+        }
+    }
+
+    /// Where to write the outputs, defaults to `$OUT_DIR`.
+    pub fn out_dir(&mut self, out_dir: impl Into<PathBuf>) -> &mut Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Path to the `metal_irconverter` library, which is searched for with
+    /// [`MetalIrConverter::discover()`] when not set.
+    pub fn library(&mut self, library: impl Into<PathBuf>) -> &mut Self {
+        self.library = Some(library.into());
+        self
+    }
+
+    /// Entry point to convert in every shader.  Defaults to the entry point recorded in the
+    /// pipeline state validation part of each DXIL container, or `main` if it has none.
+    pub fn entry_point(&mut self, entry_point: impl Into<CString>) -> &mut Self {
+        self.entry_point = Some(entry_point.into());
+        self
+    }
+
+    /// Root signature JSON file (see [`MetalIrConverter::create_root_signature_from_json()`]) to
+    /// convert every shader with.
+    pub fn root_signature(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.root_signature = Some(path.into());
+        self
+    }
+
+    /// Called on the [`IRCompiler`] of every shader before converting it, to set options such as
+    /// [`IRCompiler::set_minimum_gpu_family()`].
+    pub fn configure(&mut self, configure: impl Fn(&mut IRCompiler) + 'static) -> &mut Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// File stem of the generated module in the output directory, defaults to `shaders`.
+    pub fn module_name(&mut self, module_name: impl Into<String>) -> &mut Self {
+        self.module_name = module_name.into();
+        self
+    }
+
+    /// Whether to print `cargo:rerun-if-changed` and `cargo:rerun-if-env-changed` directives,
+    /// defaults to `true`.
+    pub fn cargo_metadata(&mut self, cargo_metadata: bool) -> &mut Self {
+        self.cargo_metadata = cargo_metadata;
+        self
+    }
+
+    /// Converts every shader and generates the module, stopping at the first shader that fails
+    /// to convert.
+    pub fn build(&self) -> Result<Output, Error> {
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => std::env::var_os("OUT_DIR").ok_or(Error::OutDir)?.into(),
+        };
+        let converter = match &self.library {
+            Some(library) => MetalIrConverter::new(library)?,
+            None => {
+                self.rerun_if_env_changed(saxaboom::discover::ENV_VAR);
+                MetalIrConverter::discover()?
+            }
+        };
+
+        // Also picks up added and removed files
+        self.rerun_if_changed(&self.input_dir);
+        let mut inputs = fs::read_dir(&self.input_dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|source| Error::Read {
+                path: self.input_dir.clone(),
+                source,
+            })?;
+        inputs.retain(|path| path.is_file() && path.extension().is_some_and(|e| e == "dxil"));
+        inputs.sort();
+
+        // Keep the root signature alive until compilation finished
+        let root_signature = self
+            .root_signature
+            .as_deref()
+            .map(|path| {
+                self.rerun_if_changed(path);
+                let json = CString::new(read(path)?).map_err(|_| Error::Nul {
+                    path: path.to_owned(),
+                })?;
+                Ok::<_, Error>(converter.create_root_signature_from_json(&json)?)
+            })
+            .transpose()?;
+
+        let metallib_dir = out_dir.join("saxaboom");
+        fs::create_dir_all(&metallib_dir).map_err(|source| Error::Write {
+            path: metallib_dir.clone(),
+            source,
+        })?;
+
+        let mut names = HashMap::<String, PathBuf>::new();
+        if let Some(path) = &self.root_signature {
+            names.insert(ROOT_SIGNATURE_MODULE.to_owned(), path.clone());
+        }
+        let mut shaders = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            self.rerun_if_changed(&input);
+            let name = module_name(&input.file_stem().unwrap_or_default().to_string_lossy());
+            if let Some(first) = names.insert(name.clone(), input.clone()) {
+                return Err(Error::DuplicateName {
+                    name,
+                    first,
+                    second: input,
+                });
+            }
+            shaders.push(self.convert(
+                &converter,
+                root_signature.as_ref(),
+                input,
+                name,
+                &metallib_dir,
+            )?);
+        }
+
+        let module = out_dir.join(format!("{}.rs", self.module_name));
+        let root_signature = root_signature.as_ref().map(Bindings::from_root_signature);
+        write(
+            &module,
+            generate(&self.input_dir, root_signature.as_ref(), &shaders),
+        )?;
+        Ok(Output { module, shaders })
+    }
+
+    fn convert(
+        &self,
+        converter: &MetalIrConverter,
+        root_signature: Option<&IRRootSignature>,
+        input: PathBuf,
+        name: String,
+        metallib_dir: &Path,
+    ) -> Result<Shader, Error> {
+        let bytecode = read(&input)?;
+        let entry_point = match &self.entry_point {
+            Some(entry_point) => entry_point.clone(),
+            None => recorded_entry_point(&bytecode).unwrap_or_else(|| c"main".to_owned()),
+        };
+
+        let mut compiler = converter.create_compiler();
+        if let Some(root_signature) = root_signature {
+            compiler.set_global_root_signature(root_signature);
+        }
+        if let Some(configure) = &self.configure {
+            configure(&mut compiler);
+        }
+        let mut dxil = converter.create_object_from_dxil(&bytecode);
+        dxil.set_name(input.display().to_string());
+        let object = compiler.alloc_compile_and_link(&entry_point, &dxil)?;
+
+        let metallib = metallib_dir.join(format!("{name}.metallib"));
+        write(&metallib, object.metal_lib_binary()?.byte_code())?;
+
+        let reflection = object.reflection()?;
+        let reflection_json = if converter.supports_json_reflection() {
+            let path = metallib_dir.join(format!("{name}.json"));
+            write(&path, reflection.json()?)?;
+            Some(path)
+        } else {
+            None
+        };
+
+        Ok(Shader {
+            name,
+            input,
+            entry_point: entry_point.to_string_lossy().into_owned(),
+            stage: object.metal_ir_shader_stage(),
+            metallib,
+            reflection: reflection_json,
+            bindings: Bindings::from_reflection(&reflection),
+        })
+    }
+
+    fn rerun_if_changed(&self, path: &Path) {
+        if self.cargo_metadata {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+
+    fn rerun_if_env_changed(&self, var: &str) {
+        if self.cargo_metadata {
+            println!("cargo:rerun-if-env-changed={var}");
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|source| Error::Read {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    fs::write(path, contents).map_err(|source| Error::Write {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Entry point name from the pipeline state validation part, which DXC records for shader model
+/// 6.1 and up.
+fn recorded_entry_point(bytecode: &[u8]) -> Option<CString> {
+    let container = saxaboom::dxil::Container::parse(bytecode).ok()?;
+    let entry_point = container.pipeline_state().ok()??.entry_point?;
+    CString::new(entry_point).ok()
+}
+
+fn generate(input_dir: &Path, root_signature: Option<&Bindings>, shaders: &[Shader]) -> String {
+    let mut module = Module::new();
+    if let Some(bindings) = root_signature {
+        module.begin_mod(
+            ROOT_SIGNATURE_MODULE,
+            "Resources of the root signature that every shader was converted with.",
+        );
+        module.bindings(bindings);
+        module.end_mod();
+    }
+    for shader in shaders {
+        let file_name = shader.input.file_name().unwrap_or_default();
+        module.begin_mod(
+            &shader.name,
+            &format!("Converted from `{}`.", file_name.to_string_lossy()),
+        );
+        module.constant(
+            "",
+            "METALLIB",
+            "&[u8]",
+            &format!(
+                "include_bytes!({:?})",
+                shader.metallib.display().to_string()
+            ),
+        );
+        if let Some(reflection) = &shader.reflection {
+            module.constant(
+                "Reflection in the format of `metal-shaderconverter --output-reflection-file`.",
+                "REFLECTION_JSON",
+                "&str",
+                &format!("include_str!({:?})", reflection.display().to_string()),
+            );
+        }
+        module.constant(
+            "",
+            "ENTRY_POINT",
+            "&str",
+            &format!("{:?}", shader.entry_point),
+        );
+        module.bindings(&shader.bindings);
+        module.end_mod();
+    }
+    format!(
+        "// @generated by saxaboom-build from `{}`, do not edit.\n\n{}",
+        input_dir.display(),
+        module.finish()
+    )
+}
@@ -0,0 +1,131 @@
+//! Runs [`Builder`] against the `irconverter_stub` library.
+use std::path::{Path, PathBuf};
+
+use saxaboom::ffi;
+use saxaboom_build::{Builder, Error};
+
+fn stub_library() -> PathBuf {
+    // Cargo places the `cdylib` of the `irconverter_stub` dev-dependency next to the test
+    // executable.
+    std::env::current_exe()
+        .unwrap()
+        .with_file_name(libloading::library_filename("metalirconverter"))
+}
+
+/// Fresh directory containing an empty `shaders/` directory.
+fn work_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("shaders")).unwrap();
+    dir
+}
+
+fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// DXBC container with only a pipeline state validation part, recording `entry_point`.
+fn container_with_entry_point(entry_point: &str) -> Vec<u8> {
+    // `PSVRuntimeInfo3` of a compute shader, without resources
+    let mut info = vec![0; 52];
+    info[24] = 5;
+    let mut psv = u32s(&[info.len() as u32]);
+    psv.extend(info);
+    psv.extend(u32s(&[0]));
+    let mut names = entry_point.as_bytes().to_vec();
+    names.resize(entry_point.len().next_multiple_of(4) + 4, 0);
+    psv.extend(u32s(&[names.len() as u32]));
+    psv.extend(names);
+
+    let mut bytes = b"DXBC".to_vec();
+    bytes.extend([0; 16]);
+    bytes.extend([1, 0, 0, 0]);
+    bytes.extend(u32s(&[(36 + 8 + psv.len()) as u32, 1, 36]));
+    bytes.extend(b"PSV0");
+    bytes.extend(u32s(&[psv.len() as u32]));
+    bytes.extend(psv);
+    bytes
+}
+
+#[test]
+fn build() {
+    let dir = work_dir("build");
+    let shaders = dir.join("shaders");
+    std::fs::write(shaders.join("blur.dxil"), b"DXBC").unwrap();
+    std::fs::write(
+        shaders.join("tone-map.dxil"),
+        container_with_entry_point("cs_main"),
+    )
+    .unwrap();
+    std::fs::write(shaders.join("README.md"), "Not a shader").unwrap();
+    std::fs::write(dir.join("root_signature.json"), "{}").unwrap();
+
+    let output = Builder::new(&shaders)
+        .out_dir(dir.join("out"))
+        .library(stub_library())
+        .root_signature(dir.join("root_signature.json"))
+        .configure(|compiler| compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7))
+        .build()
+        .unwrap();
+
+    assert_eq!(output.module, dir.join("out/shaders.rs"));
+    let names = output.shaders.iter().map(|s| &s.name).collect::<Vec<_>>();
+    assert_eq!(names, ["blur", "tone_map"]);
+    let blur = &output.shaders[0];
+    assert_eq!(blur.entry_point, "main");
+    assert_eq!(blur.stage, ffi::IRShaderStage::Compute);
+    assert_eq!(
+        std::fs::read(&blur.metallib).unwrap(),
+        b"MTLB saxaboom stub metallib"
+    );
+    assert!(blur.reflection.as_ref().unwrap().exists());
+    assert_eq!(blur.bindings.threadgroup_size, Some([8, 8, 1]));
+    assert_eq!(blur.bindings.resources.len(), 1);
+    assert_eq!(
+        blur.bindings.resources[0].name.as_deref(),
+        Some("constants")
+    );
+    // Taken from the pipeline state validation part
+    assert_eq!(output.shaders[1].entry_point, "cs_main");
+
+    let module = std::fs::read_to_string(&output.module).unwrap();
+    syn::parse_file(&module).unwrap();
+    for expected in [
+        "pub mod blur {",
+        "pub mod tone_map {",
+        "pub const METALLIB: &[u8] = include_bytes!(",
+        "pub const ENTRY_POINT: &str = \"cs_main\";",
+        "pub const THREADGROUP_SIZE: [u32; 3] = [8, 8, 1];",
+        "pub const CONSTANTS: ResourceLocation = ResourceLocation { resource_type: ResourceType::CBV, space: 0, slot: 0, top_level_offset: 0, size_bytes: 8 };",
+        "pub mod root_signature {",
+    ] {
+        assert!(
+            module.contains(expected),
+            "missing `{expected}` in:\n{module}"
+        );
+    }
+}
+
+#[test]
+fn duplicate_name() {
+    let dir = work_dir("duplicate_name");
+    std::fs::write(dir.join("shaders/blur-h.dxil"), b"DXBC").unwrap();
+    std::fs::write(dir.join("shaders/blur_h.dxil"), b"DXBC").unwrap();
+    let error = Builder::new(dir.join("shaders"))
+        .out_dir(dir.join("out"))
+        .library(stub_library())
+        .build()
+        .unwrap_err();
+    assert!(
+        matches!(&error, Error::DuplicateName { name, .. } if name == "blur_h"),
+        "{error}"
+    );
+}
+
+#[test]
+fn missing_out_dir() {
+    // Only set for packages with a build script
+    assert!(std::env::var_os("OUT_DIR").is_none());
+    let error = Builder::new("shaders").build().unwrap_err();
+    assert!(matches!(error, Error::OutDir), "{error}");
+}
@@ -0,0 +1,119 @@
+//! Generates constants from [`FakeConverter`] reflection with [`saxaboom_build::codegen`].
+use saxaboom::{
+    ffi,
+    testing::{FakeConverter, FakeResource, FakeShader},
+    MetalIrConverter,
+};
+use saxaboom_build::codegen::{constant_name, module_name, Bindings, Module, Resource};
+
+#[test]
+fn names() {
+    assert_eq!(module_name("Blur-H.cs"), "blur_h_cs");
+    assert_eq!(module_name("type"), "type_");
+    assert_eq!(module_name("2d"), "_2d");
+    assert_eq!(module_name("-"), "unnamed");
+    assert_eq!(constant_name("g_BlurWeights"), "G_BLUR_WEIGHTS");
+    assert_eq!(constant_name("lightCount"), "LIGHT_COUNT");
+}
+
+#[test]
+fn bindings() {
+    let fake = FakeConverter::default();
+    let mut shader = FakeShader::new(ffi::IRShaderStage::Mesh, vec![]);
+    shader.mesh_info = Some(ffi::IRMSInfo_1_0 {
+        max_vertex_output_count: 64,
+        max_primitive_output_count: 124,
+        primitive_topology: ffi::IRMeshShaderPrimitiveTopology::Triangle,
+        max_payload_size_in_bytes: 0,
+        num_threads: [32, 1, 1],
+    });
+    shader.function_constants = vec![
+        ("useFog".to_owned(), ffi::IRFunctionConstantType::Bool),
+        ("lightCount".to_owned(), ffi::IRFunctionConstantType::Int),
+    ];
+    let resource = |name: Option<&str>, slot, top_level_offset| FakeResource {
+        resource_type: ffi::IRResourceType::SRV,
+        space: 1,
+        slot,
+        top_level_offset,
+        size_bytes: 24,
+        name: name.map(str::to_owned),
+    };
+    shader.resources = vec![
+        resource(Some("g_Vertices"), 0, 0),
+        resource(Some("g_Vertices"), 1, 24),
+        resource(None, 2, 48),
+    ];
+    fake.on_entry_point("ms_main", Ok(shader));
+    fake.on_root_signature(Ok(vec![resource(Some("g_Vertices"), 0, 0)]));
+    let converter = MetalIrConverter::from_backend(fake);
+
+    let compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let object = compiler.alloc_compile_and_link(c"ms_main", &dxil).unwrap();
+    let bindings = Bindings::from_reflection(&object.reflection().unwrap());
+    assert_eq!(bindings.threadgroup_size, Some([32, 1, 1]));
+    assert_eq!(bindings.resources.len(), 3);
+    assert_eq!(bindings.function_constants.len(), 2);
+
+    let root_signature = converter.create_root_signature_from_json(c"{}").unwrap();
+    let root_bindings = Bindings::from_root_signature(&root_signature);
+    assert_eq!(root_bindings.threadgroup_size, None);
+    assert_eq!(root_bindings.resources, bindings.resources[..1]);
+
+    let mut module = Module::new();
+    module.begin_mod(
+        "pipeline",
+        "Nested modules refer to the prelude at the top of the file.",
+    );
+    module.bindings(&root_bindings);
+    module.begin_mod("mesh", "");
+    module.bindings(&bindings);
+    let source = module.finish();
+
+    syn::parse_file(&source).unwrap();
+    for expected in [
+        "pub const THREADGROUP_SIZE: [u32; 3] = [32, 1, 1];",
+        "        use super::super::{ResourceLocation, ResourceType};",
+        "            use super::super::super::{ResourceLocation, ResourceType};",
+        "pub const G_VERTICES: ResourceLocation = ResourceLocation { resource_type: ResourceType::SRV, space: 1, slot: 0, top_level_offset: 0, size_bytes: 24 };",
+        "pub const G_VERTICES_2: ResourceLocation = ResourceLocation { resource_type: ResourceType::SRV, space: 1, slot: 1, top_level_offset: 24, size_bytes: 24 };",
+        "pub const SRV_SPACE1_SLOT2: ResourceLocation",
+        "pub const USE_FOG: FunctionConstant = FunctionConstant { name: \"useFog\", r#type: FunctionConstantType::Bool };",
+        "pub const LIGHT_COUNT: FunctionConstant = FunctionConstant { name: \"lightCount\", r#type: FunctionConstantType::Int };",
+    ] {
+        assert!(source.contains(expected), "missing `{expected}` in:\n{source}");
+    }
+}
+
+#[test]
+fn clashing_names() {
+    let resource = |name: &str, slot| Resource {
+        name: Some(name.to_owned()),
+        resource_type: ffi::IRResourceType::SRV,
+        space: 0,
+        slot,
+        top_level_offset: 8 * slot,
+        size_bytes: 8,
+    };
+    let bindings = Bindings {
+        resources: vec![
+            resource("Tex", 0),
+            resource("tex", 1),
+            resource("Tex_2", 2),
+            resource("tex", 3),
+        ],
+        ..Bindings::default()
+    };
+    let mut module = Module::new();
+    module.bindings(&bindings);
+    let source = module.finish();
+
+    syn::parse_file(&source).unwrap();
+    let names = source
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("pub const "))
+        .map(|line| &line[..line.find(':').unwrap()])
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["TEX", "TEX_2", "TEX_2_2", "TEX_3"]);
+}
@@ -0,0 +1,362 @@
+//! `saxaboom build`: converts every shader listed in a [manifest](crate::manifest) in parallel,
+//! skipping shaders whose inputs and settings did not change since the last build.
+use std::{
+    collections::BTreeMap,
+    ffi::CString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+use rayon::prelude::*;
+use saxaboom::{ffi, IRInputLayoutDescriptor1, MetalIrConverter};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    convert::{configure, load_root_signature, StageIn},
+    manifest::{Manifest, Shader},
+    read, write, CliError,
+};
+
+/// Fingerprints of the last successful build of every shader, relative to the output directory.
+const STATE_FILE: &str = ".saxaboom-build.json";
+
+#[derive(Args, Debug)]
+pub struct BuildArgs {
+    /// Path to the manifest
+    #[arg(default_value = "saxaboom.toml")]
+    pub manifest: PathBuf,
+
+    /// Number of shaders to convert in parallel, defaults to the number of CPUs
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    /// Rebuild every shader, even when it is up to date
+    #[arg(long)]
+    pub force: bool,
+
+    /// Also write the summary as JSON to this path
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+}
+
+enum Outcome {
+    Built,
+    UpToDate,
+    Failed(CliError),
+}
+
+pub fn run(converter: &MetalIrConverter, args: &BuildArgs) -> Result<(), CliError> {
+    let source = read(&args.manifest)?;
+    let base_dir = args.manifest.parent().unwrap_or(Path::new(""));
+    let manifest =
+        Manifest::parse(&String::from_utf8_lossy(&source), base_dir).map_err(|source| {
+            CliError::Manifest {
+                path: args.manifest.clone(),
+                source,
+            }
+        })?;
+    fs::create_dir_all(&manifest.output_dir).map_err(|source| CliError::Write {
+        path: manifest.output_dir.clone(),
+        source,
+    })?;
+
+    let state_path = manifest.output_dir.join(STATE_FILE);
+    // A missing or corrupt state file only means that everything is rebuilt
+    let state: BTreeMap<String, String> = fs::read(&state_path)
+        .ok()
+        .and_then(|state| serde_json::from_slice(&state).ok())
+        .unwrap_or_default();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.unwrap_or(0))
+        .build()?;
+    let library = library_fingerprint(converter);
+    let results = pool.install(|| {
+        manifest
+            .shaders
+            .par_iter()
+            .map(|shader| {
+                let fingerprint = match fingerprint(&library, shader) {
+                    Ok(fingerprint) => fingerprint,
+                    Err(e) => return (None, Outcome::Failed(e)),
+                };
+                let up_to_date = !args.force
+                    && state.get(&shader.name) == Some(&fingerprint)
+                    && shader
+                        .outputs(&manifest.output_dir)
+                        .iter()
+                        .all(|path| path.exists());
+                if up_to_date {
+                    return (Some(fingerprint), Outcome::UpToDate);
+                }
+                match build(converter, shader, &manifest.output_dir) {
+                    Ok(()) => (Some(fingerprint), Outcome::Built),
+                    Err(e) => (None, Outcome::Failed(e)),
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut new_state = BTreeMap::new();
+    let mut report = Vec::new();
+    let (mut built, mut up_to_date, mut failed) = (0, 0, 0);
+    for (shader, (fingerprint, outcome)) in manifest.shaders.iter().zip(&results) {
+        if let Some(fingerprint) = fingerprint {
+            new_state.insert(shader.name.clone(), fingerprint.clone());
+        }
+        let (status, error, diagnostics) = match outcome {
+            Outcome::Built => {
+                built += 1;
+                ("built", None, None)
+            }
+            Outcome::UpToDate => {
+                up_to_date += 1;
+                ("up to date", None, None)
+            }
+            Outcome::Failed(e) => {
+                failed += 1;
+                (
+                    "failed",
+                    Some(e.to_string()),
+                    crate::diagnostics(e).map(|d| d.to_string_lossy().into_owned()),
+                )
+            }
+        };
+
+        match &error {
+            Some(error) => eprintln!("{status:>10} {}: {error}", shader.name),
+            None => eprintln!("{status:>10} {}", shader.name),
+        }
+        for line in diagnostics.iter().flat_map(|d| d.lines()) {
+            eprintln!("           | {line}");
+        }
+        report.push(json!({
+            "name": shader.name,
+            "input": shader.input,
+            "status": status,
+            "outputs": shader.outputs(&manifest.output_dir),
+            "error": error,
+            "diagnostics": diagnostics,
+        }));
+    }
+    eprintln!("{built} built, {up_to_date} up to date, {failed} failed");
+
+    write(
+        &state_path,
+        serde_json::to_string_pretty(&new_state).expect("string map serializes"),
+    )?;
+    if let Some(path) = &args.report {
+        let report = json!({
+            "built": built,
+            "up_to_date": up_to_date,
+            "failed": failed,
+            "shaders": report,
+        });
+        write(
+            path,
+            serde_json::to_string_pretty(&report).expect("JSON value serializes"),
+        )?;
+    }
+
+    if failed > 0 {
+        return Err(CliError::BuildFailed {
+            failed,
+            total: manifest.shaders.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Identifies the converter library, so that upgrading it rebuilds every shader.
+fn library_fingerprint(converter: &MetalIrConverter) -> String {
+    let metadata = converter
+        .library_path()
+        .and_then(|path| fs::metadata(path).ok())
+        .map(|metadata| (metadata.len(), metadata.modified().ok()));
+    format!("{:?} {metadata:?}", converter.library_path())
+}
+
+/// Hash of everything that affects the outputs of `shader`.
+fn fingerprint(library: &str, shader: &Shader) -> Result<String, CliError> {
+    let mut hasher = Sha256::new();
+    let mut update = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    update(env!("CARGO_PKG_VERSION").as_bytes());
+    update(library.as_bytes());
+    update(&shader_fields(shader));
+    update(&read(&shader.input)?);
+    for path in [&shader.root_signature, &shader.local_root_signature]
+        .into_iter()
+        .flatten()
+    {
+        update(&read(path)?);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Serializes the fields of `shader` explicitly, rather than through their [`Debug`]
+/// representation, so that the fingerprint only changes when the manifest does.  Enums are
+/// written as their converter values.
+fn shader_fields(shader: &Shader) -> Vec<u8> {
+    fn u32(bytes: &mut Vec<u8>, value: u32) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn str(bytes: &mut Vec<u8>, value: &[u8]) {
+        u32(bytes, value.len() as u32);
+        bytes.extend_from_slice(value);
+    }
+    fn opt_u32(bytes: &mut Vec<u8>, value: Option<u32>) {
+        bytes.push(value.is_some().into());
+        u32(bytes, value.unwrap_or_default());
+    }
+    fn opt_path(bytes: &mut Vec<u8>, value: Option<&PathBuf>) {
+        bytes.push(value.is_some().into());
+        str(
+            bytes,
+            value.map_or(&[], |p| p.as_os_str().as_encoded_bytes()),
+        );
+    }
+
+    let mut bytes = Vec::new();
+    str(&mut bytes, shader.name.as_bytes());
+    str(&mut bytes, shader.input.as_os_str().as_encoded_bytes());
+    str(&mut bytes, shader.entry_point.as_bytes());
+    opt_u32(
+        &mut bytes,
+        shader.stage.map(|s| ffi::IRShaderStage::from(s) as u32),
+    );
+    opt_path(&mut bytes, shader.root_signature.as_ref());
+    opt_path(&mut bytes, shader.local_root_signature.as_ref());
+    opt_u32(
+        &mut bytes,
+        shader
+            .hitgroup_type
+            .map(|h| ffi::IRHitGroupType::from(h) as u32),
+    );
+    u32(&mut bytes, shader.input_layout.len() as u32);
+    for element in &shader.input_layout {
+        str(&mut bytes, element.semantic.as_bytes());
+        u32(&mut bytes, element.semantic_index);
+        u32(&mut bytes, element.format as u32);
+        u32(&mut bytes, element.slot);
+        u32(&mut bytes, element.offset);
+        bytes.push(element.per_instance.into());
+        u32(&mut bytes, element.step_rate);
+    }
+
+    let settings = &shader.settings;
+    opt_u32(
+        &mut bytes,
+        settings
+            .gpu_family
+            .map(|f| ffi::IRGPUFamily::from(f) as u32),
+    );
+    bytes.push(settings.deployment_target.is_some().into());
+    if let Some((os, version)) = &settings.deployment_target {
+        u32(&mut bytes, *os as u32);
+        str(&mut bytes, version.as_bytes());
+    }
+    u32(&mut bytes, settings.validation_flags.len() as u32);
+    for &flag in &settings.validation_flags {
+        u32(
+            &mut bytes,
+            ffi::IRCompilerValidationFlags::from(flag).0 as u32,
+        );
+    }
+    u32(&mut bytes, settings.compatibility_flags.len() as u32);
+    for &flag in &settings.compatibility_flags {
+        u32(&mut bytes, ffi::IRCompatibilityFlags::from(flag).0);
+    }
+    opt_u32(
+        &mut bytes,
+        settings
+            .stage_in
+            .map(|s| ffi::IRStageInCodeGenerationMode::from(s) as u32),
+    );
+    bytes.push(shader.reflection.into());
+    bytes
+}
+
+fn build(converter: &MetalIrConverter, shader: &Shader, output_dir: &Path) -> Result<(), CliError> {
+    let mut compiler = converter.create_compiler();
+    let mut settings = shader.settings.clone();
+    if !shader.input_layout.is_empty() {
+        settings.stage_in = Some(StageIn::SeparateFunction);
+    }
+    configure(&mut compiler, &settings);
+
+    // Keep the root signatures alive until compilation finished
+    let root_signature = shader
+        .root_signature
+        .as_deref()
+        .map(|path| load_root_signature(converter, path))
+        .transpose()?;
+    if let Some(root_signature) = &root_signature {
+        compiler.set_global_root_signature(root_signature);
+    }
+    let local_root_signature = shader
+        .local_root_signature
+        .as_deref()
+        .map(|path| load_root_signature(converter, path))
+        .transpose()?;
+    if let Some(root_signature) = &local_root_signature {
+        compiler.set_local_root_signature(root_signature);
+    }
+    if let Some(hitgroup_type) = shader.hitgroup_type {
+        compiler.set_hitgroup_type(hitgroup_type.into());
+    }
+
+    let mut dxil = converter.create_object_from_dxil(&read(&shader.input)?);
+    dxil.set_name(format!("{} ({})", shader.name, shader.input.display()));
+    let object = compiler.alloc_compile_and_link(&shader.entry_point, &dxil)?;
+
+    let stage = object.metal_ir_shader_stage();
+    if let Some(expected) = shader.stage {
+        let expected = ffi::IRShaderStage::from(expected);
+        if stage != expected {
+            return Err(CliError::StageMismatch {
+                expected,
+                found: stage,
+            });
+        }
+    }
+    write(
+        &shader.metallib_path(output_dir),
+        object.metal_lib_binary()?.byte_code(),
+    )?;
+
+    if shader.reflection || !shader.input_layout.is_empty() {
+        let reflection = object.reflection()?;
+        if let Some(path) = shader.reflection_path(output_dir) {
+            write(&path, reflection.json()?)?;
+        }
+        if let Some(path) = shader.stage_in_path(output_dir) {
+            let semantics = shader
+                .input_layout
+                .iter()
+                .map(|e| CString::new(e.semantic.as_str()).expect("validated by the manifest"))
+                .collect::<Vec<_>>();
+            let semantic_refs = semantics.iter().map(|s| s.as_c_str()).collect::<Vec<_>>();
+            let elements = shader
+                .input_layout
+                .iter()
+                .map(|e| e.descriptor())
+                .collect::<Vec<_>>();
+            let layout = IRInputLayoutDescriptor1::new(&semantic_refs, &elements);
+            let binary = converter.create_metal_lib_binary();
+            if !compiler.synthesize_stage_in_function(&reflection, layout, &binary) {
+                return Err(CliError::StageIn);
+            }
+            write(&path, binary.byte_code())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,265 @@
+//! `saxaboom convert`: compiles a DXIL entry point to a `metallib`.
+use std::{
+    ffi::CString,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, ValueEnum};
+use saxaboom::{ffi, IRCompiler, IRRootSignature, MetalIrConverter};
+use serde::Deserialize;
+
+use crate::{read, root_signature, write, CliError};
+
+#[derive(Args, Debug)]
+pub struct ConvertArgs {
+    /// DXIL file to convert
+    input: PathBuf,
+
+    /// Entry point to compile
+    #[arg(short, long, default_value = "main", value_parser = parse_c_string)]
+    entry_point: CString,
+
+    /// Where to write the `metallib`, defaults to the input path with a `.metallib` extension
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Where to write the shader reflection, as JSON
+    #[arg(long)]
+    reflection: Option<PathBuf>,
+
+    /// Write provenance sidecars next to the `metallib`, recording the input hash, entry point,
+    /// settings, root signature and converter that produced it
+    #[arg(long)]
+    provenance: bool,
+
+    /// Explicit root signature, as JSON (`.json` files) or an HLSL root signature string (any
+    /// other file)
+    #[arg(long)]
+    root_signature: Option<PathBuf>,
+
+    #[command(flatten)]
+    settings: Settings,
+}
+
+/// Compiler state shared by `saxaboom convert` and the shaders of a `saxaboom build` manifest.
+#[derive(Args, Clone, Debug, Default)]
+pub struct Settings {
+    /// Minimum GPU family the `metallib` has to run on
+    #[arg(long)]
+    pub gpu_family: Option<GpuFamily>,
+
+    /// Minimum OS version the `metallib` has to run on, as `<os>:<version>` such as `macos:14.0.0`
+    #[arg(long, value_parser = parse_deployment_target)]
+    pub deployment_target: Option<(ffi::IROperatingSystem, CString)>,
+
+    /// Validation to perform while compiling, may be repeated
+    #[arg(long = "validation", value_name = "VALIDATION")]
+    pub validation_flags: Vec<ValidationFlag>,
+
+    /// Compatibility flags, may be repeated
+    #[arg(long = "compatibility", value_name = "COMPATIBILITY")]
+    pub compatibility_flags: Vec<CompatibilityFlag>,
+
+    /// How vertex shader inputs are fetched
+    #[arg(long)]
+    pub stage_in: Option<StageIn>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GpuFamily {
+    Apple6,
+    Apple7,
+    Apple8,
+    Apple9,
+    Metal3,
+}
+
+impl From<GpuFamily> for ffi::IRGPUFamily {
+    fn from(value: GpuFamily) -> Self {
+        match value {
+            GpuFamily::Apple6 => Self::Apple6,
+            GpuFamily::Apple7 => Self::Apple7,
+            GpuFamily::Apple8 => Self::Apple8,
+            GpuFamily::Apple9 => Self::Apple9,
+            GpuFamily::Metal3 => Self::Metal3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ValidationFlag {
+    RawRootResources,
+    AllResourcesBound,
+    Dxil,
+    All,
+}
+
+impl From<ValidationFlag> for ffi::IRCompilerValidationFlags {
+    fn from(value: ValidationFlag) -> Self {
+        match value {
+            ValidationFlag::RawRootResources => Self::ValidateRawRootResources,
+            ValidationFlag::AllResourcesBound => Self::ValidateAllResourcesBound,
+            ValidationFlag::Dxil => Self::ValidateDXIL,
+            ValidationFlag::All => Self::All,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompatibilityFlag {
+    BoundsCheck,
+    VertexPositionInfToNan,
+    TextureMinLodClamp,
+    SamplerLodBias,
+    PositionInvariance,
+    SampleNanToZero,
+    TexWriteRoundingRtz,
+    Suppress2dComputeDerivativeErrors,
+}
+
+impl From<CompatibilityFlag> for ffi::IRCompatibilityFlags {
+    fn from(value: CompatibilityFlag) -> Self {
+        match value {
+            CompatibilityFlag::BoundsCheck => Self::BoundsCheck,
+            CompatibilityFlag::VertexPositionInfToNan => Self::VertexPositionInfToNan,
+            CompatibilityFlag::TextureMinLodClamp => Self::TextureMinLODClamp,
+            CompatibilityFlag::SamplerLodBias => Self::SamplerLODBias,
+            CompatibilityFlag::PositionInvariance => Self::PositionInvariance,
+            CompatibilityFlag::SampleNanToZero => Self::SampleNanToZero,
+            CompatibilityFlag::TexWriteRoundingRtz => Self::TexWriteRoundingRTZ,
+            CompatibilityFlag::Suppress2dComputeDerivativeErrors => {
+                Self::Suppress2DComputeDerivativeErrors
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StageIn {
+    /// Fetch vertex inputs in the vertex function itself
+    MetalVertexFetch,
+    /// Generate a separate stage-in function
+    SeparateFunction,
+}
+
+impl From<StageIn> for ffi::IRStageInCodeGenerationMode {
+    fn from(value: StageIn) -> Self {
+        match value {
+            StageIn::MetalVertexFetch => Self::UseMetalVertexFetch,
+            StageIn::SeparateFunction => Self::UseSeparateStageInFunction,
+        }
+    }
+}
+
+pub fn parse_c_string(value: &str) -> Result<CString, String> {
+    CString::new(value).map_err(|e| e.to_string())
+}
+
+pub fn parse_deployment_target(value: &str) -> Result<(ffi::IROperatingSystem, CString), String> {
+    let (os, version) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected `<os>:<version>`, found `{value}`"))?;
+    let os = match os.to_ascii_lowercase().as_str() {
+        "macos" => ffi::IROperatingSystem::macOS,
+        "ios" => ffi::IROperatingSystem::iOS,
+        "tvos" => ffi::IROperatingSystem::tvOS,
+        "ios-simulator" => ffi::IROperatingSystem::iOSSimulator,
+        _ => return Err(format!("unknown operating system `{os}`")),
+    };
+    Ok((os, parse_c_string(version)?))
+}
+
+pub fn load_root_signature(
+    converter: &MetalIrConverter,
+    path: &Path,
+) -> Result<IRRootSignature, CliError> {
+    let source = read(path)?;
+    if path.extension().is_some_and(|e| e == "json") {
+        let json = CString::new(source).map_err(|_| CliError::Nul {
+            path: path.to_owned(),
+        })?;
+        return converter
+            .create_root_signature_from_json(&json)
+            .map_err(|error| match error {
+                saxaboom::Error::Serialization { .. } => CliError::RootSignatureJson {
+                    path: path.to_owned(),
+                },
+                error => error.into(),
+            });
+    }
+
+    let source = String::from_utf8_lossy(&source);
+    let mut root_signature = root_signature::RootSignature::parse(&source).map_err(|source| {
+        CliError::RootSignature {
+            path: path.to_owned(),
+            source,
+        }
+    })?;
+    Ok(converter.create_root_signature_from_descriptor(&root_signature.descriptor())?)
+}
+
+pub fn configure(compiler: &mut IRCompiler, settings: &Settings) {
+    if !settings.validation_flags.is_empty() {
+        let flags = settings
+            .validation_flags
+            .iter()
+            .fold(ffi::IRCompilerValidationFlags::None, |flags, &f| {
+                flags | f.into()
+            });
+        compiler.set_validation_flags(flags);
+    }
+    if !settings.compatibility_flags.is_empty() {
+        let flags = settings
+            .compatibility_flags
+            .iter()
+            .fold(ffi::IRCompatibilityFlags::None, |flags, &f| {
+                flags | f.into()
+            });
+        compiler.set_compatibility_flags(flags);
+    }
+    if let Some(gpu_family) = settings.gpu_family {
+        compiler.set_minimum_gpu_family(gpu_family.into());
+    }
+    if let Some((os, version)) = &settings.deployment_target {
+        compiler.set_minimum_deployment_target(*os, version);
+    }
+    if let Some(stage_in) = settings.stage_in {
+        compiler.set_stage_in_generation_mode(stage_in.into());
+    }
+}
+
+pub fn run(converter: &MetalIrConverter, args: &ConvertArgs) -> Result<(), CliError> {
+    let mut compiler = converter.create_compiler();
+    configure(&mut compiler, &args.settings);
+    // Keep the root signature alive until compilation finished
+    let root_signature = args
+        .root_signature
+        .as_deref()
+        .map(|path| load_root_signature(converter, path))
+        .transpose()?;
+    if let Some(root_signature) = &root_signature {
+        compiler.set_global_root_signature(root_signature);
+    }
+
+    let mut dxil = converter.create_object_from_dxil(&read(&args.input)?);
+    dxil.set_name(args.input.display().to_string());
+    let object = compiler.alloc_compile_and_link(&args.entry_point, &dxil)?;
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| args.input.with_extension("metallib"));
+    write(&output, object.metal_lib_binary()?.byte_code())?;
+
+    if let Some(path) = &args.reflection {
+        write(path, object.reflection()?.json()?)?;
+    }
+    if let Some(provenance) = args.provenance.then(|| object.provenance()).flatten() {
+        provenance.write_sidecars(&output)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! `saxaboom diff`: converts DXIL files with two `metal_irconverter` libraries and reports what
+//! changed, see [`saxaboom::diff`].
+use std::{ffi::CString, path::PathBuf};
+
+use clap::Args;
+use saxaboom::{diff::Shader, MetalIrConverter};
+
+use crate::{
+    convert::{configure, load_root_signature, parse_c_string, Settings},
+    read, CliError,
+};
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// DXIL files to convert
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Path to the metal_irconverter library to compare against
+    #[arg(long)]
+    old: PathBuf,
+
+    /// Path to the metal_irconverter library to compare
+    #[arg(long)]
+    new: PathBuf,
+
+    /// Entry point to compile in every input
+    #[arg(short, long, default_value = "main", value_parser = parse_c_string)]
+    entry_point: CString,
+
+    /// Explicit root signature, as JSON (`.json` files) or an HLSL root signature string (any
+    /// other file)
+    #[arg(long)]
+    root_signature: Option<PathBuf>,
+
+    #[command(flatten)]
+    settings: Settings,
+}
+
+pub fn run(args: &DiffArgs) -> Result<(), CliError> {
+    let old = MetalIrConverter::new(&args.old)?;
+    let new = MetalIrConverter::new(&args.new)?;
+    let corpus = args
+        .inputs
+        .iter()
+        .map(|path| {
+            Ok(Shader::new(
+                path.display().to_string(),
+                read(path)?,
+                &args.entry_point,
+            ))
+        })
+        .collect::<Result<Vec<_>, CliError>>()?;
+
+    let report = saxaboom::diff::diff(&old, &new, &corpus, |converter, compiler| {
+        configure(compiler, &args.settings);
+        // Kept alive by `diff()` until every input was converted
+        let root_signature = args
+            .root_signature
+            .as_deref()
+            .map(|path| load_root_signature(converter, path))
+            .transpose()?;
+        if let Some(root_signature) = &root_signature {
+            compiler.set_global_root_signature(root_signature);
+        }
+        Ok::<_, CliError>(root_signature)
+    })?;
+    print!("{report}");
+    if !report.is_identical() {
+        return Err(CliError::Differences {
+            changed: report.changed().count(),
+            failed: report.failed().count(),
+            total: report.shaders.len(),
+        });
+    }
+    Ok(())
+}
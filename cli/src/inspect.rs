@@ -0,0 +1,387 @@
+//! `saxaboom inspect`: prints what went into and what came out of a conversion.
+use std::{
+    ffi::CString,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, ValueEnum};
+use saxaboom::{
+    dxil::{self, Container, SignatureElement},
+    ffi, IRShaderReflection,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{read, Cli, CliError};
+
+#[derive(Args, Debug)]
+pub struct InspectArgs {
+    /// DXIL container or `metallib` to inspect
+    input: PathBuf,
+
+    /// Reflection JSON of a `metallib` input, defaults to the input path with a `.json` extension
+    /// when that file exists.  Reading it requires the metal_irconverter library
+    #[arg(long)]
+    reflection: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, default_value = "text")]
+    format: Format,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    /// Indented, human readable text
+    Text,
+    /// Pretty-printed JSON with the same contents as the text output
+    Json,
+}
+
+pub fn run(cli: &Cli, args: &InspectArgs) -> Result<(), CliError> {
+    let bytes = read(&args.input)?;
+    let report = if bytes.starts_with(b"DXBC") {
+        inspect_dxil(&bytes)?
+    } else if bytes.starts_with(b"MTLB") {
+        let reflection = match &args.reflection {
+            Some(path) => Some(path.clone()),
+            None => Some(args.input.with_extension("json")).filter(|p| p.exists()),
+        };
+        inspect_metallib(cli, &bytes, reflection.as_deref())?
+    } else {
+        return Err(CliError::UnrecognizedInput {
+            path: args.input.clone(),
+        });
+    };
+
+    match args.format {
+        Format::Text => {
+            let mut text = String::new();
+            write_text(&mut text, &report, 0);
+            print!("{text}");
+        }
+        Format::Json => println!("{report:#}"),
+    }
+    Ok(())
+}
+
+fn inspect_dxil(bytes: &[u8]) -> Result<Value, CliError> {
+    let container = Container::parse(bytes)?;
+    let parts = container
+        .parts
+        .iter()
+        .map(|p| json!({ "fourcc": p.name(), "size": p.data.len() }))
+        .collect::<Vec<_>>();
+    let program = container.program()?.map(|program| {
+        json!({
+            "stage": format!("{:?}", program.kind),
+            "shader_model": format!("{}.{}", program.shader_model.0, program.shader_model.1),
+            "dxil_version": format!("{}.{}", program.dxil_version.0, program.dxil_version.1),
+            "bitcode_size": program.bitcode.len(),
+        })
+    });
+
+    // Prefer the newest layout of every signature
+    let mut signatures = Map::new();
+    for (name, fourccs) in [
+        ("input", [*b"ISG1", *b"ISGN"].as_slice()),
+        ("output", &[*b"OSG1", *b"OSG5", *b"OSGN"]),
+        ("patch_constant", &[*b"PSG1", *b"PCSG"]),
+    ] {
+        let Some(fourcc) = fourccs.iter().find(|&&f| container.part(f).is_some()) else {
+            continue;
+        };
+        let elements = container.signature(*fourcc)?.unwrap_or_default();
+        signatures.insert(
+            name.to_owned(),
+            elements.iter().map(signature_element).collect(),
+        );
+    }
+
+    let pipeline_state = container.pipeline_state()?;
+    let resource_bindings = pipeline_state.as_ref().map(|psv| {
+        psv.resources
+            .iter()
+            .map(|r| {
+                json!({
+                    "type": format!("{:?}", r.resource_type),
+                    "space": r.space,
+                    "lower_bound": r.lower_bound,
+                    // Unbounded ranges end at `u32::MAX`
+                    "upper_bound": (r.upper_bound != u32::MAX).then_some(r.upper_bound),
+                })
+            })
+            .collect::<Vec<_>>()
+    });
+    let pipeline_state = pipeline_state.map(|psv| {
+        json!({
+            "stage": psv.stage.map(|s| format!("{s:?}")),
+            "entry_point": psv.entry_point,
+            "num_threads": psv.num_threads,
+            "wave_lane_counts": [psv.wave_lane_counts.0, psv.wave_lane_counts.1],
+        })
+    });
+
+    Ok(json!({
+        "container": {
+            "version": format!("{}.{}", container.version.0, container.version.1),
+            "hash": container.hash.iter().map(|b| format!("{b:02x}")).collect::<String>(),
+            "has_root_signature": container.part(dxil::ROOT_SIGNATURE).is_some(),
+        },
+        "parts": parts,
+        "program": program,
+        "pipeline_state": pipeline_state,
+        "signatures": signatures,
+        "resource_bindings": resource_bindings,
+    }))
+}
+
+fn signature_element(element: &SignatureElement) -> Value {
+    let mask = "xyzw"
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| element.mask & (1 << i) != 0)
+        .map(|(_, c)| c)
+        .collect::<String>();
+    json!({
+        "semantic": format!("{}{}", element.semantic_name, element.semantic_index),
+        "system_value": system_value_name(element.system_value),
+        "component_type": component_type_name(element.component_type),
+        "register": element.register,
+        "mask": mask,
+        "stream": element.stream,
+    })
+}
+
+/// `D3D_NAME`, with the `SV_` spelling used in HLSL.
+fn system_value_name(value: u32) -> String {
+    let name = match value {
+        0 => "none",
+        1 => "SV_Position",
+        2 => "SV_ClipDistance",
+        3 => "SV_CullDistance",
+        4 => "SV_RenderTargetArrayIndex",
+        5 => "SV_ViewportArrayIndex",
+        6 => "SV_VertexID",
+        7 => "SV_PrimitiveID",
+        8 => "SV_InstanceID",
+        9 => "SV_IsFrontFace",
+        10 => "SV_SampleIndex",
+        11 | 13 | 15 | 16 => "SV_TessFactor",
+        12 | 14 => "SV_InsideTessFactor",
+        17 => "SV_Barycentrics",
+        18 => "SV_ShadingRate",
+        19 => "SV_CullPrimitive",
+        64 => "SV_Target",
+        65 => "SV_Depth",
+        66 => "SV_Coverage",
+        67 => "SV_DepthGreaterEqual",
+        68 => "SV_DepthLessEqual",
+        69 => "SV_StencilRef",
+        70 => "SV_InnerCoverage",
+        _ => return format!("unknown ({value})"),
+    };
+    name.to_owned()
+}
+
+/// `D3D_REGISTER_COMPONENT_TYPE`, extended with the 16 and 64 bit types of DXIL.
+fn component_type_name(value: u32) -> String {
+    let name = match value {
+        0 => "unknown",
+        1 => "uint32",
+        2 => "sint32",
+        3 => "float32",
+        4 => "uint16",
+        5 => "sint16",
+        6 => "float16",
+        7 => "uint64",
+        8 => "sint64",
+        9 => "float64",
+        _ => return format!("unknown ({value})"),
+    };
+    name.to_owned()
+}
+
+fn inspect_metallib(cli: &Cli, bytes: &[u8], reflection: Option<&Path>) -> Result<Value, CliError> {
+    let mut report = json!({ "metallib": { "size": bytes.len() } });
+    if let Some(path) = reflection {
+        let converter = cli.converter()?;
+        let json = CString::new(read(path)?).map_err(|_| CliError::Nul {
+            path: path.to_owned(),
+        })?;
+        let reflection = converter.create_shader_reflection_from_json(&json)?;
+        report["reflection"] = reflection_report(&reflection);
+    }
+    Ok(report)
+}
+
+fn reflection_report(reflection: &IRShaderReflection) -> Value {
+    let version = ffi::IRReflectionVersion::_1_0;
+    let function_constants = reflection
+        .function_constants()
+        .into_iter()
+        .map(|c| json!({ "name": c.name, "type": format!("{:?}", c.r#type) }))
+        .collect::<Vec<_>>();
+    let resources = reflection
+        .resource_locations()
+        .iter()
+        .map(|r| {
+            let name = (!r.resourceName.is_null())
+                .then(|| unsafe { std::ffi::CStr::from_ptr(r.resourceName) }.to_string_lossy());
+            json!({
+                "name": name,
+                "type": format!("{:?}", r.resourceType),
+                "space": r.space,
+                "slot": r.slot,
+                "top_level_offset": r.topLevelOffset,
+                "size": r.sizeBytes,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut report = json!({
+        "entry_point": reflection
+            .entry_point_function_name()
+            .map(|n| n.to_string_lossy()),
+        "needs_function_constants": reflection.needs_function_constants(),
+        "function_constants": function_constants,
+        "resources": resources,
+    });
+
+    // The reflection does not record its stage, report every stage that has info
+    if let Some(info) = reflection.compute_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["compute"] = json!({ "threadgroup_size": info.tg_size });
+    }
+    if let Some(info) = reflection.vertex_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        let inputs = (0..info.num_vertex_inputs)
+            .map(|i| {
+                let input = unsafe { *info.vertex_inputs.add(i) };
+                json!({
+                    "name": unsafe { std::ffi::CStr::from_ptr(input.name) }.to_string_lossy(),
+                    "attribute_index": input.attributeIndex,
+                })
+            })
+            .collect::<Vec<_>>();
+        report["vertex"] = json!({
+            "vertex_id_index": info.vertex_id_index,
+            "instance_id_index": info.instance_id_index,
+            "vertex_output_size": info.vertex_output_size_in_bytes,
+            "needs_draw_params": info.needs_draw_params,
+            "inputs": inputs,
+        });
+    }
+    if let Some(info) = reflection.fragment_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["fragment"] = json!({
+            "render_targets": info.num_render_targets,
+            "rt_index_int": info.rt_index_int,
+            "discards": info.discards,
+        });
+    }
+    if let Some(info) = reflection.hull_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["hull"] = json!({
+            "domain": format!("{:?}", info.tessellator_domain),
+            "partitioning": format!("{:?}", info.tessellator_partitioning),
+            "output_primitive": format!("{:?}", info.tessellator_output_primitive),
+            "input_control_points": info.input_control_point_count,
+            "output_control_points": info.output_control_point_count,
+            "output_control_point_size": info.output_control_point_size,
+            "patch_constants_size": info.patch_constants_size,
+            "max_tessellation_factor": info.max_tessellation_factor,
+            "half_precision_factors": info.tessellation_type_half,
+        });
+    }
+    if let Some(info) = reflection.domain_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["domain"] = json!({
+            "domain": format!("{:?}", info.tessellator_domain),
+            "input_control_points": info.input_control_point_count,
+            "input_control_point_size": info.input_control_point_size,
+            "patch_constants_size": info.patch_constants_size,
+            "half_precision_factors": info.tessellation_type_half,
+        });
+    }
+    if let Some(info) = reflection.geometry_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["geometry"] = json!({
+            "input_primitive": format!("{:?}", info.input_primitive),
+            "instance_count": info.instance_count,
+            "vertex_outputs": info.num_vertex_outputs,
+            "max_payload_size": info.max_payload_size_in_bytes,
+            "passthrough": info.is_passthrough,
+        });
+    }
+    if let Some(info) = reflection.mesh_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["mesh"] = json!({
+            "threadgroup_size": info.num_threads,
+            "max_vertices": info.max_vertex_output_count,
+            "max_primitives": info.max_primitive_output_count,
+            "primitive_topology": format!("{:?}", info.primitive_topology),
+            "max_payload_size": info.max_payload_size_in_bytes,
+        });
+    }
+    if let Some(info) = reflection.amplification_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["amplification"] = json!({
+            "threadgroup_size": info.num_threads,
+            "max_payload_size": info.max_payload_size_in_bytes,
+        });
+    }
+    if let Some(info) = reflection.raytracing_info(version) {
+        let info = unsafe { info.u_1.info_1_0 };
+        report["raytracing"] = json!({
+            "indirect_intersection_function": info.is_indirect_intersection_function,
+        });
+    }
+    report
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_owned(),
+        Value::Object(_) => "{}".to_owned(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => {
+            let values = values.iter().map(scalar_text).collect::<Vec<_>>();
+            format!("[{}]", values.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Renders `value` as `key: value` lines, nesting objects and lists of objects by indentation.
+fn write_text(out: &mut String, value: &Value, indent: usize) {
+    let Value::Object(map) = value else {
+        let _ = writeln!(out, "{:indent$}{}", "", scalar_text(value));
+        return;
+    };
+    for (key, value) in map {
+        match value {
+            Value::Array(items) if !items.iter().all(is_scalar) => {
+                let _ = writeln!(out, "{:indent$}{key}:", "");
+                for item in items {
+                    // Render the item one level deeper, then mark its first line as a list item
+                    let mut item_text = String::new();
+                    write_text(&mut item_text, item, indent + 4);
+                    let start = out.len() + indent + 2;
+                    out.push_str(&item_text);
+                    out.replace_range(start..start + 2, "- ");
+                }
+            }
+            Value::Object(fields) if !fields.is_empty() => {
+                let _ = writeln!(out, "{:indent$}{key}:", "");
+                write_text(out, value, indent + 2);
+            }
+            value => {
+                let _ = writeln!(out, "{:indent$}{key}: {}", "", scalar_text(value));
+            }
+        }
+    }
+}
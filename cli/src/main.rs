@@ -0,0 +1,186 @@
+//! `saxaboom` command line tool: converts DXIL to `metallib` with Metal Shader Converter, one
+//! shader at a time or a whole project from a manifest, and inspects either.
+use std::{
+    ffi::CStr,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+use saxaboom::{ffi, MetalIrConverter};
+use thiserror::Error;
+
+mod build;
+mod convert;
+mod diff;
+mod inspect;
+mod manifest;
+mod root_signature;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0       Success
+  1       Failed to read or write a file, or any other error
+  2       Invalid command line arguments
+  3       The metal_irconverter library could not be loaded
+  4       The root signature file could not be parsed
+  5       `build` failed to convert at least one shader, the others were still converted
+  6       `diff` found shaders whose output differs between both libraries, or that failed to
+          convert with both
+  10+N    The metal_irconverter library failed with IRErrorCode N, for example 18 for
+          CompilationError (8)";
+
+#[derive(Parser, Debug)]
+#[command(version, about, after_help = EXIT_CODES)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Path to the metal_irconverter library, searched for with `MetalIrConverter::discover()`
+    /// (honoring `SAXABOOM_IRCONVERTER_PATH`) when not set
+    #[arg(long, global = true)]
+    library: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert a DXIL entry point to a `metallib`
+    Convert(convert::ConvertArgs),
+    /// Print the contents of a DXIL container, or of a `metallib` and its reflection
+    Inspect(inspect::InspectArgs),
+    /// Convert every shader listed in a TOML manifest, skipping those that are up to date
+    Build(build::BuildArgs),
+    /// Convert DXIL files with two metal_irconverter libraries and report what changed
+    Diff(diff::DiffArgs),
+}
+
+impl Cli {
+    fn converter(&self) -> Result<MetalIrConverter, CliError> {
+        Ok(match &self.library {
+            Some(path) => MetalIrConverter::new(path)?,
+            None => MetalIrConverter::discover()?,
+        })
+    }
+}
+
+#[derive(Error, Debug)]
+enum CliError {
+    #[error("Failed to read `{}`: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to write `{}`: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("`{}`: {source}", path.display())]
+    RootSignature {
+        path: PathBuf,
+        source: root_signature::ParseError,
+    },
+    #[error("`{}` is not a valid JSON root signature", path.display())]
+    RootSignatureJson { path: PathBuf },
+    #[error("`{}` contains a NUL byte", path.display())]
+    Nul { path: PathBuf },
+    #[error("`{}` is neither a DXIL container nor a metallib", path.display())]
+    UnrecognizedInput { path: PathBuf },
+    #[error("`{}`: {source}", path.display())]
+    Manifest {
+        path: PathBuf,
+        source: manifest::ManifestError,
+    },
+    #[error("Expected a {expected:?} shader, but the entry point is a {found:?} shader")]
+    StageMismatch {
+        expected: ffi::IRShaderStage,
+        found: ffi::IRShaderStage,
+    },
+    #[error("Failed to synthesize the stage-in function")]
+    StageIn,
+    #[error("{failed} of {total} shaders failed to build")]
+    BuildFailed { failed: usize, total: usize },
+    #[error(
+        "{changed} of {total} shaders differ between both libraries, {failed} failed with both"
+    )]
+    Differences {
+        changed: usize,
+        failed: usize,
+        total: usize,
+    },
+    #[error("Failed to start the build threads: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+    #[error(transparent)]
+    Saxaboom(#[from] saxaboom::Error),
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Self::Saxaboom(saxaboom::Error::Load(_) | saxaboom::Error::Discover(_)) => 3,
+            Self::RootSignature { .. } | Self::RootSignatureJson { .. } | Self::Nul { .. } => 4,
+            Self::Saxaboom(e) => match e.ir_error() {
+                Some(ir_error) => 10 + ir_error.code() as u8,
+                None => 1,
+            },
+            Self::BuildFailed { .. } => 5,
+            Self::Differences { .. } => 6,
+            Self::Read { .. }
+            | Self::Write { .. }
+            | Self::UnrecognizedInput { .. }
+            | Self::Manifest { .. }
+            | Self::StageMismatch { .. }
+            | Self::StageIn
+            | Self::ThreadPool(_) => 1,
+        }
+    }
+}
+
+/// Multi-line compiler diagnostics carried by the `IRError` behind `error`, if any.
+fn diagnostics(error: &CliError) -> Option<&CStr> {
+    match error {
+        CliError::Saxaboom(e) => e.ir_error().map(|ir_error| ir_error.payload()),
+        _ => None,
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, CliError> {
+    fs::read(path).map_err(|source| CliError::Read {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), CliError> {
+    fs::write(path, contents).map_err(|source| CliError::Write {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn run(cli: &Cli) -> Result<(), CliError> {
+    match &cli.command {
+        Command::Convert(args) => convert::run(&cli.converter()?, args),
+        Command::Inspect(args) => inspect::run(cli, args),
+        Command::Build(args) => build::run(&cli.converter()?, args),
+        Command::Diff(args) => diff::run(args),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            // Diagnostics are escaped in the message above
+            if let Some(diagnostics) = diagnostics(&e) {
+                for line in diagnostics.to_string_lossy().lines() {
+                    eprintln!("  | {line}");
+                }
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
@@ -0,0 +1,393 @@
+//! The TOML manifest read by `saxaboom build`.
+//!
+//! ```toml
+//! # Relative paths are relative to the manifest, and so is the output directory (default: `.`)
+//! output_dir = "build/shaders"
+//!
+//! # Applied to every shader that does not set the field itself
+//! [defaults]
+//! root_signature = "root_signature.hlsl"
+//! gpu_family = "apple7"
+//! deployment_target = "macos:14.0.0"
+//! validation = ["all"]
+//!
+//! [[shader]]
+//! input = "blur.dxil"
+//! stage = "compute"
+//!
+//! [[shader]]
+//! input = "mesh.dxil"
+//! name = "mesh_vs"
+//! entry_point = "vs_main"
+//! input_layout = [
+//!     { semantic = "POSITION", format = "R32G32B32_FLOAT" },
+//!     { semantic = "TEXCOORD", format = "R16G16_FLOAT", offset = 12 },
+//! ]
+//! ```
+use std::{
+    collections::HashSet,
+    ffi::CString,
+    path::{Path, PathBuf},
+};
+
+use saxaboom::ffi;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::convert::{
+    parse_deployment_target, CompatibilityFlag, GpuFamily, Settings, StageIn, ValidationFlag,
+};
+
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Stage a shader is expected to compile to.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage {
+    Vertex,
+    Fragment,
+    Hull,
+    Domain,
+    Geometry,
+    Mesh,
+    Amplification,
+    Compute,
+    RayGeneration,
+    ClosestHit,
+    AnyHit,
+    Intersection,
+    Miss,
+    Callable,
+}
+
+impl From<Stage> for ffi::IRShaderStage {
+    fn from(value: Stage) -> Self {
+        match value {
+            Stage::Vertex => Self::Vertex,
+            Stage::Fragment => Self::Fragment,
+            Stage::Hull => Self::Hull,
+            Stage::Domain => Self::Domain,
+            Stage::Geometry => Self::Geometry,
+            Stage::Mesh => Self::Mesh,
+            Stage::Amplification => Self::Amplification,
+            Stage::Compute => Self::Compute,
+            Stage::RayGeneration => Self::RayGeneration,
+            Stage::ClosestHit => Self::ClosestHit,
+            Stage::AnyHit => Self::AnyHit,
+            Stage::Intersection => Self::Intersection,
+            Stage::Miss => Self::Miss,
+            Stage::Callable => Self::Callable,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HitGroupType {
+    Triangles,
+    ProceduralPrimitive,
+}
+
+impl From<HitGroupType> for ffi::IRHitGroupType {
+    fn from(value: HitGroupType) -> Self {
+        match value {
+            HitGroupType::Triangles => Self::Triangles,
+            HitGroupType::ProceduralPrimitive => Self::ProceduralPrimitive,
+        }
+    }
+}
+
+/// Element of the vertex input layout that a stage-in function is synthesized for, mirroring
+/// `D3D12_INPUT_ELEMENT_DESC`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputElement {
+    pub semantic: String,
+    #[serde(default)]
+    pub semantic_index: u32,
+    /// `DXGI_FORMAT` name without the prefix, such as `R32G32B32_FLOAT`
+    #[serde(deserialize_with = "deserialize_format")]
+    pub format: ffi::IRFormat,
+    #[serde(default)]
+    pub slot: u32,
+    #[serde(default)]
+    pub offset: u32,
+    #[serde(default)]
+    pub per_instance: bool,
+    #[serde(default)]
+    pub step_rate: u32,
+}
+
+impl InputElement {
+    pub fn descriptor(&self) -> ffi::IRInputElementDescriptor1 {
+        ffi::IRInputElementDescriptor1 {
+            semanticIndex: self.semantic_index,
+            format: self.format,
+            inputSlot: self.slot,
+            alignedByteOffset: self.offset,
+            instanceDataStepRate: self.step_rate,
+            inputSlotClass: if self.per_instance {
+                ffi::IRInputClassification::PerInstanceData
+            } else {
+                ffi::IRInputClassification::PerVertexData
+            },
+        }
+    }
+}
+
+fn deserialize_format<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<ffi::IRFormat, D::Error> {
+    use ffi::IRFormat as F;
+
+    let name = String::deserialize(deserializer)?;
+    Ok(match name.as_str() {
+        "R32G32B32A32_FLOAT" => F::R32G32B32A32Float,
+        "R32G32B32A32_UINT" => F::R32G32B32A32Uint,
+        "R32G32B32A32_SINT" => F::R32G32B32A32Sint,
+        "R32G32B32_FLOAT" => F::R32G32B32Float,
+        "R32G32B32_UINT" => F::R32G32B32Uint,
+        "R32G32B32_SINT" => F::R32G32B32Sint,
+        "R16G16B16A16_FLOAT" => F::R16G16B16A16Float,
+        "R16G16B16A16_UNORM" => F::R16G16B16A16Unorm,
+        "R16G16B16A16_UINT" => F::R16G16B16A16Uint,
+        "R16G16B16A16_SNORM" => F::R16G16B16A16Snorm,
+        "R16G16B16A16_SINT" => F::R16G16B16A16Sint,
+        "R32G32_FLOAT" => F::R32G32Float,
+        "R32G32_UINT" => F::R32G32Uint,
+        "R32G32_SINT" => F::R32G32Sint,
+        "R10G10B10A2_UNORM" => F::R10G10B10A2Unorm,
+        "R10G10B10A2_UINT" => F::R10G10B10A2Uint,
+        "R8G8B8A8_UNORM" => F::R8G8B8A8Unorm,
+        "R8G8B8A8_UINT" => F::R8G8B8A8Uint,
+        "R8G8B8A8_SNORM" => F::R8G8B8A8Snorm,
+        "R8G8B8A8_SINT" => F::R8G8B8A8Sint,
+        "B8G8R8A8_UNORM" => F::B8G8R8A8Unorm,
+        "R16G16_FLOAT" => F::R16G16Float,
+        "R16G16_UNORM" => F::R16G16Unorm,
+        "R16G16_UINT" => F::R16G16Uint,
+        "R16G16_SNORM" => F::R16G16Snorm,
+        "R16G16_SINT" => F::R16G16Sint,
+        "R32_FLOAT" => F::R32Float,
+        "R32_UINT" => F::R32Uint,
+        "R32_SINT" => F::R32Sint,
+        "R8G8_UNORM" => F::R8G8Unorm,
+        "R8G8_UINT" => F::R8G8Uint,
+        "R8G8_SNORM" => F::R8G8Snorm,
+        "R8G8_SINT" => F::R8G8Sint,
+        "R16_FLOAT" => F::R16Float,
+        "R16_UNORM" => F::R16Unorm,
+        "R16_UINT" => F::R16Uint,
+        "R16_SNORM" => F::R16Snorm,
+        "R16_SINT" => F::R16Sint,
+        "R8_UNORM" => F::R8Unorm,
+        "R8_UINT" => F::R8Uint,
+        "R8_SNORM" => F::R8Snorm,
+        "R8_SINT" => F::R8Sint,
+        _ => {
+            return Err(serde::de::Error::custom(format!(
+                "unsupported vertex format `{name}`"
+            )))
+        }
+    })
+}
+
+fn deserialize_deployment_target<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<(ffi::IROperatingSystem, CString)>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_deployment_target(&value)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// A `[[shader]]` or the `[defaults]` table, every field of a shader falls back to the
+/// defaults.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShaderTable {
+    input: Option<PathBuf>,
+    name: Option<String>,
+    entry_point: Option<String>,
+    stage: Option<Stage>,
+    root_signature: Option<PathBuf>,
+    local_root_signature: Option<PathBuf>,
+    hitgroup_type: Option<HitGroupType>,
+    input_layout: Option<Vec<InputElement>>,
+    stage_in: Option<StageIn>,
+    gpu_family: Option<GpuFamily>,
+    #[serde(default, deserialize_with = "deserialize_deployment_target")]
+    deployment_target: Option<(ffi::IROperatingSystem, CString)>,
+    validation: Option<Vec<ValidationFlag>>,
+    compatibility: Option<Vec<CompatibilityFlag>>,
+    reflection: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+    output_dir: Option<PathBuf>,
+    #[serde(default)]
+    defaults: ShaderTable,
+    #[serde(default, rename = "shader")]
+    shaders: Vec<ShaderTable>,
+}
+
+/// A shader from the manifest with the defaults applied and paths resolved.
+#[derive(Debug)]
+pub struct Shader {
+    /// Unique within the manifest, the file stem of all outputs.
+    pub name: String,
+    pub input: PathBuf,
+    pub entry_point: CString,
+    pub stage: Option<Stage>,
+    pub root_signature: Option<PathBuf>,
+    pub local_root_signature: Option<PathBuf>,
+    pub hitgroup_type: Option<HitGroupType>,
+    /// Synthesize a stage-in function for this layout when not empty.
+    pub input_layout: Vec<InputElement>,
+    pub settings: Settings,
+    pub reflection: bool,
+}
+
+impl Shader {
+    pub fn metallib_path(&self, output_dir: &Path) -> PathBuf {
+        output_dir.join(format!("{}.metallib", self.name))
+    }
+
+    pub fn reflection_path(&self, output_dir: &Path) -> Option<PathBuf> {
+        self.reflection
+            .then(|| output_dir.join(format!("{}.json", self.name)))
+    }
+
+    pub fn stage_in_path(&self, output_dir: &Path) -> Option<PathBuf> {
+        (!self.input_layout.is_empty())
+            .then(|| output_dir.join(format!("{}.stage_in.metallib", self.name)))
+    }
+
+    /// Every file that building this shader writes.
+    pub fn outputs(&self, output_dir: &Path) -> Vec<PathBuf> {
+        [
+            Some(self.metallib_path(output_dir)),
+            self.reflection_path(output_dir),
+            self.stage_in_path(output_dir),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct Manifest {
+    pub output_dir: PathBuf,
+    pub shaders: Vec<Shader>,
+}
+
+impl Manifest {
+    /// Parse `source`, resolving relative paths against `base_dir`.
+    pub fn parse(source: &str, base_dir: &Path) -> Result<Self, ManifestError> {
+        let file: ManifestFile = toml::from_str(source)?;
+        let defaults = file.defaults;
+        if defaults.input.is_some() || defaults.name.is_some() {
+            return Err(ManifestError::Invalid(
+                "`input` and `name` cannot be set in `[defaults]`".to_owned(),
+            ));
+        }
+
+        let mut names = HashSet::new();
+        let shaders = file
+            .shaders
+            .into_iter()
+            .enumerate()
+            .map(|(i, table)| {
+                let shader = resolve(table, &defaults, base_dir)
+                    .map_err(|e| ManifestError::Invalid(format!("shader {}: {e}", i + 1)))?;
+                if !names.insert(shader.name.clone()) {
+                    return Err(ManifestError::Invalid(format!(
+                        "shader {}: the name `{}` is already used, set a unique `name`",
+                        i + 1,
+                        shader.name
+                    )));
+                }
+                Ok(shader)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            output_dir: base_dir.join(file.output_dir.unwrap_or_default()),
+            shaders,
+        })
+    }
+}
+
+fn resolve(table: ShaderTable, defaults: &ShaderTable, base_dir: &Path) -> Result<Shader, String> {
+    let input = table.input.ok_or("`input` is required")?;
+    let name = match table.name {
+        Some(name) => name,
+        None => input
+            .file_stem()
+            .ok_or("`input` has no file name")?
+            .to_string_lossy()
+            .into_owned(),
+    };
+    // Used as the file stem of every output, which has to stay inside the output directory
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!(
+            "the name `{name}` is not a valid file name, it cannot be empty, `.` or `..`, or \
+             contain `/` or `\\`"
+        ));
+    }
+    let entry_point = table
+        .entry_point
+        .or_else(|| defaults.entry_point.clone())
+        .unwrap_or_else(|| "main".to_owned());
+    let entry_point = CString::new(entry_point).map_err(|e| e.to_string())?;
+    let input_layout = table
+        .input_layout
+        .or_else(|| defaults.input_layout.clone())
+        .unwrap_or_default();
+    // Hardcoded in `IRInputLayoutDescriptor1`
+    if input_layout.len() > 31 {
+        return Err("`input_layout` has more than 31 elements".to_owned());
+    }
+    if input_layout.iter().any(|e| e.semantic.contains('\0')) {
+        return Err("`input_layout` semantic contains a NUL byte".to_owned());
+    }
+
+    let path = |path: Option<PathBuf>, default: &Option<PathBuf>| {
+        path.or_else(|| default.clone()).map(|p| base_dir.join(p))
+    };
+    Ok(Shader {
+        name,
+        input: base_dir.join(input),
+        entry_point,
+        stage: table.stage.or(defaults.stage),
+        root_signature: path(table.root_signature, &defaults.root_signature),
+        local_root_signature: path(table.local_root_signature, &defaults.local_root_signature),
+        hitgroup_type: table.hitgroup_type.or(defaults.hitgroup_type),
+        input_layout,
+        settings: Settings {
+            gpu_family: table.gpu_family.or(defaults.gpu_family),
+            deployment_target: table
+                .deployment_target
+                .or_else(|| defaults.deployment_target.clone()),
+            validation_flags: table
+                .validation
+                .or_else(|| defaults.validation.clone())
+                .unwrap_or_default(),
+            compatibility_flags: table
+                .compatibility
+                .or_else(|| defaults.compatibility.clone())
+                .unwrap_or_default(),
+            stage_in: table.stage_in.or(defaults.stage_in),
+        },
+        reflection: table.reflection.or(defaults.reflection).unwrap_or(true),
+    })
+}
@@ -0,0 +1,645 @@
+//! Parser for HLSL root signature strings, the `RootFlags(...), CBV(b0), ...` language accepted by
+//! `[RootSignature(...)]` attributes and `dxc -rootsig-define`.  See
+//! <https://learn.microsoft.com/en-us/windows/win32/direct3d12/specifying-root-signatures-in-hlsl>.
+use saxaboom::ffi;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("Invalid root signature: {0}")]
+pub struct ParseError(String);
+
+fn error<T>(message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError(message.into()))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Punct(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == '"' || c == '\\' {
+            // Quotes and line continuations are left over from `#define` macros
+            chars.next();
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else if c.is_ascii_digit() || c == '-' || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+            {
+                number.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Number(number));
+        } else if matches!(c, '(' | ')' | ',' | '=' | '|') {
+            tokens.push(Token::Punct(c));
+            chars.next();
+        } else {
+            return error(format!("unexpected character `{c}`"));
+        }
+    }
+    Ok(tokens)
+}
+
+/// `Name(arg, key = value, Nested(...))`
+#[derive(Debug)]
+struct Element {
+    name: String,
+    args: Vec<Arg>,
+}
+
+#[derive(Debug)]
+enum Arg {
+    Positional(Vec<Token>),
+    Named(String, Vec<Token>),
+    Nested(Element),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), ParseError> {
+        match self.next() {
+            Some(Token::Punct(c)) if c == punct => Ok(()),
+            other => error(format!("expected `{punct}`, found {other:?}")),
+        }
+    }
+
+    fn element(&mut self) -> Result<Element, ParseError> {
+        let Some(Token::Ident(name)) = self.next() else {
+            return error("expected a root signature element");
+        };
+        self.expect('(')?;
+        let mut args = Vec::new();
+        if self.peek(0) == Some(&Token::Punct(')')) {
+            self.next();
+            return Ok(Element { name, args });
+        }
+        loop {
+            args.push(self.arg()?);
+            match self.next() {
+                Some(Token::Punct(',')) => continue,
+                Some(Token::Punct(')')) => return Ok(Element { name, args }),
+                other => return error(format!("expected `,` or `)` in `{name}`, found {other:?}")),
+            }
+        }
+    }
+
+    fn arg(&mut self) -> Result<Arg, ParseError> {
+        match (self.peek(0), self.peek(1)) {
+            (Some(Token::Ident(_)), Some(Token::Punct('('))) => Ok(Arg::Nested(self.element()?)),
+            (Some(Token::Ident(key)), Some(Token::Punct('='))) => {
+                let key = key.clone();
+                self.pos += 2;
+                Ok(Arg::Named(key, self.value()?))
+            }
+            _ => Ok(Arg::Positional(self.value()?)),
+        }
+    }
+
+    /// Tokens up to the next `,` or `)`, such as `b0` or `DATA_STATIC | DATA_VOLATILE`.
+    fn value(&mut self) -> Result<Vec<Token>, ParseError> {
+        let mut value = Vec::new();
+        while let Some(token) = self.peek(0) {
+            if matches!(token, Token::Punct(',' | ')')) {
+                break;
+            }
+            value.push(token.clone());
+            self.pos += 1;
+        }
+        if value.is_empty() {
+            return error("expected a value");
+        }
+        Ok(value)
+    }
+}
+
+fn single(value: &[Token]) -> Result<&str, ParseError> {
+    match value {
+        [Token::Ident(s) | Token::Number(s)] => Ok(s),
+        _ => error(format!("expected a single value, found {value:?}")),
+    }
+}
+
+fn uint(value: &[Token]) -> Result<u32, ParseError> {
+    let s = single(value)?;
+    match s {
+        // D3D12_DESCRIPTOR_RANGE_OFFSET_APPEND and UINT_MAX
+        "unbounded" | "DESCRIPTOR_RANGE_OFFSET_APPEND" => Ok(u32::MAX),
+        _ => s
+            .parse()
+            .or_else(|_| error(format!("expected an unsigned integer, found `{s}`"))),
+    }
+}
+
+fn float(value: &[Token]) -> Result<f32, ParseError> {
+    let s = single(value)?;
+    s.trim_end_matches('f')
+        .parse()
+        .or_else(|_| error(format!("expected a number, found `{s}`")))
+}
+
+/// Parse `b3` into `3` after checking the register type.
+fn register(value: &[Token], kind: char) -> Result<u32, ParseError> {
+    let s = single(value)?;
+    match s.strip_prefix(kind).map(str::parse) {
+        Some(Ok(register)) => Ok(register),
+        _ => error(format!("expected a `{kind}` register, found `{s}`")),
+    }
+}
+
+/// Combine `A | B | 0` using `lookup` for every name.
+fn flags<T: Copy + std::ops::BitOr<Output = T>>(
+    value: &[Token],
+    none: T,
+    lookup: impl Fn(&str) -> Option<T>,
+) -> Result<T, ParseError> {
+    let mut result = none;
+    for (i, token) in value.iter().enumerate() {
+        match token {
+            Token::Punct('|') if i % 2 == 1 => {}
+            Token::Number(n) if i % 2 == 0 && n == "0" => {}
+            Token::Ident(name) if i % 2 == 0 => match lookup(name) {
+                Some(flag) => result = result | flag,
+                None => return error(format!("unknown flag `{name}`")),
+            },
+            _ => return error(format!("malformed flags {value:?}")),
+        }
+    }
+    Ok(result)
+}
+
+fn root_signature_flag(name: &str) -> Option<ffi::IRRootSignatureFlags> {
+    use ffi::IRRootSignatureFlags as F;
+    Some(match name {
+        "ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT" => F::AllowInputAssemblerInputLayout,
+        "DENY_VERTEX_SHADER_ROOT_ACCESS" => F::DenyVertexShaderRootAccess,
+        "DENY_HULL_SHADER_ROOT_ACCESS" => F::DenyHullShaderRootAccess,
+        "DENY_DOMAIN_SHADER_ROOT_ACCESS" => F::DenyDomainShaderRootAccess,
+        "DENY_GEOMETRY_SHADER_ROOT_ACCESS" => F::DenyGeometryShaderRootAccess,
+        "DENY_PIXEL_SHADER_ROOT_ACCESS" => F::DenyPixelShaderRootAccess,
+        "ALLOW_STREAM_OUTPUT" => F::AllowStreamOutput,
+        "LOCAL_ROOT_SIGNATURE" => F::LocalRootSignature,
+        "DENY_AMPLIFICATION_SHADER_ROOT_ACCESS" => F::DenyAmplificationShaderRootAccess,
+        "DENY_MESH_SHADER_ROOT_ACCESS" => F::DenyMeshShaderRootAccess,
+        "CBV_SRV_UAV_HEAP_DIRECTLY_INDEXED" => F::CBVSRVUAVHeapDirectlyIndexed,
+        "SAMPLER_HEAP_DIRECTLY_INDEXED" => F::SamplerHeapDirectlyIndexed,
+        _ => return None,
+    })
+}
+
+fn root_descriptor_flag(name: &str) -> Option<ffi::IRRootDescriptorFlags> {
+    use ffi::IRRootDescriptorFlags as F;
+    Some(match name {
+        "DATA_VOLATILE" => F::DataVolatile,
+        "DATA_STATIC_WHILE_SET_AT_EXECUTE" => F::DataStaticWhileSetAtExecute,
+        "DATA_STATIC" => F::DataStatic,
+        _ => return None,
+    })
+}
+
+fn descriptor_range_flag(name: &str) -> Option<ffi::IRDescriptorRangeFlags> {
+    use ffi::IRDescriptorRangeFlags as F;
+    Some(match name {
+        "DESCRIPTORS_VOLATILE" => F::DescriptorsVolatile,
+        "DATA_VOLATILE" => F::DataVolatile,
+        "DATA_STATIC_WHILE_SET_AT_EXECUTE" => F::DataStaticWhileSetAtExecute,
+        "DATA_STATIC" => F::DataStatic,
+        "DESCRIPTORS_STATIC_KEEPING_BUFFER_BOUNDS_CHECKS" => {
+            F::DescriptorsStaticKeepingBufferBoundsChecks
+        }
+        _ => return None,
+    })
+}
+
+fn visibility(value: &[Token]) -> Result<ffi::IRShaderVisibility, ParseError> {
+    use ffi::IRShaderVisibility as V;
+    Ok(match single(value)? {
+        "SHADER_VISIBILITY_ALL" => V::All,
+        "SHADER_VISIBILITY_VERTEX" => V::Vertex,
+        "SHADER_VISIBILITY_HULL" => V::Hull,
+        "SHADER_VISIBILITY_DOMAIN" => V::Domain,
+        "SHADER_VISIBILITY_GEOMETRY" => V::Geometry,
+        "SHADER_VISIBILITY_PIXEL" => V::Pixel,
+        "SHADER_VISIBILITY_AMPLIFICATION" => V::Amplification,
+        "SHADER_VISIBILITY_MESH" => V::Mesh,
+        other => return error(format!("unknown shader visibility `{other}`")),
+    })
+}
+
+fn filter(value: &[Token]) -> Result<ffi::IRFilter, ParseError> {
+    use ffi::IRFilter as F;
+    Ok(match single(value)? {
+        "FILTER_MIN_MAG_MIP_POINT" => F::MinMagMipPoint,
+        "FILTER_MIN_MAG_POINT_MIP_LINEAR" => F::MinMagPointMipLinear,
+        "FILTER_MIN_POINT_MAG_LINEAR_MIP_POINT" => F::MinPointMagLinearMipPoint,
+        "FILTER_MIN_POINT_MAG_MIP_LINEAR" => F::MinPointMagMipLinear,
+        "FILTER_MIN_LINEAR_MAG_MIP_POINT" => F::MinLinearMagMipPoint,
+        "FILTER_MIN_LINEAR_MAG_POINT_MIP_LINEAR" => F::MinLinearMagPointMipLinear,
+        "FILTER_MIN_MAG_LINEAR_MIP_POINT" => F::MinMagLinearMipPoint,
+        "FILTER_MIN_MAG_MIP_LINEAR" => F::MinMagMipLinear,
+        "FILTER_ANISOTROPIC" => F::Anisotropic,
+        "FILTER_COMPARISON_MIN_MAG_MIP_POINT" => F::ComparisonMinMagMipPoint,
+        "FILTER_COMPARISON_MIN_MAG_POINT_MIP_LINEAR" => F::ComparisonMinMagPointMipLinear,
+        "FILTER_COMPARISON_MIN_POINT_MAG_LINEAR_MIP_POINT" => {
+            F::ComparisonMinPointMagLinearMipPoint
+        }
+        "FILTER_COMPARISON_MIN_POINT_MAG_MIP_LINEAR" => F::ComparisonMinPointMagMipLinear,
+        "FILTER_COMPARISON_MIN_LINEAR_MAG_MIP_POINT" => F::ComparisonMinLinearMagMipPoint,
+        "FILTER_COMPARISON_MIN_LINEAR_MAG_POINT_MIP_LINEAR" => {
+            F::ComparisonMinLinearMagPointMipLinear
+        }
+        "FILTER_COMPARISON_MIN_MAG_LINEAR_MIP_POINT" => F::ComparisonMinMagLinearMipPoint,
+        "FILTER_COMPARISON_MIN_MAG_MIP_LINEAR" => F::ComparisonMinMagMipLinear,
+        "FILTER_COMPARISON_ANISOTROPIC" => F::ComparisonAnisotropic,
+        "FILTER_MINIMUM_MIN_MAG_MIP_POINT" => F::MinimumMinMagMipPoint,
+        "FILTER_MINIMUM_MIN_MAG_POINT_MIP_LINEAR" => F::MinimumMinMagPointMipLinear,
+        "FILTER_MINIMUM_MIN_POINT_MAG_LINEAR_MIP_POINT" => F::MinimumMinPointMagLinearMipPoint,
+        "FILTER_MINIMUM_MIN_POINT_MAG_MIP_LINEAR" => F::MinimumMinPointMagMipLinear,
+        "FILTER_MINIMUM_MIN_LINEAR_MAG_MIP_POINT" => F::MinimumMinLinearMagMipPoint,
+        "FILTER_MINIMUM_MIN_LINEAR_MAG_POINT_MIP_LINEAR" => F::MinimumMinLinearMagPointMipLinear,
+        "FILTER_MINIMUM_MIN_MAG_LINEAR_MIP_POINT" => F::MinimumMinMagLinearMipPoint,
+        "FILTER_MINIMUM_MIN_MAG_MIP_LINEAR" => F::MinimumMinMagMipLinear,
+        "FILTER_MINIMUM_ANISOTROPIC" => F::MinimumAnisotropic,
+        "FILTER_MAXIMUM_MIN_MAG_MIP_POINT" => F::MaximumMinMagMipPoint,
+        "FILTER_MAXIMUM_MIN_MAG_POINT_MIP_LINEAR" => F::MaximumMinMagPointMipLinear,
+        "FILTER_MAXIMUM_MIN_POINT_MAG_LINEAR_MIP_POINT" => F::MaximumMinPointMagLinearMipPoint,
+        "FILTER_MAXIMUM_MIN_POINT_MAG_MIP_LINEAR" => F::MaximumMinPointMagMipLinear,
+        "FILTER_MAXIMUM_MIN_LINEAR_MAG_MIP_POINT" => F::MaximumMinLinearMagMipPoint,
+        "FILTER_MAXIMUM_MIN_LINEAR_MAG_POINT_MIP_LINEAR" => F::MaximumMinLinearMagPointMipLinear,
+        "FILTER_MAXIMUM_MIN_MAG_LINEAR_MIP_POINT" => F::MaximumMinMagLinearMipPoint,
+        "FILTER_MAXIMUM_MIN_MAG_MIP_LINEAR" => F::MaximumMinMagMipLinear,
+        "FILTER_MAXIMUM_ANISOTROPIC" => F::MaximumAnisotropic,
+        other => return error(format!("unknown filter `{other}`")),
+    })
+}
+
+fn address_mode(value: &[Token]) -> Result<ffi::IRTextureAddressMode, ParseError> {
+    use ffi::IRTextureAddressMode as A;
+    Ok(match single(value)? {
+        "TEXTURE_ADDRESS_WRAP" => A::Wrap,
+        "TEXTURE_ADDRESS_MIRROR" => A::Mirror,
+        "TEXTURE_ADDRESS_CLAMP" => A::Clamp,
+        "TEXTURE_ADDRESS_BORDER" => A::Border,
+        "TEXTURE_ADDRESS_MIRROR_ONCE" => A::MirrorOnce,
+        other => return error(format!("unknown texture address mode `{other}`")),
+    })
+}
+
+fn comparison_function(value: &[Token]) -> Result<ffi::IRComparisonFunction, ParseError> {
+    use ffi::IRComparisonFunction as C;
+    Ok(match single(value)? {
+        "COMPARISON_NEVER" => C::Never,
+        "COMPARISON_LESS" => C::Less,
+        "COMPARISON_EQUAL" => C::Equal,
+        "COMPARISON_LESS_EQUAL" => C::LessEqual,
+        "COMPARISON_GREATER" => C::Greater,
+        "COMPARISON_NOT_EQUAL" => C::NotEqual,
+        "COMPARISON_GREATER_EQUAL" => C::GreaterEqual,
+        "COMPARISON_ALWAYS" => C::Always,
+        other => return error(format!("unknown comparison function `{other}`")),
+    })
+}
+
+fn border_color(value: &[Token]) -> Result<ffi::IRStaticBorderColor, ParseError> {
+    use ffi::IRStaticBorderColor as B;
+    Ok(match single(value)? {
+        "STATIC_BORDER_COLOR_TRANSPARENT_BLACK" => B::TransparentBlack,
+        "STATIC_BORDER_COLOR_OPAQUE_BLACK" => B::OpaqueBlack,
+        "STATIC_BORDER_COLOR_OPAQUE_WHITE" => B::OpaqueWhite,
+        other => return error(format!("unknown border color `{other}`")),
+    })
+}
+
+fn unknown_arg<T>(element: &str, arg: &Arg) -> Result<T, ParseError> {
+    error(format!("unexpected argument {arg:?} in `{element}`"))
+}
+
+/// Register type and parameter type of the root descriptors `CBV`, `SRV` and `UAV`.
+fn descriptor_kind(name: &str) -> Option<(char, ffi::IRRootParameterType)> {
+    match name {
+        "CBV" => Some(('b', ffi::IRRootParameterType::CBV)),
+        "SRV" => Some(('t', ffi::IRRootParameterType::SRV)),
+        "UAV" => Some(('u', ffi::IRRootParameterType::UAV)),
+        _ => None,
+    }
+}
+
+/// A root signature parsed from HLSL, owning everything that
+/// [`ffi::IRVersionedRootSignatureDescriptor`] points to.
+#[derive(Default)]
+pub struct RootSignature {
+    flags: Option<ffi::IRRootSignatureFlags>,
+    parameters: Vec<ffi::IRRootParameter1>,
+    /// Descriptor ranges of the table at the given index in `parameters`.
+    tables: Vec<(usize, Vec<ffi::IRDescriptorRange1>)>,
+    static_samplers: Vec<ffi::IRStaticSamplerDescriptor>,
+}
+
+impl RootSignature {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let mut root_signature = Self::default();
+        while parser.peek(0).is_some() {
+            let element = parser.element()?;
+            root_signature.add(element)?;
+            match parser.next() {
+                None => break,
+                Some(Token::Punct(',')) => {}
+                Some(other) => return error(format!("expected `,`, found {other:?}")),
+            }
+        }
+        Ok(root_signature)
+    }
+
+    fn add(&mut self, element: Element) -> Result<(), ParseError> {
+        match element.name.as_str() {
+            "RootFlags" => {
+                let [Arg::Positional(value)] = element.args.as_slice() else {
+                    return error("`RootFlags` takes a single set of flags");
+                };
+                if self.flags.is_some() {
+                    return error("`RootFlags` specified more than once");
+                }
+                self.flags = Some(flags(
+                    value,
+                    ffi::IRRootSignatureFlags::None,
+                    root_signature_flag,
+                )?);
+            }
+            "RootConstants" => self.add_root_constants(&element)?,
+            "DescriptorTable" => self.add_descriptor_table(&element)?,
+            "StaticSampler" => self.add_static_sampler(&element)?,
+            name => {
+                let Some((kind, parameter_type)) = descriptor_kind(name) else {
+                    return error(format!("unknown root signature element `{name}`"));
+                };
+                self.add_root_descriptor(&element, kind, parameter_type)?
+            }
+        }
+        Ok(())
+    }
+
+    fn add_root_constants(&mut self, element: &Element) -> Result<(), ParseError> {
+        let mut constants = ffi::IRRootConstants {
+            ShaderRegister: u32::MAX,
+            RegisterSpace: 0,
+            Num32BitValues: u32::MAX,
+        };
+        let mut shader_visibility = ffi::IRShaderVisibility::All;
+        for arg in &element.args {
+            match arg {
+                Arg::Positional(value) => constants.ShaderRegister = register(value, 'b')?,
+                Arg::Named(key, value) => match key.as_str() {
+                    "num32BitConstants" => constants.Num32BitValues = uint(value)?,
+                    "space" => constants.RegisterSpace = uint(value)?,
+                    "visibility" => shader_visibility = visibility(value)?,
+                    _ => return unknown_arg(&element.name, arg),
+                },
+                Arg::Nested(_) => return unknown_arg(&element.name, arg),
+            }
+        }
+        if constants.ShaderRegister == u32::MAX || constants.Num32BitValues == u32::MAX {
+            return error("`RootConstants` requires `num32BitConstants` and a `b` register");
+        }
+        self.parameters.push(ffi::IRRootParameter1 {
+            ParameterType: ffi::IRRootParameterType::_32BitConstants,
+            u_1: ffi::IRRootParameter1_u {
+                Constants: constants,
+            },
+            ShaderVisibility: shader_visibility,
+        });
+        Ok(())
+    }
+
+    fn add_root_descriptor(
+        &mut self,
+        element: &Element,
+        kind: char,
+        parameter_type: ffi::IRRootParameterType,
+    ) -> Result<(), ParseError> {
+        let mut descriptor = ffi::IRRootDescriptor1 {
+            ShaderRegister: u32::MAX,
+            RegisterSpace: 0,
+            Flags: if kind == 'u' {
+                ffi::IRRootDescriptorFlags::DataVolatile
+            } else {
+                ffi::IRRootDescriptorFlags::DataStaticWhileSetAtExecute
+            },
+        };
+        let mut shader_visibility = ffi::IRShaderVisibility::All;
+        for arg in &element.args {
+            match arg {
+                Arg::Positional(value) => descriptor.ShaderRegister = register(value, kind)?,
+                Arg::Named(key, value) => match key.as_str() {
+                    "space" => descriptor.RegisterSpace = uint(value)?,
+                    "visibility" => shader_visibility = visibility(value)?,
+                    "flags" => {
+                        descriptor.Flags = flags(
+                            value,
+                            ffi::IRRootDescriptorFlags::None,
+                            root_descriptor_flag,
+                        )?
+                    }
+                    _ => return unknown_arg(&element.name, arg),
+                },
+                Arg::Nested(_) => return unknown_arg(&element.name, arg),
+            }
+        }
+        if descriptor.ShaderRegister == u32::MAX {
+            return error(format!("`{}` requires a `{kind}` register", element.name));
+        }
+        self.parameters.push(ffi::IRRootParameter1 {
+            ParameterType: parameter_type,
+            u_1: ffi::IRRootParameter1_u {
+                Descriptor: descriptor,
+            },
+            ShaderVisibility: shader_visibility,
+        });
+        Ok(())
+    }
+
+    fn add_descriptor_table(&mut self, element: &Element) -> Result<(), ParseError> {
+        let mut ranges = Vec::new();
+        let mut shader_visibility = ffi::IRShaderVisibility::All;
+        for arg in &element.args {
+            match arg {
+                Arg::Named(key, value) if key == "visibility" => {
+                    shader_visibility = visibility(value)?
+                }
+                Arg::Nested(range) => ranges.push(descriptor_range(range)?),
+                _ => return unknown_arg(&element.name, arg),
+            }
+        }
+        self.tables.push((self.parameters.len(), ranges));
+        self.parameters.push(ffi::IRRootParameter1 {
+            ParameterType: ffi::IRRootParameterType::DescriptorTable,
+            u_1: ffi::IRRootParameter1_u {
+                DescriptorTable: ffi::IRRootDescriptorTable1 {
+                    NumDescriptorRanges: 0,
+                    pDescriptorRanges: std::ptr::null_mut(),
+                },
+            },
+            ShaderVisibility: shader_visibility,
+        });
+        Ok(())
+    }
+
+    fn add_static_sampler(&mut self, element: &Element) -> Result<(), ParseError> {
+        // Defaults from the HLSL root signature documentation
+        let mut sampler = ffi::IRStaticSamplerDescriptor {
+            Filter: ffi::IRFilter::Anisotropic,
+            AddressU: ffi::IRTextureAddressMode::Wrap,
+            AddressV: ffi::IRTextureAddressMode::Wrap,
+            AddressW: ffi::IRTextureAddressMode::Wrap,
+            MipLODBias: 0.0,
+            MaxAnisotropy: 16,
+            ComparisonFunc: ffi::IRComparisonFunction::LessEqual,
+            BorderColor: ffi::IRStaticBorderColor::OpaqueWhite,
+            MinLOD: 0.0,
+            MaxLOD: f32::MAX,
+            ShaderRegister: u32::MAX,
+            RegisterSpace: 0,
+            ShaderVisibility: ffi::IRShaderVisibility::All,
+        };
+        for arg in &element.args {
+            match arg {
+                Arg::Positional(value) => sampler.ShaderRegister = register(value, 's')?,
+                Arg::Named(key, value) => match key.as_str() {
+                    "filter" => sampler.Filter = filter(value)?,
+                    "addressU" => sampler.AddressU = address_mode(value)?,
+                    "addressV" => sampler.AddressV = address_mode(value)?,
+                    "addressW" => sampler.AddressW = address_mode(value)?,
+                    "mipLODBias" => sampler.MipLODBias = float(value)?,
+                    "maxAnisotropy" => sampler.MaxAnisotropy = uint(value)?,
+                    "comparisonFunc" => sampler.ComparisonFunc = comparison_function(value)?,
+                    "borderColor" => sampler.BorderColor = border_color(value)?,
+                    "minLOD" => sampler.MinLOD = float(value)?,
+                    "maxLOD" => sampler.MaxLOD = float(value)?,
+                    "space" => sampler.RegisterSpace = uint(value)?,
+                    "visibility" => sampler.ShaderVisibility = visibility(value)?,
+                    _ => return unknown_arg(&element.name, arg),
+                },
+                Arg::Nested(_) => return unknown_arg(&element.name, arg),
+            }
+        }
+        if sampler.ShaderRegister == u32::MAX {
+            return error("`StaticSampler` requires an `s` register");
+        }
+        self.static_samplers.push(sampler);
+        Ok(())
+    }
+
+    /// The descriptor to pass to
+    /// [`saxaboom::MetalIrConverter::create_root_signature_from_descriptor()`], which borrows from
+    /// `self`.
+    pub fn descriptor(&mut self) -> ffi::IRVersionedRootSignatureDescriptor {
+        for (index, ranges) in &mut self.tables {
+            self.parameters[*index].u_1.DescriptorTable = ffi::IRRootDescriptorTable1 {
+                NumDescriptorRanges: ranges.len() as u32,
+                pDescriptorRanges: ranges.as_mut_ptr(),
+            };
+        }
+        ffi::IRVersionedRootSignatureDescriptor {
+            version: ffi::IRRootSignatureVersion::_1_1,
+            u_1: ffi::IRVersionedRootSignatureDescriptor_u {
+                desc_1_1: ffi::IRRootSignatureDescriptor1 {
+                    NumParameters: self.parameters.len() as u32,
+                    pParameters: self.parameters.as_mut_ptr(),
+                    NumStaticSamplers: self.static_samplers.len() as u32,
+                    pStaticSamplers: self.static_samplers.as_mut_ptr(),
+                    Flags: self.flags.unwrap_or(ffi::IRRootSignatureFlags::None),
+                },
+            },
+        }
+    }
+}
+
+fn descriptor_range(element: &Element) -> Result<ffi::IRDescriptorRange1, ParseError> {
+    let (kind, range_type, default_flags) = match element.name.as_str() {
+        "CBV" => (
+            'b',
+            ffi::IRDescriptorRangeType::CBV,
+            ffi::IRDescriptorRangeFlags::DataStaticWhileSetAtExecute,
+        ),
+        "SRV" => (
+            't',
+            ffi::IRDescriptorRangeType::SRV,
+            ffi::IRDescriptorRangeFlags::DataStaticWhileSetAtExecute,
+        ),
+        "UAV" => (
+            'u',
+            ffi::IRDescriptorRangeType::UAV,
+            ffi::IRDescriptorRangeFlags::DataVolatile,
+        ),
+        "Sampler" => (
+            's',
+            ffi::IRDescriptorRangeType::Sampler,
+            ffi::IRDescriptorRangeFlags::None,
+        ),
+        name => return error(format!("unknown descriptor range `{name}`")),
+    };
+    let mut range = ffi::IRDescriptorRange1 {
+        RangeType: range_type,
+        NumDescriptors: 1,
+        BaseShaderRegister: u32::MAX,
+        RegisterSpace: 0,
+        Flags: default_flags,
+        // DESCRIPTOR_RANGE_OFFSET_APPEND
+        OffsetInDescriptorsFromTableStart: u32::MAX,
+    };
+    for arg in &element.args {
+        match arg {
+            Arg::Positional(value) => range.BaseShaderRegister = register(value, kind)?,
+            Arg::Named(key, value) => match key.as_str() {
+                "numDescriptors" => range.NumDescriptors = uint(value)?,
+                "space" => range.RegisterSpace = uint(value)?,
+                "offset" => range.OffsetInDescriptorsFromTableStart = uint(value)?,
+                "flags" => {
+                    range.Flags = flags(
+                        value,
+                        ffi::IRDescriptorRangeFlags::None,
+                        descriptor_range_flag,
+                    )?
+                }
+                _ => return unknown_arg(&element.name, arg),
+            },
+            Arg::Nested(_) => return unknown_arg(&element.name, arg),
+        }
+    }
+    if range.BaseShaderRegister == u32::MAX {
+        return error(format!("`{}` requires a `{kind}` register", element.name));
+    }
+    Ok(range)
+}
@@ -0,0 +1,476 @@
+//! Runs the `saxaboom` binary against the `irconverter_stub` library.
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn stub_library() -> PathBuf {
+    // Cargo places the `cdylib` of the `irconverter_stub` dev-dependency next to the test
+    // executable.
+    std::env::current_exe()
+        .unwrap()
+        .with_file_name(libloading::library_filename("metalirconverter"))
+}
+
+/// Fresh directory containing a (fake) `shader.dxil`.
+fn work_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("shader.dxil"), b"DXBC").unwrap();
+    dir
+}
+
+fn saxaboom(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_saxaboom"))
+        .current_dir(dir)
+        .arg("--library")
+        .arg(stub_library())
+        .args(args)
+        .env("SAXABOOM_STUB_FAIL_ENTRY_POINT", "broken")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn convert() {
+    let dir = work_dir("convert");
+    std::fs::write(
+        dir.join("root_signature.hlsl"),
+        r#""RootFlags(CBV_SRV_UAV_HEAP_DIRECTLY_INDEXED), " \
+           "RootConstants(num32BitConstants = 4, b0, visibility = SHADER_VISIBILITY_ALL), " \
+           "CBV(b1, space = 1), " \
+           "DescriptorTable(SRV(t0, numDescriptors = unbounded), UAV(u0, flags = DATA_VOLATILE)), " \
+           "StaticSampler(s0, filter = FILTER_MIN_MAG_MIP_LINEAR, addressU = TEXTURE_ADDRESS_CLAMP)""#,
+    )
+    .unwrap();
+
+    let output = saxaboom(
+        &dir,
+        &[
+            "convert",
+            "shader.dxil",
+            "--entry-point=main",
+            "--root-signature=root_signature.hlsl",
+            "--reflection=shader.json",
+            "--provenance",
+            "--gpu-family=apple7",
+            "--deployment-target=macos:14.0.0",
+            "--validation=all",
+            "--compatibility=bounds-check",
+            "--compatibility=sample-nan-to-zero",
+            "--stage-in=separate-function",
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        std::fs::read(dir.join("shader.metallib")).unwrap(),
+        b"MTLB saxaboom stub metallib"
+    );
+    assert!(dir.join("shader.json").exists());
+    let provenance = std::fs::read_to_string(dir.join("shader.provenance.json")).unwrap();
+    assert!(
+        provenance.contains(r#"{"name": "set_minimum_gpu_family", "value": "Apple7"}"#),
+        "{provenance}"
+    );
+    assert!(dir.join("shader.provenance.bin").exists());
+}
+
+#[test]
+fn invalid_root_signature() {
+    let dir = work_dir("invalid_root_signature");
+    std::fs::write(dir.join("root_signature.hlsl"), "CBV(t0)").unwrap();
+    let output = saxaboom(
+        &dir,
+        &[
+            "convert",
+            "shader.dxil",
+            "--root-signature=root_signature.hlsl",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(4), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("expected a `b` register"), "{stderr}");
+
+    // Rejected by the converter library
+    std::fs::write(dir.join("root_signature.json"), "").unwrap();
+    let output = saxaboom(
+        &dir,
+        &[
+            "convert",
+            "shader.dxil",
+            "--root-signature=root_signature.json",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(4), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("`root_signature.json` is not a valid JSON root signature"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn compile_error() {
+    let dir = work_dir("compile_error");
+    let output = saxaboom(&dir, &["convert", "shader.dxil", "--entry-point=broken"]);
+    // CompilationError is 8
+    assert_eq!(output.status.code(), Some(18), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("`broken`") && stderr.contains("shader.dxil"),
+        "{stderr}"
+    );
+    assert!(!dir.join("shader.metallib").exists());
+}
+
+#[test]
+fn missing_library() {
+    let dir = work_dir("missing_library");
+    let output = Command::new(env!("CARGO_BIN_EXE_saxaboom"))
+        .current_dir(&dir)
+        .args([
+            "convert",
+            "shader.dxil",
+            "--library=/nonexistent/libmetalirconverter.so",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3), "{output:?}");
+}
+
+#[test]
+fn json_root_signature() {
+    let dir = work_dir("json_root_signature");
+    std::fs::write(dir.join("root_signature.json"), "{}").unwrap();
+    let output = saxaboom(
+        &dir,
+        &[
+            "convert",
+            "shader.dxil",
+            "--root-signature=root_signature.json",
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[test]
+fn diff() {
+    let dir = work_dir("diff");
+    std::fs::write(dir.join("root_signature.json"), "{}").unwrap();
+    let library = stub_library();
+    let old = format!("--old={}", library.display());
+    let new = format!("--new={}", library.display());
+
+    let output = saxaboom(
+        &dir,
+        &[
+            "diff",
+            &old,
+            &new,
+            "shader.dxil",
+            "--root-signature=root_signature.json",
+            "--gpu-family=apple7",
+        ],
+    );
+    // The placeholder `metallib` of the stub cannot be parsed, so both libraries fail the same
+    // way, which is not reported as identical
+    assert_eq!(output.status.code(), Some(6), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "`shader.dxil` `main`:
+  failed with both converters:
+    Invalid metallib: unexpected end of data at offset 24
+0 of 1 shaders differ, 1 failed with both converters
+"
+    );
+
+    let output = saxaboom(&dir, &["diff", &old, &new, "shader.dxil", "-e", "broken"]);
+    assert_eq!(output.status.code(), Some(6), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("`broken`"), "{stdout}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 failed with both"), "{stderr}");
+
+    let output = saxaboom(
+        &dir,
+        &[
+            "diff",
+            &old,
+            "--new=/nonexistent/libmetalirconverter.so",
+            "shader.dxil",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(3), "{output:?}");
+}
+
+fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// DXBC container with a shader model 6.0 compute program, an input signature and pipeline state
+/// validation data declaring a single CBV.
+fn compute_container() -> Vec<u8> {
+    let mut program = u32s(&[5 << 16 | 6 << 4, 10]);
+    program.extend(b"DXIL");
+    program.extend(u32s(&[0x100, 16, 4]));
+    program.extend(b"BC\xc0\xde");
+
+    let mut signature = u32s(&[1, 8]);
+    signature.extend(u32s(&[0, 40, 0, 0, 1, 0, 0x07, 0]));
+    signature.extend(b"SV_DispatchThreadID\0");
+
+    // `PSVRuntimeInfo2`, without an entry point name
+    let mut psv = u32s(&[48]);
+    psv.extend([0; 24]);
+    psv.extend([5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    psv.extend(u32s(&[8, 8, 1]));
+    psv.extend(u32s(&[1, 16, 2, 2, 3, 3]));
+
+    let parts: [(&[u8; 4], Vec<u8>); 3] =
+        [(b"DXIL", program), (b"ISG1", signature), (b"PSV0", psv)];
+    let mut offset = 32 + 4 * parts.len();
+    let mut header = b"DXBC".to_vec();
+    header.extend([0; 16]);
+    header.extend([1, 0, 0, 0]);
+    let mut data = Vec::new();
+    let mut offsets = Vec::new();
+    for (fourcc, part) in parts {
+        offsets.push(offset as u32);
+        data.extend(fourcc);
+        data.extend(u32s(&[part.len() as u32]));
+        offset += 8 + part.len();
+        data.extend(part);
+    }
+    header.extend(u32s(&[offset as u32, offsets.len() as u32]));
+    header.extend(u32s(&offsets));
+    header.extend(data);
+    header
+}
+
+#[test]
+fn inspect_dxil() {
+    let dir = work_dir("inspect_dxil");
+    std::fs::write(dir.join("compute.dxil"), compute_container()).unwrap();
+
+    let output = saxaboom(&dir, &["inspect", "compute.dxil"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "stage: Compute",
+        "shader_model: 6.0",
+        "- fourcc: PSV0",
+        "semantic: SV_DispatchThreadID0",
+        "system_value: none",
+        "mask: xyz",
+        "num_threads: [8, 8, 1]",
+        "- type: CBV",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "missing `{expected}` in:\n{stdout}"
+        );
+    }
+
+    let output = saxaboom(&dir, &["inspect", "compute.dxil", "--format=json"]);
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["program"]["stage"], "Compute");
+    assert_eq!(report["signatures"]["input"][0]["register"], 0);
+    assert_eq!(report["resource_bindings"][0]["space"], 2);
+    assert_eq!(report["resource_bindings"][0]["lower_bound"], 3);
+}
+
+#[test]
+fn inspect_metallib() {
+    let dir = work_dir("inspect_metallib");
+    let output = saxaboom(
+        &dir,
+        &["convert", "shader.dxil", "--reflection=shader.json"],
+    );
+    assert!(output.status.success(), "{output:?}");
+
+    // Picks up `shader.json` next to the `metallib`
+    let output = saxaboom(&dir, &["inspect", "shader.metallib", "--format=json"]);
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["metallib"]["size"], 27);
+    let reflection = &report["reflection"];
+    assert_eq!(
+        reflection["compute"]["threadgroup_size"],
+        serde_json::json!([8, 8, 1])
+    );
+    assert_eq!(reflection["resources"][0]["name"], "constants");
+    assert_eq!(reflection["resources"][0]["top_level_offset"], 0);
+    assert_eq!(reflection["needs_function_constants"], false);
+
+    let output = saxaboom(&dir, &["inspect", "shader.metallib"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("threadgroup_size: [8, 8, 1]"), "{stdout}");
+}
+
+#[test]
+fn inspect_unrecognized() {
+    let dir = work_dir("inspect_unrecognized");
+    std::fs::write(dir.join("shader.txt"), "hello").unwrap();
+    let output = saxaboom(&dir, &["inspect", "shader.txt"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+}
+
+const MANIFEST: &str = r#"
+output_dir = "out"
+
+[defaults]
+gpu_family = "apple7"
+deployment_target = "macos:14.0.0"
+validation = ["all"]
+
+[[shader]]
+input = "shader.dxil"
+stage = "compute"
+
+[[shader]]
+input = "shader.dxil"
+name = "vertex"
+input_layout = [
+    { semantic = "POSITION", format = "R32G32B32_FLOAT" },
+    { semantic = "TEXCOORD", format = "R16G16_FLOAT", offset = 12 },
+]
+
+[[shader]]
+input = "shader.dxil"
+name = "broken"
+entry_point = "broken"
+
+[[shader]]
+input = "shader.dxil"
+name = "not_a_vertex_shader"
+stage = "vertex"
+"#;
+
+#[test]
+fn build() {
+    let dir = work_dir("build");
+    std::fs::write(dir.join("saxaboom.toml"), MANIFEST).unwrap();
+
+    let output = saxaboom(&dir, &["build", "--report=report.json", "-j2"]);
+    // The failures do not stop the other shaders
+    assert_eq!(output.status.code(), Some(5), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("2 built, 0 up to date, 2 failed"),
+        "{stderr}"
+    );
+    let out = dir.join("out");
+    assert_eq!(
+        std::fs::read(out.join("shader.metallib")).unwrap(),
+        b"MTLB saxaboom stub metallib"
+    );
+    assert!(out.join("shader.json").exists());
+    assert_eq!(
+        std::fs::read(out.join("vertex.stage_in.metallib")).unwrap(),
+        b"MTLB saxaboom stub synthesized metallib"
+    );
+    assert!(!out.join("broken.metallib").exists());
+    assert!(!out.join("not_a_vertex_shader.metallib").exists());
+
+    let report: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("report.json")).unwrap()).unwrap();
+    assert_eq!(report["built"], 2);
+    assert_eq!(report["failed"], 2);
+    let shaders = report["shaders"].as_array().unwrap();
+    assert_eq!(shaders[0]["status"], "built");
+    assert_eq!(shaders[2]["name"], "broken");
+    assert_eq!(shaders[2]["status"], "failed");
+    assert!(shaders[2]["diagnostics"]
+        .as_str()
+        .unwrap()
+        .contains("failed as requested"));
+    assert!(shaders[3]["error"].as_str().unwrap().contains("Vertex"));
+
+    // Only the failed shaders are converted again
+    let output = saxaboom(&dir, &["build"]);
+    assert_eq!(output.status.code(), Some(5), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("0 built, 2 up to date, 2 failed"),
+        "{stderr}"
+    );
+
+    // Changing the input rebuilds every shader that uses it
+    std::fs::write(dir.join("shader.dxil"), b"DXBC changed").unwrap();
+    let output = saxaboom(&dir, &["build"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("2 built, 0 up to date, 2 failed"),
+        "{stderr}"
+    );
+
+    // And changing a setting rebuilds only the shaders it applies to
+    let manifest = MANIFEST.replacen(
+        "{ semantic = \"TEXCOORD\", format = \"R16G16_FLOAT\", offset = 12 }",
+        "{ semantic = \"TEXCOORD\", format = \"R16G16_FLOAT\", offset = 16 }",
+        1,
+    );
+    assert_ne!(manifest, MANIFEST);
+    std::fs::write(dir.join("saxaboom.toml"), manifest).unwrap();
+    let output = saxaboom(&dir, &["build"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("1 built, 1 up to date, 2 failed"),
+        "{stderr}"
+    );
+
+    // So do missing outputs and `--force`
+    std::fs::remove_file(out.join("shader.json")).unwrap();
+    let output = saxaboom(&dir, &["build"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("1 built, 1 up to date, 2 failed"),
+        "{stderr}"
+    );
+    let output = saxaboom(&dir, &["build", "--force"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("2 built, 0 up to date, 2 failed"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn build_invalid_manifest() {
+    let dir = work_dir("build_invalid_manifest");
+    std::fs::write(
+        dir.join("saxaboom.toml"),
+        "[[shader]]\ninput = \"shader.dxil\"\nentrypoint = \"main\"\n",
+    )
+    .unwrap();
+    let output = saxaboom(&dir, &["build"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("entrypoint"), "{stderr}");
+
+    std::fs::write(
+        dir.join("saxaboom.toml"),
+        "[[shader]]\ninput = \"shader.dxil\"\n[[shader]]\ninput = \"other/shader.dxil\"\n",
+    )
+    .unwrap();
+    let output = saxaboom(&dir, &["build"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already used"), "{stderr}");
+
+    for name in ["../../escaped", "sub/dir", "sub\\\\dir", "..", ""] {
+        std::fs::write(
+            dir.join("saxaboom.toml"),
+            format!("[[shader]]\ninput = \"shader.dxil\"\nname = \"{name}\"\n"),
+        )
+        .unwrap();
+        let output = saxaboom(&dir, &["build"]);
+        assert_eq!(output.status.code(), Some(1), "{output:?}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("not a valid file name"), "{stderr}");
+    }
+    assert!(!dir.join("../../escaped.metallib").exists());
+}
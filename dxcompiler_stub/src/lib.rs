@@ -0,0 +1,437 @@
+//! Stand-in for `libdxcompiler`, exporting `DxcCreateInstance()` with just enough of
+//! `IDxcCompiler3` and `IDxcUtils` for `saxaboom::dxc::Dxc` to load it on any platform.
+//!
+//! Instead of compiling, the stub preprocesses the source: `#include "file"` lines are replaced by
+//! the source returned from the include handler, and an `#error message` line fails compilation
+//! with a DXC-style diagnostic.  The resulting "DXIL" is the `DXBC` magic followed by the
+//! arguments and the preprocessed source as text, so that tests can verify what was passed.
+//!
+//! The vtables follow the layout of the real library, including the two destructor entries that
+//! the virtual destructor of DXC's `IUnknown` adds outside of Windows.  `Release()` destroys
+//! objects through them, like `delete this` does in DXC.
+#![expect(clippy::missing_safety_doc)]
+
+use std::{
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+};
+
+#[repr(C)]
+pub struct Guid(u32, u16, u16, [u8; 8]);
+
+const CLSID_DXC_COMPILER: Guid = Guid(
+    0x73e22d93,
+    0xe6ce,
+    0x47f3,
+    [0xb5, 0xbf, 0xf0, 0x66, 0x4f, 0x39, 0xc1, 0xb0],
+);
+const CLSID_DXC_UTILS: Guid = Guid(
+    0x6245d6af,
+    0x66e0,
+    0x48fd,
+    [0x80, 0xb4, 0x4d, 0x27, 0x17, 0x96, 0x74, 0x8c],
+);
+
+const S_OK: i32 = 0;
+const E_NOTIMPL: i32 = 0x80004001_u32 as i32;
+const E_FAIL: i32 = 0x80004005_u32 as i32;
+const CLASS_E_CLASSNOTAVAILABLE: i32 = 0x80040111_u32 as i32;
+const DXC_CP_UTF8: u32 = 65001;
+
+#[cfg(windows)]
+type WChar = u16;
+#[cfg(not(windows))]
+type WChar = u32;
+
+/// Number of objects handed out and not yet released, see [`saxaboom_dxc_stub_live_objects()`].
+static LIVE_OBJECTS: AtomicUsize = AtomicUsize::new(0);
+
+#[repr(C)]
+struct IUnknownVtbl {
+    query_interface: unsafe extern "system" fn(*mut c_void, *const Guid, *mut *mut c_void) -> i32,
+    add_ref: unsafe extern "system" fn(*mut c_void) -> u32,
+    release: unsafe extern "system" fn(*mut c_void) -> u32,
+    /// `~IUnknown()`, destroys the object without freeing it.
+    #[cfg(not(windows))]
+    complete_destructor: unsafe extern "C" fn(*mut c_void),
+    /// `~IUnknown()` followed by `operator delete`.
+    #[cfg(not(windows))]
+    deleting_destructor: unsafe extern "C" fn(*mut c_void),
+}
+
+/// Placeholder for vtable entries that `saxaboom` never calls.
+type Unimplemented = unsafe extern "system" fn() -> i32;
+
+unsafe extern "system" fn unimplemented() -> i32 {
+    E_NOTIMPL
+}
+
+/// Reference counted COM object, starting with the pointer to its vtable `V`.
+#[repr(C)]
+struct Object<V: 'static, T> {
+    vtbl: &'static V,
+    references: AtomicU32,
+    data: T,
+}
+
+impl<V, T> Object<V, T> {
+    fn create(vtbl: &'static V, data: T) -> *mut c_void {
+        LIVE_OBJECTS.fetch_add(1, Ordering::SeqCst);
+        Box::into_raw(Box::new(Self {
+            vtbl,
+            references: AtomicU32::new(1),
+            data,
+        }))
+        .cast()
+    }
+
+    unsafe fn data<'a>(this: *mut c_void) -> &'a T {
+        unsafe { &(*this.cast::<Self>()).data }
+    }
+
+    const UNKNOWN: IUnknownVtbl = IUnknownVtbl {
+        query_interface: Self::query_interface,
+        add_ref: Self::add_ref,
+        release: Self::release,
+        #[cfg(not(windows))]
+        complete_destructor: Self::complete_destructor,
+        #[cfg(not(windows))]
+        deleting_destructor: Self::deleting_destructor,
+    };
+
+    /// Every object pretends to implement any interface it is asked for.
+    unsafe extern "system" fn query_interface(
+        this: *mut c_void,
+        _iid: *const Guid,
+        object: *mut *mut c_void,
+    ) -> i32 {
+        unsafe {
+            Self::add_ref(this);
+            *object = this;
+        }
+        S_OK
+    }
+
+    unsafe extern "system" fn add_ref(this: *mut c_void) -> u32 {
+        let this = unsafe { &*this.cast::<Self>() };
+        this.references.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    unsafe extern "system" fn release(this: *mut c_void) -> u32 {
+        let references = unsafe { &*this.cast::<Self>() }
+            .references
+            .fetch_sub(1, Ordering::SeqCst)
+            - 1;
+        if references == 0 {
+            #[cfg(not(windows))]
+            unsafe {
+                let vtbl = &**this.cast::<*const IUnknownVtbl>();
+                (vtbl.deleting_destructor)(this);
+            }
+            #[cfg(windows)]
+            drop(unsafe { Box::from_raw(this.cast::<Self>()) });
+            LIVE_OBJECTS.fetch_sub(1, Ordering::SeqCst);
+        }
+        references
+    }
+
+    #[cfg(not(windows))]
+    unsafe extern "C" fn complete_destructor(this: *mut c_void) {
+        unsafe { ptr::drop_in_place(this.cast::<Self>()) }
+    }
+
+    #[cfg(not(windows))]
+    unsafe extern "C" fn deleting_destructor(this: *mut c_void) {
+        drop(unsafe { Box::from_raw(this.cast::<Self>()) });
+    }
+}
+
+#[repr(C)]
+struct BlobVtbl {
+    unknown: IUnknownVtbl,
+    get_buffer_pointer: unsafe extern "system" fn(*mut c_void) -> *mut c_void,
+    get_buffer_size: unsafe extern "system" fn(*mut c_void) -> usize,
+    get_encoding: unsafe extern "system" fn(*mut c_void, *mut i32, *mut u32) -> i32,
+}
+
+type Blob = Object<BlobVtbl, Vec<u8>>;
+
+static BLOB_VTBL: BlobVtbl = BlobVtbl {
+    unknown: Blob::UNKNOWN,
+    get_buffer_pointer: blob_get_buffer_pointer,
+    get_buffer_size: blob_get_buffer_size,
+    get_encoding: blob_get_encoding,
+};
+
+unsafe extern "system" fn blob_get_buffer_pointer(this: *mut c_void) -> *mut c_void {
+    unsafe { Blob::data(this) }.as_ptr().cast_mut().cast()
+}
+
+unsafe extern "system" fn blob_get_buffer_size(this: *mut c_void) -> usize {
+    unsafe { Blob::data(this) }.len()
+}
+
+unsafe extern "system" fn blob_get_encoding(
+    _this: *mut c_void,
+    known: *mut i32,
+    code_page: *mut u32,
+) -> i32 {
+    unsafe {
+        *known = 1;
+        *code_page = DXC_CP_UTF8;
+    }
+    S_OK
+}
+
+/// Reads any `IDxcBlob`, including those created by another library.
+unsafe fn blob_bytes(blob: *mut c_void) -> Vec<u8> {
+    unsafe {
+        let vtbl = &**blob.cast::<*const BlobVtbl>();
+        let ptr = (vtbl.get_buffer_pointer)(blob);
+        let size = (vtbl.get_buffer_size)(blob);
+        std::slice::from_raw_parts(ptr.cast::<u8>(), size).to_vec()
+    }
+}
+
+unsafe fn release(object: *mut c_void) {
+    unsafe {
+        let vtbl = &**object.cast::<*const IUnknownVtbl>();
+        (vtbl.release)(object);
+    }
+}
+
+struct CompileResult {
+    status: i32,
+    dxil: Vec<u8>,
+    diagnostics: Vec<u8>,
+}
+
+#[repr(C)]
+struct ResultVtbl {
+    unknown: IUnknownVtbl,
+    get_status: unsafe extern "system" fn(*mut c_void, *mut i32) -> i32,
+    get_result: unsafe extern "system" fn(*mut c_void, *mut *mut c_void) -> i32,
+    get_error_buffer: unsafe extern "system" fn(*mut c_void, *mut *mut c_void) -> i32,
+}
+
+type ResultObject = Object<ResultVtbl, CompileResult>;
+
+static RESULT_VTBL: ResultVtbl = ResultVtbl {
+    unknown: ResultObject::UNKNOWN,
+    get_status: result_get_status,
+    get_result: result_get_result,
+    get_error_buffer: result_get_error_buffer,
+};
+
+unsafe extern "system" fn result_get_status(this: *mut c_void, status: *mut i32) -> i32 {
+    unsafe { *status = ResultObject::data(this).status };
+    S_OK
+}
+
+unsafe extern "system" fn result_get_result(this: *mut c_void, blob: *mut *mut c_void) -> i32 {
+    let result = unsafe { ResultObject::data(this) };
+    unsafe { *blob = Blob::create(&BLOB_VTBL, result.dxil.clone()) };
+    S_OK
+}
+
+unsafe extern "system" fn result_get_error_buffer(
+    this: *mut c_void,
+    blob: *mut *mut c_void,
+) -> i32 {
+    let result = unsafe { ResultObject::data(this) };
+    unsafe { *blob = Blob::create(&BLOB_VTBL, result.diagnostics.clone()) };
+    S_OK
+}
+
+#[repr(C)]
+struct UtilsVtbl {
+    unknown: IUnknownVtbl,
+    create_blob_from_blob: Unimplemented,
+    create_blob_from_pinned: Unimplemented,
+    move_to_blob: Unimplemented,
+    create_blob:
+        unsafe extern "system" fn(*mut c_void, *const c_void, u32, u32, *mut *mut c_void) -> i32,
+}
+
+type Utils = Object<UtilsVtbl, ()>;
+
+static UTILS_VTBL: UtilsVtbl = UtilsVtbl {
+    unknown: Utils::UNKNOWN,
+    create_blob_from_blob: unimplemented,
+    create_blob_from_pinned: unimplemented,
+    move_to_blob: unimplemented,
+    create_blob: utils_create_blob,
+};
+
+unsafe extern "system" fn utils_create_blob(
+    _this: *mut c_void,
+    data: *const c_void,
+    size: u32,
+    _code_page: u32,
+    blob: *mut *mut c_void,
+) -> i32 {
+    let data = unsafe { std::slice::from_raw_parts(data.cast::<u8>(), size as usize) };
+    unsafe { *blob = Blob::create(&BLOB_VTBL, data.to_vec()) };
+    S_OK
+}
+
+#[repr(C)]
+struct DxcBuffer {
+    ptr: *const c_void,
+    size: usize,
+    encoding: u32,
+}
+
+#[repr(C)]
+struct CompilerVtbl {
+    unknown: IUnknownVtbl,
+    compile: unsafe extern "system" fn(
+        *mut c_void,
+        *const DxcBuffer,
+        *const *const WChar,
+        u32,
+        *mut c_void,
+        *const Guid,
+        *mut *mut c_void,
+    ) -> i32,
+    disassemble: Unimplemented,
+}
+
+type Compiler = Object<CompilerVtbl, ()>;
+
+static COMPILER_VTBL: CompilerVtbl = CompilerVtbl {
+    unknown: Compiler::UNKNOWN,
+    compile: compiler_compile,
+    disassemble: unimplemented,
+};
+
+#[repr(C)]
+struct IncludeHandlerVtbl {
+    unknown: IUnknownVtbl,
+    load_source: unsafe extern "system" fn(*mut c_void, *const WChar, *mut *mut c_void) -> i32,
+}
+
+unsafe fn from_wide(s: *const WChar) -> String {
+    let mut len = 0;
+    while unsafe { *s.add(len) } != 0 {
+        len += 1;
+    }
+    let wide = unsafe { std::slice::from_raw_parts(s, len) };
+    #[cfg(windows)]
+    return String::from_utf16_lossy(wide);
+    #[cfg(not(windows))]
+    wide.iter()
+        .map(|&c| char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn to_wide(s: &str) -> Vec<WChar> {
+    #[cfg(windows)]
+    let wide = s.encode_utf16();
+    #[cfg(not(windows))]
+    let wide = s.chars().map(u32::from);
+    wide.chain([0]).collect()
+}
+
+/// Inlines `#include`s through `include_handler`, and fails on `#error`.
+fn preprocess(source: &str, name: &str, include_handler: *mut c_void) -> Result<String, String> {
+    let mut output = String::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let line_number = line_number + 1;
+        let directive = line.trim_start();
+        if let Some(message) = directive.strip_prefix("#error") {
+            return Err(format!("{name}:{line_number}:2: error: {}", message.trim()));
+        } else if let Some(file) = directive.strip_prefix("#include") {
+            let file = file.trim().trim_matches(['"', '<', '>']);
+            let mut blob = ptr::null_mut();
+            let hresult = if include_handler.is_null() {
+                E_FAIL
+            } else {
+                unsafe {
+                    let vtbl = &**include_handler.cast::<*const IncludeHandlerVtbl>();
+                    (vtbl.load_source)(include_handler, to_wide(file).as_ptr(), &mut blob)
+                }
+            };
+            if hresult < 0 || blob.is_null() {
+                return Err(format!(
+                    "{name}:{line_number}:10: fatal error: '{file}' file not found"
+                ));
+            }
+            let included = unsafe { blob_bytes(blob) };
+            unsafe { release(blob) };
+            output += &preprocess(&String::from_utf8_lossy(&included), file, include_handler)?;
+        } else {
+            output += line;
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+unsafe extern "system" fn compiler_compile(
+    _this: *mut c_void,
+    source: *const DxcBuffer,
+    arguments: *const *const WChar,
+    argument_count: u32,
+    include_handler: *mut c_void,
+    _iid: *const Guid,
+    result: *mut *mut c_void,
+) -> i32 {
+    let source = unsafe {
+        let source = &*source;
+        std::slice::from_raw_parts(source.ptr.cast::<u8>(), source.size)
+    };
+    let arguments = (0..argument_count as usize)
+        .map(|i| unsafe { from_wide(*arguments.add(i)) })
+        .collect::<Vec<_>>();
+    let name = arguments
+        .first()
+        .filter(|a| !a.starts_with('-'))
+        .map_or("hlsl.hlsl", |a| a.as_str());
+
+    let compiled = preprocess(&String::from_utf8_lossy(source), name, include_handler);
+    let compiled = match compiled {
+        Ok(source) => CompileResult {
+            status: S_OK,
+            dxil: format!("DXBC\n{}\n{source}", arguments.join(" ")).into_bytes(),
+            diagnostics: Vec::new(),
+        },
+        Err(diagnostic) => CompileResult {
+            status: E_FAIL,
+            dxil: Vec::new(),
+            diagnostics: format!("{diagnostic}\n").into_bytes(),
+        },
+    };
+    unsafe { *result = ResultObject::create(&RESULT_VTBL, compiled) };
+    S_OK
+}
+
+fn guid_eq(a: &Guid, b: &Guid) -> bool {
+    (a.0, a.1, a.2, a.3) == (b.0, b.1, b.2, b.3)
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn DxcCreateInstance(
+    clsid: *const Guid,
+    _iid: *const Guid,
+    object: *mut *mut c_void,
+) -> i32 {
+    let clsid = unsafe { &*clsid };
+    let created = if guid_eq(clsid, &CLSID_DXC_COMPILER) {
+        Compiler::create(&COMPILER_VTBL, ())
+    } else if guid_eq(clsid, &CLSID_DXC_UTILS) {
+        Utils::create(&UTILS_VTBL, ())
+    } else {
+        unsafe { *object = ptr::null_mut() };
+        return CLASS_E_CLASSNOTAVAILABLE;
+    };
+    unsafe { *object = created };
+    S_OK
+}
+
+/// Number of COM objects that were created and not released yet, to detect leaks.
+#[no_mangle]
+pub extern "C" fn saxaboom_dxc_stub_live_objects() -> usize {
+    LIVE_OBJECTS.load(Ordering::SeqCst)
+}
@@ -26,9 +26,8 @@ fn create_static_sampler(
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Load the library
-    let metal_irconverter =
-        MetalIrConverter::new(libloading::library_filename("metalirconverter")).unwrap();
+    // Load the library from `SAXABOOM_IRCONVERTER_PATH` or the standard install location
+    let metal_irconverter = MetalIrConverter::discover()?;
     // Create an instance of IRCompiler
     let mut compiler = metal_irconverter.create_compiler();
 
@@ -54,13 +53,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load DXIL
     let dxil = include_bytes!("assets/memcpy.cs.dxil");
-    let dxil = metal_irconverter.create_object_from_dxil(dxil);
+    let mut dxil = metal_irconverter.create_object_from_dxil(dxil);
+    // Name the input so that errors point at it
+    dxil.set_name("assets/memcpy.cs.dxil");
 
     // Convert to Metal
     let mtllib = compiler.alloc_compile_and_link(c"main", &dxil)?;
-    let mtl_binary = mtllib
-        .metal_lib_binary()
-        .expect("Compiled object should contain a `metallib`");
+    let mtl_binary = mtllib.metal_lib_binary()?;
 
     // Get Metal bytecode
     let metal_bytecode = mtl_binary.byte_code();
@@ -69,15 +68,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     dbg!(mtllib.metal_ir_shader_stage());
 
     // Get reflection from the shader
-    let mtl_reflection = mtllib.reflection();
+    let mtl_reflection = mtllib.reflection()?;
 
-    let compute_info = mtl_reflection.map(|mtl_reflection| unsafe {
+    let compute_info = unsafe {
         mtl_reflection
             .compute_info(ffi::IRReflectionVersion::_1_0)
             .unwrap()
             .u_1
             .info_1_0
-    });
+    };
     dbg!(compute_info);
 
     Ok(())
@@ -0,0 +1,171 @@
+//! Stand-in for Apple's `libmetalirconverter`, exporting the complete `metal_irconverter` C ABI
+//! so that [`saxaboom::MetalIrConverter::new()`] can load it on any platform.
+//!
+//! Every function in [`saxaboom::converter_backend_functions!`], including the JSON conversions
+//! of reflection, root signatures and input layouts, forwards to a process-wide
+//! [`saxaboom::testing::FakeConverter`], which compiles any DXIL into the same fake compute shader
+//! unless an error was scripted through the `saxaboom_stub_*` exports.  The remaining functions,
+//! which `saxaboom` does not wrap (such as `IRObjectSerialize()` and the
+//! `*AllocStringAndSerialize()` functions), are exported so that the library loads, but always
+//! fail.
+#![expect(clippy::missing_safety_doc)]
+
+use std::{
+    ffi::{c_char, CStr},
+    sync::LazyLock,
+};
+
+use saxaboom::{
+    ffi,
+    testing::{FakeConverter, FakeError, FakeResource, FakeShader},
+    ConverterBackend,
+};
+
+/// `metallib` bytes returned for every successful compilation.
+const STUB_METALLIB: &[u8] = b"MTLB saxaboom stub metallib";
+/// `metallib` bytes returned by every `IRMetalLibSynthesize*()` function.
+const STUB_SYNTHESIZED_METALLIB: &[u8] = b"MTLB saxaboom stub synthesized metallib";
+/// Thread group size reported in the compute reflection of every compiled shader.
+const STUB_THREADGROUP_SIZE: [u32; 3] = [8, 8, 1];
+/// Reflection JSON of every compiled shader, which parses back into the stub reflection.
+const STUB_REFLECTION_JSON: &str = r#"{"saxaboom_stub":true}"#;
+
+static CONVERTER: LazyLock<FakeConverter> = LazyLock::new(|| {
+    let converter = FakeConverter::default();
+    script_defaults(&converter);
+    converter
+});
+
+/// Entry point that fails to compile with [`ffi::IRErrorCode::CompilationError`], for tests that
+/// load the stub in a separate process (such as the `saxaboom` command line tool).
+const FAIL_ENTRY_POINT_VAR: &str = "SAXABOOM_STUB_FAIL_ENTRY_POINT";
+
+fn script_defaults(converter: &FakeConverter) {
+    let mut shader = FakeShader::new(ffi::IRShaderStage::Compute, STUB_METALLIB.to_vec());
+    shader.compute_info = Some(ffi::IRCSInfo_1_0 {
+        tg_size: STUB_THREADGROUP_SIZE,
+    });
+    // Matches the root signature below
+    let constants = FakeResource {
+        resource_type: ffi::IRResourceType::CBV,
+        space: 0,
+        slot: 0,
+        top_level_offset: 0,
+        size_bytes: 8,
+        name: Some("constants".to_owned()),
+    };
+    shader.resources = vec![constants.clone()];
+    shader.reflection_json = STUB_REFLECTION_JSON.to_owned();
+    converter
+        .on_any(Ok(shader))
+        .on_synthesize(STUB_SYNTHESIZED_METALLIB)
+        .on_root_signature(Ok(vec![constants]));
+    if let Ok(entry_point) = std::env::var(FAIL_ENTRY_POINT_VAR) {
+        let error = FakeError::new(
+            ffi::IRErrorCode::CompilationError,
+            format!("{entry_point} failed as requested by {FAIL_ENTRY_POINT_VAR}"),
+        );
+        converter.on_entry_point(entry_point, Err(error));
+    }
+}
+
+macro_rules! export_functions {
+    ($(fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        $(
+            #[no_mangle]
+            pub unsafe extern "C" fn $name($($arg: $ty),*) $(-> $ret)? {
+                unsafe { CONVERTER.$name($($arg),*) }
+            }
+        )*
+    };
+}
+
+saxaboom::converter_backend_functions!(export_functions);
+
+unsafe fn message(message: *const c_char) -> String {
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Make every subsequent compilation of `entry_point` fail with `code` and `message`.
+#[no_mangle]
+pub unsafe extern "C" fn saxaboom_stub_fail_entry_point(
+    entry_point: *const c_char,
+    code: ffi::IRErrorCode,
+    message: *const c_char,
+) {
+    let entry_point = unsafe { self::message(entry_point) };
+    let error = FakeError::new(code, unsafe { self::message(message) });
+    CONVERTER.on_entry_point(entry_point, Err(error));
+}
+
+/// Make every subsequent root signature creation fail with `code` and `message`.
+#[no_mangle]
+pub unsafe extern "C" fn saxaboom_stub_fail_root_signature(
+    code: ffi::IRErrorCode,
+    message: *const c_char,
+) {
+    let error = FakeError::new(code, unsafe { self::message(message) });
+    CONVERTER.on_root_signature(Err(error));
+}
+
+/// Undo [`saxaboom_stub_fail_root_signature()`].  Failures scripted for specific entry points are
+/// kept.
+#[no_mangle]
+pub extern "C" fn saxaboom_stub_reset() {
+    script_defaults(&CONVERTER);
+}
+
+/// Number of objects handed out by this library that were not destroyed yet.
+#[no_mangle]
+pub extern "C" fn saxaboom_stub_live_objects() -> usize {
+    CONVERTER.live_handles()
+}
+
+// The rest of the C ABI, which is not wrapped by `saxaboom` and hence not part of
+// `ConverterBackend`.
+
+#[no_mangle]
+pub unsafe extern "C" fn IRObjectSerialize(
+    _output_path: *const c_char,
+    _obj: *const ffi::IRObject,
+    _stage: ffi::IRShaderStage,
+) -> bool {
+    false
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn IRShaderReflectionAllocStringAndSerialize(
+    _reflection: *mut ffi::IRShaderReflection,
+) -> *const c_char {
+    std::ptr::null()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn IRShaderReflectionFreeString(_serialized: *const c_char) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn IRShaderReflectionDeserialize(
+    _blob: *const c_char,
+    _reflection: *mut ffi::IRShaderReflection,
+) {
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn IRVersionedRootSignatureDescriptorAllocStringAndSerialize(
+    _root_signature_descriptor: *mut ffi::IRVersionedRootSignatureDescriptor,
+) -> *const c_char {
+    std::ptr::null()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn IRVersionedRootSignatureDescriptorFreeString(_serialized: *const c_char) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn IRVersionedRootSignatureDescriptorDeserialize(
+    _serialized: *const c_char,
+    _root_signature_descriptor: *mut ffi::IRVersionedRootSignatureDescriptor,
+) -> bool {
+    false
+}
@@ -0,0 +1,139 @@
+//! Reading shader asset bundles written by `saxaboom::bundle::BundleWriter`, without the
+//! converter library.
+//!
+//! [`Bundle`] only parses the index and borrows everything else from the bytes it was parsed
+//! from, so a memory-mapped file is read without copying any `metallib`:
+//!
+//! ```ignore
+//! let file = std::fs::File::open("shaders.bundle")?;
+//! let mmap = unsafe { memmap2::Mmap::map(&file)? };
+//! let bundle = saxaboom_runtime::bundle::Bundle::parse(&mmap)?;
+//! let shader = bundle.get("material", "ALPHA_TEST=0 LIGHTING=pbr").unwrap();
+//! // Hand `shader.metallib` to `MTLDevice::newLibraryWithData_error()`
+//! let reflection = shader.reflection()?;
+//! let [x, y, z] = reflection.threadgroup_size.unwrap();
+//! ```
+use crate::{
+    format::{FormatError, Reader},
+    reflection::ReflectionSnapshot,
+};
+
+pub const MAGIC: [u8; 4] = *b"SXBN";
+/// Newest format version that [`Bundle::parse()`] reads.
+pub const VERSION: u32 = 1;
+const NO_ROOT_SIGNATURE: u32 = u32::MAX;
+
+/// Serialized root signature in a [`Bundle`], as it was passed to `saxaboom`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RootSignature<'a> {
+    pub name: &'a str,
+    pub serialized: &'a [u8],
+}
+
+/// Shader in a [`Bundle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shader<'a> {
+    pub name: &'a str,
+    /// Such as `ALPHA_TEST=0 LIGHTING=pbr`, or empty for shaders without permutations.
+    pub permutation: &'a str,
+    pub metallib: &'a [u8],
+    /// Encoded reflection, see [`Self::reflection()`].
+    pub reflection_bytes: &'a [u8],
+    pub root_signature: Option<RootSignature<'a>>,
+}
+
+impl Shader<'_> {
+    pub fn reflection(&self) -> Result<ReflectionSnapshot, FormatError> {
+        ReflectionSnapshot::from_bytes(self.reflection_bytes)
+    }
+}
+
+/// A parsed bundle, borrowing the bytes it was parsed from.
+#[derive(Clone, Debug)]
+pub struct Bundle<'a> {
+    /// Format version the bundle was written with.
+    pub version: u32,
+    pub root_signatures: Vec<RootSignature<'a>>,
+    /// Sorted by name and then permutation.
+    pub shaders: Vec<Shader<'a>>,
+}
+
+impl<'a> Bundle<'a> {
+    /// Parses the index, checking that every string and data item lies within `bytes`.  Fails
+    /// with [`FormatError::UnsupportedVersion`] for bundles written by a newer `saxaboom`.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        let mut r = Reader::new("bundle", bytes);
+        let version = r.header(MAGIC, VERSION)?;
+        let root_signature_count = r.u32()?;
+        let shader_count = r.u32()?;
+
+        let string = |r: &mut Reader<'a>| {
+            let data = r.range(bytes)?;
+            std::str::from_utf8(data).map_err(|e| r.invalid(format!("invalid string: {e}")))
+        };
+        let root_signatures = (0..root_signature_count)
+            .map(|_| {
+                Ok(RootSignature {
+                    name: string(&mut r)?,
+                    serialized: r.range(bytes)?,
+                })
+            })
+            .collect::<Result<Vec<_>, FormatError>>()?;
+        let shaders = (0..shader_count)
+            .map(|_| {
+                let name = string(&mut r)?;
+                let permutation = string(&mut r)?;
+                let metallib = r.range(bytes)?;
+                let reflection_bytes = r.range(bytes)?;
+                let root_signature = match r.u32()? {
+                    NO_ROOT_SIGNATURE => None,
+                    index => Some(*root_signatures.get(index as usize).ok_or_else(|| {
+                        r.invalid(format!("shader `{name}` refers to root signature {index}"))
+                    })?),
+                };
+                // Padding
+                r.u32()?;
+                Ok(Shader {
+                    name,
+                    permutation,
+                    metallib,
+                    reflection_bytes,
+                    root_signature,
+                })
+            })
+            .collect::<Result<Vec<_>, FormatError>>()?;
+
+        if shaders
+            .windows(2)
+            .any(|w| (w[0].name, w[0].permutation) >= (w[1].name, w[1].permutation))
+        {
+            return Err(r.invalid("shaders are not sorted by name and permutation"));
+        }
+
+        Ok(Self {
+            version,
+            root_signatures,
+            shaders,
+        })
+    }
+
+    /// The shader named `name` with `permutation`, which is empty for shaders without
+    /// permutations.
+    pub fn get(&self, name: &str, permutation: &str) -> Option<&Shader<'a>> {
+        self.shaders
+            .binary_search_by(|s| (s.name, s.permutation).cmp(&(name, permutation)))
+            .ok()
+            .map(|index| &self.shaders[index])
+    }
+
+    /// Every permutation of the shader named `name`.
+    pub fn permutations(&self, name: &str) -> &[Shader<'a>] {
+        let start = self.shaders.partition_point(|s| s.name < name);
+        let end = self.shaders.partition_point(|s| s.name <= name);
+        &self.shaders[start..end]
+    }
+
+    pub fn root_signature(&self, name: &str) -> Option<&RootSignature<'a>> {
+        self.root_signatures.iter().find(|r| r.name == name)
+    }
+}
@@ -0,0 +1,487 @@
+//! Decoding of the binary formats written by `saxaboom`, see [`crate::bundle`],
+//! [`crate::provenance`] and [`crate::reflection`].
+use std::fmt;
+
+/// Error reading a file written by `saxaboom`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// The data is truncated or malformed.
+    Invalid {
+        /// Such as `bundle`.
+        format: &'static str,
+        reason: String,
+    },
+    /// The data was written by a newer version of `saxaboom` than this reader supports.
+    UnsupportedVersion {
+        format: &'static str,
+        version: u32,
+        supported: u32,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { format, reason } => write!(f, "Invalid {format}: {reason}"),
+            Self::UnsupportedVersion {
+                format,
+                version,
+                supported,
+            } => write!(
+                f,
+                "Unsupported {format} version {version}: this version of saxaboom-runtime only reads up to version {supported}, update it to read files written by newer tools"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Reads little-endian values from the front of a slice, failing with
+/// [`FormatError::Invalid`] instead of reading out of bounds.
+pub(crate) struct Reader<'a> {
+    pub(crate) format: &'static str,
+    data: &'a [u8],
+    pub(crate) offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(format: &'static str, data: &'a [u8]) -> Self {
+        Self {
+            format,
+            data,
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn invalid(&self, reason: impl Into<String>) -> FormatError {
+        FormatError::Invalid {
+            format: self.format,
+            reason: reason.into(),
+        }
+    }
+
+    /// Checks the magic and version at the start of a file.
+    pub(crate) fn header(&mut self, magic: [u8; 4], supported: u32) -> Result<u32, FormatError> {
+        if self.array()? != magic {
+            return Err(self.invalid(format!(
+                "missing `{}` header",
+                String::from_utf8_lossy(&magic)
+            )));
+        }
+        let version = self.u32()?;
+        if version > supported {
+            return Err(FormatError::UnsupportedVersion {
+                format: self.format,
+                version,
+                supported,
+            });
+        }
+        Ok(version)
+    }
+
+    /// Fails when there are bytes left.
+    pub(crate) fn finish(&self) -> Result<(), FormatError> {
+        match self.data.len() - self.offset {
+            0 => Ok(()),
+            left => Err(self.invalid(format!("{left} unexpected bytes at the end"))),
+        }
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], FormatError> {
+        let bytes = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.offset..end))
+            .ok_or_else(|| {
+                self.invalid(format!("unexpected end of data at offset {}", self.offset))
+            })?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], FormatError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, FormatError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, FormatError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(self.invalid(format!("invalid boolean {value}"))),
+        }
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, FormatError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, FormatError> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, FormatError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    /// Number of items, which is checked against the remaining bytes so that corrupt lengths do
+    /// not cause huge allocations.
+    pub(crate) fn len(&mut self) -> Result<usize, FormatError> {
+        let len = self.u32()? as usize;
+        if len > self.data.len() - self.offset {
+            return Err(self.invalid(format!("{len} items do not fit in the remaining data")));
+        }
+        Ok(len)
+    }
+
+    pub(crate) fn str(&mut self) -> Result<&'a str, FormatError> {
+        let len = self.u32()? as usize;
+        let bytes = self.bytes(len)?;
+        std::str::from_utf8(bytes).map_err(|e| self.invalid(format!("invalid string: {e}")))
+    }
+
+    pub(crate) fn opt_str(&mut self) -> Result<Option<&'a str>, FormatError> {
+        Ok(if self.bool()? {
+            Some(self.str()?)
+        } else {
+            None
+        })
+    }
+
+    /// `u64` offset and length of a range in `file`.
+    pub(crate) fn range(&mut self, file: &'a [u8]) -> Result<&'a [u8], FormatError> {
+        let offset = self.u64()?;
+        let len = self.u64()?;
+        offset
+            .checked_add(len)
+            .and_then(|end| file.get(usize::try_from(offset).ok()?..usize::try_from(end).ok()?))
+            .ok_or_else(|| self.invalid(format!("{len} bytes at offset {offset} out of bounds")))
+    }
+}
+
+/// Parsed JSON value, for the JSON variants of the formats.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    /// Kept as text so that every integer type can be parsed from it without loss.
+    Number(String),
+    String(String),
+    Array(Vec<Self>),
+    Object(Vec<(String, Self)>),
+}
+
+impl Json {
+    pub(crate) fn parse(format: &'static str, text: &str) -> Result<Self, FormatError> {
+        let mut parser = JsonParser {
+            format,
+            text: text.as_bytes(),
+            offset: 0,
+        };
+        let value = parser.value(0)?;
+        parser.whitespace();
+        if parser.offset != parser.text.len() {
+            return Err(parser.invalid("unexpected characters after the JSON value"));
+        }
+        Ok(value)
+    }
+}
+
+/// Nesting deeper than this is rejected instead of overflowing the stack.
+const MAX_JSON_DEPTH: usize = 32;
+
+struct JsonParser<'a> {
+    format: &'static str,
+    text: &'a [u8],
+    offset: usize,
+}
+
+impl JsonParser<'_> {
+    fn invalid(&self, reason: &str) -> FormatError {
+        FormatError::Invalid {
+            format: self.format,
+            reason: format!("{reason} at offset {} of the JSON", self.offset),
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while self
+            .text
+            .get(self.offset)
+            .is_some_and(|c| c.is_ascii_whitespace())
+        {
+            self.offset += 1;
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let matches = self.text[self.offset..].starts_with(token.as_bytes());
+        if matches {
+            self.offset += token.len();
+        }
+        matches
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, FormatError> {
+        if depth > MAX_JSON_DEPTH {
+            return Err(self.invalid("JSON nested too deeply"));
+        }
+        self.whitespace();
+        match self.text.get(self.offset) {
+            Some(b'{') => {
+                self.offset += 1;
+                let mut fields = Vec::new();
+                self.whitespace();
+                if self.eat("}") {
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.whitespace();
+                    if !self.eat(":") {
+                        return Err(self.invalid("expected `:`"));
+                    }
+                    fields.push((key, self.value(depth + 1)?));
+                    self.whitespace();
+                    if self.eat("}") {
+                        return Ok(Json::Object(fields));
+                    }
+                    if !self.eat(",") {
+                        return Err(self.invalid("expected `,` or `}`"));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.offset += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.eat("]") {
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.whitespace();
+                    if self.eat("]") {
+                        return Ok(Json::Array(items));
+                    }
+                    if !self.eat(",") {
+                        return Err(self.invalid("expected `,` or `]`"));
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.offset;
+                while self
+                    .text
+                    .get(self.offset)
+                    .is_some_and(|c| matches!(c, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                {
+                    self.offset += 1;
+                }
+                let number = String::from_utf8_lossy(&self.text[start..self.offset]);
+                Ok(Json::Number(number.into_owned()))
+            }
+            _ if self.eat("null") => Ok(Json::Null),
+            _ if self.eat("true") => Ok(Json::Bool(true)),
+            _ if self.eat("false") => Ok(Json::Bool(false)),
+            _ => Err(self.invalid("expected a JSON value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, FormatError> {
+        if !self.eat("\"") {
+            return Err(self.invalid("expected a string"));
+        }
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&c) = self.text.get(self.offset) else {
+                return Err(self.invalid("unterminated string"));
+            };
+            self.offset += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.text.get(self.offset) else {
+                        return Err(self.invalid("unterminated string"));
+                    };
+                    self.offset += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let c = self.unicode_escape()?;
+                            // Surrogate pairs
+                            if (0xd800..0xdc00).contains(&c) && self.eat("\\u") {
+                                let low = self.unicode_escape()?;
+                                char::from_u32(
+                                    0x10000
+                                        + ((c - 0xd800) << 10)
+                                        + (low.wrapping_sub(0xdc00) & 0x3ff),
+                                )
+                            } else {
+                                char::from_u32(c)
+                            }
+                            .ok_or_else(|| self.invalid("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.invalid("invalid escape")),
+                    };
+                    bytes.extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.invalid("invalid UTF-8 in string"))
+    }
+
+    fn unicode_escape(&mut self) -> Result<u32, FormatError> {
+        let hex = self
+            .text
+            .get(self.offset..self.offset + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| self.invalid("invalid unicode escape"))?;
+        self.offset += 4;
+        Ok(hex)
+    }
+}
+
+/// Typed access to the fields of a JSON object, with errors naming the field.
+pub(crate) struct JsonObject<'a> {
+    format: &'static str,
+    /// Such as `vertex.inputs[1]`, empty for the root.
+    path: String,
+    fields: &'a [(String, Json)],
+}
+
+impl<'a> JsonObject<'a> {
+    pub(crate) fn new(format: &'static str, value: &'a Json) -> Result<Self, FormatError> {
+        Self::from_value(format, String::new(), value)
+    }
+
+    fn from_value(
+        format: &'static str,
+        path: String,
+        value: &'a Json,
+    ) -> Result<Self, FormatError> {
+        match value {
+            Json::Object(fields) => Ok(Self {
+                format,
+                path,
+                fields,
+            }),
+            _ => Err(FormatError::Invalid {
+                format,
+                reason: format!("`{path}` is not an object"),
+            }),
+        }
+    }
+
+    fn path(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{key}", self.path)
+        }
+    }
+
+    fn invalid(&self, key: &str, expected: &str) -> FormatError {
+        FormatError::Invalid {
+            format: self.format,
+            reason: format!("`{}` is missing or not {expected}", self.path(key)),
+        }
+    }
+
+    /// [`None`] for missing fields and `null`.
+    fn get(&self, key: &str) -> Option<&'a Json> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+            .filter(|value| **value != Json::Null)
+    }
+
+    pub(crate) fn is_null(&self, key: &str) -> bool {
+        self.get(key).is_none()
+    }
+
+    pub(crate) fn number<T: std::str::FromStr>(&self, key: &str) -> Result<T, FormatError> {
+        match self.get(key) {
+            Some(Json::Number(number)) => number.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| self.invalid(key, "an integer in range"))
+    }
+
+    pub(crate) fn bool(&self, key: &str) -> Result<bool, FormatError> {
+        match self.get(key) {
+            Some(Json::Bool(value)) => Ok(*value),
+            _ => Err(self.invalid(key, "a boolean")),
+        }
+    }
+
+    pub(crate) fn str(&self, key: &str) -> Result<&'a str, FormatError> {
+        match self.get(key) {
+            Some(Json::String(value)) => Ok(value),
+            _ => Err(self.invalid(key, "a string")),
+        }
+    }
+
+    pub(crate) fn opt_str(&self, key: &str) -> Result<Option<&'a str>, FormatError> {
+        if self.is_null(key) {
+            Ok(None)
+        } else {
+            self.str(key).map(Some)
+        }
+    }
+
+    pub(crate) fn object(&self, key: &str) -> Result<Self, FormatError> {
+        let value = self
+            .get(key)
+            .ok_or_else(|| self.invalid(key, "an object"))?;
+        Self::from_value(self.format, self.path(key), value)
+    }
+
+    pub(crate) fn array(&self, key: &str) -> Result<&'a [Json], FormatError> {
+        match self.get(key) {
+            Some(Json::Array(items)) => Ok(items),
+            _ => Err(self.invalid(key, "an array")),
+        }
+    }
+
+    /// The objects in the array `key`.
+    pub(crate) fn objects(&self, key: &str) -> Result<Vec<Self>, FormatError> {
+        self.array(key)?
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                Self::from_value(self.format, format!("{}[{i}]", self.path(key)), item)
+            })
+            .collect()
+    }
+
+    /// The integers in the array `key`.
+    pub(crate) fn numbers<T: std::str::FromStr>(&self, key: &str) -> Result<Vec<T>, FormatError> {
+        self.array(key)?
+            .iter()
+            .map(|item| match item {
+                Json::Number(number) => number.parse().ok(),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(|| self.invalid(key, "an array of integers in range"))
+    }
+}
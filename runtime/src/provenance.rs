@@ -0,0 +1,158 @@
+//! How a shader was converted, decoded from the sidecar files that `saxaboom` writes next to a
+//! `metallib` with `saxaboom::provenance::Provenance::write_sidecars()`:
+//!
+//! ```no_run
+//! use saxaboom_runtime::provenance::{Provenance, BINARY_EXTENSION};
+//!
+//! let path = std::path::Path::new("shaders/blur.metallib").with_extension(BINARY_EXTENSION);
+//! let provenance = Provenance::from_sidecar(&std::fs::read(path)?)?;
+//! println!(
+//!     "converted by saxaboom {} for {:?}",
+//!     provenance.saxaboom_version,
+//!     provenance.setting("set_minimum_gpu_family"),
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use crate::format::{FormatError, Json, JsonObject, Reader};
+
+/// Starts binary sidecar files.
+pub const SIDECAR_MAGIC: [u8; 4] = *b"SXPV";
+/// Newest sidecar version that [`Provenance::from_sidecar()`] and
+/// [`Provenance::from_sidecar_json()`] read.
+pub const SIDECAR_VERSION: u32 = 1;
+/// Extension of binary sidecar files, replacing `metallib`.
+pub const BINARY_EXTENSION: &str = "provenance.bin";
+/// Extension of JSON sidecar files, replacing `metallib`.
+pub const JSON_EXTENSION: &str = "provenance.json";
+
+/// Provenance record of a single converted shader.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Provenance {
+    /// SHA-256 of the DXIL input.
+    pub input_hash: [u8; 32],
+    pub entry_point: String,
+    /// Name of every setting applied to the compiler, such as `set_minimum_gpu_family`, with its
+    /// value.
+    pub settings: Vec<(String, String)>,
+    /// SHA-256 of the JSON of the global root signature, if known.
+    pub root_signature_hash: Option<[u8; 32]>,
+    /// Path of the converter library.
+    pub converter_path: Option<String>,
+    /// SHA-256 of the converter library file, which identifies its release.
+    pub converter_hash: Option<[u8; 32]>,
+    /// Such as `0.2.0+irconverter-2.0`.
+    pub saxaboom_version: String,
+}
+
+impl Provenance {
+    /// Decodes a record as encoded by `saxaboom::provenance::Provenance::to_bytes()`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        let mut reader = Reader::new("provenance", bytes);
+        let provenance = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok(provenance)
+    }
+
+    /// Decodes a binary sidecar file, which is [`SIDECAR_MAGIC`] and the sidecar version
+    /// followed by the encoding of [`Self::from_bytes()`].  Fails with
+    /// [`FormatError::UnsupportedVersion`] for sidecars written by a newer `saxaboom`.
+    pub fn from_sidecar(bytes: &[u8]) -> Result<Self, FormatError> {
+        let mut reader = Reader::new("provenance sidecar", bytes);
+        reader.header(SIDECAR_MAGIC, SIDECAR_VERSION)?;
+        let provenance = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok(provenance)
+    }
+
+    /// Parses a JSON sidecar file, which holds the same fields as this struct and a `version`,
+    /// with hashes as hexadecimal strings.  Fails with [`FormatError::UnsupportedVersion`] for
+    /// sidecars written by a newer `saxaboom`.
+    pub fn from_sidecar_json(json: &str) -> Result<Self, FormatError> {
+        const FORMAT: &str = "provenance sidecar";
+        let json = Json::parse(FORMAT, json)?;
+        let root = JsonObject::new(FORMAT, &json)?;
+        let version = root.number("version")?;
+        if version > SIDECAR_VERSION {
+            return Err(FormatError::UnsupportedVersion {
+                format: FORMAT,
+                version,
+                supported: SIDECAR_VERSION,
+            });
+        }
+        let hash = |key: &str| {
+            let hex = root.str(key)?;
+            parse_hash(hex).ok_or_else(|| FormatError::Invalid {
+                format: FORMAT,
+                reason: format!("`{key}` is not a SHA-256 hash: `{hex}`"),
+            })
+        };
+        let opt_hash = |key: &str| {
+            if root.is_null(key) {
+                Ok(None)
+            } else {
+                hash(key).map(Some)
+            }
+        };
+
+        let settings = root
+            .objects("settings")?
+            .iter()
+            .map(|setting| {
+                Ok((
+                    setting.str("name")?.to_owned(),
+                    setting.str("value")?.to_owned(),
+                ))
+            })
+            .collect::<Result<_, FormatError>>()?;
+        Ok(Self {
+            input_hash: hash("input_hash")?,
+            entry_point: root.str("entry_point")?.to_owned(),
+            settings,
+            root_signature_hash: opt_hash("root_signature_hash")?,
+            converter_path: root.opt_str("converter_path")?.map(str::to_owned),
+            converter_hash: opt_hash("converter_hash")?,
+            saxaboom_version: root.str("saxaboom_version")?.to_owned(),
+        })
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>) -> Result<Self, FormatError> {
+        let input_hash = r.array()?;
+        let entry_point = r.str()?.to_owned();
+        let settings = (0..r.len()?)
+            .map(|_| Ok((r.str()?.to_owned(), r.str()?.to_owned())))
+            .collect::<Result<_, FormatError>>()?;
+        let root_signature_hash = if r.bool()? { Some(r.array()?) } else { None };
+        let converter_path = r.opt_str()?.map(str::to_owned);
+        let converter_hash = if r.bool()? { Some(r.array()?) } else { None };
+        Ok(Self {
+            input_hash,
+            entry_point,
+            settings,
+            root_signature_hash,
+            converter_path,
+            converter_hash,
+            saxaboom_version: r.str()?.to_owned(),
+        })
+    }
+
+    /// The value of the setting `name`, such as `Apple7` for `set_minimum_gpu_family`, or
+    /// [`None`] when it was not applied.
+    pub fn setting(&self, name: &str) -> Option<&str> {
+        self.settings
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 64 hexadecimal digits.
+fn parse_hash(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (byte, digits) in hash.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(hash)
+}
@@ -0,0 +1,336 @@
+//! Shader reflection as plain Rust types, decoded from what `saxaboom` wrote with
+//! `saxaboom::reflection::ReflectionSnapshot`, so that it can be used without loading the
+//! converter library.
+//!
+//! Sidecar files next to a `metallib` are read with [`ReflectionSnapshot::from_sidecar()`] or
+//! [`ReflectionSnapshot::from_sidecar_json()`]:
+//!
+//! ```no_run
+//! use saxaboom_runtime::reflection::{ReflectionSnapshot, BINARY_EXTENSION};
+//!
+//! let path = std::path::Path::new("shaders/blur.metallib").with_extension(BINARY_EXTENSION);
+//! let reflection = ReflectionSnapshot::from_sidecar(&std::fs::read(path)?)?;
+//! let [x, y, z] = reflection.threadgroup_size.unwrap_or([1, 1, 1]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use crate::format::{FormatError, Json, JsonObject, Reader};
+
+/// Starts binary sidecar files.
+pub const SIDECAR_MAGIC: [u8; 4] = *b"SXRF";
+/// Newest sidecar version that [`ReflectionSnapshot::from_sidecar()`] and
+/// [`ReflectionSnapshot::from_sidecar_json()`] read.
+pub const SIDECAR_VERSION: u32 = 1;
+/// Extension of binary sidecar files, replacing `metallib`.
+pub const BINARY_EXTENSION: &str = "reflection.bin";
+/// Extension of JSON sidecar files, replacing `metallib`.
+pub const JSON_EXTENSION: &str = "reflection.json";
+
+/// Kind of resource, the counterpart of `IRResourceType`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceType {
+    Table,
+    Constant,
+    Cbv,
+    Srv,
+    Uav,
+    Sampler,
+    Invalid,
+    Unknown(u32),
+}
+
+impl ResourceType {
+    pub fn from_raw(ty: u32) -> Self {
+        match ty {
+            0 => Self::Table,
+            1 => Self::Constant,
+            2 => Self::Cbv,
+            3 => Self::Srv,
+            4 => Self::Uav,
+            5 => Self::Sampler,
+            6 => Self::Invalid,
+            ty => Self::Unknown(ty),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Table" => Self::Table,
+            "Constant" => Self::Constant,
+            "CBV" => Self::Cbv,
+            "SRV" => Self::Srv,
+            "UAV" => Self::Uav,
+            "Sampler" => Self::Sampler,
+            "Invalid" => Self::Invalid,
+            _ => return None,
+        })
+    }
+}
+
+/// Type of a function constant, the counterpart of `IRFunctionConstantType`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FunctionConstantType {
+    Bool,
+    Int,
+    Float,
+    Unknown(u32),
+}
+
+impl FunctionConstantType {
+    pub fn from_raw(ty: u32) -> Self {
+        match ty {
+            53 => Self::Bool,
+            29 => Self::Int,
+            3 => Self::Float,
+            ty => Self::Unknown(ty),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Bool" => Self::Bool,
+            "Int" => Self::Int,
+            "Float" => Self::Float,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionConstant {
+    pub name: String,
+    pub r#type: FunctionConstantType,
+}
+
+/// Location of a resource in the top-level argument buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub name: Option<String>,
+    pub resource_type: ResourceType,
+    pub space: u32,
+    pub slot: u32,
+    pub top_level_offset: u32,
+    pub size_bytes: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexInput {
+    pub name: String,
+    pub attribute_index: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexInfo {
+    pub instance_id_index: i32,
+    pub vertex_id_index: i32,
+    pub vertex_output_size_in_bytes: u32,
+    pub needs_draw_params: bool,
+    pub inputs: Vec<VertexInput>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FragmentInfo {
+    pub num_render_targets: i32,
+    pub rt_index_int: u8,
+    pub discards: bool,
+}
+
+/// Reflection of a single entry point.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReflectionSnapshot {
+    pub entry_point: Option<String>,
+    pub needs_function_constants: bool,
+    pub function_constants: Vec<FunctionConstant>,
+    pub resources: Vec<Resource>,
+    /// Of compute, mesh and amplification shaders.
+    pub threadgroup_size: Option<[u32; 3]>,
+    pub vertex: Option<VertexInfo>,
+    pub fragment: Option<FragmentInfo>,
+}
+
+impl ReflectionSnapshot {
+    /// Decodes reflection as encoded by `saxaboom::reflection::ReflectionSnapshot::to_bytes()`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        let mut reader = Reader::new("reflection", bytes);
+        let reflection = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok(reflection)
+    }
+
+    /// Decodes a binary sidecar file, which is [`SIDECAR_MAGIC`] and the sidecar version
+    /// followed by the encoding of [`Self::from_bytes()`].  Fails with
+    /// [`FormatError::UnsupportedVersion`] for sidecars written by a newer `saxaboom`.
+    pub fn from_sidecar(bytes: &[u8]) -> Result<Self, FormatError> {
+        let mut reader = Reader::new("reflection sidecar", bytes);
+        reader.header(SIDECAR_MAGIC, SIDECAR_VERSION)?;
+        let reflection = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok(reflection)
+    }
+
+    /// Parses a JSON sidecar file, which holds the same fields as this struct and a `version`.
+    /// Fails with [`FormatError::UnsupportedVersion`] for sidecars written by a newer
+    /// `saxaboom`.
+    pub fn from_sidecar_json(json: &str) -> Result<Self, FormatError> {
+        const FORMAT: &str = "reflection sidecar";
+        let json = Json::parse(FORMAT, json)?;
+        let root = JsonObject::new(FORMAT, &json)?;
+        let version = root.number("version")?;
+        if version > SIDECAR_VERSION {
+            return Err(FormatError::UnsupportedVersion {
+                format: FORMAT,
+                version,
+                supported: SIDECAR_VERSION,
+            });
+        }
+        let invalid = |reason: String| FormatError::Invalid {
+            format: FORMAT,
+            reason,
+        };
+
+        let function_constants = root
+            .objects("function_constants")?
+            .iter()
+            .map(|constant| {
+                let ty = constant.str("type")?;
+                Ok(FunctionConstant {
+                    name: constant.str("name")?.to_owned(),
+                    r#type: FunctionConstantType::from_name(ty)
+                        .ok_or_else(|| invalid(format!("unknown function constant type `{ty}`")))?,
+                })
+            })
+            .collect::<Result<_, FormatError>>()?;
+        let resources = root
+            .objects("resources")?
+            .iter()
+            .map(|resource| {
+                let ty = resource.str("type")?;
+                Ok(Resource {
+                    name: resource.opt_str("name")?.map(str::to_owned),
+                    resource_type: ResourceType::from_name(ty)
+                        .ok_or_else(|| invalid(format!("unknown resource type `{ty}`")))?,
+                    space: resource.number("space")?,
+                    slot: resource.number("slot")?,
+                    top_level_offset: resource.number("top_level_offset")?,
+                    size_bytes: resource.number("size_bytes")?,
+                })
+            })
+            .collect::<Result<_, FormatError>>()?;
+        let threadgroup_size = if root.is_null("threadgroup_size") {
+            None
+        } else {
+            let size = root.numbers("threadgroup_size")?;
+            Some(
+                size.try_into()
+                    .map_err(|_| invalid("`threadgroup_size` does not have 3 items".to_owned()))?,
+            )
+        };
+        let vertex = if root.is_null("vertex") {
+            None
+        } else {
+            let vertex = root.object("vertex")?;
+            Some(VertexInfo {
+                instance_id_index: vertex.number("instance_id_index")?,
+                vertex_id_index: vertex.number("vertex_id_index")?,
+                vertex_output_size_in_bytes: vertex.number("vertex_output_size_in_bytes")?,
+                needs_draw_params: vertex.bool("needs_draw_params")?,
+                inputs: vertex
+                    .objects("inputs")?
+                    .iter()
+                    .map(|input| {
+                        Ok(VertexInput {
+                            name: input.str("name")?.to_owned(),
+                            attribute_index: input.number("attribute_index")?,
+                        })
+                    })
+                    .collect::<Result<_, FormatError>>()?,
+            })
+        };
+        let fragment = if root.is_null("fragment") {
+            None
+        } else {
+            let fragment = root.object("fragment")?;
+            Some(FragmentInfo {
+                num_render_targets: fragment.number("num_render_targets")?,
+                rt_index_int: fragment.number("rt_index_int")?,
+                discards: fragment.bool("discards")?,
+            })
+        };
+
+        Ok(Self {
+            entry_point: root.opt_str("entry_point")?.map(str::to_owned),
+            needs_function_constants: root.bool("needs_function_constants")?,
+            function_constants,
+            resources,
+            threadgroup_size,
+            vertex,
+            fragment,
+        })
+    }
+
+    pub(crate) fn read(r: &mut Reader<'_>) -> Result<Self, FormatError> {
+        let entry_point = r.opt_str()?.map(str::to_owned);
+        let needs_function_constants = r.bool()?;
+        let function_constants = (0..r.len()?)
+            .map(|_| {
+                Ok(FunctionConstant {
+                    name: r.str()?.to_owned(),
+                    r#type: FunctionConstantType::from_raw(r.u32()?),
+                })
+            })
+            .collect::<Result<_, FormatError>>()?;
+        let resources = (0..r.len()?)
+            .map(|_| {
+                Ok(Resource {
+                    name: r.opt_str()?.map(str::to_owned),
+                    resource_type: ResourceType::from_raw(r.u32()?),
+                    space: r.u32()?,
+                    slot: r.u32()?,
+                    top_level_offset: r.u32()?,
+                    size_bytes: r.u64()?,
+                })
+            })
+            .collect::<Result<_, FormatError>>()?;
+        let threadgroup_size = if r.bool()? {
+            Some([r.u32()?, r.u32()?, r.u32()?])
+        } else {
+            None
+        };
+        let vertex = if r.bool()? {
+            Some(VertexInfo {
+                instance_id_index: r.i32()?,
+                vertex_id_index: r.i32()?,
+                vertex_output_size_in_bytes: r.u32()?,
+                needs_draw_params: r.bool()?,
+                inputs: (0..r.len()?)
+                    .map(|_| {
+                        Ok(VertexInput {
+                            name: r.str()?.to_owned(),
+                            attribute_index: r.u8()?,
+                        })
+                    })
+                    .collect::<Result<_, FormatError>>()?,
+            })
+        } else {
+            None
+        };
+        let fragment = if r.bool()? {
+            Some(FragmentInfo {
+                num_render_targets: r.i32()?,
+                rt_index_int: r.u8()?,
+                discards: r.bool()?,
+            })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            entry_point,
+            needs_function_constants,
+            function_constants,
+            resources,
+            threadgroup_size,
+            vertex,
+            fragment,
+        })
+    }
+}
@@ -0,0 +1,365 @@
+//! Abstraction over the `metal_irconverter` C API, so that the wrappers in this crate can run
+//! against something other than the dynamic library provided by Apple.
+use crate::bindings;
+
+macro_rules! converter_backend {
+    ($(fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        /// The set of `metal_irconverter` functions used by [`crate::MetalIrConverter`] and the
+        /// objects it creates.
+        ///
+        /// Methods carry the name and signature of the C function they stand in for, and are
+        /// expected to behave identically: opaque handles returned by one method are only ever
+        /// passed back into methods of the same backend, never dereferenced by the caller.
+        ///
+        /// [`bindings::metal_irconverter`] (the dynamically loaded library) is the canonical
+        /// implementation.  An in-memory fake is available in `saxaboom::testing` behind the
+        /// `testing` feature.
+        ///
+        /// # Safety
+        ///
+        /// Implementers must uphold the contracts documented in the `metal_irconverter.h` header
+        /// for every method, since the safe wrappers in this crate rely on them.
+        #[expect(clippy::missing_safety_doc, clippy::too_many_arguments, non_snake_case)]
+        pub unsafe trait ConverterBackend: Send + Sync {
+            /// Whether `function` (one of [`FUNCTIONS`]) can be called on this backend.  Calling
+            /// a function that is not loaded panics.
+            fn is_loaded(&self, function: &str) -> bool {
+                let _ = function;
+                true
+            }
+
+            $(unsafe fn $name(&self, $($arg: $ty),*) $(-> $ret)?;)*
+        }
+
+        /// Names of all functions in [`ConverterBackend`].
+        pub const FUNCTIONS: &[&str] = &[$(stringify!($name)),*];
+
+        unsafe impl ConverterBackend for bindings::metal_irconverter {
+            fn is_loaded(&self, function: &str) -> bool {
+                match function {
+                    $(stringify!($name) => self.$name.is_ok(),)*
+                    _ => false,
+                }
+            }
+
+            $(unsafe fn $name(&self, $($arg: $ty),*) $(-> $ret)? {
+                unsafe { bindings::metal_irconverter::$name(self, $($arg),*) }
+            })*
+        }
+    };
+}
+
+/// Functions in [`ConverterBackend`] that are not present in every release of the
+/// `metal_irconverter` library.  A library lacking any of these still loads, but the wrappers
+/// calling them return [`crate::UnsupportedError`].  All other [`FUNCTIONS`] are required.
+pub const OPTIONAL_FUNCTIONS: &[&str] = &[
+    "IRCompilerSetInputTopology",
+    "IRCompilerEnableGeometryAndTessellationEmulation",
+    "IRCompilerSetDepthFeedbackConfiguration",
+    "IRCompilerSetIntRTMask",
+    "IRCompilerIgnoreDebugInformation",
+    "IRObjectGatherRaytracingIntrinsics",
+    "IRMetalLibSynthesizeIndirectRayDispatchFunction",
+    "IRMetalLibSynthesizeIndirectIntersectionFunction",
+    "IRShaderReflectionCopyJSONString",
+    "IRShaderReflectionReleaseString",
+    "IRShaderReflectionCreateFromJSON",
+    "IRVersionedRootSignatureDescriptorCreateFromJSON",
+    "IRVersionedRootSignatureDescriptorRelease",
+    "IRVersionedRootSignatureDescriptorCopyJSONString",
+    "IRVersionedRootSignatureDescriptorReleaseString",
+    "IRInputLayoutDescriptor1CopyJSONString",
+    "IRInputLayoutDescriptor1ReleaseString",
+    "IRInputLayoutDescriptor1CreateFromJSON",
+    "IRInputLayoutDescriptor1Release",
+];
+
+/// Invokes `$callback! { fn ...; }` with the signature of every function in [`ConverterBackend`],
+/// for generating code that mirrors it (such as the exports of the `irconverter_stub` library).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! converter_backend_functions {
+    ($callback:ident) => {
+        $callback! {
+            fn IRErrorGetCode(error: *const $crate::ffi::IRError) -> u32;
+            fn IRErrorGetPayload(error: *const $crate::ffi::IRError) -> *const ::std::ffi::c_void;
+            fn IRErrorDestroy(error: *mut $crate::ffi::IRError);
+            fn IRRootSignatureCreateFromDescriptor(
+                descriptor: *const $crate::ffi::IRVersionedRootSignatureDescriptor,
+                error: *mut *mut $crate::ffi::IRError,
+            ) -> *mut $crate::ffi::IRRootSignature;
+            fn IRRootSignatureDestroy(sig: *mut $crate::ffi::IRRootSignature);
+            fn IRVersionedRootSignatureDescriptorCreateFromJSON(
+                serialized: *const ::std::ffi::c_char,
+            ) -> *mut $crate::ffi::IRVersionedRootSignatureDescriptor;
+            fn IRVersionedRootSignatureDescriptorRelease(
+                root_signature_descriptor: *mut $crate::ffi::IRVersionedRootSignatureDescriptor,
+            );
+            fn IRVersionedRootSignatureDescriptorCopyJSONString(
+                root_signature_descriptor: *mut $crate::ffi::IRVersionedRootSignatureDescriptor,
+            ) -> *const ::std::ffi::c_char;
+            fn IRVersionedRootSignatureDescriptorReleaseString(
+                serialized: *const ::std::ffi::c_char,
+            );
+            fn IRInputLayoutDescriptor1CopyJSONString(
+                input_layout_descriptor: *mut $crate::ffi::IRInputLayoutDescriptor1,
+            ) -> *const ::std::ffi::c_char;
+            fn IRInputLayoutDescriptor1ReleaseString(serialized: *const ::std::ffi::c_char);
+            fn IRInputLayoutDescriptor1CreateFromJSON(
+                serialized: *const ::std::ffi::c_char,
+            ) -> *mut $crate::ffi::IRInputLayoutDescriptor1;
+            fn IRInputLayoutDescriptor1Release(
+                input_layout_descriptor: *mut $crate::ffi::IRInputLayoutDescriptor1,
+            );
+            fn IRObjectCreateFromDXIL(
+                bytecode: *const u8,
+                size: usize,
+                bytecode_ownership: $crate::ffi::IRBytecodeOwnership,
+            ) -> *mut $crate::ffi::IRObject;
+            fn IRObjectDestroy(object: *mut $crate::ffi::IRObject);
+            fn IRObjectGetType(object: *const $crate::ffi::IRObject) -> $crate::ffi::IRObjectType;
+            fn IRObjectGetMetalIRShaderStage(
+                object: *const $crate::ffi::IRObject,
+            ) -> $crate::ffi::IRShaderStage;
+            fn IRCompilerSetValidationFlags(
+                compiler: *mut $crate::ffi::IRCompiler,
+                validation_flags: $crate::ffi::IRCompilerValidationFlags,
+            );
+            fn IRCompilerCreate() -> *mut $crate::ffi::IRCompiler;
+            fn IRCompilerDestroy(compiler: *mut $crate::ffi::IRCompiler);
+            fn IRCompilerAllocCompileAndLink(
+                compiler: *mut $crate::ffi::IRCompiler,
+                entry_point_name: *const ::std::ffi::c_char,
+                input: *const $crate::ffi::IRObject,
+                error: *mut *mut $crate::ffi::IRError,
+            ) -> *mut $crate::ffi::IRObject;
+            fn IRCompilerAllocCombineCompileAndLink(
+                compiler: *mut $crate::ffi::IRCompiler,
+                intersection_function_entry_point_name: *const ::std::ffi::c_char,
+                intersection_function_bytecode: *const $crate::ffi::IRObject,
+                any_hit_function_entry_point_name: *const ::std::ffi::c_char,
+                any_hit_function_bytecode: *const $crate::ffi::IRObject,
+                error: *mut *mut $crate::ffi::IRError,
+            ) -> *mut $crate::ffi::IRObject;
+            fn IRObjectGetMetalLibBinary(
+                obj: *const $crate::ffi::IRObject,
+                stage: $crate::ffi::IRShaderStage,
+                lib: *mut $crate::ffi::IRMetalLibBinary,
+            ) -> bool;
+            fn IRCompilerSetStageInGenerationMode(
+                compiler: *mut $crate::ffi::IRCompiler,
+                stage_in_code_generation_mode: $crate::ffi::IRStageInCodeGenerationMode,
+            );
+            fn IRMetalLibSynthesizeStageInFunction(
+                compiler: *const $crate::ffi::IRCompiler,
+                vertex_shader_reflection: *const $crate::ffi::IRShaderReflection,
+                layout: *const $crate::ffi::IRVersionedInputLayoutDescriptor,
+                binary: *mut $crate::ffi::IRMetalLibBinary,
+            ) -> bool;
+            fn IRObjectGetReflection(
+                obj: *const $crate::ffi::IRObject,
+                stage: $crate::ffi::IRShaderStage,
+                reflection: *mut $crate::ffi::IRShaderReflection,
+            ) -> bool;
+            fn IRCompilerSetGlobalRootSignature(
+                compiler: *mut $crate::ffi::IRCompiler,
+                root_signature: *const $crate::ffi::IRRootSignature,
+            );
+            fn IRCompilerSetLocalRootSignature(
+                compiler: *mut $crate::ffi::IRCompiler,
+                root_signature: *const $crate::ffi::IRRootSignature,
+            );
+            fn IRCompilerSetHitgroupType(
+                compiler: *mut $crate::ffi::IRCompiler,
+                hit_group_type: $crate::ffi::IRHitGroupType,
+            );
+            fn IRObjectGatherRaytracingIntrinsics(
+                input: *mut $crate::ffi::IRObject,
+                entry_point: *const ::std::ffi::c_char,
+            ) -> u64;
+            fn IRCompilerSetRayTracingPipelineArguments(
+                compiler: *mut $crate::ffi::IRCompiler,
+                max_attribute_size_in_bytes: u32,
+                raytracing_pipeline_flags: $crate::ffi::IRRaytracingPipelineFlags,
+                chs: u64,
+                miss: u64,
+                any_hit: u64,
+                callable_args: u64,
+                max_recursive_depth: ::std::ffi::c_int,
+                ray_generation_compilation_mode: $crate::ffi::IRRayGenerationCompilationMode,
+                intersection_function_compilation_mode: $crate::ffi::IRIntersectionFunctionCompilationMode,
+            );
+            fn IRCompilerSetCompatibilityFlags(
+                compiler: *mut $crate::ffi::IRCompiler,
+                flags: $crate::ffi::IRCompatibilityFlags,
+            );
+            fn IRCompilerSetInputTopology(
+                compiler: *mut $crate::ffi::IRCompiler,
+                input_topology: $crate::ffi::IRInputTopology,
+            );
+            fn IRCompilerEnableGeometryAndTessellationEmulation(
+                compiler: *mut $crate::ffi::IRCompiler,
+                enable: bool,
+            );
+            fn IRCompilerSetDualSourceBlendingConfiguration(
+                compiler: *mut $crate::ffi::IRCompiler,
+                configuration: $crate::ffi::IRDualSourceBlendingConfiguration,
+            );
+            fn IRCompilerSetDepthFeedbackConfiguration(
+                compiler: *mut $crate::ffi::IRCompiler,
+                configuration: $crate::ffi::IRDepthFeedbackConfiguration,
+            );
+            fn IRCompilerSetIntRTMask(compiler: *mut $crate::ffi::IRCompiler, int_rtmask: u8);
+            fn IRMetalLibSynthesizeIndirectRayDispatchFunction(
+                compiler: *const $crate::ffi::IRCompiler,
+                binary: *mut $crate::ffi::IRMetalLibBinary,
+            ) -> bool;
+            fn IRMetalLibSynthesizeIndirectIntersectionFunction(
+                compiler: *const $crate::ffi::IRCompiler,
+                binary: *mut $crate::ffi::IRMetalLibBinary,
+            ) -> bool;
+            fn IRCompilerSetEntryPointName(
+                compiler: *mut $crate::ffi::IRCompiler,
+                new_name: *const ::std::ffi::c_char,
+            );
+            fn IRCompilerSetMinimumGPUFamily(
+                compiler: *mut $crate::ffi::IRCompiler,
+                family: $crate::ffi::IRGPUFamily,
+            );
+            fn IRCompilerIgnoreRootSignature(
+                compiler: *mut $crate::ffi::IRCompiler,
+                ignore_embedded_root_signature: bool,
+            );
+            fn IRCompilerIgnoreDebugInformation(
+                compiler: *mut $crate::ffi::IRCompiler,
+                ignore_debug_information: bool,
+            );
+            fn IRCompilerSetMinimumDeploymentTarget(
+                compiler: *mut $crate::ffi::IRCompiler,
+                operating_system: $crate::ffi::IROperatingSystem,
+                version: *const ::std::ffi::c_char,
+            );
+            fn IRMetalLibBinaryCreate() -> *mut $crate::ffi::IRMetalLibBinary;
+            fn IRMetalLibBinaryDestroy(lib: *mut $crate::ffi::IRMetalLibBinary);
+            fn IRMetalLibGetBytecode(
+                lib: *const $crate::ffi::IRMetalLibBinary,
+                out_bytecode: *mut u8,
+            ) -> usize;
+            fn IRMetalLibGetBytecodeSize(lib: *const $crate::ffi::IRMetalLibBinary) -> usize;
+            fn IRShaderReflectionCreate() -> *mut $crate::ffi::IRShaderReflection;
+            fn IRShaderReflectionDestroy(reflection: *mut $crate::ffi::IRShaderReflection);
+            fn IRShaderReflectionCopyJSONString(
+                reflection: *const $crate::ffi::IRShaderReflection,
+            ) -> *const ::std::ffi::c_char;
+            fn IRShaderReflectionReleaseString(serialized: *const ::std::ffi::c_char);
+            fn IRShaderReflectionCreateFromJSON(
+                json: *const ::std::ffi::c_char,
+            ) -> *mut $crate::ffi::IRShaderReflection;
+            fn IRShaderReflectionCopyComputeInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                csinfo: *mut $crate::ffi::IRVersionedCSInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyVertexInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                vsinfo: *mut $crate::ffi::IRVersionedVSInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyFragmentInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                fsinfo: *mut $crate::ffi::IRVersionedFSInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyGeometryInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                gsinfo: *mut $crate::ffi::IRVersionedGSInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyHullInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                hsinfo: *mut $crate::ffi::IRVersionedHSInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyDomainInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                dsinfo: *mut $crate::ffi::IRVersionedDSInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyMeshInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                msinfo: *mut $crate::ffi::IRVersionedMSInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyAmplificationInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                asinfo: *mut $crate::ffi::IRVersionedASInfo,
+            ) -> bool;
+            fn IRShaderReflectionCopyRaytracingInfo(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                version: $crate::ffi::IRReflectionVersion,
+                rtinfo: *mut $crate::ffi::IRVersionedRTInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseComputeInfo(
+                csinfo: *mut $crate::ffi::IRVersionedCSInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseVertexInfo(
+                vsinfo: *mut $crate::ffi::IRVersionedVSInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseFragmentInfo(
+                fsinfo: *mut $crate::ffi::IRVersionedFSInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseGeometryInfo(
+                gsinfo: *mut $crate::ffi::IRVersionedGSInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseHullInfo(
+                hsinfo: *mut $crate::ffi::IRVersionedHSInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseDomainInfo(
+                dsinfo: *mut $crate::ffi::IRVersionedDSInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseMeshInfo(
+                msinfo: *mut $crate::ffi::IRVersionedMSInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseAmplificationInfo(
+                asinfo: *mut $crate::ffi::IRVersionedASInfo,
+            ) -> bool;
+            fn IRShaderReflectionReleaseRaytracingInfo(
+                rtinfo: *mut $crate::ffi::IRVersionedRTInfo,
+            ) -> bool;
+            fn IRShaderReflectionGetEntryPointFunctionName(
+                reflection: *const $crate::ffi::IRShaderReflection,
+            ) -> *const ::std::ffi::c_char;
+            fn IRShaderReflectionNeedsFunctionConstants(
+                reflection: *const $crate::ffi::IRShaderReflection,
+            ) -> bool;
+            fn IRShaderReflectionGetFunctionConstantCount(
+                reflection: *const $crate::ffi::IRShaderReflection,
+            ) -> usize;
+            fn IRShaderReflectionCopyFunctionConstants(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                function_constants: *mut $crate::ffi::IRFunctionConstant,
+            );
+            fn IRShaderReflectionReleaseFunctionConstants(
+                function_constants: *mut $crate::ffi::IRFunctionConstant,
+                function_constant_count: usize,
+            );
+            fn IRShaderReflectionGetResourceCount(
+                reflection: *const $crate::ffi::IRShaderReflection,
+            ) -> usize;
+            fn IRShaderReflectionGetResourceLocations(
+                reflection: *const $crate::ffi::IRShaderReflection,
+                resource_locations: *mut $crate::ffi::IRResourceLocation,
+            );
+            fn IRRootSignatureGetResourceCount(
+                root_signature: *const $crate::ffi::IRRootSignature,
+            ) -> usize;
+            fn IRRootSignatureGetResourceLocations(
+                root_signature: *const $crate::ffi::IRRootSignature,
+                resource_locations: *mut $crate::ffi::IRResourceLocation,
+            );
+        }
+    };
+}
+
+converter_backend_functions!(converter_backend);
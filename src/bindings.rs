@@ -1150,269 +1150,434 @@ pub struct IRResourceLocation {
 }
 pub struct metal_irconverter {
     __library: ::libloading::Library,
-    pub IRErrorGetCode: unsafe extern "C" fn(error: *const IRError) -> u32,
-    pub IRErrorGetPayload:
+    pub IRErrorGetCode:
+        Result<unsafe extern "C" fn(error: *const IRError) -> u32, ::libloading::Error>,
+    pub IRErrorGetPayload: Result<
         unsafe extern "C" fn(error: *const IRError) -> *const ::std::os::raw::c_void,
-    pub IRErrorDestroy: unsafe extern "C" fn(error: *mut IRError),
-    pub IRRootSignatureCreateFromDescriptor: unsafe extern "C" fn(
-        descriptor: *const IRVersionedRootSignatureDescriptor,
-        error: *mut *mut IRError,
-    ) -> *mut IRRootSignature,
-    pub IRRootSignatureDestroy: unsafe extern "C" fn(sig: *mut IRRootSignature),
-    pub IRObjectCreateFromDXIL: unsafe extern "C" fn(
-        bytecode: *const u8,
-        size: usize,
-        bytecodeOwnership: IRBytecodeOwnership,
-    ) -> *mut IRObject,
-    pub IRObjectDestroy: unsafe extern "C" fn(object: *mut IRObject),
-    pub IRObjectGetType: unsafe extern "C" fn(object: *const IRObject) -> IRObjectType,
+        ::libloading::Error,
+    >,
+    pub IRErrorDestroy: Result<unsafe extern "C" fn(error: *mut IRError), ::libloading::Error>,
+    pub IRRootSignatureCreateFromDescriptor: Result<
+        unsafe extern "C" fn(
+            descriptor: *const IRVersionedRootSignatureDescriptor,
+            error: *mut *mut IRError,
+        ) -> *mut IRRootSignature,
+        ::libloading::Error,
+    >,
+    pub IRRootSignatureDestroy:
+        Result<unsafe extern "C" fn(sig: *mut IRRootSignature), ::libloading::Error>,
+    pub IRObjectCreateFromDXIL: Result<
+        unsafe extern "C" fn(
+            bytecode: *const u8,
+            size: usize,
+            bytecodeOwnership: IRBytecodeOwnership,
+        ) -> *mut IRObject,
+        ::libloading::Error,
+    >,
+    pub IRObjectDestroy: Result<unsafe extern "C" fn(object: *mut IRObject), ::libloading::Error>,
+    pub IRObjectGetType:
+        Result<unsafe extern "C" fn(object: *const IRObject) -> IRObjectType, ::libloading::Error>,
     pub IRObjectGetMetalIRShaderStage:
-        unsafe extern "C" fn(object: *const IRObject) -> IRShaderStage,
-    pub IRCompilerSetValidationFlags:
+        Result<unsafe extern "C" fn(object: *const IRObject) -> IRShaderStage, ::libloading::Error>,
+    pub IRCompilerSetValidationFlags: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, validationFlags: IRCompilerValidationFlags),
-    pub IRCompilerCreate: unsafe extern "C" fn() -> *mut IRCompiler,
-    pub IRCompilerDestroy: unsafe extern "C" fn(compiler: *mut IRCompiler),
-    pub IRCompilerAllocCompileAndLink: unsafe extern "C" fn(
-        compiler: *mut IRCompiler,
-        entryPointName: *const ::std::os::raw::c_char,
-        input: *const IRObject,
-        error: *mut *mut IRError,
-    ) -> *mut IRObject,
-    pub IRCompilerAllocCombineCompileAndLink: unsafe extern "C" fn(
-        compiler: *mut IRCompiler,
-        intersectionFunctionEntryPointName: *const ::std::os::raw::c_char,
-        intersectionFunctionBytecode: *const IRObject,
-        anyHitFunctionEntryPointName: *const ::std::os::raw::c_char,
-        anyHitFunctionBytecode: *const IRObject,
-        error: *mut *mut IRError,
-    ) -> *mut IRObject,
-    pub IRObjectGetMetalLibBinary: unsafe extern "C" fn(
-        obj: *const IRObject,
-        stage: IRShaderStage,
-        lib: *mut IRMetalLibBinary,
-    ) -> bool,
-    pub IRCompilerSetStageInGenerationMode: unsafe extern "C" fn(
-        compiler: *mut IRCompiler,
-        stageInCodeGenerationMode: IRStageInCodeGenerationMode,
-    ),
-    pub IRMetalLibSynthesizeStageInFunction: unsafe extern "C" fn(
-        compiler: *const IRCompiler,
-        vertexShaderReflection: *const IRShaderReflection,
-        layout: *const IRVersionedInputLayoutDescriptor,
-        binary: *mut IRMetalLibBinary,
-    ) -> bool,
-    pub IRObjectGetReflection: unsafe extern "C" fn(
-        obj: *const IRObject,
-        stage: IRShaderStage,
-        reflection: *mut IRShaderReflection,
-    ) -> bool,
-    pub IRCompilerSetGlobalRootSignature:
+        ::libloading::Error,
+    >,
+    pub IRCompilerCreate: Result<unsafe extern "C" fn() -> *mut IRCompiler, ::libloading::Error>,
+    pub IRCompilerDestroy:
+        Result<unsafe extern "C" fn(compiler: *mut IRCompiler), ::libloading::Error>,
+    pub IRCompilerAllocCompileAndLink: Result<
+        unsafe extern "C" fn(
+            compiler: *mut IRCompiler,
+            entryPointName: *const ::std::os::raw::c_char,
+            input: *const IRObject,
+            error: *mut *mut IRError,
+        ) -> *mut IRObject,
+        ::libloading::Error,
+    >,
+    pub IRCompilerAllocCombineCompileAndLink: Result<
+        unsafe extern "C" fn(
+            compiler: *mut IRCompiler,
+            intersectionFunctionEntryPointName: *const ::std::os::raw::c_char,
+            intersectionFunctionBytecode: *const IRObject,
+            anyHitFunctionEntryPointName: *const ::std::os::raw::c_char,
+            anyHitFunctionBytecode: *const IRObject,
+            error: *mut *mut IRError,
+        ) -> *mut IRObject,
+        ::libloading::Error,
+    >,
+    pub IRObjectGetMetalLibBinary: Result<
+        unsafe extern "C" fn(
+            obj: *const IRObject,
+            stage: IRShaderStage,
+            lib: *mut IRMetalLibBinary,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetStageInGenerationMode: Result<
+        unsafe extern "C" fn(
+            compiler: *mut IRCompiler,
+            stageInCodeGenerationMode: IRStageInCodeGenerationMode,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRMetalLibSynthesizeStageInFunction: Result<
+        unsafe extern "C" fn(
+            compiler: *const IRCompiler,
+            vertexShaderReflection: *const IRShaderReflection,
+            layout: *const IRVersionedInputLayoutDescriptor,
+            binary: *mut IRMetalLibBinary,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRObjectGetReflection: Result<
+        unsafe extern "C" fn(
+            obj: *const IRObject,
+            stage: IRShaderStage,
+            reflection: *mut IRShaderReflection,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetGlobalRootSignature: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, rootSignature: *const IRRootSignature),
-    pub IRCompilerSetLocalRootSignature:
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetLocalRootSignature: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, rootSignature: *const IRRootSignature),
-    pub IRCompilerSetHitgroupType:
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetHitgroupType: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, hitGroupType: IRHitGroupType),
-    pub IRObjectGatherRaytracingIntrinsics: unsafe extern "C" fn(
-        input: *mut IRObject,
-        entryPoint: *const ::std::os::raw::c_char,
-    ) -> u64,
-    pub IRCompilerSetRayTracingPipelineArguments: unsafe extern "C" fn(
-        compiler: *mut IRCompiler,
-        maxAttributeSizeInBytes: u32,
-        raytracingPipelineFlags: IRRaytracingPipelineFlags,
-        chs: u64,
-        miss: u64,
-        anyHit: u64,
-        callableArgs: u64,
-        maxRecursiveDepth: ::std::os::raw::c_int,
-        rayGenerationCompilationMode: IRRayGenerationCompilationMode,
-        intersectionFunctionCompilationMode: IRIntersectionFunctionCompilationMode,
-    ),
-    pub IRCompilerSetCompatibilityFlags:
+        ::libloading::Error,
+    >,
+    pub IRObjectGatherRaytracingIntrinsics: Result<
+        unsafe extern "C" fn(
+            input: *mut IRObject,
+            entryPoint: *const ::std::os::raw::c_char,
+        ) -> u64,
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetRayTracingPipelineArguments: Result<
+        unsafe extern "C" fn(
+            compiler: *mut IRCompiler,
+            maxAttributeSizeInBytes: u32,
+            raytracingPipelineFlags: IRRaytracingPipelineFlags,
+            chs: u64,
+            miss: u64,
+            anyHit: u64,
+            callableArgs: u64,
+            maxRecursiveDepth: ::std::os::raw::c_int,
+            rayGenerationCompilationMode: IRRayGenerationCompilationMode,
+            intersectionFunctionCompilationMode: IRIntersectionFunctionCompilationMode,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetCompatibilityFlags: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, flags: IRCompatibilityFlags),
-    pub IRCompilerSetInputTopology:
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetInputTopology: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, inputTopology: IRInputTopology),
+        ::libloading::Error,
+    >,
     pub IRCompilerEnableGeometryAndTessellationEmulation:
-        unsafe extern "C" fn(compiler: *mut IRCompiler, enable: bool),
-    pub IRCompilerSetDualSourceBlendingConfiguration: unsafe extern "C" fn(
-        compiler: *mut IRCompiler,
-        configuration: IRDualSourceBlendingConfiguration,
-    ),
-    pub IRCompilerSetDepthFeedbackConfiguration: unsafe extern "C" fn(
-        compiler: *mut IRCompiler,
-        configuration: IRDepthFeedbackConfiguration,
-    ),
-    pub IRCompilerSetIntRTMask: unsafe extern "C" fn(compiler: *mut IRCompiler, intRTMask: u8),
-    pub IRMetalLibSynthesizeIndirectRayDispatchFunction:
+        Result<unsafe extern "C" fn(compiler: *mut IRCompiler, enable: bool), ::libloading::Error>,
+    pub IRCompilerSetDualSourceBlendingConfiguration: Result<
+        unsafe extern "C" fn(
+            compiler: *mut IRCompiler,
+            configuration: IRDualSourceBlendingConfiguration,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetDepthFeedbackConfiguration: Result<
+        unsafe extern "C" fn(
+            compiler: *mut IRCompiler,
+            configuration: IRDepthFeedbackConfiguration,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetIntRTMask:
+        Result<unsafe extern "C" fn(compiler: *mut IRCompiler, intRTMask: u8), ::libloading::Error>,
+    pub IRMetalLibSynthesizeIndirectRayDispatchFunction: Result<
         unsafe extern "C" fn(compiler: *const IRCompiler, binary: *mut IRMetalLibBinary) -> bool,
-    pub IRMetalLibSynthesizeIndirectIntersectionFunction:
+        ::libloading::Error,
+    >,
+    pub IRMetalLibSynthesizeIndirectIntersectionFunction: Result<
         unsafe extern "C" fn(compiler: *const IRCompiler, binary: *mut IRMetalLibBinary) -> bool,
-    pub IRCompilerSetEntryPointName:
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetEntryPointName: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, newName: *const ::std::os::raw::c_char),
-    pub IRCompilerSetMinimumGPUFamily:
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetMinimumGPUFamily: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, family: IRGPUFamily),
-    pub IRCompilerIgnoreRootSignature:
+        ::libloading::Error,
+    >,
+    pub IRCompilerIgnoreRootSignature: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, ignoreEmbeddedRootSignature: bool),
-    pub IRCompilerIgnoreDebugInformation:
+        ::libloading::Error,
+    >,
+    pub IRCompilerIgnoreDebugInformation: Result<
         unsafe extern "C" fn(compiler: *mut IRCompiler, ignoreDebugInformation: bool),
-    pub IRCompilerSetMinimumDeploymentTarget: unsafe extern "C" fn(
-        compiler: *mut IRCompiler,
-        operatingSystem: IROperatingSystem,
-        version: *const ::std::os::raw::c_char,
-    ),
-    pub IRMetalLibBinaryCreate: unsafe extern "C" fn() -> *mut IRMetalLibBinary,
-    pub IRMetalLibBinaryDestroy: unsafe extern "C" fn(lib: *mut IRMetalLibBinary),
-    pub IRMetalLibGetBytecode:
+        ::libloading::Error,
+    >,
+    pub IRCompilerSetMinimumDeploymentTarget: Result<
+        unsafe extern "C" fn(
+            compiler: *mut IRCompiler,
+            operatingSystem: IROperatingSystem,
+            version: *const ::std::os::raw::c_char,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRMetalLibBinaryCreate:
+        Result<unsafe extern "C" fn() -> *mut IRMetalLibBinary, ::libloading::Error>,
+    pub IRMetalLibBinaryDestroy:
+        Result<unsafe extern "C" fn(lib: *mut IRMetalLibBinary), ::libloading::Error>,
+    pub IRMetalLibGetBytecode: Result<
         unsafe extern "C" fn(lib: *const IRMetalLibBinary, outBytecode: *mut u8) -> usize,
-    pub IRMetalLibGetBytecodeSize: unsafe extern "C" fn(lib: *const IRMetalLibBinary) -> usize,
-    pub IRObjectSerialize: unsafe extern "C" fn(
-        outputPath: *const ::std::os::raw::c_char,
-        obj: *const IRObject,
-        stage: IRShaderStage,
-    ) -> bool,
-    pub IRShaderReflectionCreate: unsafe extern "C" fn() -> *mut IRShaderReflection,
-    pub IRShaderReflectionDestroy: unsafe extern "C" fn(reflection: *mut IRShaderReflection),
-    pub IRShaderReflectionGetEntryPointFunctionName:
+        ::libloading::Error,
+    >,
+    pub IRMetalLibGetBytecodeSize:
+        Result<unsafe extern "C" fn(lib: *const IRMetalLibBinary) -> usize, ::libloading::Error>,
+    pub IRObjectSerialize: Result<
+        unsafe extern "C" fn(
+            outputPath: *const ::std::os::raw::c_char,
+            obj: *const IRObject,
+            stage: IRShaderStage,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCreate:
+        Result<unsafe extern "C" fn() -> *mut IRShaderReflection, ::libloading::Error>,
+    pub IRShaderReflectionDestroy:
+        Result<unsafe extern "C" fn(reflection: *mut IRShaderReflection), ::libloading::Error>,
+    pub IRShaderReflectionGetEntryPointFunctionName: Result<
         unsafe extern "C" fn(
             reflection: *const IRShaderReflection,
         ) -> *const ::std::os::raw::c_char,
-    pub IRShaderReflectionNeedsFunctionConstants:
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionNeedsFunctionConstants: Result<
         unsafe extern "C" fn(reflection: *const IRShaderReflection) -> bool,
-    pub IRShaderReflectionGetFunctionConstantCount:
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionGetFunctionConstantCount: Result<
         unsafe extern "C" fn(reflection: *const IRShaderReflection) -> usize,
-    pub IRShaderReflectionCopyFunctionConstants: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        functionConstants: *mut IRFunctionConstant,
-    ),
-    pub IRShaderReflectionReleaseFunctionConstants: unsafe extern "C" fn(
-        functionConstants: *mut IRFunctionConstant,
-        functionConstantCount: usize,
-    ),
-    pub IRShaderReflectionCopyComputeInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        csinfo: *mut IRVersionedCSInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyVertexInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        vsinfo: *mut IRVersionedVSInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyFragmentInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        fsinfo: *mut IRVersionedFSInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyGeometryInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        gsinfo: *mut IRVersionedGSInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyHullInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        hsinfo: *mut IRVersionedHSInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyDomainInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        dsinfo: *mut IRVersionedDSInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyMeshInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        msinfo: *mut IRVersionedMSInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyAmplificationInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        asinfo: *mut IRVersionedASInfo,
-    ) -> bool,
-    pub IRShaderReflectionCopyRaytracingInfo: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        version: IRReflectionVersion,
-        rtinfo: *mut IRVersionedRTInfo,
-    ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyFunctionConstants: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            functionConstants: *mut IRFunctionConstant,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionReleaseFunctionConstants: Result<
+        unsafe extern "C" fn(
+            functionConstants: *mut IRFunctionConstant,
+            functionConstantCount: usize,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyComputeInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            csinfo: *mut IRVersionedCSInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyVertexInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            vsinfo: *mut IRVersionedVSInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyFragmentInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            fsinfo: *mut IRVersionedFSInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyGeometryInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            gsinfo: *mut IRVersionedGSInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyHullInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            hsinfo: *mut IRVersionedHSInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyDomainInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            dsinfo: *mut IRVersionedDSInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyMeshInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            msinfo: *mut IRVersionedMSInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyAmplificationInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            asinfo: *mut IRVersionedASInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyRaytracingInfo: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            version: IRReflectionVersion,
+            rtinfo: *mut IRVersionedRTInfo,
+        ) -> bool,
+        ::libloading::Error,
+    >,
     pub IRShaderReflectionReleaseComputeInfo:
-        unsafe extern "C" fn(csinfo: *mut IRVersionedCSInfo) -> bool,
+        Result<unsafe extern "C" fn(csinfo: *mut IRVersionedCSInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseVertexInfo:
-        unsafe extern "C" fn(vsinfo: *mut IRVersionedVSInfo) -> bool,
+        Result<unsafe extern "C" fn(vsinfo: *mut IRVersionedVSInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseFragmentInfo:
-        unsafe extern "C" fn(fsinfo: *mut IRVersionedFSInfo) -> bool,
+        Result<unsafe extern "C" fn(fsinfo: *mut IRVersionedFSInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseGeometryInfo:
-        unsafe extern "C" fn(gsinfo: *mut IRVersionedGSInfo) -> bool,
+        Result<unsafe extern "C" fn(gsinfo: *mut IRVersionedGSInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseHullInfo:
-        unsafe extern "C" fn(hsinfo: *mut IRVersionedHSInfo) -> bool,
+        Result<unsafe extern "C" fn(hsinfo: *mut IRVersionedHSInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseDomainInfo:
-        unsafe extern "C" fn(dsinfo: *mut IRVersionedDSInfo) -> bool,
+        Result<unsafe extern "C" fn(dsinfo: *mut IRVersionedDSInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseMeshInfo:
-        unsafe extern "C" fn(msinfo: *mut IRVersionedMSInfo) -> bool,
+        Result<unsafe extern "C" fn(msinfo: *mut IRVersionedMSInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseAmplificationInfo:
-        unsafe extern "C" fn(asinfo: *mut IRVersionedASInfo) -> bool,
+        Result<unsafe extern "C" fn(asinfo: *mut IRVersionedASInfo) -> bool, ::libloading::Error>,
     pub IRShaderReflectionReleaseRaytracingInfo:
-        unsafe extern "C" fn(rtinfo: *mut IRVersionedRTInfo) -> bool,
-    pub IRShaderReflectionGetResourceCount:
+        Result<unsafe extern "C" fn(rtinfo: *mut IRVersionedRTInfo) -> bool, ::libloading::Error>,
+    pub IRShaderReflectionGetResourceCount: Result<
         unsafe extern "C" fn(reflection: *const IRShaderReflection) -> usize,
-    pub IRShaderReflectionGetResourceLocations: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-        resourceLocations: *mut IRResourceLocation,
-    ),
-    pub IRRootSignatureGetResourceCount:
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionGetResourceLocations: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+            resourceLocations: *mut IRResourceLocation,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRRootSignatureGetResourceCount: Result<
         unsafe extern "C" fn(rootSignature: *const IRRootSignature) -> usize,
-    pub IRRootSignatureGetResourceLocations: unsafe extern "C" fn(
-        rootSignature: *const IRRootSignature,
-        resourceLocations: *mut IRResourceLocation,
-    ),
-    pub IRShaderReflectionAllocStringAndSerialize:
+        ::libloading::Error,
+    >,
+    pub IRRootSignatureGetResourceLocations: Result<
+        unsafe extern "C" fn(
+            rootSignature: *const IRRootSignature,
+            resourceLocations: *mut IRResourceLocation,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionAllocStringAndSerialize: Result<
         unsafe extern "C" fn(reflection: *mut IRShaderReflection) -> *const ::std::os::raw::c_char,
-    pub IRShaderReflectionCopyJSONString: unsafe extern "C" fn(
-        reflection: *const IRShaderReflection,
-    )
-        -> *const ::std::os::raw::c_char,
-    pub IRShaderReflectionFreeString:
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCopyJSONString: Result<
+        unsafe extern "C" fn(
+            reflection: *const IRShaderReflection,
+        ) -> *const ::std::os::raw::c_char,
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionFreeString: Result<
         unsafe extern "C" fn(serialized: *const ::std::os::raw::c_char),
-    pub IRShaderReflectionReleaseString:
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionReleaseString: Result<
         unsafe extern "C" fn(serialized: *const ::std::os::raw::c_char),
-    pub IRShaderReflectionDeserialize: unsafe extern "C" fn(
-        blob: *const ::std::os::raw::c_char,
-        reflection: *mut IRShaderReflection,
-    ),
-    pub IRShaderReflectionCreateFromJSON:
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionDeserialize: Result<
+        unsafe extern "C" fn(
+            blob: *const ::std::os::raw::c_char,
+            reflection: *mut IRShaderReflection,
+        ),
+        ::libloading::Error,
+    >,
+    pub IRShaderReflectionCreateFromJSON: Result<
         unsafe extern "C" fn(json: *const ::std::os::raw::c_char) -> *mut IRShaderReflection,
-    pub IRVersionedRootSignatureDescriptorAllocStringAndSerialize:
+        ::libloading::Error,
+    >,
+    pub IRVersionedRootSignatureDescriptorAllocStringAndSerialize: Result<
         unsafe extern "C" fn(
             rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
         ) -> *const ::std::os::raw::c_char,
-    pub IRVersionedRootSignatureDescriptorCopyJSONString:
+        ::libloading::Error,
+    >,
+    pub IRVersionedRootSignatureDescriptorCopyJSONString: Result<
         unsafe extern "C" fn(
             rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
         ) -> *const ::std::os::raw::c_char,
-    pub IRVersionedRootSignatureDescriptorFreeString:
+        ::libloading::Error,
+    >,
+    pub IRVersionedRootSignatureDescriptorFreeString: Result<
         unsafe extern "C" fn(serialized: *const ::std::os::raw::c_char),
-    pub IRVersionedRootSignatureDescriptorReleaseString:
+        ::libloading::Error,
+    >,
+    pub IRVersionedRootSignatureDescriptorReleaseString: Result<
         unsafe extern "C" fn(serialized: *const ::std::os::raw::c_char),
-    pub IRVersionedRootSignatureDescriptorDeserialize: unsafe extern "C" fn(
-        serialized: *const ::std::os::raw::c_char,
-        rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
-    ) -> bool,
-    pub IRVersionedRootSignatureDescriptorCreateFromJSON:
+        ::libloading::Error,
+    >,
+    pub IRVersionedRootSignatureDescriptorDeserialize: Result<
+        unsafe extern "C" fn(
+            serialized: *const ::std::os::raw::c_char,
+            rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
+        ) -> bool,
+        ::libloading::Error,
+    >,
+    pub IRVersionedRootSignatureDescriptorCreateFromJSON: Result<
         unsafe extern "C" fn(
             serialized: *const ::std::os::raw::c_char,
         ) -> *mut IRVersionedRootSignatureDescriptor,
-    pub IRVersionedRootSignatureDescriptorRelease:
+        ::libloading::Error,
+    >,
+    pub IRVersionedRootSignatureDescriptorRelease: Result<
         unsafe extern "C" fn(rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor),
-    pub IRInputLayoutDescriptor1CopyJSONString:
+        ::libloading::Error,
+    >,
+    pub IRInputLayoutDescriptor1CopyJSONString: Result<
         unsafe extern "C" fn(
             inputLayoutDescriptor: *mut IRInputLayoutDescriptor1,
         ) -> *const ::std::os::raw::c_char,
-    pub IRInputLayoutDescriptor1ReleaseString:
+        ::libloading::Error,
+    >,
+    pub IRInputLayoutDescriptor1ReleaseString: Result<
         unsafe extern "C" fn(serialized: *const ::std::os::raw::c_char),
-    pub IRInputLayoutDescriptor1CreateFromJSON:
+        ::libloading::Error,
+    >,
+    pub IRInputLayoutDescriptor1CreateFromJSON: Result<
         unsafe extern "C" fn(
             serialized: *const ::std::os::raw::c_char,
         ) -> *mut IRInputLayoutDescriptor1,
-    pub IRInputLayoutDescriptor1Release:
+        ::libloading::Error,
+    >,
+    pub IRInputLayoutDescriptor1Release: Result<
         unsafe extern "C" fn(inputLayoutDescriptor: *mut IRInputLayoutDescriptor1),
+        ::libloading::Error,
+    >,
 }
 impl metal_irconverter {
     pub unsafe fn new<P>(path: P) -> Result<Self, ::libloading::Error>
@@ -1427,239 +1592,235 @@ impl metal_irconverter {
         L: Into<::libloading::Library>,
     {
         let __library = library.into();
-        let IRErrorGetCode = __library.get(b"IRErrorGetCode\0").map(|sym| *sym)?;
-        let IRErrorGetPayload = __library.get(b"IRErrorGetPayload\0").map(|sym| *sym)?;
-        let IRErrorDestroy = __library.get(b"IRErrorDestroy\0").map(|sym| *sym)?;
+        let IRErrorGetCode = __library.get(b"IRErrorGetCode\0").map(|sym| *sym);
+        let IRErrorGetPayload = __library.get(b"IRErrorGetPayload\0").map(|sym| *sym);
+        let IRErrorDestroy = __library.get(b"IRErrorDestroy\0").map(|sym| *sym);
         let IRRootSignatureCreateFromDescriptor = __library
             .get(b"IRRootSignatureCreateFromDescriptor\0")
-            .map(|sym| *sym)?;
-        let IRRootSignatureDestroy = __library.get(b"IRRootSignatureDestroy\0").map(|sym| *sym)?;
-        let IRObjectCreateFromDXIL = __library.get(b"IRObjectCreateFromDXIL\0").map(|sym| *sym)?;
-        let IRObjectDestroy = __library.get(b"IRObjectDestroy\0").map(|sym| *sym)?;
-        let IRObjectGetType = __library.get(b"IRObjectGetType\0").map(|sym| *sym)?;
+            .map(|sym| *sym);
+        let IRRootSignatureDestroy = __library.get(b"IRRootSignatureDestroy\0").map(|sym| *sym);
+        let IRObjectCreateFromDXIL = __library.get(b"IRObjectCreateFromDXIL\0").map(|sym| *sym);
+        let IRObjectDestroy = __library.get(b"IRObjectDestroy\0").map(|sym| *sym);
+        let IRObjectGetType = __library.get(b"IRObjectGetType\0").map(|sym| *sym);
         let IRObjectGetMetalIRShaderStage = __library
             .get(b"IRObjectGetMetalIRShaderStage\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetValidationFlags = __library
             .get(b"IRCompilerSetValidationFlags\0")
-            .map(|sym| *sym)?;
-        let IRCompilerCreate = __library.get(b"IRCompilerCreate\0").map(|sym| *sym)?;
-        let IRCompilerDestroy = __library.get(b"IRCompilerDestroy\0").map(|sym| *sym)?;
+            .map(|sym| *sym);
+        let IRCompilerCreate = __library.get(b"IRCompilerCreate\0").map(|sym| *sym);
+        let IRCompilerDestroy = __library.get(b"IRCompilerDestroy\0").map(|sym| *sym);
         let IRCompilerAllocCompileAndLink = __library
             .get(b"IRCompilerAllocCompileAndLink\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerAllocCombineCompileAndLink = __library
             .get(b"IRCompilerAllocCombineCompileAndLink\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRObjectGetMetalLibBinary = __library
             .get(b"IRObjectGetMetalLibBinary\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetStageInGenerationMode = __library
             .get(b"IRCompilerSetStageInGenerationMode\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRMetalLibSynthesizeStageInFunction = __library
             .get(b"IRMetalLibSynthesizeStageInFunction\0")
-            .map(|sym| *sym)?;
-        let IRObjectGetReflection = __library.get(b"IRObjectGetReflection\0").map(|sym| *sym)?;
+            .map(|sym| *sym);
+        let IRObjectGetReflection = __library.get(b"IRObjectGetReflection\0").map(|sym| *sym);
         let IRCompilerSetGlobalRootSignature = __library
             .get(b"IRCompilerSetGlobalRootSignature\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetLocalRootSignature = __library
             .get(b"IRCompilerSetLocalRootSignature\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetHitgroupType = __library
             .get(b"IRCompilerSetHitgroupType\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRObjectGatherRaytracingIntrinsics = __library
             .get(b"IRObjectGatherRaytracingIntrinsics\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetRayTracingPipelineArguments = __library
             .get(b"IRCompilerSetRayTracingPipelineArguments\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetCompatibilityFlags = __library
             .get(b"IRCompilerSetCompatibilityFlags\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetInputTopology = __library
             .get(b"IRCompilerSetInputTopology\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerEnableGeometryAndTessellationEmulation = __library
             .get(b"IRCompilerEnableGeometryAndTessellationEmulation\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetDualSourceBlendingConfiguration = __library
             .get(b"IRCompilerSetDualSourceBlendingConfiguration\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetDepthFeedbackConfiguration = __library
             .get(b"IRCompilerSetDepthFeedbackConfiguration\0")
-            .map(|sym| *sym)?;
-        let IRCompilerSetIntRTMask = __library.get(b"IRCompilerSetIntRTMask\0").map(|sym| *sym)?;
+            .map(|sym| *sym);
+        let IRCompilerSetIntRTMask = __library.get(b"IRCompilerSetIntRTMask\0").map(|sym| *sym);
         let IRMetalLibSynthesizeIndirectRayDispatchFunction = __library
             .get(b"IRMetalLibSynthesizeIndirectRayDispatchFunction\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRMetalLibSynthesizeIndirectIntersectionFunction = __library
             .get(b"IRMetalLibSynthesizeIndirectIntersectionFunction\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetEntryPointName = __library
             .get(b"IRCompilerSetEntryPointName\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetMinimumGPUFamily = __library
             .get(b"IRCompilerSetMinimumGPUFamily\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerIgnoreRootSignature = __library
             .get(b"IRCompilerIgnoreRootSignature\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerIgnoreDebugInformation = __library
             .get(b"IRCompilerIgnoreDebugInformation\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRCompilerSetMinimumDeploymentTarget = __library
             .get(b"IRCompilerSetMinimumDeploymentTarget\0")
-            .map(|sym| *sym)?;
-        let IRMetalLibBinaryCreate = __library.get(b"IRMetalLibBinaryCreate\0").map(|sym| *sym)?;
-        let IRMetalLibBinaryDestroy = __library
-            .get(b"IRMetalLibBinaryDestroy\0")
-            .map(|sym| *sym)?;
-        let IRMetalLibGetBytecode = __library.get(b"IRMetalLibGetBytecode\0").map(|sym| *sym)?;
+            .map(|sym| *sym);
+        let IRMetalLibBinaryCreate = __library.get(b"IRMetalLibBinaryCreate\0").map(|sym| *sym);
+        let IRMetalLibBinaryDestroy = __library.get(b"IRMetalLibBinaryDestroy\0").map(|sym| *sym);
+        let IRMetalLibGetBytecode = __library.get(b"IRMetalLibGetBytecode\0").map(|sym| *sym);
         let IRMetalLibGetBytecodeSize = __library
             .get(b"IRMetalLibGetBytecodeSize\0")
-            .map(|sym| *sym)?;
-        let IRObjectSerialize = __library.get(b"IRObjectSerialize\0").map(|sym| *sym)?;
-        let IRShaderReflectionCreate = __library
-            .get(b"IRShaderReflectionCreate\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
+        let IRObjectSerialize = __library.get(b"IRObjectSerialize\0").map(|sym| *sym);
+        let IRShaderReflectionCreate = __library.get(b"IRShaderReflectionCreate\0").map(|sym| *sym);
         let IRShaderReflectionDestroy = __library
             .get(b"IRShaderReflectionDestroy\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionGetEntryPointFunctionName = __library
             .get(b"IRShaderReflectionGetEntryPointFunctionName\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionNeedsFunctionConstants = __library
             .get(b"IRShaderReflectionNeedsFunctionConstants\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionGetFunctionConstantCount = __library
             .get(b"IRShaderReflectionGetFunctionConstantCount\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyFunctionConstants = __library
             .get(b"IRShaderReflectionCopyFunctionConstants\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseFunctionConstants = __library
             .get(b"IRShaderReflectionReleaseFunctionConstants\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyComputeInfo = __library
             .get(b"IRShaderReflectionCopyComputeInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyVertexInfo = __library
             .get(b"IRShaderReflectionCopyVertexInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyFragmentInfo = __library
             .get(b"IRShaderReflectionCopyFragmentInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyGeometryInfo = __library
             .get(b"IRShaderReflectionCopyGeometryInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyHullInfo = __library
             .get(b"IRShaderReflectionCopyHullInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyDomainInfo = __library
             .get(b"IRShaderReflectionCopyDomainInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyMeshInfo = __library
             .get(b"IRShaderReflectionCopyMeshInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyAmplificationInfo = __library
             .get(b"IRShaderReflectionCopyAmplificationInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyRaytracingInfo = __library
             .get(b"IRShaderReflectionCopyRaytracingInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseComputeInfo = __library
             .get(b"IRShaderReflectionReleaseComputeInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseVertexInfo = __library
             .get(b"IRShaderReflectionReleaseVertexInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseFragmentInfo = __library
             .get(b"IRShaderReflectionReleaseFragmentInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseGeometryInfo = __library
             .get(b"IRShaderReflectionReleaseGeometryInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseHullInfo = __library
             .get(b"IRShaderReflectionReleaseHullInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseDomainInfo = __library
             .get(b"IRShaderReflectionReleaseDomainInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseMeshInfo = __library
             .get(b"IRShaderReflectionReleaseMeshInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseAmplificationInfo = __library
             .get(b"IRShaderReflectionReleaseAmplificationInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseRaytracingInfo = __library
             .get(b"IRShaderReflectionReleaseRaytracingInfo\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionGetResourceCount = __library
             .get(b"IRShaderReflectionGetResourceCount\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionGetResourceLocations = __library
             .get(b"IRShaderReflectionGetResourceLocations\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRRootSignatureGetResourceCount = __library
             .get(b"IRRootSignatureGetResourceCount\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRRootSignatureGetResourceLocations = __library
             .get(b"IRRootSignatureGetResourceLocations\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionAllocStringAndSerialize = __library
             .get(b"IRShaderReflectionAllocStringAndSerialize\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCopyJSONString = __library
             .get(b"IRShaderReflectionCopyJSONString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionFreeString = __library
             .get(b"IRShaderReflectionFreeString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionReleaseString = __library
             .get(b"IRShaderReflectionReleaseString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionDeserialize = __library
             .get(b"IRShaderReflectionDeserialize\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRShaderReflectionCreateFromJSON = __library
             .get(b"IRShaderReflectionCreateFromJSON\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRVersionedRootSignatureDescriptorAllocStringAndSerialize = __library
             .get(b"IRVersionedRootSignatureDescriptorAllocStringAndSerialize\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRVersionedRootSignatureDescriptorCopyJSONString = __library
             .get(b"IRVersionedRootSignatureDescriptorCopyJSONString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRVersionedRootSignatureDescriptorFreeString = __library
             .get(b"IRVersionedRootSignatureDescriptorFreeString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRVersionedRootSignatureDescriptorReleaseString = __library
             .get(b"IRVersionedRootSignatureDescriptorReleaseString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRVersionedRootSignatureDescriptorDeserialize = __library
             .get(b"IRVersionedRootSignatureDescriptorDeserialize\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRVersionedRootSignatureDescriptorCreateFromJSON = __library
             .get(b"IRVersionedRootSignatureDescriptorCreateFromJSON\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRVersionedRootSignatureDescriptorRelease = __library
             .get(b"IRVersionedRootSignatureDescriptorRelease\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRInputLayoutDescriptor1CopyJSONString = __library
             .get(b"IRInputLayoutDescriptor1CopyJSONString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRInputLayoutDescriptor1ReleaseString = __library
             .get(b"IRInputLayoutDescriptor1ReleaseString\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRInputLayoutDescriptor1CreateFromJSON = __library
             .get(b"IRInputLayoutDescriptor1CreateFromJSON\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         let IRInputLayoutDescriptor1Release = __library
             .get(b"IRInputLayoutDescriptor1Release\0")
-            .map(|sym| *sym)?;
+            .map(|sym| *sym);
         Ok(metal_irconverter {
             __library,
             IRErrorGetCode,
@@ -1753,15 +1914,24 @@ impl metal_irconverter {
     }
     #[doc = " Obtain the error code of an error.\n @param error error object to query.\n @return error code."]
     pub unsafe fn IRErrorGetCode(&self, error: *const IRError) -> u32 {
-        (self.IRErrorGetCode)(error)
+        (self
+            .IRErrorGetCode
+            .as_ref()
+            .expect("Expected function, got error."))(error)
     }
     #[doc = " Obtain any payload associated with an error.\n @param error error object to query.\n @return error payload. You must cast this pointer to the appropriate error payload struct for the error code."]
     pub unsafe fn IRErrorGetPayload(&self, error: *const IRError) -> *const ::std::os::raw::c_void {
-        (self.IRErrorGetPayload)(error)
+        (self
+            .IRErrorGetPayload
+            .as_ref()
+            .expect("Expected function, got error."))(error)
     }
     #[doc = " Release resources associated with an error object.\n @param error error objects for which to release its associated resources."]
     pub unsafe fn IRErrorDestroy(&self, error: *mut IRError) {
-        (self.IRErrorDestroy)(error)
+        (self
+            .IRErrorDestroy
+            .as_ref()
+            .expect("Expected function, got error."))(error)
     }
     #[doc = " Create a new root signature from its descriptor.\n @param descriptor description of the root signature to create.\n @param error on return, if the compiler generates any errors, this optional out parameter contains error information. If an error occurs and this parameter is non-NULL, you must free it by calling IRErrorDestroy.\n @return a new root signature suitable for configuring the top-level argument buffer layout of the produced MetalIR, or NULL upon encountering an error. You must destroy this object by calling IRRootSignatureDestroy."]
     pub unsafe fn IRRootSignatureCreateFromDescriptor(
@@ -1769,11 +1939,17 @@ impl metal_irconverter {
         descriptor: *const IRVersionedRootSignatureDescriptor,
         error: *mut *mut IRError,
     ) -> *mut IRRootSignature {
-        (self.IRRootSignatureCreateFromDescriptor)(descriptor, error)
+        (self
+            .IRRootSignatureCreateFromDescriptor
+            .as_ref()
+            .expect("Expected function, got error."))(descriptor, error)
     }
     #[doc = " Destroy a root signature object.\n @param sig root signature object to destroy."]
     pub unsafe fn IRRootSignatureDestroy(&self, sig: *mut IRRootSignature) {
-        (self.IRRootSignatureDestroy)(sig)
+        (self
+            .IRRootSignatureDestroy
+            .as_ref()
+            .expect("Expected function, got error."))(sig)
     }
     #[doc = " Create a DXIL object from DXIL bytecode.\n @param bytecode bytecode representing legal DXIL. When using IRBytecodeOwnershipNone, you must ensure this buffer is not freed while the returned object is in use.\n @param size size of the bytecode in bytes.\n @param bytecodeOwnership determine whether the IRObject shall copy the bytecode or just hold a weak reference.\n @return a new DXIL object representing the DXIL shader. You must destroy this object by calling IRObjectDestroy."]
     pub unsafe fn IRObjectCreateFromDXIL(
@@ -1782,19 +1958,31 @@ impl metal_irconverter {
         size: usize,
         bytecodeOwnership: IRBytecodeOwnership,
     ) -> *mut IRObject {
-        (self.IRObjectCreateFromDXIL)(bytecode, size, bytecodeOwnership)
+        (self
+            .IRObjectCreateFromDXIL
+            .as_ref()
+            .expect("Expected function, got error."))(bytecode, size, bytecodeOwnership)
     }
     #[doc = " Destroy a IRObject.\n @param object IRObject to destroy."]
     pub unsafe fn IRObjectDestroy(&self, object: *mut IRObject) {
-        (self.IRObjectDestroy)(object)
+        (self
+            .IRObjectDestroy
+            .as_ref()
+            .expect("Expected function, got error."))(object)
     }
     #[doc = " Query a IRObject's type.\n @param object IRObject to query.\n @return IRObjectTypeDXILBytecode if the object represents DXIL bytecode, IRObjectTypeMetalIRObject if the object represents MetalIR bytecode."]
     pub unsafe fn IRObjectGetType(&self, object: *const IRObject) -> IRObjectType {
-        (self.IRObjectGetType)(object)
+        (self
+            .IRObjectGetType
+            .as_ref()
+            .expect("Expected function, got error."))(object)
     }
     #[doc = " Query the shader stage of this IRObject.\n @param object IRObject to query.\n @return the shader stage of this IRObject."]
     pub unsafe fn IRObjectGetMetalIRShaderStage(&self, object: *const IRObject) -> IRShaderStage {
-        (self.IRObjectGetMetalIRShaderStage)(object)
+        (self
+            .IRObjectGetMetalIRShaderStage
+            .as_ref()
+            .expect("Expected function, got error."))(object)
     }
     #[doc = " Set a compiler's compile-time validation flags.\n @param compiler compiler for which to set the flags.\n @param validationFlags validation flags denoting the checks the compiler performs."]
     pub unsafe fn IRCompilerSetValidationFlags(
@@ -1802,15 +1990,24 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         validationFlags: IRCompilerValidationFlags,
     ) {
-        (self.IRCompilerSetValidationFlags)(compiler, validationFlags)
+        (self
+            .IRCompilerSetValidationFlags
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, validationFlags)
     }
     #[doc = " Create a new compiler instance.\n @return a new compiler instance. You must destroy this object by calling IRCompilerDestroy."]
     pub unsafe fn IRCompilerCreate(&self) -> *mut IRCompiler {
-        (self.IRCompilerCreate)()
+        (self
+            .IRCompilerCreate
+            .as_ref()
+            .expect("Expected function, got error."))()
     }
     #[doc = " Destroy a compiler instance.\n @param compiler compiler to destroy."]
     pub unsafe fn IRCompilerDestroy(&self, compiler: *mut IRCompiler) {
-        (self.IRCompilerDestroy)(compiler)
+        (self
+            .IRCompilerDestroy
+            .as_ref()
+            .expect("Expected function, got error."))(compiler)
     }
     #[doc = " Allocate a new object and populate it with the results of compiling and linking IR bytecode.\n @note Prior calls to `IRCompilerSetHitgroupType`, `IRCompilerSetRayTracingPipelineArguments`, `IRCompilerSetGlobalRootSignature`, `IRCompilerSetLocalRootSignature` influence the bytecode this function produces.\n @note you need to call `IRCompilerSetRayTracingPipelineArguments` before compiling ray tracing shaders.\n @param compiler compiler to use for the translation process.\n @param entryPointName optional entry point name to compile when converting a library with multiple entry points.\n @param input input IR object.\n @param error on return, if the compiler generates any errors, this optional out parameter contains error information. If an error occurs and this parameter is non-NULL, you must free it by calling IRErrorDestroy.\n @return an IR Object containing MetalIR compiled and linked from the input IR, or NULL if an error occurs. You must destroy this object by calling IRObjectDestroy."]
     pub unsafe fn IRCompilerAllocCompileAndLink(
@@ -1820,7 +2017,10 @@ impl metal_irconverter {
         input: *const IRObject,
         error: *mut *mut IRError,
     ) -> *mut IRObject {
-        (self.IRCompilerAllocCompileAndLink)(compiler, entryPointName, input, error)
+        (self
+            .IRCompilerAllocCompileAndLink
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, entryPointName, input, error)
     }
     #[doc = " Allocate a new object and populate with the results of combining, compiling, and linking IR bytecode of an intersection and an any-hit function.\n This function allows representing the interactions between intersection and any-hit shaders on Metal, where these functions are fused together.\n @warning Ensure you call `IRCompilerSetHitgroupType` prior to this function to specify the intersection type.\n @note One of parameters `intersectionFunctionBytecode` and `anyHitFunctionBytecode` needs to be non-NULL.\n @note If both parameters are non-NULL, this function uses the `anyHitfunctionBytecode` the latter references.\n @note Prior calls to `IRCompilerSetHitgroupType`, `IRCompilerSetRayTracingPipelineArguments`, `IRCompilerSetGlobalRootSignature`, `IRCompilerSetLocalRootSignature` influence the bytecode this function produces.\n @note you need to call `IRCompilerSetRayTracingPipelineArguments` before compiling ray tracing shaders.\n @param compiler compiler to use for the translation process.\n @param intersectionFunctionEntryPointName optional entry point name corresponding to the intersection function.\n @param intersectionFunctionBytecode optional input bytecode that provides the intersection function bytecode.\n @param anyHitFunctionEntryPointName optional entry point name corresponding to the any-hit function.\n @param anyHitFunctionBytecode optional input bytecode that provides the any-hit function\n @param error on return, if the compiler generates any errors, this optional out parameter contains error information. If an error occurs and this parameter is non-NULL, you must free it by calling IRErrorDestroy.\n @return an IR Object containing MetalIR compiled from the input IR, or NULL if an error occurs. You must destroy this object by calling IRObjectDestroy."]
     pub unsafe fn IRCompilerAllocCombineCompileAndLink(
@@ -1832,7 +2032,10 @@ impl metal_irconverter {
         anyHitFunctionBytecode: *const IRObject,
         error: *mut *mut IRError,
     ) -> *mut IRObject {
-        (self.IRCompilerAllocCombineCompileAndLink)(
+        (self
+            .IRCompilerAllocCombineCompileAndLink
+            .as_ref()
+            .expect("Expected function, got error."))(
             compiler,
             intersectionFunctionEntryPointName,
             intersectionFunctionBytecode,
@@ -1848,7 +2051,10 @@ impl metal_irconverter {
         stage: IRShaderStage,
         lib: *mut IRMetalLibBinary,
     ) -> bool {
-        (self.IRObjectGetMetalLibBinary)(obj, stage, lib)
+        (self
+            .IRObjectGetMetalLibBinary
+            .as_ref()
+            .expect("Expected function, got error."))(obj, stage, lib)
     }
     #[doc = " Configure whether the compiler should generate a Metal vertex fetch, or allow synthesizing a separate stage-in function.\n Use Metal vertex fetch to specify a MTLVertexDescriptor to your pipelines at runtime. Request a separate stage-in function to link vertex fetch as a Metal linked function.\n Using a separate stage-in function provides your shader with more flexitibility to perform type conversions, however, it requires more work to set up.\n @param compiler compiler to configure.\n @param stageInCodeGenerationMode code generation mode for the stage-in function."]
     pub unsafe fn IRCompilerSetStageInGenerationMode(
@@ -1856,7 +2062,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         stageInCodeGenerationMode: IRStageInCodeGenerationMode,
     ) {
-        (self.IRCompilerSetStageInGenerationMode)(compiler, stageInCodeGenerationMode)
+        (self
+            .IRCompilerSetStageInGenerationMode
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, stageInCodeGenerationMode)
     }
     #[doc = " Synthesize a stage in function.\n @param compiler compiler configuration to use.\n @param vertexShaderReflection reflection object of a vertex stage containing the inputs used as a base for synthesis.\n @param layout vertex input layout descriptor.\n @param binary pointer to a binary into which to write the synthesized MetalIR.\n @return true if the stageIn function could be successfully created, false otherwise."]
     pub unsafe fn IRMetalLibSynthesizeStageInFunction(
@@ -1866,7 +2075,15 @@ impl metal_irconverter {
         layout: *const IRVersionedInputLayoutDescriptor,
         binary: *mut IRMetalLibBinary,
     ) -> bool {
-        (self.IRMetalLibSynthesizeStageInFunction)(compiler, vertexShaderReflection, layout, binary)
+        (self
+            .IRMetalLibSynthesizeStageInFunction
+            .as_ref()
+            .expect("Expected function, got error."))(
+            compiler,
+            vertexShaderReflection,
+            layout,
+            binary,
+        )
     }
     #[doc = " Copy reflection data stemming from the process of compiling DXIL to MetalIR.\n @param obj MetalIR object containing the metadata to copy.\n @param stage shader stage from where to obtain the reflection.\n @param reflection reflection object into which to copy the reflection data.\n @return true if the metallib binary contains bytecode for this shader stage, false otherwise."]
     pub unsafe fn IRObjectGetReflection(
@@ -1875,7 +2092,10 @@ impl metal_irconverter {
         stage: IRShaderStage,
         reflection: *mut IRShaderReflection,
     ) -> bool {
-        (self.IRObjectGetReflection)(obj, stage, reflection)
+        (self
+            .IRObjectGetReflection
+            .as_ref()
+            .expect("Expected function, got error."))(obj, stage, reflection)
     }
     #[doc = " Configure a compiler to emit a shader that consumes a top-level Argument Buffer layout matching a specific root signature.\n @param compiler compiler to configure.\n @param rootSignature root signature that defines the layout of the top-level Argument Buffer for any shaders you compile with this compiler.\n Pass in NULL (the default) to have the compiler generate a linear resource layout instead. If you provide a non-NULL rootSignature, you must ensure it is not destroyed before the compiler."]
     pub unsafe fn IRCompilerSetGlobalRootSignature(
@@ -1883,7 +2103,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         rootSignature: *const IRRootSignature,
     ) {
-        (self.IRCompilerSetGlobalRootSignature)(compiler, rootSignature)
+        (self
+            .IRCompilerSetGlobalRootSignature
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, rootSignature)
     }
     #[doc = " Configure a compiler to produce a shader that consumes a local root signature matching a specific layout.\n @param compiler compiler to configure.\n @param rootSignature If you provide a non-NULL rootSignature, you must ensure it is not destroyed before the compiler."]
     pub unsafe fn IRCompilerSetLocalRootSignature(
@@ -1891,7 +2114,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         rootSignature: *const IRRootSignature,
     ) {
-        (self.IRCompilerSetLocalRootSignature)(compiler, rootSignature)
+        (self
+            .IRCompilerSetLocalRootSignature
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, rootSignature)
     }
     #[doc = " Configure the hit group type for all subsequent shaders a compiler compiles.\n @param compiler compiler to configure\n @param hitGroupType type of hitgroup the shader expects"]
     pub unsafe fn IRCompilerSetHitgroupType(
@@ -1899,7 +2125,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         hitGroupType: IRHitGroupType,
     ) {
-        (self.IRCompilerSetHitgroupType)(compiler, hitGroupType)
+        (self
+            .IRCompilerSetHitgroupType
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, hitGroupType)
     }
     #[doc = " Analyze an input IR and produce a mask representing all ray tracing intrinsics it references.\n Use this function to gather all intrinsics for your closest hit, any hit, intersection, and callable shaders and produce optimal hit group arguments."]
     pub unsafe fn IRObjectGatherRaytracingIntrinsics(
@@ -1907,7 +2136,10 @@ impl metal_irconverter {
         input: *mut IRObject,
         entryPoint: *const ::std::os::raw::c_char,
     ) -> u64 {
-        (self.IRObjectGatherRaytracingIntrinsics)(input, entryPoint)
+        (self
+            .IRObjectGatherRaytracingIntrinsics
+            .as_ref()
+            .expect("Expected function, got error."))(input, entryPoint)
     }
     #[doc = " Configure a compiler with upfront information to generate an optimal interface between ray tracing functions.\n Calling this function is optional, but when omitted, the compiler needs to assume a worst-case scenario, significantly affecting runtime performance.\n Use function `IRObjectGatherRaytracingIntrinsics` to collect the intrinsic usage mask for all closest hit, any hit, intersection, and callable shaders in the pipeline to build.\n After calling this function, all subsequent shaders compiled need to conform to the masks provided, otherwise undefined behavior occurs.\n Specifying a mask and then adding additional shaders to a pipeline that don't conform to it causes undefined behavior.\n @param compiler compiler to configure\n @param maxAttributeSizeInBytes the maximum number of ray tracing attributes (in bytes) that a pipeline consisting of these shaders uses.\n @param raytracingPipelineFlags flags for the ray tracing pipeline your application builds from these shaders.\n @param chs bitwise OR mask of all closest hit shaders for a ray tracing pipeline your application builds using subsequent converted shaders (defaults to `IRIntrinsicMaskClosestHitAll`). The value must match across all functions of all types used in this RT pipeline.\n @param miss bitwise OR mask of all miss shaders for a ray tracing pipeline your application builds using subsequent converted shaders (defaults to `IRIntrinsicMaskMissShaderAll`). The value must match across all functions of all types used in this RT pipeline.\n @param anyHit bitwise OR mask of all any hit shaders for a ray tracing pipeline your application builds using subsequent converted shaders (defaults to `IRIntrinsicMaskAnyHitShaderAll`). The value must match across all functions of all types used in this RT pipeline.\n @param callableArgs bitwise OR mask of all callable shaders for a ray tracing pipeline your application builds using subsequent converted shaders (defaults to `IRIntrinsicMaskCallableShaderAll`). The value must match across all functions of all types used in this RT pipeline.\n @param maxRecursiveDepth stop point for recursion. Pass `IRRayTracingUnlimitedRecursionDepth` for no limit.\n @param rayGenerationCompilationMode set the ray-generation shader compilation mode to compile either as a compute kernel, or as a visible function for a shader binding table.\n @param intersectionFunctionCompilationMode set the any-hit/intersection function compilation mode to compile either as a visible function or Metal Intersection Function. The value must match across all functions of all types used in this RT pipeline.\n @warning providing mask values other than the defaults or those returned by `IRObjectGatherRaytracingIntrinsics` may cause subsequent shader compilations to fail."]
     pub unsafe fn IRCompilerSetRayTracingPipelineArguments(
@@ -1923,7 +2155,10 @@ impl metal_irconverter {
         rayGenerationCompilationMode: IRRayGenerationCompilationMode,
         intersectionFunctionCompilationMode: IRIntersectionFunctionCompilationMode,
     ) {
-        (self.IRCompilerSetRayTracingPipelineArguments)(
+        (self
+            .IRCompilerSetRayTracingPipelineArguments
+            .as_ref()
+            .expect("Expected function, got error."))(
             compiler,
             maxAttributeSizeInBytes,
             raytracingPipelineFlags,
@@ -1942,7 +2177,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         flags: IRCompatibilityFlags,
     ) {
-        (self.IRCompilerSetCompatibilityFlags)(compiler, flags)
+        (self
+            .IRCompilerSetCompatibilityFlags
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, flags)
     }
     #[doc = " Set primitive input topology\n Provides the compiler with information about input topology this shader will be used with.\n This information is required to correctly compile shaders that will render point primitive and may be used for other optimizations.\n @param compiler the compiler to configure\n @param inputTopology input topology"]
     pub unsafe fn IRCompilerSetInputTopology(
@@ -1950,7 +2188,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         inputTopology: IRInputTopology,
     ) {
-        (self.IRCompilerSetInputTopology)(compiler, inputTopology)
+        (self
+            .IRCompilerSetInputTopology
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, inputTopology)
     }
     #[doc = " Enable geometry and tessellation emulation.\n @param compiler compiler to configure with geometry and tessellation emulation.\n @param enable pass in true to enable geometry and tessellation emulation, false to disable it."]
     pub unsafe fn IRCompilerEnableGeometryAndTessellationEmulation(
@@ -1958,7 +2199,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         enable: bool,
     ) {
-        (self.IRCompilerEnableGeometryAndTessellationEmulation)(compiler, enable)
+        (self
+            .IRCompilerEnableGeometryAndTessellationEmulation
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, enable)
     }
     #[doc = " Enable dual-source blending support.\n When the configuration parameter is set to `IRDualSourceBlendingConfigurationDecideAtRuntime`, you must provide a function constant named \"`dualSourceBlendingEnabled`\" of type `uint8_t` at\n pipeline creation time, specifying whether to enable dual source blending.\n @param compiler compiler to configure.\n @param configuration set to enabled or disable to enable and disable support. Set to decide-at-runtime to control support at PSO-creation time via function constants.\n This parameter is reset after each compilation."]
     pub unsafe fn IRCompilerSetDualSourceBlendingConfiguration(
@@ -1966,7 +2210,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         configuration: IRDualSourceBlendingConfiguration,
     ) {
-        (self.IRCompilerSetDualSourceBlendingConfiguration)(compiler, configuration)
+        (self
+            .IRCompilerSetDualSourceBlendingConfiguration
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, configuration)
     }
     #[doc = " Enable depth feedback support.\n When the configuration parameter is set to `IRDepthWriteConfigurationDecideAtRuntime`, you must provide a function constant named \"`depthFeedbackEnabled`\" of type `uint8_t` at\n pipeline creation time, specifying whether to enable depth feedback.\n @param compiler compiler to configure.\n @param configuration set to enabled or disable to enable and disable support. Set to decide-at-runtime to control support at PSO-creation time via function constants.\n This parameter is reset after each compilation."]
     pub unsafe fn IRCompilerSetDepthFeedbackConfiguration(
@@ -1974,11 +2221,17 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         configuration: IRDepthFeedbackConfiguration,
     ) {
-        (self.IRCompilerSetDepthFeedbackConfiguration)(compiler, configuration)
+        (self
+            .IRCompilerSetDepthFeedbackConfiguration
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, configuration)
     }
     #[doc = " Set Int compatible render target mask.\n Inform the compiler if the underlying render target is int compatible or not. The compiler will inject appropriate bitcast.\n @param compiler compiler to configure.\n @param intRTMask bitmask for each render target - 0 means that the RT is float compatible - 1 means that the RT is int compatible\n This parameter is reset after each compilation."]
     pub unsafe fn IRCompilerSetIntRTMask(&self, compiler: *mut IRCompiler, intRTMask: u8) {
-        (self.IRCompilerSetIntRTMask)(compiler, intRTMask)
+        (self
+            .IRCompilerSetIntRTMask
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, intRTMask)
     }
     #[doc = " Synthesize an ray dispatch function that indirectly dispatches ray generation shaders.\n @param compiler compiler configuration to use.\n @param binary pointer to a binary into which to write the synthesized MetalIR.\n @return true if the intersection function could be successfully synthesized, false otherwise."]
     pub unsafe fn IRMetalLibSynthesizeIndirectRayDispatchFunction(
@@ -1986,7 +2239,10 @@ impl metal_irconverter {
         compiler: *const IRCompiler,
         binary: *mut IRMetalLibBinary,
     ) -> bool {
-        (self.IRMetalLibSynthesizeIndirectRayDispatchFunction)(compiler, binary)
+        (self
+            .IRMetalLibSynthesizeIndirectRayDispatchFunction
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, binary)
     }
     #[doc = " Synthesize a Metal ray tracing intersection function that calls into a visible function representing\n a custom intersection function.\n @param compiler compiler configuration to use. Current hit group and ray tracing configuration influences code generation.\n @param binary pointer to a binary into which to write the synthesized MetalIR.\n @return true if the intersection function could be successfully synthesized, false otherwise."]
     pub unsafe fn IRMetalLibSynthesizeIndirectIntersectionFunction(
@@ -1994,7 +2250,10 @@ impl metal_irconverter {
         compiler: *const IRCompiler,
         binary: *mut IRMetalLibBinary,
     ) -> bool {
-        (self.IRMetalLibSynthesizeIndirectIntersectionFunction)(compiler, binary)
+        (self
+            .IRMetalLibSynthesizeIndirectIntersectionFunction
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, binary)
     }
     #[doc = " Customize the name of the entry point functions generated by a compiler.\n @param compiler compiler to configure.\n @param newName name IRConverter assigns to the emited entry point."]
     pub unsafe fn IRCompilerSetEntryPointName(
@@ -2002,7 +2261,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         newName: *const ::std::os::raw::c_char,
     ) {
-        (self.IRCompilerSetEntryPointName)(compiler, newName)
+        (self
+            .IRCompilerSetEntryPointName
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, newName)
     }
     #[doc = " Set the minimum GPU deployment target for MetalIR code generation.\n Targetting a newer family may enable the compiler to emit MetalIR further optimized for newer GPUs, but may render it incompatible with older models.\n @param compiler compiler to configure.\n @param family minimum GPU family supported by code generation."]
     pub unsafe fn IRCompilerSetMinimumGPUFamily(
@@ -2010,7 +2272,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         family: IRGPUFamily,
     ) {
-        (self.IRCompilerSetMinimumGPUFamily)(compiler, family)
+        (self
+            .IRCompilerSetMinimumGPUFamily
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, family)
     }
     #[doc = " Set compiler settings to ignore root signature when needed\n @param compiler compiler for which to set the flags.\n @param ignoreEmbeddedRootSignature whether embeddedRootSignature should be ignored"]
     pub unsafe fn IRCompilerIgnoreRootSignature(
@@ -2018,7 +2283,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         ignoreEmbeddedRootSignature: bool,
     ) {
-        (self.IRCompilerIgnoreRootSignature)(compiler, ignoreEmbeddedRootSignature)
+        (self
+            .IRCompilerIgnoreRootSignature
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, ignoreEmbeddedRootSignature)
     }
     #[doc = " Set compiler settings to ignore debug information.\n @param compiler compiler for which to set the flags.\n @param ignoreDebugInformation whether dxil debug information should be ignored. Defaults to false."]
     pub unsafe fn IRCompilerIgnoreDebugInformation(
@@ -2026,7 +2294,10 @@ impl metal_irconverter {
         compiler: *mut IRCompiler,
         ignoreDebugInformation: bool,
     ) {
-        (self.IRCompilerIgnoreDebugInformation)(compiler, ignoreDebugInformation)
+        (self
+            .IRCompilerIgnoreDebugInformation
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, ignoreDebugInformation)
     }
     #[doc = " Set the minimum operating system software version target for Metal IR code generation.\n Targetting a newer software version may enable the compiler to emi MetalIR further optimized for newer macOS and iOS releases, but it may render it incompatible with older operating system versions.\n Setting a minimum deployment target newer than your SDK may produce an `IRErrorCodeUnableToLinkModule` error.\n @param compiler compiler to configure.\n @param operatingSystem operating system name.\n @param version operating system version, such as \"13.0.0\" or \"16.0.0\"."]
     pub unsafe fn IRCompilerSetMinimumDeploymentTarget(
@@ -2035,15 +2306,24 @@ impl metal_irconverter {
         operatingSystem: IROperatingSystem,
         version: *const ::std::os::raw::c_char,
     ) {
-        (self.IRCompilerSetMinimumDeploymentTarget)(compiler, operatingSystem, version)
+        (self
+            .IRCompilerSetMinimumDeploymentTarget
+            .as_ref()
+            .expect("Expected function, got error."))(compiler, operatingSystem, version)
     }
     #[doc = " Create an empty metallib binary.\n @return a new, empty metallib binary."]
     pub unsafe fn IRMetalLibBinaryCreate(&self) -> *mut IRMetalLibBinary {
-        (self.IRMetalLibBinaryCreate)()
+        (self
+            .IRMetalLibBinaryCreate
+            .as_ref()
+            .expect("Expected function, got error."))()
     }
     #[doc = " Destroy a metallib binary.\n @param lib library to destroy."]
     pub unsafe fn IRMetalLibBinaryDestroy(&self, lib: *mut IRMetalLibBinary) {
-        (self.IRMetalLibBinaryDestroy)(lib)
+        (self
+            .IRMetalLibBinaryDestroy
+            .as_ref()
+            .expect("Expected function, got error."))(lib)
     }
     #[doc = " Copy the bytecode from a metallib library into a byte array.\n @param lib metallib library from where to copy the bytecode.\n @param outBytecode into which to write the bytecode. This parameter must be an array of at least IRMetalLibGetBytecodeSize bytes.\n @return number of bytes written."]
     pub unsafe fn IRMetalLibGetBytecode(
@@ -2051,11 +2331,17 @@ impl metal_irconverter {
         lib: *const IRMetalLibBinary,
         outBytecode: *mut u8,
     ) -> usize {
-        (self.IRMetalLibGetBytecode)(lib, outBytecode)
+        (self
+            .IRMetalLibGetBytecode
+            .as_ref()
+            .expect("Expected function, got error."))(lib, outBytecode)
     }
     #[doc = " Obtain the number of bytes needed to store the bytecode in a metallib.\n @param lib metallib to query.\n @return size in bytes needed to store the metallib's bytecode."]
     pub unsafe fn IRMetalLibGetBytecodeSize(&self, lib: *const IRMetalLibBinary) -> usize {
-        (self.IRMetalLibGetBytecodeSize)(lib)
+        (self
+            .IRMetalLibGetBytecodeSize
+            .as_ref()
+            .expect("Expected function, got error."))(lib)
     }
     #[doc = " Serialize a MetalIR object's shader bytecode to disk.\n @param outputPath path into which to write the serialized MetalIR.\n @param obj IRObject containing the bytecode to serialize.\n @param stage shader stage to serialize."]
     pub unsafe fn IRObjectSerialize(
@@ -2064,36 +2350,54 @@ impl metal_irconverter {
         obj: *const IRObject,
         stage: IRShaderStage,
     ) -> bool {
-        (self.IRObjectSerialize)(outputPath, obj, stage)
+        (self
+            .IRObjectSerialize
+            .as_ref()
+            .expect("Expected function, got error."))(outputPath, obj, stage)
     }
     #[doc = " Create an empty reflection object.\n @return a new empty reflection object."]
     pub unsafe fn IRShaderReflectionCreate(&self) -> *mut IRShaderReflection {
-        (self.IRShaderReflectionCreate)()
+        (self
+            .IRShaderReflectionCreate
+            .as_ref()
+            .expect("Expected function, got error."))()
     }
     #[doc = " Release a reflection object.\n @param reflection reflection object to release."]
     pub unsafe fn IRShaderReflectionDestroy(&self, reflection: *mut IRShaderReflection) {
-        (self.IRShaderReflectionDestroy)(reflection)
+        (self
+            .IRShaderReflectionDestroy
+            .as_ref()
+            .expect("Expected function, got error."))(reflection)
     }
     #[doc = " Obtain the name of the entry point from a reflection object.\n @return pointer to the name of the entry point. Do not free this pointer, its lifecycle is managed by, and coincides with, the reflection object's."]
     pub unsafe fn IRShaderReflectionGetEntryPointFunctionName(
         &self,
         reflection: *const IRShaderReflection,
     ) -> *const ::std::os::raw::c_char {
-        (self.IRShaderReflectionGetEntryPointFunctionName)(reflection)
+        (self
+            .IRShaderReflectionGetEntryPointFunctionName
+            .as_ref()
+            .expect("Expected function, got error."))(reflection)
     }
     #[doc = " Determine whether the shader requires supplemental information to operate correctly.\n @param reflection reflection object to evaluate.\n @return true if the compiled shader requires supplemental information in the form of function constants, false otherwise."]
     pub unsafe fn IRShaderReflectionNeedsFunctionConstants(
         &self,
         reflection: *const IRShaderReflection,
     ) -> bool {
-        (self.IRShaderReflectionNeedsFunctionConstants)(reflection)
+        (self
+            .IRShaderReflectionNeedsFunctionConstants
+            .as_ref()
+            .expect("Expected function, got error."))(reflection)
     }
     #[doc = " Obtain the number of function constants in the reflection object.\n @param reflection reflection object to query.\n @return function constant count. May be zero if the shader stage to which this reflection corresponds has no function constants."]
     pub unsafe fn IRShaderReflectionGetFunctionConstantCount(
         &self,
         reflection: *const IRShaderReflection,
     ) -> usize {
-        (self.IRShaderReflectionGetFunctionConstantCount)(reflection)
+        (self
+            .IRShaderReflectionGetFunctionConstantCount
+            .as_ref()
+            .expect("Expected function, got error."))(reflection)
     }
     #[doc = " Copy function constant reflection data from a reflection object to an array.\n You are responsible for calling IRShaderReflectionReleaseFunctionConstants on the array to ensure copied function constant reflection data are released.\n @param reflection reflection object to query.\n @param functionConstants function constants array into which to write the reflection data. This object needs to be able to store at least\n IRShaderReflectionFunctionConstantCount function constants."]
     pub unsafe fn IRShaderReflectionCopyFunctionConstants(
@@ -2101,7 +2405,10 @@ impl metal_irconverter {
         reflection: *const IRShaderReflection,
         functionConstants: *mut IRFunctionConstant,
     ) {
-        (self.IRShaderReflectionCopyFunctionConstants)(reflection, functionConstants)
+        (self
+            .IRShaderReflectionCopyFunctionConstants
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, functionConstants)
     }
     #[doc = " Release function constant data.\n @param functionConstants array of function constants.\n @param functionConstantCount number of function constants in the array."]
     pub unsafe fn IRShaderReflectionReleaseFunctionConstants(
@@ -2109,7 +2416,12 @@ impl metal_irconverter {
         functionConstants: *mut IRFunctionConstant,
         functionConstantCount: usize,
     ) {
-        (self.IRShaderReflectionReleaseFunctionConstants)(functionConstants, functionConstantCount)
+        (self
+            .IRShaderReflectionReleaseFunctionConstants
+            .as_ref()
+            .expect("Expected function, got error."))(
+            functionConstants, functionConstantCount
+        )
     }
     #[doc = " Copy shader reflection for a compute stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param csinfo pointer to a versioned CS Info struct into which to copy the reflection data for the stage. You must release the contents of this stuct by calling IRShaderReflectionReleaseComputeInfo.\n @return true if the reflection object contains compute reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyComputeInfo(
@@ -2118,7 +2430,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         csinfo: *mut IRVersionedCSInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyComputeInfo)(reflection, version, csinfo)
+        (self
+            .IRShaderReflectionCopyComputeInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, csinfo)
     }
     #[doc = " Copy shader reflection for a vertex stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param vsinfo pointer to a versioned VS Info struct into which to copy the reflection data for the stage.  You must release the contents of this stuct by calling IRShaderReflectionReleaseVertexInfo.\n @return true if the reflection object contains vertex stage reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyVertexInfo(
@@ -2127,7 +2442,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         vsinfo: *mut IRVersionedVSInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyVertexInfo)(reflection, version, vsinfo)
+        (self
+            .IRShaderReflectionCopyVertexInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, vsinfo)
     }
     #[doc = " Copy shader reflection for a fragment stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param fsinfo pointer to a versioned FS Info struct into which to copy the reflection data for the stage.  You must release the contents of this stuct by calling IRShaderReflectionReleaseFragmentInfo.\n @return true if the reflection object contains fragment stage reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyFragmentInfo(
@@ -2136,7 +2454,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         fsinfo: *mut IRVersionedFSInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyFragmentInfo)(reflection, version, fsinfo)
+        (self
+            .IRShaderReflectionCopyFragmentInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, fsinfo)
     }
     #[doc = " Copy shader reflection for a geometry stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param gsinfo pointer to a versioned GS Info struct into which to copy the reflection data for the stage. You must release the contents of this stuct by calling IRShaderReflectionReleaseGeometryInfo.\n @return true if the reflection object contains geometry stage reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyGeometryInfo(
@@ -2145,7 +2466,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         gsinfo: *mut IRVersionedGSInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyGeometryInfo)(reflection, version, gsinfo)
+        (self
+            .IRShaderReflectionCopyGeometryInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, gsinfo)
     }
     #[doc = " Copy shader reflection for a hull shader stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param hsinfo pointer to a versioned HS Info struct into which to copy the reflection data for the stage. You must release the contents of this stuct by calling IRShaderReflectionReleaseHullInfo.\n @return true if the reflection object contains hull shader stage reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyHullInfo(
@@ -2154,7 +2478,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         hsinfo: *mut IRVersionedHSInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyHullInfo)(reflection, version, hsinfo)
+        (self
+            .IRShaderReflectionCopyHullInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, hsinfo)
     }
     #[doc = " Copy shader reflection for a domain shader stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param dsinfo pointer to a versioned DS Info struct into which to copy the reflection data for the stage.  You must release the contents of this stuct by calling IRShaderReflectionReleaseDomainInfo.\n @return true if the reflection object contains domain shader stage reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyDomainInfo(
@@ -2163,7 +2490,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         dsinfo: *mut IRVersionedDSInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyDomainInfo)(reflection, version, dsinfo)
+        (self
+            .IRShaderReflectionCopyDomainInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, dsinfo)
     }
     #[doc = " Copy shader reflection for a mesh shader stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param msinfo pointer to a versioned MS Info struct into which to copy the reflection data for the stage.  You must release the contents of this stuct by calling IRShaderReflectionReleaseMeshInfo.\n @return true if the reflection object contains domain shader stage reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyMeshInfo(
@@ -2172,7 +2502,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         msinfo: *mut IRVersionedMSInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyMeshInfo)(reflection, version, msinfo)
+        (self
+            .IRShaderReflectionCopyMeshInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, msinfo)
     }
     #[doc = " Copy shader reflection for an amplification shader stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param asinfo pointer to a versioned AS Info struct into which to copy the reflection data for the stage.  You must release the contents of this stuct by calling IRShaderReflectionReleaseAmplificationInfo.\n @return true if the reflection object contains domain shader stage reflection information for the specified version."]
     pub unsafe fn IRShaderReflectionCopyAmplificationInfo(
@@ -2181,7 +2514,10 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         asinfo: *mut IRVersionedASInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyAmplificationInfo)(reflection, version, asinfo)
+        (self
+            .IRShaderReflectionCopyAmplificationInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, asinfo)
     }
     #[doc = " Copy shader reflection for a ray tracing shader stage.\n @param reflection reflection object to query.\n @param version version of the reflection data to obtain.\n @param rtinfo pointer to a versioned RT Info struct into which to copy the reflection data for the stage.  You must release the contents of this struct by calling IRShaderReflectionReleaseRaytracingInfo.\n @return true if the reflection object contains reflection for a ray tracing stage for the specified version."]
     pub unsafe fn IRShaderReflectionCopyRaytracingInfo(
@@ -2190,71 +2526,104 @@ impl metal_irconverter {
         version: IRReflectionVersion,
         rtinfo: *mut IRVersionedRTInfo,
     ) -> bool {
-        (self.IRShaderReflectionCopyRaytracingInfo)(reflection, version, rtinfo)
+        (self
+            .IRShaderReflectionCopyRaytracingInfo
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, version, rtinfo)
     }
     #[doc = " Release versioned compute information.\n @param csinfo pointer to the compute shader reflection information to release.\n @return false if the csinfo version is an unrecognized version or the csinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseComputeInfo(
         &self,
         csinfo: *mut IRVersionedCSInfo,
     ) -> bool {
-        (self.IRShaderReflectionReleaseComputeInfo)(csinfo)
+        (self
+            .IRShaderReflectionReleaseComputeInfo
+            .as_ref()
+            .expect("Expected function, got error."))(csinfo)
     }
     #[doc = " Release versioned vertex stage information.\n @param vsinfo pointer to the vertex shader reflection information to release.\n @return false if the vsinfo version is an unrecognized version or the vsinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseVertexInfo(
         &self,
         vsinfo: *mut IRVersionedVSInfo,
     ) -> bool {
-        (self.IRShaderReflectionReleaseVertexInfo)(vsinfo)
+        (self
+            .IRShaderReflectionReleaseVertexInfo
+            .as_ref()
+            .expect("Expected function, got error."))(vsinfo)
     }
     #[doc = " Release versioned fragment stage information.\n @param fsinfo pointer to the fragment shader reflection information to release.\n @return false if the fsinfo version is an unrecognized version or the fsinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseFragmentInfo(
         &self,
         fsinfo: *mut IRVersionedFSInfo,
     ) -> bool {
-        (self.IRShaderReflectionReleaseFragmentInfo)(fsinfo)
+        (self
+            .IRShaderReflectionReleaseFragmentInfo
+            .as_ref()
+            .expect("Expected function, got error."))(fsinfo)
     }
     #[doc = " Release versioned geometry stage information.\n @param gsinfo pointer to the geometry shader reflection information to release.\n @return false if the gsinfo version is an unrecognized version or the gsinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseGeometryInfo(
         &self,
         gsinfo: *mut IRVersionedGSInfo,
     ) -> bool {
-        (self.IRShaderReflectionReleaseGeometryInfo)(gsinfo)
+        (self
+            .IRShaderReflectionReleaseGeometryInfo
+            .as_ref()
+            .expect("Expected function, got error."))(gsinfo)
     }
     #[doc = " Release versioned hull stage information.\n @param hsinfo pointer to the hull shader reflection information to release.\n @return false if the hsinfo version is an unrecognized version or the hsinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseHullInfo(&self, hsinfo: *mut IRVersionedHSInfo) -> bool {
-        (self.IRShaderReflectionReleaseHullInfo)(hsinfo)
+        (self
+            .IRShaderReflectionReleaseHullInfo
+            .as_ref()
+            .expect("Expected function, got error."))(hsinfo)
     }
     #[doc = " Release versioned domain stage information.\n @param dsinfo pointer to the domain shader reflection information to release.\n @return false if the dsinfo version is an unrecognized version or the dsinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseDomainInfo(
         &self,
         dsinfo: *mut IRVersionedDSInfo,
     ) -> bool {
-        (self.IRShaderReflectionReleaseDomainInfo)(dsinfo)
+        (self
+            .IRShaderReflectionReleaseDomainInfo
+            .as_ref()
+            .expect("Expected function, got error."))(dsinfo)
     }
     #[doc = " Release versioned mesh stage information.\n @param msinfo pointer to the mesh shader reflection information to release.\n @return false if the msinfo version is an unrecognized version or the msinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseMeshInfo(&self, msinfo: *mut IRVersionedMSInfo) -> bool {
-        (self.IRShaderReflectionReleaseMeshInfo)(msinfo)
+        (self
+            .IRShaderReflectionReleaseMeshInfo
+            .as_ref()
+            .expect("Expected function, got error."))(msinfo)
     }
     #[doc = " Release versioned amplification stage information.\n @param asinfo pointer to the amplification shader reflection information to release.\n @return false if the asinfo version is an unrecognized version or the asinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseAmplificationInfo(
         &self,
         asinfo: *mut IRVersionedASInfo,
     ) -> bool {
-        (self.IRShaderReflectionReleaseAmplificationInfo)(asinfo)
+        (self
+            .IRShaderReflectionReleaseAmplificationInfo
+            .as_ref()
+            .expect("Expected function, got error."))(asinfo)
     }
     #[doc = " Release versioned ray tracing stage information.\n @param rtinfo pointer to the ray tracing shader reflection information to release.\n @return false if the rtinfo version is an unrecognized version or the rtinfo pointer is null."]
     pub unsafe fn IRShaderReflectionReleaseRaytracingInfo(
         &self,
         rtinfo: *mut IRVersionedRTInfo,
     ) -> bool {
-        (self.IRShaderReflectionReleaseRaytracingInfo)(rtinfo)
+        (self
+            .IRShaderReflectionReleaseRaytracingInfo
+            .as_ref()
+            .expect("Expected function, got error."))(rtinfo)
     }
     #[doc = " Obtain the number of resources referenced by the top-level argument buffer\n @param reflection the reflection object for which to obtain the resource count.\n @return number of resources the top-level argument buffer references."]
     pub unsafe fn IRShaderReflectionGetResourceCount(
         &self,
         reflection: *const IRShaderReflection,
     ) -> usize {
-        (self.IRShaderReflectionGetResourceCount)(reflection)
+        (self
+            .IRShaderReflectionGetResourceCount
+            .as_ref()
+            .expect("Expected function, got error."))(reflection)
     }
     #[doc = " Get the locations within the top-level Argument Buffer for all top-level resources.\n @param reflection the reflection object resulting from the compilation process.\n @param resourceLocations parameter into which to write resource locations. This array must contain enough storage to write IRShaderReflectionGetResourceCount() elements.\n @note string references within the resource locations are pointers into the reflection object. The reflection object's lifecycle must be preserved while accessing these strings. Names may be NULL."]
     pub unsafe fn IRShaderReflectionGetResourceLocations(
@@ -2262,14 +2631,20 @@ impl metal_irconverter {
         reflection: *const IRShaderReflection,
         resourceLocations: *mut IRResourceLocation,
     ) {
-        (self.IRShaderReflectionGetResourceLocations)(reflection, resourceLocations)
+        (self
+            .IRShaderReflectionGetResourceLocations
+            .as_ref()
+            .expect("Expected function, got error."))(reflection, resourceLocations)
     }
     #[doc = " Obtain the number of resources referenced in a top-level Argument Buffer using a hierarchical layout.\n @param rootSignature root signature corresponding to the hierarchical layout definition.\n @return number of resources the top-level argument buffer references."]
     pub unsafe fn IRRootSignatureGetResourceCount(
         &self,
         rootSignature: *const IRRootSignature,
     ) -> usize {
-        (self.IRRootSignatureGetResourceCount)(rootSignature)
+        (self
+            .IRRootSignatureGetResourceCount
+            .as_ref()
+            .expect("Expected function, got error."))(rootSignature)
     }
     #[doc = " Get the locations of resources in the top-level Argument Buffer.\n @param rootSignature root signature corresponding to the hierarchical layout definition.\n @param resourceLocations parameter into which to write resource locations. This array must contain enough storage to write IRRootSignatureGetResourceCount() elements."]
     pub unsafe fn IRRootSignatureGetResourceLocations(
@@ -2277,32 +2652,47 @@ impl metal_irconverter {
         rootSignature: *const IRRootSignature,
         resourceLocations: *mut IRResourceLocation,
     ) {
-        (self.IRRootSignatureGetResourceLocations)(rootSignature, resourceLocations)
+        (self
+            .IRRootSignatureGetResourceLocations
+            .as_ref()
+            .expect("Expected function, got error."))(rootSignature, resourceLocations)
     }
     #[doc = " Serialize reflection information into JSON.\n @param reflection reflection object.\n @return null-terminated string containing JSON. You need to release this string by calling IRShaderReflectionFreeString.\n @deprecated use IRShaderReflectionCopyJSONString instead."]
     pub unsafe fn IRShaderReflectionAllocStringAndSerialize(
         &self,
         reflection: *mut IRShaderReflection,
     ) -> *const ::std::os::raw::c_char {
-        (self.IRShaderReflectionAllocStringAndSerialize)(reflection)
+        (self
+            .IRShaderReflectionAllocStringAndSerialize
+            .as_ref()
+            .expect("Expected function, got error."))(reflection)
     }
     #[doc = " Serialize reflection information into JSON.\n @param reflection reflection object.\n @return null-terminated string containing JSON. You need to release this string by calling IRShaderReflectionFreeString."]
     pub unsafe fn IRShaderReflectionCopyJSONString(
         &self,
         reflection: *const IRShaderReflection,
     ) -> *const ::std::os::raw::c_char {
-        (self.IRShaderReflectionCopyJSONString)(reflection)
+        (self
+            .IRShaderReflectionCopyJSONString
+            .as_ref()
+            .expect("Expected function, got error."))(reflection)
     }
     #[doc = " Release a string allocated by IRShaderReflectionAllocStringAndSerialize.\n @param serialized string to release.\n @deprecated use IRShaderReflectionReleaseString instead."]
     pub unsafe fn IRShaderReflectionFreeString(&self, serialized: *const ::std::os::raw::c_char) {
-        (self.IRShaderReflectionFreeString)(serialized)
+        (self
+            .IRShaderReflectionFreeString
+            .as_ref()
+            .expect("Expected function, got error."))(serialized)
     }
     #[doc = " Release a string allocated by IRShaderReflectionAllocStringAndSerialize.\n @param serialized string to release."]
     pub unsafe fn IRShaderReflectionReleaseString(
         &self,
         serialized: *const ::std::os::raw::c_char,
     ) {
-        (self.IRShaderReflectionReleaseString)(serialized)
+        (self
+            .IRShaderReflectionReleaseString
+            .as_ref()
+            .expect("Expected function, got error."))(serialized)
     }
     #[doc = " Deserialize a JSON string into a reflection object.\n @param blob null-terminated JSON string containing reflection information.\n @param reflection reflection object into which to deserialize.\n @deprecated use IRShaderReflectionCreateFromJSON instead."]
     pub unsafe fn IRShaderReflectionDeserialize(
@@ -2310,42 +2700,60 @@ impl metal_irconverter {
         blob: *const ::std::os::raw::c_char,
         reflection: *mut IRShaderReflection,
     ) {
-        (self.IRShaderReflectionDeserialize)(blob, reflection)
+        (self
+            .IRShaderReflectionDeserialize
+            .as_ref()
+            .expect("Expected function, got error."))(blob, reflection)
     }
     #[doc = " Deserialize a JSON string into a reflection object.\n @param json null-terminated JSON string containing reflection information.\n @return a newly-allocated shader reflection object that you need to release by calling IRShaderReflectionDestroy,\n or NULL on error."]
     pub unsafe fn IRShaderReflectionCreateFromJSON(
         &self,
         json: *const ::std::os::raw::c_char,
     ) -> *mut IRShaderReflection {
-        (self.IRShaderReflectionCreateFromJSON)(json)
+        (self
+            .IRShaderReflectionCreateFromJSON
+            .as_ref()
+            .expect("Expected function, got error."))(json)
     }
     #[doc = " Serialize a root signature descriptor into a string representation.\n @param rootSignatureDescriptor root signature descriptor to serialize.\n @return a string representation of the root signature descriptor. You need to release this string by calling IRVersionedRootSignatureDescriptorFreeString.\n @deprecated use IRVersionedRootSignatureDescriptorCopyJSONString instead."]
     pub unsafe fn IRVersionedRootSignatureDescriptorAllocStringAndSerialize(
         &self,
         rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
     ) -> *const ::std::os::raw::c_char {
-        (self.IRVersionedRootSignatureDescriptorAllocStringAndSerialize)(rootSignatureDescriptor)
+        (self
+            .IRVersionedRootSignatureDescriptorAllocStringAndSerialize
+            .as_ref()
+            .expect("Expected function, got error."))(rootSignatureDescriptor)
     }
     #[doc = " Serialize a root signature descriptor into a string representation.\n @param rootSignatureDescriptor root signature descriptor to serialize.\n @return a string representation of the root signature descriptor. You need to release this string by calling IRVersionedRootSignatureDescriptorFreeString."]
     pub unsafe fn IRVersionedRootSignatureDescriptorCopyJSONString(
         &self,
         rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
     ) -> *const ::std::os::raw::c_char {
-        (self.IRVersionedRootSignatureDescriptorCopyJSONString)(rootSignatureDescriptor)
+        (self
+            .IRVersionedRootSignatureDescriptorCopyJSONString
+            .as_ref()
+            .expect("Expected function, got error."))(rootSignatureDescriptor)
     }
     #[doc = " Release a string allocated by IRVersionedRootSignatureDescriptorAllocStringAndSerialize.\n @param serialized string to release.\n @deprecated use IRVersionedRootSignatureDescriptorReleaseString instead."]
     pub unsafe fn IRVersionedRootSignatureDescriptorFreeString(
         &self,
         serialized: *const ::std::os::raw::c_char,
     ) {
-        (self.IRVersionedRootSignatureDescriptorFreeString)(serialized)
+        (self
+            .IRVersionedRootSignatureDescriptorFreeString
+            .as_ref()
+            .expect("Expected function, got error."))(serialized)
     }
     #[doc = " Release a string allocated by IRVersionedRootSignatureDescriptorAllocStringAndSerialize.\n @param serialized string to release."]
     pub unsafe fn IRVersionedRootSignatureDescriptorReleaseString(
         &self,
         serialized: *const ::std::os::raw::c_char,
     ) {
-        (self.IRVersionedRootSignatureDescriptorReleaseString)(serialized)
+        (self
+            .IRVersionedRootSignatureDescriptorReleaseString
+            .as_ref()
+            .expect("Expected function, got error."))(serialized)
     }
     #[doc = " Deserialize a string representation of a root signature into a root signature object.\n @param serialized a string representation of a root signature.\n @param rootSignatureDescriptor root signature object into which to deserialize the root signature.\n @return true if deserialization is successful, false otherwise.\n @warning this function may allocate memory, call IRVersionedRootSignatureDescriptorReleaseArrays to deallocate any allocated memory.\n @deprecated use IRVersionedRootSignatureDescriptorCreateFromJSON instead."]
     pub unsafe fn IRVersionedRootSignatureDescriptorDeserialize(
@@ -2353,48 +2761,69 @@ impl metal_irconverter {
         serialized: *const ::std::os::raw::c_char,
         rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
     ) -> bool {
-        (self.IRVersionedRootSignatureDescriptorDeserialize)(serialized, rootSignatureDescriptor)
+        (self
+            .IRVersionedRootSignatureDescriptorDeserialize
+            .as_ref()
+            .expect("Expected function, got error."))(serialized, rootSignatureDescriptor)
     }
     #[doc = " Deserialize a string representation of a root signature into a root signature object.\n @param serialized a string representation of a root signature.\n @return a newly-allocated root signature object that you need to release, or NULL on error."]
     pub unsafe fn IRVersionedRootSignatureDescriptorCreateFromJSON(
         &self,
         serialized: *const ::std::os::raw::c_char,
     ) -> *mut IRVersionedRootSignatureDescriptor {
-        (self.IRVersionedRootSignatureDescriptorCreateFromJSON)(serialized)
+        (self
+            .IRVersionedRootSignatureDescriptorCreateFromJSON
+            .as_ref()
+            .expect("Expected function, got error."))(serialized)
     }
     #[doc = " Release any arrays allocated by IRVersionedRootSignatureDescriptorDeserialize.\n @param rootSignatureDescriptor root signature descriptor to release."]
     pub unsafe fn IRVersionedRootSignatureDescriptorRelease(
         &self,
         rootSignatureDescriptor: *mut IRVersionedRootSignatureDescriptor,
     ) {
-        (self.IRVersionedRootSignatureDescriptorRelease)(rootSignatureDescriptor)
+        (self
+            .IRVersionedRootSignatureDescriptorRelease
+            .as_ref()
+            .expect("Expected function, got error."))(rootSignatureDescriptor)
     }
     #[doc = " Serialize an input layout descriptor version 1 into a string.\n @param inputLayoutDescriptor descriptor to serialize.\n @return a string representation of the input layout descriptor. You need to release this string by calling IRInputLayoutDescriptor1FreeString."]
     pub unsafe fn IRInputLayoutDescriptor1CopyJSONString(
         &self,
         inputLayoutDescriptor: *mut IRInputLayoutDescriptor1,
     ) -> *const ::std::os::raw::c_char {
-        (self.IRInputLayoutDescriptor1CopyJSONString)(inputLayoutDescriptor)
+        (self
+            .IRInputLayoutDescriptor1CopyJSONString
+            .as_ref()
+            .expect("Expected function, got error."))(inputLayoutDescriptor)
     }
     #[doc = " Release a string allocated by IRInputLayoutDescriptor1CopyJSONString.\n @param serialized string to release."]
     pub unsafe fn IRInputLayoutDescriptor1ReleaseString(
         &self,
         serialized: *const ::std::os::raw::c_char,
     ) {
-        (self.IRInputLayoutDescriptor1ReleaseString)(serialized)
+        (self
+            .IRInputLayoutDescriptor1ReleaseString
+            .as_ref()
+            .expect("Expected function, got error."))(serialized)
     }
     #[doc = " Deserialize a string representation of an input layout descriptor version 1 into an IRInputLayoutDescriptor1 structure.\n @param serialized a string representation of an input layout descriptor version 1.\n @return a newly-allocated input layout descriptor version 1 object that you need to release by calling IRInputLayoutDescriptor1Release,\n NULL if an error occurs."]
     pub unsafe fn IRInputLayoutDescriptor1CreateFromJSON(
         &self,
         serialized: *const ::std::os::raw::c_char,
     ) -> *mut IRInputLayoutDescriptor1 {
-        (self.IRInputLayoutDescriptor1CreateFromJSON)(serialized)
+        (self
+            .IRInputLayoutDescriptor1CreateFromJSON
+            .as_ref()
+            .expect("Expected function, got error."))(serialized)
     }
     #[doc = " Release an IRInputDescriptor1 instance allocated by IRInputLayoutDescriptor1CreateFromJSON.\n @param inputLayoutDescriptor input layout descriptor to release."]
     pub unsafe fn IRInputLayoutDescriptor1Release(
         &self,
         inputLayoutDescriptor: *mut IRInputLayoutDescriptor1,
     ) {
-        (self.IRInputLayoutDescriptor1Release)(inputLayoutDescriptor)
+        (self
+            .IRInputLayoutDescriptor1Release
+            .as_ref()
+            .expect("Expected function, got error."))(inputLayoutDescriptor)
     }
 }
@@ -0,0 +1,206 @@
+//! Writing shader asset bundles: one file holding the `metallib`, [`ReflectionSnapshot`] and root
+//! signature of every shader, indexed by shader name and permutation.
+//!
+//! ```no_run
+//! # fn example(table: &saxaboom::permutations::PermutationTable) -> Result<(), saxaboom::Error> {
+//! use saxaboom::bundle::BundleWriter;
+//!
+//! let mut bundle = BundleWriter::new();
+//! bundle.add_root_signature("global", std::fs::read("global.rootsig.json").unwrap())?;
+//! bundle.add_permutations("material", table, Some("global"))?;
+//! std::fs::write("shaders.bundle", bundle.write()).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Bundles are read by `saxaboom_runtime::bundle`, which does not need the converter library and
+//! borrows every `metallib` straight from the (memory-mapped) file.
+//!
+//! # Format
+//!
+//! All integers are little-endian.  Strings and data are referred to by a `u64` offset from the
+//! start of the file followed by a `u64` length.
+//!
+//! | Size          | Contents                                                               |
+//! |---------------|------------------------------------------------------------------------|
+//! | 4             | [`MAGIC`]                                                              |
+//! | 4             | [`VERSION`]                                                            |
+//! | 4             | Number of root signatures                                              |
+//! | 4             | Number of shaders                                                      |
+//! | 32 per item   | Root signatures: name and data                                         |
+//! | 72 per item   | Shaders sorted by name and then permutation: name, permutation, `metallib` and reflection, root signature index (`u32`, [`NO_ROOT_SIGNATURE`] for none), `u32` padding |
+//! | Remainder     | Strings and data, every item aligned to [`ALIGNMENT`] bytes           |
+//!
+//! Reflection is stored as produced by [`ReflectionSnapshot::to_bytes()`].  Readers reject files
+//! with a newer [`VERSION`] than they know of.
+use std::collections::BTreeMap;
+
+use crate::{
+    encode::Encoder,
+    permutations::{PermutationKey, PermutationTable},
+    reflection::ReflectionSnapshot,
+    Error,
+};
+
+pub const MAGIC: [u8; 4] = *b"SXBN";
+/// Format version written by [`BundleWriter::write()`].
+pub const VERSION: u32 = 1;
+/// Root signature index of shaders without one.
+pub const NO_ROOT_SIGNATURE: u32 = u32::MAX;
+/// Alignment of every string and data item, so that a `metallib` can be handed to Metal straight
+/// from a memory-mapped bundle.
+pub const ALIGNMENT: usize = 16;
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::Bundle {
+        reason: reason.into(),
+    }
+}
+
+struct Shader {
+    metallib: Vec<u8>,
+    reflection: Vec<u8>,
+    root_signature: Option<u32>,
+}
+
+/// Collects shaders and root signatures, and lays them out in a single file with
+/// [`Self::write()`].
+#[derive(Default)]
+pub struct BundleWriter {
+    root_signatures: Vec<(String, Vec<u8>)>,
+    /// Keyed by name and permutation, which is also the order of the index.
+    shaders: BTreeMap<(String, String), Shader>,
+}
+
+impl BundleWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a serialized root signature, such as the JSON passed to
+    /// [`crate::MetalIrConverter::create_root_signature_from_json()`] or the
+    /// [`crate::dxil::ROOT_SIGNATURE`] part of a DXIL container, for shaders to refer to by
+    /// `name`.
+    pub fn add_root_signature(
+        &mut self,
+        name: impl Into<String>,
+        serialized: impl Into<Vec<u8>>,
+    ) -> Result<&mut Self, Error> {
+        let name = name.into();
+        if self.root_signatures.iter().any(|(n, _)| *n == name) {
+            return Err(invalid(format!("duplicate root signature `{name}`")));
+        }
+        self.root_signatures.push((name, serialized.into()));
+        Ok(self)
+    }
+
+    /// Adds a shader under `name` and `permutation`, which is stored as its
+    /// [`PermutationKey`] [`Display`][std::fmt::Display] string or an empty string for
+    /// [`None`].  `root_signature` must have been added with [`Self::add_root_signature()`].
+    pub fn add_shader(
+        &mut self,
+        name: &str,
+        permutation: Option<&PermutationKey>,
+        metallib: impl Into<Vec<u8>>,
+        reflection: &ReflectionSnapshot,
+        root_signature: Option<&str>,
+    ) -> Result<&mut Self, Error> {
+        let root_signature = root_signature
+            .map(|root_signature| {
+                self.root_signatures
+                    .iter()
+                    .position(|(n, _)| n == root_signature)
+                    .map(|index| index as u32)
+                    .ok_or_else(|| invalid(format!("unknown root signature `{root_signature}`")))
+            })
+            .transpose()?;
+        let key = (
+            name.to_owned(),
+            permutation.map(ToString::to_string).unwrap_or_default(),
+        );
+        if self.shaders.contains_key(&key) {
+            return Err(invalid(format!(
+                "duplicate shader `{name}` with permutation `{}`",
+                key.1
+            )));
+        }
+        self.shaders.insert(
+            key,
+            Shader {
+                metallib: metallib.into(),
+                reflection: reflection.to_bytes(),
+                root_signature,
+            },
+        );
+        Ok(self)
+    }
+
+    /// Adds every permutation in `table` under `name`, see [`Self::add_shader()`].
+    pub fn add_permutations(
+        &mut self,
+        name: &str,
+        table: &PermutationTable,
+        root_signature: Option<&str>,
+    ) -> Result<&mut Self, Error> {
+        for output in &table.outputs {
+            let reflection = output.reflection.snapshot();
+            for key in &output.keys {
+                self.add_shader(
+                    name,
+                    Some(key),
+                    output.metallib.as_slice(),
+                    &reflection,
+                    root_signature,
+                )?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Lays out the bundle in the format described in the [module documentation][self].
+    pub fn write(&self) -> Vec<u8> {
+        let mut index = Encoder::default();
+        index.bytes.extend(MAGIC);
+        index
+            .u32(VERSION)
+            .len(self.root_signatures.len())
+            .len(self.shaders.len());
+        let index_size =
+            index.bytes.len() + 32 * self.root_signatures.len() + 72 * self.shaders.len();
+
+        let data_offset = align(index_size);
+        let mut data = Vec::new();
+        // Appends `item` to the data and writes its offset and length to the index
+        let mut push = |index: &mut Encoder, item: &[u8]| {
+            data.resize(align(data.len()), 0);
+            index
+                .u64((data_offset + data.len()) as u64)
+                .u64(item.len() as u64);
+            data.extend(item);
+        };
+
+        for (name, serialized) in &self.root_signatures {
+            push(&mut index, name.as_bytes());
+            push(&mut index, serialized);
+        }
+        for ((name, permutation), shader) in &self.shaders {
+            push(&mut index, name.as_bytes());
+            push(&mut index, permutation.as_bytes());
+            push(&mut index, &shader.metallib);
+            push(&mut index, &shader.reflection);
+            index
+                .u32(shader.root_signature.unwrap_or(NO_ROOT_SIGNATURE))
+                .u32(0);
+        }
+        debug_assert_eq!(index.bytes.len(), index_size);
+
+        let mut bytes = index.bytes;
+        bytes.resize(data_offset, 0);
+        bytes.extend(data);
+        bytes
+    }
+}
+
+fn align(offset: usize) -> usize {
+    offset.next_multiple_of(ALIGNMENT)
+}
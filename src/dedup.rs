@@ -0,0 +1,216 @@
+//! Content-addressed storage of converter output: shaders that convert to the same `metallib` and
+//! reflection share a single [`Blob`], and logical shader names map to the [`BlobId`] of their
+//! output.
+//!
+//! ```no_run
+//! # fn example(objects: &[(&str, saxaboom::IRObject)]) -> Result<(), saxaboom::Error> {
+//! use saxaboom::dedup::BlobStore;
+//!
+//! let mut store = BlobStore::new();
+//! for (name, object) in objects {
+//!     store.insert_object(*name, object)?;
+//! }
+//! println!("{} duplicates, {} bytes saved", store.duplicates(), store.saved_bytes());
+//! store.write_dir(std::path::Path::new("shader_cache"))?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::{btree_map::Entry, hash_map, BTreeMap, HashMap},
+    fmt, fs,
+    path::Path,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    encode::{hex, json_string},
+    reflection::ReflectionSnapshot,
+    Error, IRObject,
+};
+
+/// Name of the file written by [`BlobStore::write_dir()`] that maps shader names to blob IDs.
+pub const INDEX_FILE: &str = "index.json";
+
+/// SHA-256 of the `metallib` and the [`ReflectionSnapshot::to_bytes()`] of a [`Blob`].  Formatted
+/// as lowercase hexadecimal.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlobId(pub [u8; 32]);
+
+impl BlobId {
+    pub fn of(metallib: &[u8], reflection: &ReflectionSnapshot) -> Self {
+        let mut hasher = Sha256::new();
+        // The length separates the `metallib` from the reflection
+        hasher.update((metallib.len() as u64).to_le_bytes());
+        hasher.update(metallib);
+        hasher.update(reflection.to_bytes());
+        Self(hasher.finalize().into())
+    }
+}
+
+impl fmt::Display for BlobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex(&self.0))
+    }
+}
+
+impl fmt::Debug for BlobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BlobId({self})")
+    }
+}
+
+/// Finds earlier outputs with the same `metallib` and reflection JSON by their hash, for the
+/// deduplication of permutations and targets.
+#[derive(Debug, Default)]
+pub(crate) struct OutputIndex(HashMap<[u8; 32], usize>);
+
+impl OutputIndex {
+    /// The index of the earlier output with the same `metallib` and `reflection_json`, or [`None`]
+    /// after remembering `next` as the index of this output.
+    pub(crate) fn existing(
+        &mut self,
+        metallib: &[u8],
+        reflection_json: Option<&str>,
+        next: usize,
+    ) -> Option<usize> {
+        let mut hasher = Sha256::new();
+        // The length separates the `metallib` from the reflection
+        hasher.update((metallib.len() as u64).to_le_bytes());
+        hasher.update(metallib);
+        if let Some(json) = reflection_json {
+            hasher.update(json);
+        }
+        match self.0.entry(hasher.finalize().into()) {
+            hash_map::Entry::Occupied(entry) => Some(*entry.get()),
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(next);
+                None
+            }
+        }
+    }
+}
+
+/// Output shared by every shader name that maps to its [`BlobId`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Blob {
+    pub metallib: Vec<u8>,
+    pub reflection: ReflectionSnapshot,
+}
+
+/// Stores every distinct [`Blob`] once, see the [module documentation](self).
+#[derive(Debug, Default)]
+pub struct BlobStore {
+    blobs: BTreeMap<BlobId, Blob>,
+    names: BTreeMap<String, BlobId>,
+    saved_bytes: usize,
+}
+
+impl BlobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `name` to the blob holding `metallib` and `reflection`, which is only stored if no
+    /// earlier shader produced the same output.  Fails with [`Error::DuplicateShader`] when `name`
+    /// was inserted before.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        metallib: Vec<u8>,
+        reflection: ReflectionSnapshot,
+    ) -> Result<BlobId, Error> {
+        let name = name.into();
+        if self.names.contains_key(&name) {
+            return Err(Error::DuplicateShader { name });
+        }
+        let id = BlobId::of(&metallib, &reflection);
+        match self.blobs.entry(id) {
+            Entry::Occupied(_) => self.saved_bytes += metallib.len(),
+            Entry::Vacant(entry) => {
+                entry.insert(Blob {
+                    metallib,
+                    reflection,
+                });
+            }
+        }
+        self.names.insert(name, id);
+        Ok(id)
+    }
+
+    /// Inserts the [`IRObject::metal_lib_binary()`] and [`IRShaderReflection::snapshot()`] of a
+    /// compiled `object`.
+    ///
+    /// [`IRShaderReflection::snapshot()`]: crate::IRShaderReflection::snapshot()
+    pub fn insert_object(
+        &mut self,
+        name: impl Into<String>,
+        object: &IRObject,
+    ) -> Result<BlobId, Error> {
+        let metallib = object.metal_lib_binary()?.byte_code();
+        let reflection = object.reflection()?.snapshot();
+        self.insert(name, metallib, reflection)
+    }
+
+    pub fn id(&self, name: &str) -> Option<BlobId> {
+        self.names.get(name).copied()
+    }
+
+    pub fn blob(&self, id: &BlobId) -> Option<&Blob> {
+        self.blobs.get(id)
+    }
+
+    /// The blob that `name` maps to.
+    pub fn get(&self, name: &str) -> Option<&Blob> {
+        self.id(name).and_then(|id| self.blob(&id))
+    }
+
+    /// Every shader name with the ID of its blob, ordered by name.
+    pub fn names(&self) -> impl Iterator<Item = (&str, BlobId)> {
+        self.names.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+
+    /// Every distinct blob, ordered by ID.
+    pub fn blobs(&self) -> impl Iterator<Item = (BlobId, &Blob)> {
+        self.blobs.iter().map(|(id, blob)| (*id, blob))
+    }
+
+    /// Number of inserted shaders whose output was already stored for another name.
+    pub fn duplicates(&self) -> usize {
+        self.names.len() - self.blobs.len()
+    }
+
+    /// Total `metallib` size of the [`Self::duplicates()`], which is not stored.
+    pub fn saved_bytes(&self) -> usize {
+        self.saved_bytes
+    }
+
+    /// Writes every blob to `dir` as `<id>.metallib` with its reflection sidecars (see
+    /// [`ReflectionSnapshot::write_sidecars()`]), and [`INDEX_FILE`] with a JSON object mapping
+    /// every shader name to a blob ID.  `dir` must exist.
+    pub fn write_dir(&self, dir: &Path) -> Result<(), Error> {
+        let write = |path: &Path, contents: &[u8]| {
+            fs::write(path, contents).map_err(|source| Error::Write {
+                path: path.to_owned(),
+                source,
+            })
+        };
+        for (id, blob) in &self.blobs {
+            let path = dir.join(format!("{id}.metallib"));
+            write(&path, &blob.metallib)?;
+            blob.reflection.write_sidecars(&path)?;
+        }
+
+        let entries = self
+            .names
+            .iter()
+            .map(|(name, id)| format!("  {}: \"{id}\"", json_string(name)))
+            .collect::<Vec<_>>();
+        let index = if entries.is_empty() {
+            "{}\n".to_owned()
+        } else {
+            format!("{{\n{}\n}}\n", entries.join(",\n"))
+        };
+        write(&dir.join(INDEX_FILE), index.as_bytes())
+    }
+}
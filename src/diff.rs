@@ -0,0 +1,386 @@
+//! Differential testing of two `metal_irconverter` libraries: [`diff()`] converts the same corpus
+//! of shaders with both, and reports every shader whose reflection JSON, resource locations,
+//! thread group size or `metallib` function list changed.  Use it to review an upgrade of the
+//! converter before shipping its output.
+//!
+//! ```no_run
+//! # fn example() -> Result<(), saxaboom::Error> {
+//! use saxaboom::{diff::{self, Shader}, ffi, MetalIrConverter};
+//!
+//! let old = MetalIrConverter::new("irconverter-2.0/libmetalirconverter.dylib")?;
+//! let new = MetalIrConverter::new("irconverter-3.0/libmetalirconverter.dylib")?;
+//! let corpus = [Shader::read("blur.dxil", c"main")?];
+//! let report = diff::diff(&old, &new, &corpus, |_, compiler| {
+//!     compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+//!     Ok::<_, saxaboom::Error>(())
+//! })?;
+//! print!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    ffi::{CStr, CString},
+    fmt,
+    path::Path,
+};
+
+use crate::{
+    metallib::{FunctionType, MetalLib},
+    reflection::Resource,
+    Error, IRCompiler, MetalIrConverter,
+};
+
+/// An entry point of a DXIL input in the corpus passed to [`diff()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shader {
+    /// Identifies the shader in the [`DiffReport`], and is set as the [`IRObject::set_name()`] of
+    /// the input.
+    ///
+    /// [`IRObject::set_name()`]: crate::IRObject::set_name()
+    pub name: String,
+    pub dxil: Vec<u8>,
+    pub entry_point: CString,
+}
+
+impl Shader {
+    pub fn new(name: impl Into<String>, dxil: Vec<u8>, entry_point: &CStr) -> Self {
+        Self {
+            name: name.into(),
+            dxil,
+            entry_point: entry_point.to_owned(),
+        }
+    }
+
+    /// Reads the DXIL at `path`, named after the path.
+    pub fn read(path: impl AsRef<Path>, entry_point: &CStr) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let dxil = std::fs::read(path).map_err(|source| Error::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        Ok(Self::new(path.display().to_string(), dxil, entry_point))
+    }
+}
+
+/// The parts of the converted output of a [`Shader`] that [`diff()`] compares.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderOutput {
+    /// See [`IRShaderReflection::json()`], [`None`] without
+    /// [`MetalIrConverter::supports_json_reflection()`].
+    ///
+    /// [`IRShaderReflection::json()`]: crate::IRShaderReflection::json()
+    pub reflection_json: Option<String>,
+    pub resources: Vec<Resource>,
+    pub threadgroup_size: Option<[u32; 3]>,
+    /// Name and type of every function in the `metallib`, in order.
+    pub functions: Vec<(String, Option<FunctionType>)>,
+}
+
+impl ShaderOutput {
+    /// Converts `shader` with `compiler`, and parses the resulting `metallib`.
+    pub fn convert(
+        converter: &MetalIrConverter,
+        compiler: &IRCompiler,
+        shader: &Shader,
+    ) -> Result<Self, Error> {
+        let mut object = converter.create_object_from_dxil(&shader.dxil);
+        object.set_name(shader.name.clone());
+        let compiled = compiler.alloc_compile_and_link(&shader.entry_point, &object)?;
+        let reflection = compiled.reflection()?;
+        let snapshot = reflection.snapshot();
+        let metallib = compiled.metal_lib_binary()?.byte_code();
+        let functions = MetalLib::parse(&metallib)?
+            .functions
+            .into_iter()
+            .map(|function| (function.name, function.function_type))
+            .collect();
+        Ok(Self {
+            reflection_json: if converter.supports_json_reflection() {
+                Some(reflection.json()?)
+            } else {
+                None
+            },
+            resources: snapshot.resources,
+            threadgroup_size: snapshot.threadgroup_size,
+            functions,
+        })
+    }
+}
+
+/// A change in the output of a [`Shader`] from the old to the new converter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Difference {
+    /// Converting failed with either converter, or failed differently with both.  The other
+    /// differences are only compared when both succeed, shaders that failed the same way with both
+    /// are reported by [`DiffReport::failed()`] instead.
+    Conversion {
+        old: Result<(), String>,
+        new: Result<(), String>,
+    },
+    ReflectionJson {
+        old: String,
+        new: String,
+    },
+    /// Resources that moved to another space, slot or offset show up as removed and added.
+    Resources {
+        old: Vec<Resource>,
+        new: Vec<Resource>,
+    },
+    ThreadgroupSize {
+        old: Option<[u32; 3]>,
+        new: Option<[u32; 3]>,
+    },
+    Functions {
+        old: Vec<(String, Option<FunctionType>)>,
+        new: Vec<(String, Option<FunctionType>)>,
+    },
+}
+
+impl Difference {
+    /// Every difference between the outputs of both converters, empty when they match.
+    pub fn between(
+        old: &Result<ShaderOutput, String>,
+        new: &Result<ShaderOutput, String>,
+    ) -> Vec<Self> {
+        let (old, new) = match (old, new) {
+            (Ok(old), Ok(new)) => (old, new),
+            (Err(old_error), Err(new_error)) if old_error == new_error => return Vec::new(),
+            _ => {
+                let outcome = |output: &Result<ShaderOutput, String>| {
+                    output.as_ref().map(|_| ()).map_err(Clone::clone)
+                };
+                return vec![Self::Conversion {
+                    old: outcome(old),
+                    new: outcome(new),
+                }];
+            }
+        };
+
+        let mut differences = Vec::new();
+        // Only comparable when both libraries serialize reflection
+        if let (Some(old_json), Some(new_json)) = (&old.reflection_json, &new.reflection_json) {
+            if old_json != new_json {
+                differences.push(Self::ReflectionJson {
+                    old: old_json.clone(),
+                    new: new_json.clone(),
+                });
+            }
+        }
+        if old.resources != new.resources {
+            differences.push(Self::Resources {
+                old: old.resources.clone(),
+                new: new.resources.clone(),
+            });
+        }
+        if old.threadgroup_size != new.threadgroup_size {
+            differences.push(Self::ThreadgroupSize {
+                old: old.threadgroup_size,
+                new: new.threadgroup_size,
+            });
+        }
+        if old.functions != new.functions {
+            differences.push(Self::Functions {
+                old: old.functions.clone(),
+                new: new.functions.clone(),
+            });
+        }
+        differences
+    }
+}
+
+/// Writes the items of `old` that are not in `new` prefixed with `-`, and the other way around
+/// prefixed with `+`, one per line.
+fn write_changes<T: PartialEq>(
+    f: &mut fmt::Formatter<'_>,
+    old: &[T],
+    new: &[T],
+    item: impl Fn(&T) -> String,
+) -> fmt::Result {
+    for old_item in old.iter().filter(|i| !new.contains(i)) {
+        writeln!(f, "    - {}", item(old_item))?;
+    }
+    for new_item in new.iter().filter(|i| !old.contains(i)) {
+        writeln!(f, "    + {}", item(new_item))?;
+    }
+    Ok(())
+}
+
+fn resource(resource: &Resource) -> String {
+    format!(
+        "{:?} `{}` in space {} slot {} at offset {} ({} bytes)",
+        resource.resource_type,
+        resource.name.as_deref().unwrap_or_default(),
+        resource.space,
+        resource.slot,
+        resource.top_level_offset,
+        resource.size_bytes
+    )
+}
+
+fn function((name, function_type): &(String, Option<FunctionType>)) -> String {
+    match function_type {
+        Some(function_type) => format!("`{name}` ({function_type:?})"),
+        None => format!("`{name}`"),
+    }
+}
+
+impl fmt::Display for Difference {
+    /// A heading, followed by the changes on indented lines starting with `-` or `+`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Conversion { old, new } => {
+                writeln!(f, "  conversion:")?;
+                for (prefix, outcome) in [('-', old), ('+', new)] {
+                    match outcome {
+                        Ok(()) => writeln!(f, "    {prefix} succeeded")?,
+                        Err(error) => writeln!(f, "    {prefix} {error}")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::ReflectionJson { old, new } => {
+                // Only the first changed line, the rest is usually covered by the other
+                // differences
+                let line = old
+                    .lines()
+                    .zip(new.lines())
+                    .position(|(old, new)| old != new)
+                    .unwrap_or_else(|| old.lines().count().min(new.lines().count()));
+                writeln!(f, "  reflection JSON, from line {}:", line + 1)?;
+                let nth = |json: &str| json.lines().nth(line).unwrap_or_default().trim().to_owned();
+                writeln!(f, "    - {}", nth(old))?;
+                writeln!(f, "    + {}", nth(new))
+            }
+            Self::Resources { old, new } => {
+                writeln!(f, "  resources:")?;
+                write_changes(f, old, new, resource)
+            }
+            Self::ThreadgroupSize { old, new } => {
+                writeln!(f, "  thread group size:")?;
+                writeln!(f, "    - {old:?}")?;
+                writeln!(f, "    + {new:?}")
+            }
+            Self::Functions { old, new } => {
+                writeln!(f, "  metallib functions:")?;
+                if old.iter().all(|f| new.contains(f)) && new.iter().all(|f| old.contains(f)) {
+                    writeln!(f, "    reordered")
+                } else {
+                    write_changes(f, old, new, function)
+                }
+            }
+        }
+    }
+}
+
+/// The outputs of both converters for one [`Shader`] of the corpus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderDiff {
+    pub name: String,
+    pub entry_point: String,
+    /// Converting failures are kept as their message.
+    pub old: Result<ShaderOutput, String>,
+    pub new: Result<ShaderOutput, String>,
+    /// See [`Difference::between()`].
+    pub differences: Vec<Difference>,
+}
+
+/// Returned by [`diff()`], with a [`ShaderDiff`] for every shader in the corpus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffReport {
+    /// In corpus order.
+    pub shaders: Vec<ShaderDiff>,
+}
+
+impl DiffReport {
+    /// Shaders whose output differs between both converters.
+    pub fn changed(&self) -> impl Iterator<Item = &ShaderDiff> {
+        self.shaders.iter().filter(|s| !s.differences.is_empty())
+    }
+
+    /// Shaders that failed to convert with both converters, whose output could not be compared.
+    pub fn failed(&self) -> impl Iterator<Item = &ShaderDiff> {
+        self.shaders
+            .iter()
+            .filter(|s| s.old.is_err() && s.new.is_err())
+    }
+
+    /// Whether both converters produced the same output for every shader.  Shaders that failed
+    /// with both converters are not considered identical.
+    pub fn is_identical(&self) -> bool {
+        self.changed().next().is_none() && self.failed().next().is_none()
+    }
+}
+
+impl fmt::Display for DiffReport {
+    /// Every changed shader with its differences and every shader that failed the same way with
+    /// both converters, followed by a summary line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for shader in &self.shaders {
+            let failed = match (&shader.old, &shader.new) {
+                (Err(old), Err(new)) if old == new => Some(old),
+                _ => None,
+            };
+            if shader.differences.is_empty() && failed.is_none() {
+                continue;
+            }
+            writeln!(f, "`{}` `{}`:", shader.name, shader.entry_point)?;
+            for difference in &shader.differences {
+                write!(f, "{difference}")?;
+            }
+            if let Some(error) = failed {
+                writeln!(f, "  failed with both converters:")?;
+                writeln!(f, "    {error}")?;
+            }
+        }
+        write!(
+            f,
+            "{} of {} shaders differ",
+            self.changed().count(),
+            self.shaders.len()
+        )?;
+        match self.failed().count() {
+            0 => writeln!(f),
+            failed => writeln!(f, ", {failed} failed with both converters"),
+        }
+    }
+}
+
+/// Converts every shader in `corpus` with both `old` and `new`, and compares the outputs.
+///
+/// `configure` is called once for each converter with the compiler that converts the corpus, to
+/// apply the same settings to both.  What it returns, such as an [`IRRootSignature`] that was set
+/// on the compiler, is kept alive until the corpus is converted.  Only errors of `configure` are
+/// returned, conversion failures are reported as a [`Difference::Conversion`], or by
+/// [`DiffReport::failed()`] when both converters fail the same way.
+///
+/// [`IRRootSignature`]: crate::IRRootSignature
+pub fn diff<T, E>(
+    old: &MetalIrConverter,
+    new: &MetalIrConverter,
+    corpus: &[Shader],
+    configure: impl Fn(&MetalIrConverter, &mut IRCompiler) -> Result<T, E>,
+) -> Result<DiffReport, E> {
+    let mut old_compiler = old.create_compiler();
+    let _old_configuration = configure(old, &mut old_compiler)?;
+    let mut new_compiler = new.create_compiler();
+    let _new_configuration = configure(new, &mut new_compiler)?;
+
+    let convert = |converter, compiler, shader| {
+        ShaderOutput::convert(converter, compiler, shader).map_err(|e| e.to_string())
+    };
+    let shaders = corpus
+        .iter()
+        .map(|shader| {
+            let old = convert(old, &old_compiler, shader);
+            let new = convert(new, &new_compiler, shader);
+            ShaderDiff {
+                name: shader.name.clone(),
+                entry_point: shader.entry_point.to_string_lossy().into_owned(),
+                differences: Difference::between(&old, &new),
+                old,
+                new,
+            }
+        })
+        .collect();
+    Ok(DiffReport { shaders })
+}
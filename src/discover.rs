@@ -0,0 +1,174 @@
+//! Locating the `metal_irconverter` dynamic library, see [`MetalIrConverter::discover()`].
+use std::{
+    ffi::c_void,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, LoadError, MetalIrConverter};
+
+/// Environment variable holding one or more (separated like `PATH`) library files or directories
+/// containing the library, searched before the standard install locations.
+pub const ENV_VAR: &str = "SAXABOOM_IRCONVERTER_PATH";
+
+/// Standard install locations of the Metal shader converter on the current platform.
+const INSTALL_DIRS: &[&str] = if cfg!(target_os = "macos") {
+    &["/usr/local/lib", "/opt/metal-shaderconverter/lib"]
+} else if cfg!(windows) {
+    &[r"C:\Program Files\Metal Shader Converter\lib"]
+} else {
+    &[]
+};
+
+/// Returned (as [`Error::Discover`]) by [`MetalIrConverter::discover()`] when no candidate
+/// could be loaded.
+#[derive(Debug)]
+pub struct DiscoverError {
+    /// Every path that was tried, in order, with the reason it could not be used.
+    pub attempts: Vec<(PathBuf, LoadError)>,
+}
+
+impl fmt::Display for DiscoverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not find the metal_irconverter library, set `{ENV_VAR}` to its location. Tried:"
+        )?;
+        for (path, error) in &self.attempts {
+            write!(f, "\n  {}: {error}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DiscoverError {}
+
+/// Paths tried by [`MetalIrConverter::discover()`], in order: the entries of [`ENV_VAR`], the
+/// standard install locations, and finally the bare library file name which is resolved by the
+/// platform's dynamic loader.
+pub fn search_paths() -> Vec<PathBuf> {
+    let file_name = PathBuf::from(libloading::library_filename("metalirconverter"));
+    let mut paths = env_paths(ENV_VAR, &file_name);
+    paths.extend(
+        INSTALL_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(&file_name)),
+    );
+    paths.push(file_name);
+    paths
+}
+
+/// Entries of the environment variable `var`, separated like `PATH`, where directories are
+/// joined with `file_name`.
+pub(crate) fn env_paths(var: &str, file_name: &Path) -> Vec<PathBuf> {
+    let Some(value) = std::env::var_os(var).filter(|v| !v.is_empty()) else {
+        return Vec::new();
+    };
+    std::env::split_paths(&value)
+        .map(|entry| in_dir(entry, file_name))
+        .collect()
+}
+
+/// Path of the loaded library containing `symbol`, as resolved by the dynamic loader.  Used when
+/// the library was loaded by its bare file name, which the loader searched for.
+#[cfg(unix)]
+pub(crate) fn loaded_from(symbol: *const c_void) -> Option<PathBuf> {
+    use std::{
+        ffi::{c_char, c_int, CStr, OsStr},
+        os::unix::ffi::OsStrExt,
+    };
+
+    #[repr(C)]
+    struct DlInfo {
+        fname: *const c_char,
+        fbase: *mut c_void,
+        sname: *const c_char,
+        saddr: *mut c_void,
+    }
+    extern "C" {
+        fn dladdr(addr: *const c_void, info: *mut DlInfo) -> c_int;
+    }
+
+    let mut info = DlInfo {
+        fname: std::ptr::null(),
+        fbase: std::ptr::null_mut(),
+        sname: std::ptr::null(),
+        saddr: std::ptr::null_mut(),
+    };
+    if unsafe { dladdr(symbol, &mut info) } == 0 || info.fname.is_null() {
+        return None;
+    }
+    // Owned by the loader, and valid for as long as the library stays loaded
+    let fname = unsafe { CStr::from_ptr(info.fname) };
+    Some(PathBuf::from(OsStr::from_bytes(fname.to_bytes())))
+}
+
+/// Path of the loaded library containing `symbol`, as resolved by the dynamic loader.  Used when
+/// the library was loaded by its bare file name, which the loader searched for.
+#[cfg(windows)]
+pub(crate) fn loaded_from(symbol: *const c_void) -> Option<PathBuf> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+    const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x2;
+    const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x4;
+    /// Longest path that Windows supports, in UTF-16 code units.
+    const MAX_PATH: usize = 32767;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleExW(flags: u32, module_name: *const u16, module: *mut *mut c_void)
+            -> i32;
+        fn GetModuleFileNameW(module: *mut c_void, file_name: *mut u16, size: u32) -> u32;
+    }
+
+    let mut module = std::ptr::null_mut();
+    let flags =
+        GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT;
+    if unsafe { GetModuleHandleExW(flags, symbol.cast(), &mut module) } == 0 {
+        return None;
+    }
+    let mut file_name = vec![0; MAX_PATH];
+    let len = unsafe { GetModuleFileNameW(module, file_name.as_mut_ptr(), MAX_PATH as u32) };
+    if len == 0 {
+        return None;
+    }
+    file_name.truncate(len as usize);
+    Some(OsString::from_wide(&file_name).into())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn loaded_from(_symbol: *const c_void) -> Option<PathBuf> {
+    None
+}
+
+/// `entry` joined with `file_name` when it is a directory.
+fn in_dir(entry: PathBuf, file_name: &Path) -> PathBuf {
+    if entry.is_dir() {
+        entry.join(file_name)
+    } else {
+        entry
+    }
+}
+
+impl MetalIrConverter {
+    /// Load the first `metal_irconverter` library that is found in [`search_paths()`].
+    /// [`MetalIrConverter::library_path()`] reports which one was picked, or which file the
+    /// dynamic loader found for the bare file name.
+    pub fn discover() -> Result<Self, Error> {
+        Self::discover_in(search_paths())
+    }
+
+    /// Load the first `metal_irconverter` library that is found in `paths`, which are library
+    /// files or directories containing the library like the entries of [`ENV_VAR`].
+    pub fn discover_in(paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Result<Self, Error> {
+        let file_name = PathBuf::from(libloading::library_filename("metalirconverter"));
+        let mut attempts = Vec::new();
+        for path in paths {
+            let path = in_dir(path.into(), &file_name);
+            match Self::load(&path) {
+                Ok(converter) => return Ok(converter),
+                Err(error) => attempts.push((path, error)),
+            }
+        }
+        Err(DiscoverError { attempts }.into())
+    }
+}
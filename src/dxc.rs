@@ -0,0 +1,624 @@
+//! Compiling HLSL to DXIL with a dynamically loaded `dxcompiler` library (DXC), so that HLSL can
+//! be converted without a separate build step, see [`IRCompiler::alloc_compile_hlsl_and_link()`].
+//!
+//! ```no_run
+//! use saxaboom::{
+//!     dxc::{Dxc, Hlsl},
+//!     MetalIrConverter,
+//! };
+//!
+//! let converter = MetalIrConverter::discover()?;
+//! let dxc = Dxc::discover()?;
+//! let hlsl = Hlsl::from_file("shaders/blur.hlsl", c"main", "cs_6_6")?.define("RADIUS", "4");
+//! // Fails with `Error::Dxc` holding the DXC diagnostics when the HLSL does not compile, or
+//! // with `Error::Compilation` when the DXIL does not convert
+//! let object = converter
+//!     .create_compiler()
+//!     .alloc_compile_hlsl_and_link(&dxc, &hlsl)?;
+//! # Ok::<(), saxaboom::Error>(())
+//! ```
+//!
+//! Only the `IDxcCompiler3` and `IDxcUtils` interfaces are used, through their COM vtables.  On
+//! Linux and macOS, `dxcompiler`'s own `IUnknown` has a virtual destructor, which the Itanium C++
+//! ABI places after `Release()` as two more vtable entries.
+use std::{
+    ffi::{c_void, CStr, OsStr},
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    ptr::{self, NonNull},
+    rc::Rc,
+};
+
+use crate::{discover, Error, IRCompiler, IRObject};
+
+/// Environment variable holding one or more (separated like `PATH`) library files or directories
+/// containing the `dxcompiler` library, searched before the platform's dynamic loader.
+pub const ENV_VAR: &str = "SAXABOOM_DXCOMPILER_PATH";
+
+/// Error reported by [`Dxc`], wrapped in [`Error::Dxc`].
+#[derive(thiserror::Error, Debug)]
+pub enum DxcError {
+    #[error("Failed to load the dxcompiler library: {0}")]
+    Library(#[from] libloading::Error),
+    /// Returned by [`Dxc::discover()`] when no candidate could be loaded.
+    #[error(
+        "Could not find the dxcompiler library, set `{ENV_VAR}` to its location. Tried:{}",
+        attempts.iter().map(|(path, error)| format!("\n  {}: {error}", path.display())).collect::<String>()
+    )]
+    NotFound {
+        /// Every path that was tried, in order, with the reason it could not be used.
+        attempts: Vec<(PathBuf, Self)>,
+    },
+    #[error("`{function}` failed with HRESULT {hresult:#010x}")]
+    Call {
+        function: &'static str,
+        hresult: i32,
+    },
+    /// An argument passed to DXC contains a nul character.
+    #[error("Invalid DXC argument `{argument}`")]
+    Argument { argument: String },
+    /// The HLSL source failed to compile, with the errors and warnings reported by DXC.
+    #[error("Compiling `{name}` with DXC failed:\n{diagnostics}")]
+    Compilation { name: String, diagnostics: String },
+}
+
+type IncludeHandler = dyn Fn(&str) -> Option<String>;
+
+/// HLSL source and the arguments to compile it with, see [`Dxc::compile()`].
+#[derive(Clone)]
+pub struct Hlsl {
+    source: String,
+    name: String,
+    entry_point: std::ffi::CString,
+    profile: String,
+    defines: Vec<(String, Option<String>)>,
+    arguments: Vec<String>,
+    include_handler: Option<Rc<IncludeHandler>>,
+}
+
+impl Hlsl {
+    /// Compiles `entry_point` from `source` for a target `profile` such as `cs_6_6`.
+    pub fn new(source: impl Into<String>, entry_point: &CStr, profile: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            name: "shader.hlsl".to_owned(),
+            entry_point: entry_point.to_owned(),
+            profile: profile.into(),
+            defines: Vec::new(),
+            arguments: Vec::new(),
+            include_handler: None,
+        }
+    }
+
+    /// Reads the source from `path`, and names it after `path` so that relative includes are
+    /// resolved next to it.
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        entry_point: &CStr,
+        profile: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|source| Error::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        Ok(Self::new(source, entry_point, profile).name(path.display().to_string()))
+    }
+
+    /// File name of the source in diagnostics, and in the [`crate::error::Context`] of
+    /// conversion errors.  Defaults to `shader.hlsl`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Defines the macro `name` as `value`, like `-D name=value`.
+    pub fn define(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.defines.push((name.into(), Some(value.into())));
+        self
+    }
+
+    /// Defines the macro `name` without a value, like `-D name`.
+    pub fn define_flag(mut self, name: impl Into<String>) -> Self {
+        self.defines.push((name.into(), None));
+        self
+    }
+
+    /// Passes any other DXC command line argument, such as `-HV`, `2021` or `-O3`.
+    pub fn arg(mut self, argument: impl Into<String>) -> Self {
+        self.arguments.push(argument.into());
+        self
+    }
+
+    /// Returns the source of every `#include`d file, or [`None`] when it does not exist.  The
+    /// handler receives the path as resolved by DXC, relative to the including file.  Without a
+    /// handler, includes are read from the file system.
+    pub fn include_handler(
+        mut self,
+        include_handler: impl Fn(&str) -> Option<String> + 'static,
+    ) -> Self {
+        self.include_handler = Some(Rc::new(include_handler));
+        self
+    }
+
+    pub fn entry_point(&self) -> &CStr {
+        &self.entry_point
+    }
+
+    /// Command line passed to DXC, starting with the file name.
+    fn arguments(&self) -> Vec<String> {
+        let mut arguments = vec![
+            self.name.clone(),
+            "-E".to_owned(),
+            self.entry_point.to_string_lossy().into_owned(),
+            "-T".to_owned(),
+            self.profile.clone(),
+        ];
+        for (name, value) in &self.defines {
+            arguments.push("-D".to_owned());
+            arguments.push(match value {
+                Some(value) => format!("{name}={value}"),
+                None => name.clone(),
+            });
+        }
+        arguments.extend(self.arguments.iter().cloned());
+        arguments
+    }
+}
+
+/// A loaded `dxcompiler` library, with the compiler instance that every [`Dxc::compile()`] uses.
+/// Like DXC itself, this is not thread-safe; create one per thread instead.
+pub struct Dxc {
+    compiler: ComPtr<IDxcCompiler3Vtbl>,
+    utils: ComPtr<IDxcUtilsVtbl>,
+    library_path: Option<PathBuf>,
+    // Dropped after the objects that were created by it
+    _library: libloading::Library,
+}
+
+impl Dxc {
+    #[doc(alias = "DxcCreateInstance")]
+    pub fn new(lib_path: impl AsRef<OsStr>) -> Result<Self, Error> {
+        Ok(Self::load(lib_path)?)
+    }
+
+    pub fn from_library(library: libloading::Library) -> Result<Self, Error> {
+        Ok(Self::from_loaded(library, None)?)
+    }
+
+    /// Load the first `dxcompiler` library that is found in the entries of [`ENV_VAR`], or
+    /// otherwise by the platform's dynamic loader.
+    pub fn discover() -> Result<Self, Error> {
+        let file_name = PathBuf::from(libloading::library_filename("dxcompiler"));
+        let mut paths = discover::env_paths(ENV_VAR, &file_name);
+        paths.push(file_name);
+
+        let mut attempts = Vec::new();
+        for path in paths {
+            match Self::load(&path) {
+                Ok(dxc) => return Ok(dxc),
+                Err(error) => attempts.push((path, error)),
+            }
+        }
+        Err(DxcError::NotFound { attempts }.into())
+    }
+
+    fn load(lib_path: impl AsRef<OsStr>) -> Result<Self, DxcError> {
+        let library = unsafe { libloading::Library::new(lib_path.as_ref())? };
+        Self::from_loaded(library, Some(PathBuf::from(lib_path.as_ref())))
+    }
+
+    fn from_loaded(
+        library: libloading::Library,
+        library_path: Option<PathBuf>,
+    ) -> Result<Self, DxcError> {
+        let create_instance_proc =
+            *unsafe { library.get::<DxcCreateInstanceProc>(b"DxcCreateInstance\0")? };
+        Ok(Self {
+            compiler: unsafe {
+                create_instance(
+                    create_instance_proc,
+                    &CLSID_DXC_COMPILER,
+                    &IID_IDXC_COMPILER3,
+                )?
+            },
+            utils: unsafe {
+                create_instance(create_instance_proc, &CLSID_DXC_UTILS, &IID_IDXC_UTILS)?
+            },
+            library_path,
+            _library: library,
+        })
+    }
+
+    /// Path that the library was loaded from, [`None`] for [`Dxc::from_library()`].
+    pub fn library_path(&self) -> Option<&Path> {
+        self.library_path.as_deref()
+    }
+
+    /// Compiles `hlsl` to a DXIL container.  Warnings are discarded; when compilation fails, all
+    /// diagnostics are returned in [`DxcError::Compilation`].
+    #[doc(alias = "IDxcCompiler3::Compile")]
+    pub fn compile(&self, hlsl: &Hlsl) -> Result<Vec<u8>, Error> {
+        let arguments = hlsl
+            .arguments()
+            .into_iter()
+            .map(|argument| to_wide(&argument).ok_or(DxcError::Argument { argument }))
+            .collect::<Result<Vec<_>, _>>()?;
+        let argument_ptrs = arguments.iter().map(|a| a.as_ptr()).collect::<Vec<_>>();
+        let source = DxcBuffer {
+            ptr: hlsl.source.as_ptr().cast(),
+            size: hlsl.source.len(),
+            encoding: DXC_CP_UTF8,
+        };
+        let read_file = |path: &str| fs::read_to_string(path).ok();
+        let mut include_handler = IncludeHandlerObject {
+            vtbl: &INCLUDE_HANDLER_VTBL,
+            utils: &self.utils,
+            load: hlsl.include_handler.as_deref().unwrap_or(&read_file),
+        };
+
+        let mut result = ptr::null_mut();
+        check("IDxcCompiler3::Compile", unsafe {
+            (self.compiler.vtbl().compile)(
+                self.compiler.as_ptr(),
+                &source,
+                argument_ptrs.as_ptr(),
+                argument_ptrs.len() as u32,
+                ptr::from_mut(&mut include_handler).cast(),
+                &IID_IDXC_RESULT,
+                &mut result,
+            )
+        })?;
+        let result = unsafe { ComPtr::<IDxcOperationResultVtbl>::from_raw(result) }.ok_or(
+            DxcError::Call {
+                function: "IDxcCompiler3::Compile",
+                hresult: E_POINTER,
+            },
+        )?;
+
+        let mut status = 0;
+        check("IDxcResult::GetStatus", unsafe {
+            (result.vtbl().get_status)(result.as_ptr(), &mut status)
+        })?;
+        if status < 0 {
+            let mut errors = ptr::null_mut();
+            check("IDxcResult::GetErrorBuffer", unsafe {
+                (result.vtbl().get_error_buffer)(result.as_ptr(), &mut errors)
+            })?;
+            let diagnostics = unsafe { ComPtr::<IDxcBlobVtbl>::from_raw(errors) }
+                .map(|errors| {
+                    String::from_utf8_lossy(errors.bytes())
+                        .trim_end()
+                        .to_owned()
+                })
+                .unwrap_or_default();
+            return Err(DxcError::Compilation {
+                name: hlsl.name.clone(),
+                diagnostics,
+            }
+            .into());
+        }
+
+        let mut dxil = ptr::null_mut();
+        check("IDxcResult::GetResult", unsafe {
+            (result.vtbl().get_result)(result.as_ptr(), &mut dxil)
+        })?;
+        let dxil = unsafe { ComPtr::<IDxcBlobVtbl>::from_raw(dxil) }.ok_or(DxcError::Call {
+            function: "IDxcResult::GetResult",
+            hresult: E_POINTER,
+        })?;
+        Ok(dxil.bytes().to_vec())
+    }
+}
+
+impl IRCompiler {
+    /// Compiles `hlsl` to DXIL with [`Dxc::compile()`], and converts its entry point like
+    /// [`IRCompiler::alloc_compile_and_link()`].  Errors of both steps are reported through
+    /// [`Error`], with [`Hlsl::name()`] as the input of the [`crate::error::Context`].
+    pub fn alloc_compile_hlsl_and_link(&self, dxc: &Dxc, hlsl: &Hlsl) -> Result<IRObject, Error> {
+        let dxil = dxc.compile(hlsl)?;
+        let mut input = IRObject::from_dxil(self.funcs.clone(), &dxil);
+        input.set_name(hlsl.name.clone());
+        self.alloc_compile_and_link(&hlsl.entry_point, &input)
+    }
+}
+
+// Minimal COM definitions from `dxcapi.h`
+
+#[repr(C)]
+struct Guid(u32, u16, u16, [u8; 8]);
+
+impl Guid {
+    fn eq(&self, other: &Self) -> bool {
+        (self.0, self.1, self.2, self.3) == (other.0, other.1, other.2, other.3)
+    }
+}
+
+const IID_IUNKNOWN: Guid = Guid(0, 0, 0, [0xc0, 0, 0, 0, 0, 0, 0, 0x46]);
+const CLSID_DXC_COMPILER: Guid = Guid(
+    0x73e22d93,
+    0xe6ce,
+    0x47f3,
+    [0xb5, 0xbf, 0xf0, 0x66, 0x4f, 0x39, 0xc1, 0xb0],
+);
+const CLSID_DXC_UTILS: Guid = Guid(
+    0x6245d6af,
+    0x66e0,
+    0x48fd,
+    [0x80, 0xb4, 0x4d, 0x27, 0x17, 0x96, 0x74, 0x8c],
+);
+const IID_IDXC_COMPILER3: Guid = Guid(
+    0x228b4687,
+    0x5a6a,
+    0x4730,
+    [0x90, 0x0c, 0x97, 0x02, 0xb2, 0x20, 0x3f, 0x54],
+);
+const IID_IDXC_UTILS: Guid = Guid(
+    0x4605c4cb,
+    0x2019,
+    0x492a,
+    [0xad, 0xa4, 0x65, 0xf2, 0x0b, 0xb7, 0xd6, 0x7f],
+);
+const IID_IDXC_RESULT: Guid = Guid(
+    0x58346cda,
+    0xdde7,
+    0x4497,
+    [0x94, 0x61, 0x6f, 0x87, 0xaf, 0x5e, 0x06, 0x59],
+);
+const IID_IDXC_INCLUDE_HANDLER: Guid = Guid(
+    0x7f61fc7d,
+    0x950d,
+    0x467f,
+    [0xb3, 0xe3, 0x3c, 0x02, 0xfb, 0x49, 0x18, 0x7c],
+);
+
+const S_OK: i32 = 0;
+const E_FAIL: i32 = 0x80004005_u32 as i32;
+const E_NOINTERFACE: i32 = 0x80004002_u32 as i32;
+const E_POINTER: i32 = 0x80004003_u32 as i32;
+const DXC_CP_UTF8: u32 = 65001;
+
+/// `WCHAR`, which is `wchar_t` on every platform.
+#[cfg(windows)]
+type WChar = u16;
+#[cfg(not(windows))]
+type WChar = u32;
+
+type DxcCreateInstanceProc =
+    unsafe extern "system" fn(*const Guid, *const Guid, *mut *mut c_void) -> i32;
+
+/// Outside of Windows, DXC declares `IUnknown` itself (in `WinAdapter.h`) with a virtual
+/// destructor, whose complete and deleting variants follow `Release()`.  Neither is called, objects
+/// are only destroyed through `Release()`.
+#[repr(C)]
+struct IUnknownVtbl {
+    query_interface: unsafe extern "system" fn(*mut c_void, *const Guid, *mut *mut c_void) -> i32,
+    add_ref: unsafe extern "system" fn(*mut c_void) -> u32,
+    release: unsafe extern "system" fn(*mut c_void) -> u32,
+    #[cfg(not(windows))]
+    complete_destructor: unsafe extern "C" fn(*mut c_void),
+    #[cfg(not(windows))]
+    deleting_destructor: unsafe extern "C" fn(*mut c_void),
+}
+
+#[repr(C)]
+struct IDxcBlobVtbl {
+    unknown: IUnknownVtbl,
+    get_buffer_pointer: unsafe extern "system" fn(*mut c_void) -> *mut c_void,
+    get_buffer_size: unsafe extern "system" fn(*mut c_void) -> usize,
+}
+
+#[repr(C)]
+struct IDxcOperationResultVtbl {
+    unknown: IUnknownVtbl,
+    get_status: unsafe extern "system" fn(*mut c_void, *mut i32) -> i32,
+    get_result: unsafe extern "system" fn(*mut c_void, *mut *mut c_void) -> i32,
+    get_error_buffer: unsafe extern "system" fn(*mut c_void, *mut *mut c_void) -> i32,
+}
+
+#[repr(C)]
+struct IDxcCompiler3Vtbl {
+    unknown: IUnknownVtbl,
+    compile: unsafe extern "system" fn(
+        *mut c_void,
+        *const DxcBuffer,
+        *const *const WChar,
+        u32,
+        *mut c_void,
+        *const Guid,
+        *mut *mut c_void,
+    ) -> i32,
+}
+
+#[repr(C)]
+struct IDxcUtilsVtbl {
+    unknown: IUnknownVtbl,
+    // `CreateBlobFromBlob()`, `CreateBlobFromPinned()` and `MoveToBlob()`
+    _unused: [usize; 3],
+    create_blob:
+        unsafe extern "system" fn(*mut c_void, *const c_void, u32, u32, *mut *mut c_void) -> i32,
+}
+
+#[repr(C)]
+struct IDxcIncludeHandlerVtbl {
+    unknown: IUnknownVtbl,
+    load_source: unsafe extern "system" fn(*mut c_void, *const WChar, *mut *mut c_void) -> i32,
+}
+
+#[repr(C)]
+struct DxcBuffer {
+    ptr: *const c_void,
+    size: usize,
+    encoding: u32,
+}
+
+/// # Safety
+/// `iid` must identify the interface of `V`.
+unsafe fn create_instance<V>(
+    create_instance_proc: DxcCreateInstanceProc,
+    clsid: &Guid,
+    iid: &Guid,
+) -> Result<ComPtr<V>, DxcError> {
+    let mut object = ptr::null_mut();
+    check("DxcCreateInstance", unsafe {
+        create_instance_proc(clsid, iid, &mut object)
+    })?;
+    unsafe { ComPtr::from_raw(object) }.ok_or(DxcError::Call {
+        function: "DxcCreateInstance",
+        hresult: E_POINTER,
+    })
+}
+
+fn check(function: &'static str, hresult: i32) -> Result<(), DxcError> {
+    if hresult < 0 {
+        Err(DxcError::Call { function, hresult })
+    } else {
+        Ok(())
+    }
+}
+
+/// Nul-terminated wide string, or [`None`] when `s` contains a nul character.
+fn to_wide(s: &str) -> Option<Vec<WChar>> {
+    if s.contains('\0') {
+        return None;
+    }
+    #[cfg(windows)]
+    let wide = s.encode_utf16();
+    #[cfg(not(windows))]
+    let wide = s.chars().map(u32::from);
+    Some(wide.chain([0]).collect())
+}
+
+/// # Safety
+/// `s` must point to a nul-terminated wide string.
+unsafe fn from_wide(s: *const WChar) -> String {
+    let mut len = 0;
+    while unsafe { *s.add(len) } != 0 {
+        len += 1;
+    }
+    let wide = unsafe { std::slice::from_raw_parts(s, len) };
+    #[cfg(windows)]
+    return String::from_utf16_lossy(wide);
+    #[cfg(not(windows))]
+    wide.iter()
+        .map(|&c| char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Owned reference to a COM object whose vtable starts with `V`, which always starts with
+/// [`IUnknownVtbl`].
+struct ComPtr<V> {
+    me: NonNull<*const V>,
+}
+
+impl<V> ComPtr<V> {
+    /// # Safety
+    /// `object` must be null or a COM object implementing `V`, whose reference is taken over.
+    unsafe fn from_raw(object: *mut c_void) -> Option<Self> {
+        NonNull::new(object.cast()).map(|me| Self { me })
+    }
+
+    fn as_ptr(&self) -> *mut c_void {
+        self.me.as_ptr().cast()
+    }
+
+    fn vtbl(&self) -> &V {
+        unsafe { &**self.me.as_ptr() }
+    }
+}
+
+impl ComPtr<IDxcBlobVtbl> {
+    fn bytes(&self) -> &[u8] {
+        unsafe {
+            let ptr = (self.vtbl().get_buffer_pointer)(self.as_ptr());
+            let size = (self.vtbl().get_buffer_size)(self.as_ptr());
+            if ptr.is_null() {
+                &[]
+            } else {
+                std::slice::from_raw_parts(ptr.cast(), size)
+            }
+        }
+    }
+}
+
+impl<V> Drop for ComPtr<V> {
+    fn drop(&mut self) {
+        unsafe {
+            let unknown = &*(*self.me.as_ptr()).cast::<IUnknownVtbl>();
+            (unknown.release)(self.as_ptr());
+        }
+    }
+}
+
+/// `IDxcIncludeHandler` that forwards to [`Hlsl::include_handler()`].  It lives on the stack for
+/// the duration of a single [`Dxc::compile()`], so reference counting is a no-op.
+#[repr(C)]
+struct IncludeHandlerObject<'a> {
+    vtbl: &'static IDxcIncludeHandlerVtbl,
+    utils: &'a ComPtr<IDxcUtilsVtbl>,
+    load: &'a IncludeHandler,
+}
+
+static INCLUDE_HANDLER_VTBL: IDxcIncludeHandlerVtbl = IDxcIncludeHandlerVtbl {
+    unknown: IUnknownVtbl {
+        query_interface: include_handler_query_interface,
+        add_ref: include_handler_add_ref,
+        release: include_handler_add_ref,
+        #[cfg(not(windows))]
+        complete_destructor: include_handler_destructor,
+        #[cfg(not(windows))]
+        deleting_destructor: include_handler_destructor,
+    },
+    load_source: include_handler_load_source,
+};
+
+unsafe extern "system" fn include_handler_query_interface(
+    this: *mut c_void,
+    iid: *const Guid,
+    object: *mut *mut c_void,
+) -> i32 {
+    let iid = unsafe { &*iid };
+    if iid.eq(&IID_IUNKNOWN) || iid.eq(&IID_IDXC_INCLUDE_HANDLER) {
+        unsafe { *object = this };
+        S_OK
+    } else {
+        unsafe { *object = ptr::null_mut() };
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn include_handler_add_ref(_this: *mut c_void) -> u32 {
+    1
+}
+
+#[cfg(not(windows))]
+unsafe extern "C" fn include_handler_destructor(_this: *mut c_void) {}
+
+unsafe extern "system" fn include_handler_load_source(
+    this: *mut c_void,
+    file_name: *const WChar,
+    include_source: *mut *mut c_void,
+) -> i32 {
+    let handler = unsafe { &*this.cast::<IncludeHandlerObject<'_>>() };
+    unsafe { *include_source = ptr::null_mut() };
+    let file_name = unsafe { from_wide(file_name) };
+    // Unwinding into DXC is undefined behavior, report a panic as a missing file instead
+    let Ok(Some(source)) = panic::catch_unwind(AssertUnwindSafe(|| (handler.load)(&file_name)))
+    else {
+        return E_FAIL;
+    };
+    let Ok(size) = u32::try_from(source.len()) else {
+        return E_FAIL;
+    };
+    // The blob holds a copy of `source`
+    unsafe {
+        (handler.utils.vtbl().create_blob)(
+            handler.utils.as_ptr(),
+            source.as_ptr().cast(),
+            size,
+            DXC_CP_UTF8,
+            include_source,
+        )
+    }
+}
@@ -0,0 +1,428 @@
+//! Reading the DXBC container that DXC wraps DXIL bytecode in, to inspect a shader without the
+//! `metal_irconverter` library.
+//!
+//! ```no_run
+//! let bytes = std::fs::read("shader.dxil")?;
+//! let container = saxaboom::dxil::Container::parse(&bytes)?;
+//! if let Some(program) = container.program()? {
+//!     println!("{:?} {}.{}", program.kind, program.shader_model.0, program.shader_model.1);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::fmt;
+
+use crate::Error;
+
+/// Part holding the DXIL program, see [`Container::program()`].
+pub const DXIL: [u8; 4] = *b"DXIL";
+/// Input signature, see [`Container::signature()`].
+pub const INPUT_SIGNATURE: [u8; 4] = *b"ISG1";
+/// Output signature.
+pub const OUTPUT_SIGNATURE: [u8; 4] = *b"OSG1";
+/// Patch constant (hull and domain shaders) or per-primitive (mesh shaders) signature.
+pub const PATCH_CONSTANT_SIGNATURE: [u8; 4] = *b"PSG1";
+/// Pipeline state validation data, see [`Container::pipeline_state()`].
+pub const PIPELINE_STATE: [u8; 4] = *b"PSV0";
+/// Serialized root signature embedded by the shader author.
+pub const ROOT_SIGNATURE: [u8; 4] = *b"RTS0";
+
+const HEADER_SIZE: usize = 32;
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidDxil {
+        reason: reason.into(),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    data.get(offset..offset + 4)
+        .and_then(|b| b.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or_else(|| invalid(format!("unexpected end of data at offset {offset}")))
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, Error> {
+    data.get(offset)
+        .copied()
+        .ok_or_else(|| invalid(format!("unexpected end of data at offset {offset}")))
+}
+
+fn read_str(data: &[u8], offset: usize) -> Result<String, Error> {
+    let bytes = data
+        .get(offset..)
+        .ok_or_else(|| invalid(format!("string offset {offset} out of bounds")))?;
+    let len = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| invalid(format!("unterminated string at offset {offset}")))?;
+    Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+/// Kind of shader in a DXIL program, as encoded in its program header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderKind {
+    Pixel,
+    Vertex,
+    Geometry,
+    Hull,
+    Domain,
+    Compute,
+    Library,
+    RayGeneration,
+    Intersection,
+    AnyHit,
+    ClosestHit,
+    Miss,
+    Callable,
+    Mesh,
+    Amplification,
+    Node,
+    Unknown(u32),
+}
+
+impl ShaderKind {
+    pub fn from_raw(kind: u32) -> Self {
+        match kind {
+            0 => Self::Pixel,
+            1 => Self::Vertex,
+            2 => Self::Geometry,
+            3 => Self::Hull,
+            4 => Self::Domain,
+            5 => Self::Compute,
+            6 => Self::Library,
+            7 => Self::RayGeneration,
+            8 => Self::Intersection,
+            9 => Self::AnyHit,
+            10 => Self::ClosestHit,
+            11 => Self::Miss,
+            12 => Self::Callable,
+            13 => Self::Mesh,
+            14 => Self::Amplification,
+            15 => Self::Node,
+            kind => Self::Unknown(kind),
+        }
+    }
+}
+
+/// A part of a [`Container`], identified by its four character code such as [`DXIL`].
+#[derive(Clone, Copy)]
+pub struct Part<'a> {
+    pub fourcc: [u8; 4],
+    pub data: &'a [u8],
+}
+
+impl Part<'_> {
+    /// The four character code as a string, for display.
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.fourcc).into_owned()
+    }
+}
+
+impl fmt::Debug for Part<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Part")
+            .field("fourcc", &self.name())
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+/// Header of the [`DXIL`] part.
+#[derive(Clone, Copy, Debug)]
+pub struct ProgramHeader<'a> {
+    pub kind: ShaderKind,
+    /// Major and minor shader model, such as `(6, 6)`.
+    pub shader_model: (u32, u32),
+    /// Major and minor version of DXIL itself.
+    pub dxil_version: (u32, u32),
+    /// LLVM bitcode of the program.
+    pub bitcode: &'a [u8],
+}
+
+/// Element of an input, output or patch constant signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureElement {
+    pub semantic_name: String,
+    pub semantic_index: u32,
+    /// `D3D_NAME` value, `0` for elements that are not system values.
+    pub system_value: u32,
+    /// `D3D_REGISTER_COMPONENT_TYPE` value.
+    pub component_type: u32,
+    pub register: u32,
+    /// Components in use, one bit per `xyzw` component.
+    pub mask: u8,
+    pub stream: u32,
+    /// `D3D_MIN_PRECISION` value.
+    pub min_precision: u32,
+}
+
+/// Type of a resource in [`PipelineState::resources`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceType {
+    Invalid,
+    Sampler,
+    CBV,
+    SRVTyped,
+    SRVRaw,
+    SRVStructured,
+    UAVTyped,
+    UAVRaw,
+    UAVStructured,
+    UAVStructuredWithCounter,
+    Unknown(u32),
+}
+
+impl ResourceType {
+    pub fn from_raw(ty: u32) -> Self {
+        match ty {
+            0 => Self::Invalid,
+            1 => Self::Sampler,
+            2 => Self::CBV,
+            3 => Self::SRVTyped,
+            4 => Self::SRVRaw,
+            5 => Self::SRVStructured,
+            6 => Self::UAVTyped,
+            7 => Self::UAVRaw,
+            8 => Self::UAVStructured,
+            9 => Self::UAVStructuredWithCounter,
+            ty => Self::Unknown(ty),
+        }
+    }
+}
+
+/// Resource binding range declared by the shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceBinding {
+    pub resource_type: ResourceType,
+    pub space: u32,
+    pub lower_bound: u32,
+    /// Inclusive, `u32::MAX` for unbounded ranges.
+    pub upper_bound: u32,
+}
+
+/// Contents of the [`PIPELINE_STATE`] part that are relevant to a shader's interface.  Fields
+/// that the part is too old to contain are [`None`].
+#[derive(Clone, Debug)]
+pub struct PipelineState {
+    pub stage: Option<ShaderKind>,
+    pub entry_point: Option<String>,
+    pub num_threads: Option<[u32; 3]>,
+    /// Minimum and maximum expected wave lane count.
+    pub wave_lane_counts: (u32, u32),
+    pub resources: Vec<ResourceBinding>,
+}
+
+/// A parsed DXBC container, borrowing the bytes it was parsed from.
+#[derive(Clone, Debug)]
+pub struct Container<'a> {
+    /// Digest computed by the DXIL validator, all zeroes for unsigned containers.
+    pub hash: [u8; 16],
+    pub version: (u16, u16),
+    pub parts: Vec<Part<'a>>,
+}
+
+impl<'a> Container<'a> {
+    /// Parse the container header and locate all parts, without interpreting their contents.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != b"DXBC" {
+            return Err(invalid("missing `DXBC` header"));
+        }
+        let mut hash = [0; 16];
+        hash.copy_from_slice(&bytes[4..20]);
+        let version = (
+            u16::from_le_bytes([bytes[20], bytes[21]]),
+            u16::from_le_bytes([bytes[22], bytes[23]]),
+        );
+        let size = read_u32(bytes, 24)? as usize;
+        let bytes = bytes.get(..size).ok_or_else(|| {
+            invalid(format!(
+                "container size {size} exceeds the {} available bytes",
+                bytes.len()
+            ))
+        })?;
+        let part_count = read_u32(bytes, 28)? as usize;
+
+        let parts = (0..part_count)
+            .map(|i| {
+                let offset = read_u32(bytes, HEADER_SIZE + i * 4)? as usize;
+                let fourcc = bytes
+                    .get(offset..offset + 4)
+                    .and_then(|b| b.try_into().ok())
+                    .ok_or_else(|| invalid(format!("part {i} out of bounds")))?;
+                let part_size = read_u32(bytes, offset + 4)? as usize;
+                let data = bytes
+                    .get(offset + 8..offset + 8 + part_size)
+                    .ok_or_else(|| invalid(format!("part {i} out of bounds")))?;
+                Ok(Part { fourcc, data })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            hash,
+            version,
+            parts,
+        })
+    }
+
+    /// Data of the first part with this four character code.
+    pub fn part(&self, fourcc: [u8; 4]) -> Option<&'a [u8]> {
+        self.parts
+            .iter()
+            .find(|p| p.fourcc == fourcc)
+            .map(|p| p.data)
+    }
+
+    /// Header of the [`DXIL`] part, if present.
+    pub fn program(&self) -> Result<Option<ProgramHeader<'a>>, Error> {
+        let Some(data) = self.part(DXIL) else {
+            return Ok(None);
+        };
+        let version = read_u32(data, 0)?;
+        if read_u32(data, 8)? != u32::from_le_bytes(*b"DXIL") {
+            return Err(invalid("missing DXIL bitcode header"));
+        }
+        let dxil_version = read_u32(data, 12)?;
+        // Relative to the start of the bitcode header
+        let bitcode_offset = read_u32(data, 16)? as usize;
+        let bitcode_size = read_u32(data, 20)? as usize;
+        let bitcode = data
+            .get(8 + bitcode_offset..8 + bitcode_offset + bitcode_size)
+            .ok_or_else(|| invalid("DXIL bitcode out of bounds"))?;
+        Ok(Some(ProgramHeader {
+            kind: ShaderKind::from_raw(version >> 16),
+            shader_model: ((version >> 4) & 0xf, version & 0xf),
+            dxil_version: (dxil_version >> 8, dxil_version & 0xff),
+            bitcode,
+        }))
+    }
+
+    /// Shader kind from the [`DXIL`] part, or from the [`PIPELINE_STATE`] part when the program
+    /// was stripped.
+    pub fn shader_kind(&self) -> Result<Option<ShaderKind>, Error> {
+        if let Some(program) = self.program()? {
+            return Ok(Some(program.kind));
+        }
+        Ok(self.pipeline_state()?.and_then(|psv| psv.stage))
+    }
+
+    /// Elements of the signature stored in the part with this four character code, such as
+    /// [`INPUT_SIGNATURE`].  The legacy `ISGN`, `OSGN`, `PCSG` and `OSG5` layouts are accepted
+    /// as well.
+    pub fn signature(&self, fourcc: [u8; 4]) -> Result<Option<Vec<SignatureElement>>, Error> {
+        let Some(data) = self.part(fourcc) else {
+            return Ok(None);
+        };
+        let (element_size, has_stream, has_min_precision) = match &fourcc {
+            b"ISG1" | b"OSG1" | b"PSG1" => (32, true, true),
+            b"OSG5" => (28, true, false),
+            b"ISGN" | b"OSGN" | b"PCSG" => (24, false, false),
+            _ => {
+                return Err(invalid(format!(
+                    "`{}` is not a signature part",
+                    String::from_utf8_lossy(&fourcc)
+                )))
+            }
+        };
+        let count = read_u32(data, 0)? as usize;
+        let elements_offset = read_u32(data, 4)? as usize;
+
+        (0..count)
+            .map(|i| {
+                let mut offset = elements_offset + i * element_size;
+                let stream = if has_stream {
+                    offset += 4;
+                    read_u32(data, offset - 4)?
+                } else {
+                    0
+                };
+                Ok(SignatureElement {
+                    semantic_name: read_str(data, read_u32(data, offset)? as usize)?,
+                    semantic_index: read_u32(data, offset + 4)?,
+                    system_value: read_u32(data, offset + 8)?,
+                    component_type: read_u32(data, offset + 12)?,
+                    register: read_u32(data, offset + 16)?,
+                    mask: read_u8(data, offset + 20)?,
+                    stream,
+                    min_precision: if has_min_precision {
+                        read_u32(data, offset + 24)?
+                    } else {
+                        0
+                    },
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+
+    /// Stage, thread group size and resource bindings from the [`PIPELINE_STATE`] part.
+    pub fn pipeline_state(&self) -> Result<Option<PipelineState>, Error> {
+        let Some(data) = self.part(PIPELINE_STATE) else {
+            return Ok(None);
+        };
+        // `PSVRuntimeInfo0` through `PSVRuntimeInfo3` only ever grew at the end
+        let info_size = read_u32(data, 0)? as usize;
+        let info = data
+            .get(4..4 + info_size)
+            .ok_or_else(|| invalid("PSV0 runtime info out of bounds"))?;
+        let wave_lane_counts = (read_u32(info, 16)?, read_u32(info, 20)?);
+        let stage = (info_size >= 36)
+            .then(|| read_u8(info, 24).map(|s| ShaderKind::from_raw(s.into())))
+            .transpose()?;
+        let num_threads = (info_size >= 48)
+            .then(|| {
+                Ok::<_, Error>([
+                    read_u32(info, 36)?,
+                    read_u32(info, 40)?,
+                    read_u32(info, 44)?,
+                ])
+            })
+            .transpose()?;
+
+        let mut offset = 4 + info_size;
+        let resource_count = read_u32(data, offset)? as usize;
+        offset += 4;
+        let mut resources = Vec::new();
+        if resource_count > 0 {
+            let bind_info_size = read_u32(data, offset)? as usize;
+            offset += 4;
+            // `PSVResourceBindInfo0` is 16 bytes, later versions grew at the end
+            if bind_info_size < 16 {
+                return Err(invalid(format!(
+                    "PSV0 resource bind info size {bind_info_size} is smaller than 16 bytes"
+                )));
+            }
+            if resource_count
+                .checked_mul(bind_info_size)
+                .map_or(true, |size| size > data.len() - offset)
+            {
+                return Err(invalid("PSV0 resource bindings out of bounds"));
+            }
+            for _ in 0..resource_count {
+                resources.push(ResourceBinding {
+                    resource_type: ResourceType::from_raw(read_u32(data, offset)?),
+                    space: read_u32(data, offset + 4)?,
+                    lower_bound: read_u32(data, offset + 8)?,
+                    upper_bound: read_u32(data, offset + 12)?,
+                });
+                offset += bind_info_size;
+            }
+        }
+
+        let entry_point = if info_size >= 52 {
+            let string_table_size = read_u32(data, offset)? as usize;
+            let string_table = data
+                .get(offset + 4..offset + 4 + string_table_size)
+                .ok_or_else(|| invalid("PSV0 string table out of bounds"))?;
+            Some(read_str(string_table, read_u32(info, 48)? as usize)?)
+        } else {
+            None
+        };
+
+        Ok(Some(PipelineState {
+            stage,
+            entry_point,
+            num_threads,
+            wave_lane_counts,
+            resources,
+        }))
+    }
+}
@@ -0,0 +1,185 @@
+//! Little-endian encoding shared by the binary formats that `saxaboom-runtime` reads, see
+//! [`crate::bundle`] and [`crate::reflection`], and by [`crate::replay`] recordings, which are
+//! decoded again with [`Decoder`].  Also holds the SHA-256 hashing of those formats and the
+//! other outputs of this crate.
+use sha2::{Digest, Sha256};
+
+/// Appends little-endian values to a byte buffer.
+#[derive(Debug, Default)]
+pub(crate) struct Encoder {
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn u8(&mut self, value: u8) -> &mut Self {
+        self.bytes.push(value);
+        self
+    }
+
+    pub(crate) fn bool(&mut self, value: bool) -> &mut Self {
+        self.u8(value.into())
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes.extend(value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn i32(&mut self, value: i32) -> &mut Self {
+        self.bytes.extend(value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes.extend(value.to_le_bytes());
+        self
+    }
+
+    /// Length as a `u32`, followed by the UTF-8 bytes.
+    pub(crate) fn str(&mut self, value: &str) -> &mut Self {
+        self.u32(len_u32(value.len()));
+        self.bytes.extend(value.as_bytes());
+        self
+    }
+
+    /// Length as a `u32`, followed by the bytes.
+    pub(crate) fn data(&mut self, value: &[u8]) -> &mut Self {
+        self.u32(len_u32(value.len()));
+        self.bytes.extend(value);
+        self
+    }
+
+    /// `0`, or `1` followed by the string.
+    pub(crate) fn opt_str(&mut self, value: Option<&str>) -> &mut Self {
+        self.bool(value.is_some());
+        if let Some(value) = value {
+            self.str(value);
+        }
+        self
+    }
+
+    /// Number of items as a `u32`.
+    pub(crate) fn len(&mut self, len: usize) -> &mut Self {
+        self.u32(len_u32(len))
+    }
+}
+
+/// Reads the values appended by [`Encoder`] back, in the same order.  Errors describe why the
+/// input could not be decoded.
+#[derive(Debug)]
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    /// Whether every byte was read.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.offset == self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let data = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.offset..end))
+            .ok_or_else(|| format!("unexpected end of data at offset {}", self.offset))?;
+        self.offset += len;
+        Ok(data)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(format!(
+                "invalid boolean {other} at offset {}",
+                self.offset - 1
+            )),
+        }
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, String> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, String> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, String> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn data(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub(crate) fn str(&mut self) -> Result<&'a str, String> {
+        let offset = self.offset;
+        std::str::from_utf8(self.data()?)
+            .map_err(|_| format!("invalid UTF-8 in string at offset {offset}"))
+    }
+
+    pub(crate) fn opt_str(&mut self) -> Result<Option<&'a str>, String> {
+        self.bool()?.then(|| self.str()).transpose()
+    }
+
+    /// Number of items, which are at least one byte each and must therefore fit in the input.
+    pub(crate) fn len(&mut self) -> Result<usize, String> {
+        let len = self.u32()? as usize;
+        if len > self.bytes.len() - self.offset {
+            return Err(format!(
+                "{len} items do not fit in the remaining {} bytes",
+                self.bytes.len() - self.offset
+            ));
+        }
+        Ok(len)
+    }
+}
+
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Lowercase hexadecimal, such as for hashes in JSON and error messages.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// `value` as a quoted and escaped JSON string.
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if u32::from(c) < 0x20 => json.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Lengths are stored as `u32`; nothing that is encoded comes close to 4 GiB.
+fn len_u32(len: usize) -> u32 {
+    u32::try_from(len).expect("Length does not fit in a u32")
+}
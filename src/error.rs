@@ -0,0 +1,184 @@
+//! The [`Error`] type returned by every fallible function in this crate.
+use std::fmt;
+
+#[cfg(doc)]
+use crate::{
+    backend, IRCompiler, IRMetalLibBinary, IRObject, IRShaderReflection, MetalIrConverter,
+};
+use crate::{ffi, targets::Target, DiscoverError, IRError};
+
+/// What was being converted when an [`Error`] occurred.  Fields are [`None`] when unknown, for
+/// example the stage of a shader that failed to compile.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Context {
+    pub entry_point: Option<String>,
+    pub stage: Option<ffi::IRShaderStage>,
+    /// Identifies the DXIL input, see [`IRObject::set_name()`].
+    pub input: Option<String>,
+}
+
+impl fmt::Display for Context {
+    /// Formats as ` of entry point `main` (Compute) from `shader.dxil``, omitting unknown parts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(entry_point) = &self.entry_point {
+            write!(f, " of entry point `{entry_point}`")?;
+        }
+        if let Some(stage) = self.stage {
+            write!(f, " ({stage:?})")?;
+        }
+        if let Some(input) = &self.input {
+            write!(f, " from `{input}`")?;
+        }
+        Ok(())
+    }
+}
+
+/// Why [`MetalIrConverter::new()`] or [`MetalIrConverter::from_library()`] failed, wrapped in
+/// [`Error::Load`].
+#[derive(thiserror::Error, Debug)]
+pub enum LoadError {
+    #[error("Failed to load the metal_irconverter library: {0}")]
+    Library(#[from] libloading::Error),
+    /// The library was loaded, but lacks functions that `saxaboom` cannot do without.  Functions
+    /// in [`backend::OPTIONAL_FUNCTIONS`] are never reported here.
+    #[error("The metal_irconverter library does not export required functions: {0:?}")]
+    MissingFunctions(Vec<&'static str>),
+}
+
+/// Returned when calling a function from [`backend::OPTIONAL_FUNCTIONS`] that the loaded
+/// `metal_irconverter` library does not export, typically because it is older than the headers
+/// `saxaboom` was generated from.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("`{function}` is not supported by the loaded metal_irconverter library")]
+pub struct UnsupportedError {
+    pub function: &'static str,
+}
+
+/// Error returned by all fallible functions in this crate.  Failures reported by the
+/// `metal_irconverter` library keep their [`IRError`], see [`Error::ir_error()`].
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    #[error(transparent)]
+    Load(#[from] LoadError),
+    #[error(transparent)]
+    Discover(#[from] DiscoverError),
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedError),
+    /// Returned by [`MetalIrConverter::create_root_signature_from_descriptor()`].
+    #[error("IRRootSignature creation failed: {error}")]
+    RootSignature {
+        #[source]
+        error: IRError,
+    },
+    /// Returned by [`IRCompiler::alloc_compile_and_link()`] and
+    /// [`IRCompiler::alloc_combine_compile_and_link()`].
+    #[error("Compilation{context} failed: {error}")]
+    Compilation {
+        context: Context,
+        #[source]
+        error: IRError,
+    },
+    /// The object holds no `metallib` for its stage, see [`IRObject::metal_lib_binary()`].
+    #[error("No metallib available{context}")]
+    MetalLib { context: Context },
+    /// The object holds no reflection for its stage, see [`IRObject::reflection()`].
+    #[error("No reflection available{context}")]
+    Reflection { context: Context },
+    /// Converting reflection from or to JSON failed, see [`IRShaderReflection::json()`].
+    #[error("Failed to {operation}{context}")]
+    Serialization {
+        operation: &'static str,
+        context: Context,
+    },
+    /// The bytes are not a well-formed DXBC container, see [`crate::dxil::Container::parse()`].
+    #[error("Invalid DXIL container: {reason}")]
+    InvalidDxil { reason: String },
+    /// The bytes are not a well-formed `metallib`, see [`crate::metallib::MetalLib::parse()`].
+    #[error("Invalid metallib: {reason}")]
+    InvalidMetalLib { reason: String },
+    /// A converted `metallib` has problems, found because
+    /// [`IRMetalLibBinary::set_debug_validation()`] is enabled.
+    #[error(
+        "Converted metallib{context} is invalid: {}",
+        .diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    InvalidMetalLibOutput {
+        context: Context,
+        diagnostics: Vec<crate::metallib::Diagnostic>,
+    },
+    /// A function name appears in more than one merged library, see
+    /// [`crate::metallib::MetalLibWriter::add()`].
+    #[error("Function `{name}` appears in more than one metallib")]
+    DuplicateFunction { name: String },
+    /// A shader name was inserted twice, see [`crate::dedup::BlobStore::insert()`].
+    #[error("Shader `{name}` was already inserted")]
+    DuplicateShader { name: String },
+    /// A shader or root signature cannot be added to a bundle, see
+    /// [`crate::bundle::BundleWriter`].
+    #[error("Failed to write bundle: {reason}")]
+    Bundle { reason: String },
+    /// The bytes are not a recording written by this version of saxaboom, see
+    /// [`crate::replay::Recording::from_bytes()`].
+    #[error("Invalid recording: {reason}")]
+    InvalidRecording { reason: String },
+    /// Reading an input file failed, see [`crate::watch`].
+    #[error("Failed to read `{}`: {source}", path.display())]
+    Read {
+        path: std::path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Writing an output file failed, see [`crate::reflection::ReflectionSnapshot::write_sidecars()`].
+    #[error("Failed to write `{}`: {source}", path.display())]
+    Write {
+        path: std::path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Loading `dxcompiler` or compiling HLSL with it failed, see [`crate::dxc`].
+    #[cfg(feature = "dxc")]
+    #[error(transparent)]
+    Dxc(#[from] crate::dxc::DxcError),
+    /// Converting for one of several targets failed, see
+    /// [`IRCompiler::alloc_compile_and_link_for_targets()`].
+    #[error("{error} (targeting {target})")]
+    Target {
+        target: Target,
+        #[source]
+        error: Box<Self>,
+    },
+    /// Converting one of several permutations failed, see
+    /// [`IRCompiler::alloc_compile_and_link_permutations()`].
+    #[error("{error} (permutation {key})")]
+    Permutation {
+        /// Such as `ALPHA_TEST=0 LIGHTING=pbr`.
+        key: String,
+        #[source]
+        error: Box<Self>,
+    },
+}
+
+impl Error {
+    /// The error reported by the `metal_irconverter` library, to inspect its
+    /// [`code`][IRError::code()] and [`payload`][IRError::payload()].
+    pub fn ir_error(&self) -> Option<&IRError> {
+        match self {
+            Self::RootSignature { error } | Self::Compilation { error, .. } => Some(error),
+            Self::Target { error, .. } | Self::Permutation { error, .. } => error.ir_error(),
+            _ => None,
+        }
+    }
+
+    /// What was being converted when this error occurred, if known.
+    pub fn context(&self) -> Option<&Context> {
+        match self {
+            Self::Compilation { context, .. }
+            | Self::MetalLib { context }
+            | Self::Reflection { context }
+            | Self::Serialization { context, .. } => Some(context),
+            Self::Target { error, .. } | Self::Permutation { error, .. } => error.context(),
+            _ => None,
+        }
+    }
+}
@@ -1,14 +1,19 @@
 #![doc = include_str!("../README.md")]
 #![deny(clippy::use_self, clippy::unwrap_used, rust_2018_idioms)]
 use std::{
-    error,
-    ffi::{c_char, CStr, OsStr},
+    cell::RefCell,
+    ffi::{c_char, c_void, CStr, OsStr},
     fmt,
     marker::PhantomData,
     mem::MaybeUninit,
     ops::Deref,
+    path::{Path, PathBuf},
     ptr::NonNull,
-    sync::Arc,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 #[expect(
@@ -21,27 +26,160 @@ use std::{
 )]
 pub mod bindings;
 pub use bindings as ffi;
-use thiserror::Error;
+
+pub mod backend;
+pub use backend::ConverterBackend;
+pub mod bundle;
+pub mod dedup;
+pub mod diff;
+pub mod discover;
+pub use discover::DiscoverError;
+#[cfg(feature = "dxc")]
+pub mod dxc;
+pub mod dxil;
+mod encode;
+pub mod error;
+pub use error::{Error, LoadError, UnsupportedError};
+pub mod metallib;
+pub mod permutations;
+pub mod provenance;
+pub mod reflection;
+pub mod replay;
+pub mod targets;
+pub use targets::{LowestGpuFamily, Target};
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod watch;
 
 /// [`MetalIrConverter`] is used to load the `metal_irconverter` dynamic library and holds its
 /// functions in an [`Arc`]. Since [`IRCompiler`] is not thread-safe, this struct provides an
 /// interface to create [`IRCompiler`] instances as well as other objects provided by the library
 /// ([`IRObject`], [`IRRootSignature`]), without needing an [`IRCompiler`] instance. This way, the
 /// library only has to be loaded once, but each thread can have its own [`IRCompiler`] instance.
+///
+/// All calls go through a [`ConverterBackend`], which is the loaded dynamic library unless a
+/// different backend was passed to [`MetalIrConverter::from_backend()`].
 #[derive(Clone)]
 pub struct MetalIrConverter {
-    funcs: Arc<bindings::metal_irconverter>,
+    funcs: Arc<dyn ConverterBackend>,
+    library: provenance::ConverterLibrary,
+}
+
+fn require(funcs: &dyn ConverterBackend, function: &'static str) -> Result<(), Error> {
+    if funcs.is_loaded(function) {
+        Ok(())
+    } else {
+        Err(UnsupportedError { function }.into())
+    }
+}
+
+/// See [`MetalIrConverter::supports_json_reflection()`], for the objects it creates.
+fn supports_json_reflection(funcs: &dyn ConverterBackend) -> bool {
+    [
+        "IRShaderReflectionCopyJSONString",
+        "IRShaderReflectionReleaseString",
+        "IRShaderReflectionCreateFromJSON",
+    ]
+    .iter()
+    .all(|f| funcs.is_loaded(f))
 }
 
 impl MetalIrConverter {
-    pub fn new(lib_path: impl AsRef<OsStr>) -> Result<Self, libloading::Error> {
-        let funcs = Arc::new(unsafe { bindings::metal_irconverter::new(lib_path)? });
-        Ok(Self { funcs })
+    pub fn new(lib_path: impl AsRef<OsStr>) -> Result<Self, Error> {
+        Ok(Self::load(lib_path)?)
     }
 
-    pub fn from_library(lib: libloading::Library) -> Result<Self, libloading::Error> {
-        let funcs = Arc::new(unsafe { bindings::metal_irconverter::from_library(lib)? });
-        Ok(Self { funcs })
+    pub fn from_library(lib: libloading::Library) -> Result<Self, Error> {
+        Ok(Self::from_loaded(unsafe {
+            bindings::metal_irconverter::from_library(lib).map_err(LoadError::from)?
+        })?)
+    }
+
+    fn load(lib_path: impl AsRef<OsStr>) -> Result<Self, LoadError> {
+        let mut library_path = PathBuf::from(lib_path.as_ref());
+        let funcs = unsafe { bindings::metal_irconverter::new(lib_path)? };
+        // A bare file name is searched for by the dynamic loader, ask it which file it found
+        if library_path.parent() == Some(Path::new("")) {
+            let symbol = funcs.IRCompilerCreate.as_ref().ok();
+            if let Some(path) = symbol.and_then(|f| discover::loaded_from(*f as *const c_void)) {
+                library_path = path;
+            }
+        }
+        Ok(Self {
+            library: provenance::ConverterLibrary::new(Some(library_path)),
+            ..Self::from_loaded(funcs)?
+        })
+    }
+
+    fn from_loaded(funcs: bindings::metal_irconverter) -> Result<Self, LoadError> {
+        let missing = backend::FUNCTIONS
+            .iter()
+            .copied()
+            .filter(|f| !backend::OPTIONAL_FUNCTIONS.contains(f) && !funcs.is_loaded(f))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(LoadError::MissingFunctions(missing));
+        }
+        Ok(Self {
+            funcs: Arc::new(funcs),
+            library: provenance::ConverterLibrary::default(),
+        })
+    }
+
+    /// Use a custom [`ConverterBackend`] instead of loading the `metal_irconverter` dynamic
+    /// library, for example `testing::FakeConverter` (requires the `testing` feature) in unit
+    /// tests.
+    pub fn from_backend(backend: impl ConverterBackend + 'static) -> Self {
+        Self {
+            funcs: Arc::new(backend),
+            library: provenance::ConverterLibrary::default(),
+        }
+    }
+
+    /// Path this converter was loaded from, as passed to [`MetalIrConverter::new()`] or picked by
+    /// [`MetalIrConverter::discover()`].  When that was a bare file name, this is the file that the
+    /// dynamic loader found instead.  [`None`] for [`MetalIrConverter::from_library()`] and
+    /// [`MetalIrConverter::from_backend()`].
+    pub fn library_path(&self) -> Option<&Path> {
+        self.library.path.as_deref()
+    }
+
+    /// SHA-256 of the file at [`Self::library_path()`], which identifies the exact release of the
+    /// library since it does not report its version.  Computed on the first call, [`None`] when
+    /// there is no path or the file cannot be read.
+    pub fn library_hash(&self) -> Option<[u8; 32]> {
+        self.library.hash()
+    }
+
+    /// Whether `function`, one of [`backend::FUNCTIONS`], was found in the loaded library.
+    pub fn is_loaded(&self, function: &str) -> bool {
+        self.funcs.is_loaded(function)
+    }
+
+    /// Functions from [`backend::OPTIONAL_FUNCTIONS`] that the loaded library does not export.
+    pub fn missing_optional_functions(&self) -> Vec<&'static str> {
+        backend::OPTIONAL_FUNCTIONS
+            .iter()
+            .copied()
+            .filter(|f| !self.funcs.is_loaded(f))
+            .collect()
+    }
+
+    /// Whether [`IRShaderReflection::json()`] and
+    /// [`MetalIrConverter::create_shader_reflection_from_json()`] are available.
+    pub fn supports_json_reflection(&self) -> bool {
+        supports_json_reflection(&*self.funcs)
+    }
+
+    /// Whether [`IRCompiler::enable_geometry_and_tessellation_emulation()`] and
+    /// [`IRCompiler::set_input_topology()`] are available.
+    pub fn supports_geometry_and_tessellation_emulation(&self) -> bool {
+        [
+            "IRCompilerEnableGeometryAndTessellationEmulation",
+            "IRCompilerSetInputTopology",
+        ]
+        .iter()
+        .all(|f| self.funcs.is_loaded(f))
     }
 
     #[doc(alias = "IRCompilerCreate")]
@@ -51,33 +189,43 @@ impl MetalIrConverter {
         IRCompiler {
             me: compiler,
             funcs: self.funcs.clone(),
+            library: self.library.clone(),
+            settings: Vec::new(),
+            recording: RefCell::new(None),
         }
     }
 
     #[doc(alias = "IRObjectCreateFromDXIL")]
     pub fn create_object_from_dxil(&self, bytecode: &[u8]) -> IRObject {
-        unsafe {
-            let me = NonNull::new(self.funcs.IRObjectCreateFromDXIL(
-                bytecode.as_ptr(),
-                bytecode.len(),
-                // TODO: This creates a copy of the data.  We could also set this to None
-                // with a PhantdomData lifetime on the incoming bytecode slice
-                bindings::IRBytecodeOwnership::Copy,
-            ))
-            .expect("Failed to create IRObject from DXIL");
-
-            IRObject {
-                me,
-                funcs: self.funcs.clone(),
-            }
-        }
+        IRObject::from_dxil(self.funcs.clone(), bytecode)
+    }
+
+    /// Empty binary to synthesize into, such as with
+    /// [`IRCompiler::synthesize_stage_in_function()`].
+    #[doc(alias = "IRMetalLibBinaryCreate")]
+    pub fn create_metal_lib_binary(&self) -> IRMetalLibBinary {
+        IRMetalLibBinary::new(self.funcs.clone())
     }
 
     #[doc(alias = "IRRootSignatureCreateFromDescriptor")]
     pub fn create_root_signature_from_descriptor(
         &self,
         desc: &ffi::IRVersionedRootSignatureDescriptor,
-    ) -> Result<IRRootSignature, RootSignatureError> {
+    ) -> Result<IRRootSignature, Error> {
+        let me = self.alloc_root_signature(desc)?;
+        Ok(IRRootSignature {
+            me,
+            funcs: self.funcs.clone(),
+            json: Rc::new(unsafe {
+                replay::RootSignatureJson::from_descriptor(self.funcs.clone(), desc)
+            }),
+        })
+    }
+
+    fn alloc_root_signature(
+        &self,
+        desc: &ffi::IRVersionedRootSignatureDescriptor,
+    ) -> Result<NonNull<bindings::IRRootSignature>, Error> {
         let mut error = std::ptr::null_mut();
 
         let me = NonNull::new(unsafe {
@@ -87,14 +235,64 @@ impl MetalIrConverter {
 
         if let Some(error) = NonNull::new(error) {
             let error = unsafe { IRError::from_ptr(error, self.funcs.clone()) };
-            return Err(RootSignatureError(error));
+            return Err(Error::RootSignature { error });
         }
 
-        let me =
-            me.expect("IRRootSignatureCreateFromDescriptor should not return NULL without error");
+        Ok(me.expect("IRRootSignatureCreateFromDescriptor should not return NULL without error"))
+    }
+
+    /// Create a root signature from its JSON representation, as written by
+    /// `metal-shaderconverter --output-root-signature`.
+    #[doc(alias(
+        "IRVersionedRootSignatureDescriptorCreateFromJSON",
+        "IRVersionedRootSignatureDescriptorRelease"
+    ))]
+    pub fn create_root_signature_from_json(&self, json: &CStr) -> Result<IRRootSignature, Error> {
+        require(
+            &*self.funcs,
+            "IRVersionedRootSignatureDescriptorCreateFromJSON",
+        )?;
+        require(&*self.funcs, "IRVersionedRootSignatureDescriptorRelease")?;
+        let desc = NonNull::new(unsafe {
+            self.funcs
+                .IRVersionedRootSignatureDescriptorCreateFromJSON(json.as_ptr())
+        })
+        .ok_or(Error::Serialization {
+            operation: "parse root signature JSON",
+            context: error::Context::default(),
+        })?;
+        let me = self.alloc_root_signature(unsafe { desc.as_ref() });
+        unsafe {
+            self.funcs
+                .IRVersionedRootSignatureDescriptorRelease(desc.as_ptr())
+        };
         Ok(IRRootSignature {
+            me: me?,
+            funcs: self.funcs.clone(),
+            json: Rc::new(replay::RootSignatureJson::from_json(
+                self.funcs.clone(),
+                json.to_string_lossy().into_owned(),
+            )),
+        })
+    }
+
+    /// Parse reflection previously serialized with [`IRShaderReflection::json()`].
+    #[doc(alias = "IRShaderReflectionCreateFromJSON")]
+    pub fn create_shader_reflection_from_json(
+        &self,
+        json: &CStr,
+    ) -> Result<IRShaderReflection, Error> {
+        require(&*self.funcs, "IRShaderReflectionCreateFromJSON")?;
+        let me =
+            NonNull::new(unsafe { self.funcs.IRShaderReflectionCreateFromJSON(json.as_ptr()) })
+                .ok_or(Error::Serialization {
+                    operation: "parse IRShaderReflection JSON",
+                    context: error::Context::default(),
+                })?;
+        Ok(IRShaderReflection {
             me,
             funcs: self.funcs.clone(),
+            context: error::Context::default(),
         })
     }
 }
@@ -103,7 +301,7 @@ macro_rules! versioned_info {
     ($name:ident, $create:ident, $release:ident) => {
         pub struct $name {
             me: ffi::$name,
-            funcs: Arc<bindings::metal_irconverter>,
+            funcs: Arc<dyn ConverterBackend>,
         }
 
         impl Deref for $name {
@@ -199,7 +397,8 @@ versioned_info!(
 
 pub struct IRShaderReflection {
     me: NonNull<bindings::IRShaderReflection>,
-    funcs: Arc<bindings::metal_irconverter>,
+    funcs: Arc<dyn ConverterBackend>,
+    context: error::Context,
 }
 
 impl Drop for IRShaderReflection {
@@ -213,10 +412,10 @@ impl IRShaderReflection {
     /// **Private** function that's not on [`MetalIrConverter`] because it is only used internally
     /// to return initialized objects.
     #[doc(alias = "IRShaderReflectionCreate")]
-    fn new(funcs: Arc<bindings::metal_irconverter>) -> Self {
+    fn new(funcs: Arc<dyn ConverterBackend>, context: error::Context) -> Self {
         let me = NonNull::new(unsafe { funcs.IRShaderReflectionCreate() })
             .expect("Failed to create IRShaderReflection");
-        Self { me, funcs }
+        Self { me, funcs, context }
     }
 
     #[doc(alias = "IRShaderReflectionCopyVertexInfo")]
@@ -266,11 +465,123 @@ impl IRShaderReflection {
     pub fn raytracing_info(&self, version: ffi::IRReflectionVersion) -> Option<IRVersionedRTInfo> {
         IRVersionedRTInfo::new(self, version)
     }
+
+    /// Name of the entry point this reflection was generated for.
+    #[doc(alias = "IRShaderReflectionGetEntryPointFunctionName")]
+    pub fn entry_point_function_name(&self) -> Option<&CStr> {
+        let name = unsafe {
+            self.funcs
+                .IRShaderReflectionGetEntryPointFunctionName(self.me.as_ptr())
+        };
+        (!name.is_null()).then(|| unsafe { CStr::from_ptr(name) })
+    }
+
+    /// Whether the shader only operates correctly when the [`function_constants()`][Self::function_constants()]
+    /// are provided when creating its pipeline.
+    #[doc(alias = "IRShaderReflectionNeedsFunctionConstants")]
+    pub fn needs_function_constants(&self) -> bool {
+        unsafe {
+            self.funcs
+                .IRShaderReflectionNeedsFunctionConstants(self.me.as_ptr())
+        }
+    }
+
+    #[doc(alias(
+        "IRShaderReflectionGetFunctionConstantCount",
+        "IRShaderReflectionCopyFunctionConstants",
+        "IRShaderReflectionReleaseFunctionConstants"
+    ))]
+    pub fn function_constants(&self) -> Vec<FunctionConstant> {
+        let count = unsafe {
+            self.funcs
+                .IRShaderReflectionGetFunctionConstantCount(self.me.as_ptr())
+        };
+        if count == 0 {
+            return Vec::new();
+        }
+        let mut constants = Vec::<ffi::IRFunctionConstant>::with_capacity(count);
+        unsafe {
+            self.funcs
+                .IRShaderReflectionCopyFunctionConstants(self.me.as_ptr(), constants.as_mut_ptr());
+            constants.set_len(count);
+        }
+        let owned = constants
+            .iter()
+            .map(|c| FunctionConstant {
+                name: unsafe { CStr::from_ptr(c.name) }
+                    .to_string_lossy()
+                    .into_owned(),
+                r#type: c.type_,
+            })
+            .collect();
+        unsafe {
+            self.funcs
+                .IRShaderReflectionReleaseFunctionConstants(constants.as_mut_ptr(), count)
+        };
+        owned
+    }
+
+    /// Locations of the resources within the top-level argument buffer.  Resource names point
+    /// into this reflection object.
+    #[doc(alias(
+        "IRShaderReflectionGetResourceCount",
+        "IRShaderReflectionGetResourceLocations"
+    ))]
+    pub fn resource_locations(&self) -> Vec<ffi::IRResourceLocation> {
+        let n_resources = unsafe {
+            self.funcs
+                .IRShaderReflectionGetResourceCount(self.me.as_ptr())
+        };
+        let mut resource_locations = Vec::with_capacity(n_resources);
+        unsafe {
+            self.funcs.IRShaderReflectionGetResourceLocations(
+                self.me.as_ptr(),
+                resource_locations.as_mut_ptr(),
+            );
+            resource_locations.set_len(n_resources)
+        };
+        resource_locations
+    }
+
+    /// Serialize this reflection to JSON, in the format produced by `metal-shaderconverter
+    /// --output-reflection-file`.
+    #[doc(alias("IRShaderReflectionCopyJSONString", "IRShaderReflectionReleaseString"))]
+    pub fn json(&self) -> Result<String, Error> {
+        require(&*self.funcs, "IRShaderReflectionCopyJSONString")?;
+        require(&*self.funcs, "IRShaderReflectionReleaseString")?;
+        unsafe {
+            let json = self
+                .funcs
+                .IRShaderReflectionCopyJSONString(self.me.as_ptr());
+            if json.is_null() {
+                return Err(Error::Serialization {
+                    operation: "serialize IRShaderReflection to JSON",
+                    context: self.context.clone(),
+                });
+            }
+            let string = CStr::from_ptr(json).to_string_lossy().into_owned();
+            self.funcs.IRShaderReflectionReleaseString(json);
+            Ok(string)
+        }
+    }
+}
+
+/// Function constant reported by [`IRShaderReflection::function_constants()`], the owned
+/// counterpart of [`ffi::IRFunctionConstant`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionConstant {
+    pub name: String,
+    pub r#type: ffi::IRFunctionConstantType,
 }
 
 pub struct IRObject {
     me: NonNull<bindings::IRObject>,
-    funcs: Arc<bindings::metal_irconverter>,
+    funcs: Arc<dyn ConverterBackend>,
+    context: error::Context,
+    /// Input of objects created from DXIL, read by the converter without copying it.
+    dxil: Rc<provenance::Dxil>,
+    /// See [`IRObject::provenance()`].
+    origin: Option<Box<provenance::Origin>>,
 }
 
 impl Drop for IRObject {
@@ -281,12 +592,63 @@ impl Drop for IRObject {
 }
 
 impl IRObject {
+    fn from_dxil(funcs: Arc<dyn ConverterBackend>, bytecode: &[u8]) -> Self {
+        // The one copy of the bytecode, which outlives the object because `IRObjectDestroy()` is
+        // called before the fields are dropped
+        let dxil = Rc::new(provenance::Dxil::new(bytecode));
+        let me = NonNull::new(unsafe {
+            funcs.IRObjectCreateFromDXIL(
+                dxil.bytes.as_ptr(),
+                dxil.bytes.len(),
+                bindings::IRBytecodeOwnership::None,
+            )
+        })
+        .expect("Failed to create IRObject from DXIL");
+
+        Self {
+            me,
+            funcs,
+            context: error::Context::default(),
+            dxil,
+            origin: None,
+        }
+    }
+
+    /// Wrap a freshly compiled object, completing `context` with its stage.
+    fn compiled(
+        me: NonNull<bindings::IRObject>,
+        funcs: Arc<dyn ConverterBackend>,
+        context: error::Context,
+    ) -> Self {
+        let mut object = Self {
+            me,
+            funcs,
+            context,
+            dxil: Rc::default(),
+            origin: None,
+        };
+        object.context.stage = Some(object.metal_ir_shader_stage());
+        object
+    }
+
+    /// Identify this DXIL input (typically by its file name) in the [`error::Context`] of errors
+    /// caused by it, and of the objects compiled from it.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.context.input = Some(name.into());
+    }
+
+    /// Entry point, stage and input this object was compiled from, as far as known.
+    pub fn context(&self) -> &error::Context {
+        &self.context
+    }
+
     #[doc(alias = "IRObjectGatherRaytracingIntrinsics")]
-    pub fn gather_raytracing_intrinsics(&self, entry_point: &CStr) -> u64 {
-        unsafe {
+    pub fn gather_raytracing_intrinsics(&self, entry_point: &CStr) -> Result<u64, Error> {
+        require(&*self.funcs, "IRObjectGatherRaytracingIntrinsics")?;
+        Ok(unsafe {
             self.funcs
                 .IRObjectGatherRaytracingIntrinsics(self.me.as_ptr(), entry_point.as_ptr())
-        }
+        })
     }
 
     #[doc(alias = "IRObjectGetType")]
@@ -300,7 +662,7 @@ impl IRObject {
     }
 
     #[doc(alias = "IRObjectGetMetalLibBinary")]
-    pub fn metal_lib_binary(&self) -> Option<IRMetalLibBinary> {
+    pub fn metal_lib_binary(&self) -> Result<IRMetalLibBinary, Error> {
         let binary = IRMetalLibBinary::new(self.funcs.clone());
         if unsafe {
             self.funcs.IRObjectGetMetalLibBinary(
@@ -309,15 +671,17 @@ impl IRObject {
                 binary.me.as_ptr(),
             )
         } {
-            Some(binary)
+            binary.debug_validate(&self.context)
         } else {
-            None
+            Err(Error::MetalLib {
+                context: self.context.clone(),
+            })
         }
     }
 
     #[doc(alias = "IRObjectGetReflection")]
-    pub fn reflection(&self) -> Option<IRShaderReflection> {
-        let reflection = IRShaderReflection::new(self.funcs.clone());
+    pub fn reflection(&self) -> Result<IRShaderReflection, Error> {
+        let reflection = IRShaderReflection::new(self.funcs.clone(), self.context.clone());
         if unsafe {
             self.funcs.IRObjectGetReflection(
                 self.me.as_ptr(),
@@ -325,18 +689,23 @@ impl IRObject {
                 reflection.me.as_ptr(),
             )
         } {
-            Some(reflection)
+            Ok(reflection)
         } else {
-            None
+            Err(Error::Reflection {
+                context: self.context.clone(),
+            })
         }
     }
 }
 
 pub struct IRMetalLibBinary {
     me: NonNull<bindings::IRMetalLibBinary>,
-    funcs: Arc<bindings::metal_irconverter>,
+    funcs: Arc<dyn ConverterBackend>,
 }
 
+/// Set by [`IRMetalLibBinary::set_debug_validation()`].
+static DEBUG_VALIDATION: AtomicBool = AtomicBool::new(false);
+
 impl Drop for IRMetalLibBinary {
     #[doc(alias = "IRMetalLibBinaryDestroy")]
     fn drop(&mut self) {
@@ -345,10 +714,8 @@ impl Drop for IRMetalLibBinary {
 }
 
 impl IRMetalLibBinary {
-    /// **Private** function that's not on [`MetalIrConverter`] because it is only used internally
-    /// to return initialized objects.
-    #[doc(alias = "IRMetalLibBinaryCreate")]
-    fn new(funcs: Arc<bindings::metal_irconverter>) -> Self {
+    /// Use [`MetalIrConverter::create_metal_lib_binary()`] to create an empty binary.
+    fn new(funcs: Arc<dyn ConverterBackend>) -> Self {
         unsafe {
             let me = NonNull::new(funcs.IRMetalLibBinaryCreate())
                 .expect("Failed to create empty IRMetalLibBinary");
@@ -368,11 +735,44 @@ impl IRMetalLibBinary {
         unsafe { bytes.set_len(written) }
         bytes
     }
+
+    /// Runs [`metallib::validate()`] on [`Self::byte_code()`].
+    pub fn validate(&self) -> Vec<metallib::Diagnostic> {
+        metallib::validate(&self.byte_code())
+    }
+
+    /// When enabled in a build with `debug_assertions`, every `metallib` returned by
+    /// [`IRObject::metal_lib_binary()`] and the `synthesize_*()` functions of [`IRCompiler`] is
+    /// [validated](Self::validate()) first, and fails with [`Error::InvalidMetalLibOutput`] when
+    /// there are problems.  This catches corrupt converter output at conversion time, rather than
+    /// when Metal loads it.
+    ///
+    /// Applies to the whole process, and does nothing in release builds.  Disabled by default.
+    pub fn set_debug_validation(enabled: bool) {
+        DEBUG_VALIDATION.store(enabled, Ordering::Relaxed);
+    }
+
+    fn debug_validate(self, context: &error::Context) -> Result<Self, Error> {
+        if !cfg!(debug_assertions) || !DEBUG_VALIDATION.load(Ordering::Relaxed) {
+            return Ok(self);
+        }
+        let diagnostics = self.validate();
+        if diagnostics.is_empty() {
+            Ok(self)
+        } else {
+            Err(Error::InvalidMetalLibOutput {
+                context: context.clone(),
+                diagnostics,
+            })
+        }
+    }
 }
 
 pub struct IRRootSignature {
     me: NonNull<bindings::IRRootSignature>,
-    funcs: Arc<bindings::metal_irconverter>,
+    funcs: Arc<dyn ConverterBackend>,
+    /// Shared with the settings of the compilers it is set on.
+    json: Rc<replay::RootSignatureJson>,
 }
 
 impl Drop for IRRootSignature {
@@ -382,12 +782,19 @@ impl Drop for IRRootSignature {
     }
 }
 
-/// Captures errors returned by [`MetalIrConverter::create_root_signature_from_descriptor()`].
-#[derive(Error, Debug)]
-#[error("IRRootSignature creation failed: {0:?}")]
-pub struct RootSignatureError(IRError);
-
 impl IRRootSignature {
+    /// The JSON passed to [`MetalIrConverter::create_root_signature_from_json()`], or the
+    /// descriptor passed to [`MetalIrConverter::create_root_signature_from_descriptor()`]
+    /// serialized to JSON when the loaded library supports it.  The descriptor is serialized the
+    /// first time its JSON is needed, here or for a recording or provenance.
+    #[doc(alias(
+        "IRVersionedRootSignatureDescriptorCopyJSONString",
+        "IRVersionedRootSignatureDescriptorReleaseString"
+    ))]
+    pub fn json(&self) -> Option<&str> {
+        self.json.get()
+    }
+
     #[doc(alias(
         "IRRootSignatureGetResourceCount",
         "IRRootSignatureGetResourceLocations"
@@ -406,17 +813,17 @@ impl IRRootSignature {
     }
 }
 
-/// Captures errors returned by [`IRCompiler::alloc_compile_and_link()`].
-#[derive(Error, Debug)]
-#[error("Compilation failed: {0:?}")]
-pub struct CompilerError(IRError);
-
 /// This object is not thread-safe, refer to [the Metal shader converter documentation], the "Multithreading considerations" chapter.
 ///
 /// [the Metal shader converter documentation]: https://developer.apple.com/metal/shader-converter/
 pub struct IRCompiler {
     me: NonNull<bindings::IRCompiler>,
-    funcs: Arc<bindings::metal_irconverter>,
+    funcs: Arc<dyn ConverterBackend>,
+    library: provenance::ConverterLibrary,
+    /// See [`IRCompiler::settings()`].
+    settings: Vec<provenance::Setting>,
+    /// See [`IRCompiler::start_recording()`].
+    recording: RefCell<Option<replay::Recording>>,
 }
 
 impl Drop for IRCompiler {
@@ -470,6 +877,7 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetValidationFlags(self.me.as_ptr(), validation_flags)
         }
+        self.apply(replay::Call::SetValidationFlags(validation_flags));
     }
 
     #[doc(alias = "IRCompilerSetStageInGenerationMode")]
@@ -478,6 +886,7 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetStageInGenerationMode(self.me.as_ptr(), stage)
         }
+        self.apply(replay::Call::SetStageInGenerationMode(stage));
     }
 
     #[must_use]
@@ -488,14 +897,21 @@ impl IRCompiler {
         layout: impl Into<ffi::IRVersionedInputLayoutDescriptor>,
         binary: &IRMetalLibBinary,
     ) -> bool {
-        unsafe {
+        let layout = layout.into();
+        let synthesized = unsafe {
             self.funcs.IRMetalLibSynthesizeStageInFunction(
                 self.me.as_ptr(),
                 vertex_shader_reflection.me.as_ptr(),
-                &layout.into(),
+                &layout,
                 binary.me.as_ptr(),
             )
-        }
+        };
+        self.record(|| replay::Call::SynthesizeStageInFunction {
+            vertex_shader_reflection: vertex_shader_reflection.json().ok(),
+            layout: replay::layout_json(&*self.funcs, &layout),
+            output: replay::Output::Synthesized(synthesized.then(|| binary.byte_code())),
+        });
+        synthesized
     }
 
     #[doc(alias = "IRCompilerSetGlobalRootSignature")]
@@ -504,6 +920,9 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetGlobalRootSignature(self.me.as_ptr(), root_signature.me.as_ptr())
         }
+        self.apply_setting(provenance::Setting::GlobalRootSignature(Rc::clone(
+            &root_signature.json,
+        )));
     }
 
     #[doc(alias = "IRCompilerSetLocalRootSignature")]
@@ -512,6 +931,9 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetLocalRootSignature(self.me.as_ptr(), root_signature.me.as_ptr())
         }
+        self.apply_setting(provenance::Setting::LocalRootSignature(Rc::clone(
+            &root_signature.json,
+        )));
     }
 
     #[doc(alias = "IRCompilerSetHitgroupType")]
@@ -520,6 +942,7 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetHitgroupType(self.me.as_ptr(), hit_group_type)
         }
+        self.apply(replay::Call::SetHitgroupType(hit_group_type));
     }
 
     #[doc(alias = "IRCompilerSetRayTracingPipelineArguments")]
@@ -550,6 +973,19 @@ impl IRCompiler {
                 intersection_function_compilation_mode,
             )
         }
+        self.apply(replay::Call::SetRayTracingPipelineArguments(
+            replay::RayTracingPipelineArguments {
+                max_attribute_size_in_bytes,
+                raytracing_pipeline_flags,
+                closest_hit_intrinsics_mask,
+                miss_intrinsics_mask,
+                any_hit_intrinsics_mask,
+                callable_args,
+                max_recursive_depth,
+                ray_generation_compilation_mode,
+                intersection_function_compilation_mode,
+            },
+        ));
     }
 
     #[doc(alias = "IRCompilerSetCompatibilityFlags")]
@@ -558,22 +994,38 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetCompatibilityFlags(self.me.as_ptr(), flags)
         }
+        self.apply(replay::Call::SetCompatibilityFlags(flags));
     }
 
     #[doc(alias = "IRCompilerSetInputTopology")]
-    pub fn set_input_topology(&mut self, input_topology: ffi::IRInputTopology) {
+    pub fn set_input_topology(
+        &mut self,
+        input_topology: ffi::IRInputTopology,
+    ) -> Result<(), Error> {
+        require(&*self.funcs, "IRCompilerSetInputTopology")?;
         unsafe {
             self.funcs
                 .IRCompilerSetInputTopology(self.me.as_ptr(), input_topology)
         }
+        self.apply(replay::Call::SetInputTopology(input_topology));
+        Ok(())
     }
 
     #[doc(alias = "IRCompilerEnableGeometryAndTessellationEmulation")]
-    pub fn enable_geometry_and_tessellation_emulation(&mut self, enable: bool) {
+    pub fn enable_geometry_and_tessellation_emulation(
+        &mut self,
+        enable: bool,
+    ) -> Result<(), Error> {
+        require(
+            &*self.funcs,
+            "IRCompilerEnableGeometryAndTessellationEmulation",
+        )?;
         unsafe {
             self.funcs
                 .IRCompilerEnableGeometryAndTessellationEmulation(self.me.as_ptr(), enable)
         }
+        self.apply(replay::Call::EnableGeometryAndTessellationEmulation(enable));
+        Ok(())
     }
 
     #[doc(alias = "IRCompilerSetDualSourceBlendingConfiguration")]
@@ -585,54 +1037,83 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetDualSourceBlendingConfiguration(self.me.as_ptr(), configuration)
         }
+        self.apply(replay::Call::SetDualSourceBlendingConfiguration(
+            configuration,
+        ));
     }
 
     #[doc(alias = "IRCompilerSetDepthFeedbackConfiguration")]
     pub fn set_depth_feedback_configuration(
         &mut self,
         configuration: ffi::IRDepthFeedbackConfiguration,
-    ) {
+    ) -> Result<(), Error> {
+        require(&*self.funcs, "IRCompilerSetDepthFeedbackConfiguration")?;
         unsafe {
             self.funcs
                 .IRCompilerSetDepthFeedbackConfiguration(self.me.as_ptr(), configuration)
         }
+        self.apply(replay::Call::SetDepthFeedbackConfiguration(configuration));
+        Ok(())
     }
 
     #[doc(alias = "IRCompilerSetIntRTMask")]
-    pub fn set_int_rt_mask(&mut self, int_rt_mask: u8) {
+    pub fn set_int_rt_mask(&mut self, int_rt_mask: u8) -> Result<(), Error> {
+        require(&*self.funcs, "IRCompilerSetIntRTMask")?;
         unsafe {
             self.funcs
                 .IRCompilerSetIntRTMask(self.me.as_ptr(), int_rt_mask)
         }
+        self.apply(replay::Call::SetIntRtMask(int_rt_mask));
+        Ok(())
     }
 
     #[doc(alias = "IRMetalLibSynthesizeIndirectRayDispatchFunction")]
-    pub fn synthesize_indirect_ray_dispatch_function(&mut self) -> Option<IRMetalLibBinary> {
+    pub fn synthesize_indirect_ray_dispatch_function(
+        &mut self,
+    ) -> Result<Option<IRMetalLibBinary>, Error> {
+        require(
+            &*self.funcs,
+            "IRMetalLibSynthesizeIndirectRayDispatchFunction",
+        )?;
         let binary = IRMetalLibBinary::new(self.funcs.clone());
-        if unsafe {
+        let synthesized = unsafe {
             self.funcs.IRMetalLibSynthesizeIndirectRayDispatchFunction(
                 self.me.as_ptr(),
                 binary.me.as_ptr(),
             )
-        } {
-            Some(binary)
+        };
+        self.record(|| replay::Call::SynthesizeIndirectRayDispatchFunction {
+            output: replay::Output::Synthesized(synthesized.then(|| binary.byte_code())),
+        });
+        if synthesized {
+            binary.debug_validate(&error::Context::default()).map(Some)
         } else {
-            None
+            Ok(None)
         }
     }
 
     #[doc(alias = "IRMetalLibSynthesizeIndirectIntersectionFunction")]
-    pub fn synthesize_indirect_intersection_function(&mut self) -> Option<IRMetalLibBinary> {
+    pub fn synthesize_indirect_intersection_function(
+        &mut self,
+    ) -> Result<Option<IRMetalLibBinary>, Error> {
+        require(
+            &*self.funcs,
+            "IRMetalLibSynthesizeIndirectIntersectionFunction",
+        )?;
         let binary = IRMetalLibBinary::new(self.funcs.clone());
-        if unsafe {
+        let synthesized = unsafe {
             self.funcs.IRMetalLibSynthesizeIndirectIntersectionFunction(
                 self.me.as_ptr(),
                 binary.me.as_ptr(),
             )
-        } {
-            Some(binary)
+        };
+        self.record(|| replay::Call::SynthesizeIndirectIntersectionFunction {
+            output: replay::Output::Synthesized(synthesized.then(|| binary.byte_code())),
+        });
+        if synthesized {
+            binary.debug_validate(&error::Context::default()).map(Some)
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -642,6 +1123,9 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetEntryPointName(self.me.as_ptr(), new_name.as_ptr())
         }
+        self.apply(replay::Call::SetEntryPointName(
+            new_name.to_string_lossy().into_owned(),
+        ));
     }
 
     /// See <https://developer.apple.com/documentation/metal/mtlgpufamily> for a list of GPU
@@ -654,6 +1138,7 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerSetMinimumGPUFamily(self.me.as_ptr(), family)
         }
+        self.apply(replay::Call::SetMinimumGpuFamily(family));
     }
 
     #[doc(alias = "IRCompilerIgnoreRootSignature")]
@@ -662,14 +1147,25 @@ impl IRCompiler {
             self.funcs
                 .IRCompilerIgnoreRootSignature(self.me.as_ptr(), ignore_embedded_root_signature)
         }
+        self.apply(replay::Call::IgnoreRootSignature(
+            ignore_embedded_root_signature,
+        ));
     }
 
     #[doc(alias = "IRCompilerIgnoreDebugInformation")]
-    pub fn ignore_debug_information(&mut self, ignore_debug_information: bool) {
+    pub fn ignore_debug_information(
+        &mut self,
+        ignore_debug_information: bool,
+    ) -> Result<(), Error> {
+        require(&*self.funcs, "IRCompilerIgnoreDebugInformation")?;
         unsafe {
             self.funcs
                 .IRCompilerIgnoreDebugInformation(self.me.as_ptr(), ignore_debug_information)
         }
+        self.apply(replay::Call::IgnoreDebugInformation(
+            ignore_debug_information,
+        ));
+        Ok(())
     }
 
     #[doc(alias = "IRCompilerSetMinimumDeploymentTarget")]
@@ -685,6 +1181,10 @@ impl IRCompiler {
                 version.as_ptr(),
             )
         }
+        self.apply(replay::Call::SetMinimumDeploymentTarget(
+            operating_system,
+            version.to_string_lossy().into_owned(),
+        ));
     }
 
     #[doc(alias = "IRCompilerAllocCompileAndLink")]
@@ -692,7 +1192,7 @@ impl IRCompiler {
         &self,
         entry_point: &CStr,
         input: &IRObject,
-    ) -> Result<IRObject, CompilerError> {
+    ) -> Result<IRObject, Error> {
         let mut error = std::ptr::null_mut();
 
         let object = NonNull::new(unsafe {
@@ -704,17 +1204,30 @@ impl IRCompiler {
             )
         });
 
-        if let Some(error) = NonNull::new(error) {
+        let context = error::Context {
+            entry_point: Some(entry_point.to_string_lossy().into_owned()),
+            stage: None,
+            input: input.context.input.clone(),
+        };
+        let result = if let Some(error) = NonNull::new(error) {
             let error = unsafe { IRError::from_ptr(error, self.funcs.clone()) };
-            return Err(CompilerError(error));
-        }
-
-        let object =
-            object.expect("IRCompilerAllocCompileAndLink should not return NULL without error");
-        Ok(IRObject {
-            me: object,
-            funcs: self.funcs.clone(),
-        })
+            Err(Error::Compilation { context, error })
+        } else {
+            let object =
+                object.expect("IRCompilerAllocCompileAndLink should not return NULL without error");
+            let mut object = IRObject::compiled(object, self.funcs.clone(), context);
+            object.origin = Some(self.origin(
+                vec![Rc::clone(&input.dxil)],
+                entry_point.to_string_lossy().into_owned(),
+            ));
+            Ok(object)
+        };
+        self.record(|| replay::Call::CompileAndLink {
+            entry_point: entry_point.to_string_lossy().into_owned(),
+            dxil: Arc::clone(&input.dxil.bytes),
+            output: replay::Output::compiled(&result),
+        });
+        result
     }
 
     #[doc(alias = "IRCompilerAllocCombineCompileAndLink")]
@@ -724,7 +1237,7 @@ impl IRCompiler {
         intersection_function_bytecode: &IRObject,
         any_hit_function_entry_point: &CStr,
         any_hit_function_bytecode: &IRObject,
-    ) -> Result<IRObject, CompilerError> {
+    ) -> Result<IRObject, Error> {
         let mut error = std::ptr::null_mut();
 
         let object = NonNull::new(unsafe {
@@ -738,23 +1251,55 @@ impl IRCompiler {
             )
         });
 
-        if let Some(error) = NonNull::new(error) {
+        // Errors are reported for the intersection function, the first of the two
+        let context = error::Context {
+            entry_point: Some(
+                intersection_function_entry_point
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            stage: None,
+            input: intersection_function_bytecode.context.input.clone(),
+        };
+        let result = if let Some(error) = NonNull::new(error) {
             let error = unsafe { IRError::from_ptr(error, self.funcs.clone()) };
-            return Err(CompilerError(error));
-        }
-
-        let object = object
-            .expect("IRCompilerAllocCombineCompileAndLink should not return NULL without error");
-        Ok(IRObject {
-            me: object,
-            funcs: self.funcs.clone(),
-        })
+            Err(Error::Compilation { context, error })
+        } else {
+            let object = object.expect(
+                "IRCompilerAllocCombineCompileAndLink should not return NULL without error",
+            );
+            let mut object = IRObject::compiled(object, self.funcs.clone(), context);
+            object.origin = Some(self.origin(
+                vec![
+                    Rc::clone(&intersection_function_bytecode.dxil),
+                    Rc::clone(&any_hit_function_bytecode.dxil),
+                ],
+                format!(
+                    "{}+{}",
+                    intersection_function_entry_point.to_string_lossy(),
+                    any_hit_function_entry_point.to_string_lossy()
+                ),
+            ));
+            Ok(object)
+        };
+        self.record(|| replay::Call::CombineCompileAndLink {
+            intersection_function_entry_point: intersection_function_entry_point
+                .to_string_lossy()
+                .into_owned(),
+            intersection_function_dxil: Arc::clone(&intersection_function_bytecode.dxil.bytes),
+            any_hit_function_entry_point: any_hit_function_entry_point
+                .to_string_lossy()
+                .into_owned(),
+            any_hit_function_dxil: Arc::clone(&any_hit_function_bytecode.dxil.bytes),
+            output: replay::Output::compiled(&result),
+        });
+        result
     }
 }
 
 pub struct IRError {
     me: NonNull<bindings::IRError>,
-    funcs: Arc<bindings::metal_irconverter>,
+    funcs: Arc<dyn ConverterBackend>,
 }
 
 // The underlying read-only error value and raw pointer are likely thread-safe, and don't reference
@@ -777,7 +1322,7 @@ impl fmt::Display for IRError {
     }
 }
 
-impl error::Error for IRError {}
+impl std::error::Error for IRError {}
 
 impl Drop for IRError {
     #[doc(alias = "IRErrorDestroy")]
@@ -787,10 +1332,7 @@ impl Drop for IRError {
 }
 
 impl IRError {
-    unsafe fn from_ptr(
-        me: NonNull<bindings::IRError>,
-        funcs: Arc<bindings::metal_irconverter>,
-    ) -> Self {
+    unsafe fn from_ptr(me: NonNull<bindings::IRError>, funcs: Arc<dyn ConverterBackend>) -> Self {
         Self { me, funcs }
     }
 
@@ -827,8 +1369,13 @@ impl IRError {
             x if x == UnableToLinkModule as u32 => UnableToLinkModule,
             x if x == UnrecognizedDXILHeader as u32 => UnrecognizedDXILHeader,
             x if x == InvalidRaytracingAttribute as u32 => InvalidRaytracingAttribute,
-            x if x == Unknown as u32 => Unknown,
-            _ => panic!("Invalid error code"),
+            x if x == NullHullShaderInputOutputMismatch as u32 => NullHullShaderInputOutputMismatch,
+            x if x == InvalidRaytracingUserAttributeSize as u32 => {
+                InvalidRaytracingUserAttributeSize
+            }
+            x if x == IncorrectHitgroupType as u32 => IncorrectHitgroupType,
+            // Codes added by newer libraries
+            _ => Unknown,
         }
     }
 
@@ -0,0 +1,1019 @@
+//! Reading the `MTLB` container of a `metallib`, to list and verify its functions without Apple's
+//! tools.
+//!
+//! ```no_run
+//! let bytes = std::fs::read("shader.metallib")?;
+//! let library = saxaboom::metallib::MetalLib::parse(&bytes)?;
+//! for function in &library.functions {
+//!     println!("{} {:?}: {} bytes of bitcode", function.name, function.function_type, function.bitcode.len());
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`MetalLibWriter`] merges the functions of several libraries, such as the outputs of separate
+//! conversions, into a single `metallib`.  [`MetalLib::bitcode_modules()`] extracts the AIR
+//! bitcode of every function for offline analysis, such as with `llvm-dis`.  [`validate()`] checks
+//! a `metallib` for corruption and lists every problem it finds, see also
+//! [`crate::IRMetalLibBinary::set_debug_validation()`].
+//!
+//! The format is undocumented; fields are named after what is known from inspecting libraries
+//! produced by Apple's tools.  Every offset and size is bounds checked, so malformed input results
+//! in [`Error::InvalidMetalLib`] rather than a panic.
+use std::fmt;
+
+use crate::{
+    encode::{hex, sha256},
+    Error,
+};
+
+/// Function name, nul-terminated.
+pub const NAME: [u8; 4] = *b"NAME";
+/// [`FunctionType`] as a single byte.
+pub const TYPE: [u8; 4] = *b"TYPE";
+/// SHA-256 of the function's bitcode.
+pub const HASH: [u8; 4] = *b"HASH";
+/// Size of the function's bitcode as a `u64`.
+pub const BITCODE_SIZE: [u8; 4] = *b"MDSZ";
+/// [`FunctionOffsets`] as three `u64`s.
+pub const OFFSETS: [u8; 4] = *b"OFFT";
+/// [`FunctionVersion`] as four `u16`s.
+pub const VERSION: [u8; 4] = *b"VERS";
+/// Terminates a tag list, without a size or data.
+pub const END: [u8; 4] = *b"ENDT";
+
+/// Starts the bitcode wrapper header that precedes the bitcode of a function, as a little-endian
+/// `u32`.
+pub const BITCODE_WRAPPER_MAGIC: u32 = 0x0b17_c0de;
+/// Starts a raw LLVM bitcode module: `BC` followed by `0xc0de`.
+pub const BITCODE_MAGIC: [u8; 4] = [b'B', b'C', 0xc0, 0xde];
+
+const MAGIC: &[u8; 4] = b"MTLB";
+const HEADER_SIZE: u64 = 88;
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidMetalLib {
+        reason: reason.into(),
+    }
+}
+
+/// Prefixes the reason of [`Error::InvalidMetalLib`] with the index of the function.
+fn in_function(i: usize) -> impl Fn(Error) -> Error {
+    move |e| match e {
+        Error::InvalidMetalLib { reason } => invalid(format!("function {i}: {reason}")),
+        e => e,
+    }
+}
+
+/// `size` bytes at `offset` into `data`.
+fn range<'a>(data: &'a [u8], offset: u64, size: u64, what: &str) -> Result<&'a [u8], Error> {
+    offset
+        .checked_add(size)
+        .and_then(|end| data.get(usize::try_from(offset).ok()?..usize::try_from(end).ok()?))
+        .ok_or_else(|| {
+            invalid(format!(
+                "{what} ({size} bytes at offset {offset}) out of bounds"
+            ))
+        })
+}
+
+/// Metadata at `offset` into `section`, up to the next of `offsets` or the end of `section`.
+fn metadata<'a>(
+    section: &'a [u8],
+    offsets: &[u64],
+    offset: u64,
+    what: &str,
+) -> Result<&'a [u8], Error> {
+    let end = offsets
+        .iter()
+        .copied()
+        .filter(|&o| o > offset)
+        .min()
+        .unwrap_or(section.len() as u64);
+    range(section, offset, end.saturating_sub(offset), what)
+}
+
+/// Reads little-endian values from the front of a slice.
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.offset..end))
+            .ok_or_else(|| invalid(format!("unexpected end of data at offset {}", self.offset)))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        self.array().map(u64::from_le_bytes)
+    }
+}
+
+/// Kind of library, as stored in [`Header::library_type`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LibraryType {
+    Executable,
+    CoreImage,
+    Dynamic,
+    SymbolCompanion,
+    Unknown(u8),
+}
+
+impl LibraryType {
+    pub fn from_raw(ty: u8) -> Self {
+        match ty {
+            0 => Self::Executable,
+            1 => Self::CoreImage,
+            2 => Self::Dynamic,
+            3 => Self::SymbolCompanion,
+            ty => Self::Unknown(ty),
+        }
+    }
+
+    pub fn to_raw(self) -> u8 {
+        match self {
+            Self::Executable => 0,
+            Self::CoreImage => 1,
+            Self::Dynamic => 2,
+            Self::SymbolCompanion => 3,
+            Self::Unknown(ty) => ty,
+        }
+    }
+}
+
+/// Kind of function, from the [`TYPE`] tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FunctionType {
+    Vertex,
+    Fragment,
+    Kernel,
+    Unqualified,
+    Visible,
+    Extern,
+    Intersection,
+    Mesh,
+    Object,
+    Unknown(u8),
+}
+
+impl FunctionType {
+    pub fn from_raw(ty: u8) -> Self {
+        match ty {
+            0 => Self::Vertex,
+            1 => Self::Fragment,
+            2 => Self::Kernel,
+            3 => Self::Unqualified,
+            4 => Self::Visible,
+            5 => Self::Extern,
+            6 => Self::Intersection,
+            7 => Self::Mesh,
+            8 => Self::Object,
+            ty => Self::Unknown(ty),
+        }
+    }
+
+    pub fn to_raw(self) -> u8 {
+        match self {
+            Self::Vertex => 0,
+            Self::Fragment => 1,
+            Self::Kernel => 2,
+            Self::Unqualified => 3,
+            Self::Visible => 4,
+            Self::Extern => 5,
+            Self::Intersection => 6,
+            Self::Mesh => 7,
+            Self::Object => 8,
+            Self::Unknown(ty) => ty,
+        }
+    }
+}
+
+/// Location of a section, relative to the start of the file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Section {
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// The fixed-size header at the start of every `metallib`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// Target platform, as stored.
+    pub platform: u16,
+    pub file_version: (u16, u16),
+    pub library_type: LibraryType,
+    /// Target operating system, as stored.
+    pub operating_system: u8,
+    pub operating_system_version: (u16, u16),
+    pub file_size: u64,
+    /// Number of functions followed by their tag lists, see [`Function`].
+    pub function_list: Section,
+    pub public_metadata: Section,
+    pub private_metadata: Section,
+    /// Bitcode of every function, see [`Function::bitcode`].
+    pub bitcode: Section,
+}
+
+impl Header {
+    fn read(reader: &mut Reader<'_>) -> Result<Self, Error> {
+        reader.bytes(MAGIC.len())?;
+        let platform = reader.u16()?;
+        let file_version = (reader.u16()?, reader.u16()?);
+        let library_type = LibraryType::from_raw(reader.u8()?);
+        let operating_system = reader.u8()?;
+        let operating_system_version = (reader.u16()?, reader.u16()?);
+        let file_size = reader.u64()?;
+        let mut section = || {
+            Ok::<_, Error>(Section {
+                offset: reader.u64()?,
+                size: reader.u64()?,
+            })
+        };
+        Ok(Self {
+            platform,
+            file_version,
+            library_type,
+            operating_system,
+            operating_system_version,
+            file_size,
+            function_list: section()?,
+            public_metadata: section()?,
+            private_metadata: section()?,
+            bitcode: section()?,
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend(MAGIC);
+        out.extend(self.platform.to_le_bytes());
+        out.extend(self.file_version.0.to_le_bytes());
+        out.extend(self.file_version.1.to_le_bytes());
+        out.push(self.library_type.to_raw());
+        out.push(self.operating_system);
+        out.extend(self.operating_system_version.0.to_le_bytes());
+        out.extend(self.operating_system_version.1.to_le_bytes());
+        out.extend(self.file_size.to_le_bytes());
+        for section in [
+            self.function_list,
+            self.public_metadata,
+            self.private_metadata,
+            self.bitcode,
+        ] {
+            out.extend(section.offset.to_le_bytes());
+            out.extend(section.size.to_le_bytes());
+        }
+    }
+}
+
+/// A tag in the tag list of a [`Function`], identified by its four character code such as
+/// [`NAME`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Tag<'a> {
+    pub fourcc: [u8; 4],
+    pub data: &'a [u8],
+}
+
+impl Tag<'_> {
+    /// The four character code as a string, for display.
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.fourcc).into_owned()
+    }
+}
+
+impl fmt::Debug for Tag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tag")
+            .field("fourcc", &self.name())
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+/// Offsets of a function's data from the [`OFFSETS`] tag, relative to the start of the
+/// respective [`Header`] sections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FunctionOffsets {
+    pub public_metadata: u64,
+    pub private_metadata: u64,
+    pub bitcode: u64,
+}
+
+/// Versions from the [`VERSION`] tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FunctionVersion {
+    /// Major and minor version of the AIR bitcode.
+    pub air: (u16, u16),
+    /// Major and minor version of the Metal shading language.
+    pub language: (u16, u16),
+}
+
+/// A function in the function list of a [`MetalLib`].
+#[derive(Clone, Debug)]
+pub struct Function<'a> {
+    pub name: String,
+    pub function_type: Option<FunctionType>,
+    pub hash: Option<[u8; 32]>,
+    pub offsets: FunctionOffsets,
+    pub version: Option<FunctionVersion>,
+    /// Every tag in order, including the ones above and any unknown ones, but not [`END`].
+    pub tags: Vec<Tag<'a>>,
+    /// LLVM bitcode of this function, usually in a bitcode wrapper.
+    pub bitcode: &'a [u8],
+    /// Metadata of this function, up to the metadata of the next function in the section or
+    /// the end of the section.
+    pub public_metadata: &'a [u8],
+    pub private_metadata: &'a [u8],
+}
+
+impl<'a> Function<'a> {
+    /// Data of the first tag with this four character code.
+    pub fn tag(&self, fourcc: [u8; 4]) -> Option<&'a [u8]> {
+        self.tags
+            .iter()
+            .find(|t| t.fourcc == fourcc)
+            .map(|t| t.data)
+    }
+
+    /// Unwraps [`Self::bitcode`] into the LLVM bitcode module it contains.
+    pub fn bitcode_module(&self) -> Result<BitcodeModule<'a>, Error> {
+        let mut reader = Reader::new(self.bitcode);
+        let (wrapper, module) = if reader.u32().ok() == Some(BITCODE_WRAPPER_MAGIC) {
+            let mut field = || {
+                reader.u32().map_err(|_| {
+                    invalid(format!(
+                        "truncated bitcode wrapper header of `{}`",
+                        self.name
+                    ))
+                })
+            };
+            let version = field()?;
+            let offset = field()?;
+            let size = field()?;
+            let cpu_type = field()?;
+            let module = range(
+                self.bitcode,
+                offset.into(),
+                size.into(),
+                "wrapped bitcode module",
+            )?;
+            (Some(BitcodeWrapper { version, cpu_type }), module)
+        } else {
+            (None, self.bitcode)
+        };
+        if !module.starts_with(&BITCODE_MAGIC) {
+            return Err(invalid(format!(
+                "bitcode of `{}` is not an LLVM bitcode module",
+                self.name
+            )));
+        }
+
+        Ok(BitcodeModule {
+            name: self.name.clone(),
+            function_type: self.function_type,
+            wrapper,
+            module,
+        })
+    }
+}
+
+/// Fields of the bitcode wrapper header, see [`BitcodeModule::wrapper`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitcodeWrapper {
+    pub version: u32,
+    pub cpu_type: u32,
+}
+
+/// The AIR bitcode of a single function, returned by [`Function::bitcode_module()`].
+#[derive(Clone, Debug)]
+pub struct BitcodeModule<'a> {
+    pub name: String,
+    pub function_type: Option<FunctionType>,
+    /// Header that [`Self::module`] was unwrapped from, [`None`] when the function's bitcode is
+    /// not wrapped.
+    pub wrapper: Option<BitcodeWrapper>,
+    /// The module itself, starting with [`BITCODE_MAGIC`].  Can be written to a `.bc` file for
+    /// `llvm-dis` or `llvm-bcanalyzer`.
+    pub module: &'a [u8],
+}
+
+/// A parsed `metallib`, borrowing the bytes it was parsed from.
+#[derive(Clone, Debug)]
+pub struct MetalLib<'a> {
+    pub header: Header,
+    pub functions: Vec<Function<'a>>,
+    public_metadata: &'a [u8],
+    private_metadata: &'a [u8],
+    bitcode: &'a [u8],
+}
+
+impl<'a> MetalLib<'a> {
+    /// Parse the header, the function list with the tags of every function, and locate the
+    /// bitcode of every function.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(invalid("missing `MTLB` header"));
+        }
+        let header = Header::read(&mut Reader::new(bytes))?;
+
+        let bytes = range(bytes, 0, header.file_size, "file")?;
+        let section = |section: Section, what| range(bytes, section.offset, section.size, what);
+        let function_list = section(header.function_list, "function list")?;
+        let public_metadata = section(header.public_metadata, "public metadata")?;
+        let private_metadata = section(header.private_metadata, "private metadata")?;
+        let bitcode = section(header.bitcode, "bitcode")?;
+
+        let mut reader = Reader::new(function_list);
+        let count = reader.u32()?;
+        let mut functions = (0..count)
+            .map(|i| {
+                let start = reader.offset;
+                let size = reader.u32()? as usize;
+                if size < 4 {
+                    return Err(invalid(format!(
+                        "function {i} has an invalid size of {size}"
+                    )));
+                }
+                // The size includes the size itself
+                reader.offset = start;
+                let mut entry = Reader::new(reader.bytes(size)?);
+                entry.bytes(4)?;
+                Self::parse_function(&mut entry, bitcode).map_err(in_function(i as usize))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let public_offsets = functions
+            .iter()
+            .map(|f| f.offsets.public_metadata)
+            .collect::<Vec<_>>();
+        let private_offsets = functions
+            .iter()
+            .map(|f| f.offsets.private_metadata)
+            .collect::<Vec<_>>();
+        for (i, function) in functions.iter_mut().enumerate() {
+            let offsets = function.offsets;
+            function.public_metadata = metadata(
+                public_metadata,
+                &public_offsets,
+                offsets.public_metadata,
+                "public metadata",
+            )
+            .map_err(in_function(i))?;
+            function.private_metadata = metadata(
+                private_metadata,
+                &private_offsets,
+                offsets.private_metadata,
+                "private metadata",
+            )
+            .map_err(in_function(i))?;
+        }
+
+        Ok(Self {
+            header,
+            functions,
+            public_metadata,
+            private_metadata,
+            bitcode,
+        })
+    }
+
+    fn parse_function(reader: &mut Reader<'a>, bitcode: &'a [u8]) -> Result<Function<'a>, Error> {
+        let mut tags = Vec::new();
+        loop {
+            let fourcc = reader.array()?;
+            if fourcc == END {
+                break;
+            }
+            let size = reader.u16()?;
+            tags.push(Tag {
+                fourcc,
+                data: reader.bytes(size.into())?,
+            });
+        }
+        let tag = |fourcc| tags.iter().find(|t: &&Tag<'_>| t.fourcc == fourcc);
+        let fixed = |fourcc: [u8; 4], size: usize| {
+            tag(fourcc)
+                .map(|t| {
+                    if t.data.len() == size {
+                        Ok(Reader::new(t.data))
+                    } else {
+                        Err(invalid(format!(
+                            "`{}` tag of {} bytes instead of {size}",
+                            t.name(),
+                            t.data.len()
+                        )))
+                    }
+                })
+                .transpose()
+        };
+
+        let name = tag(NAME)
+            .ok_or_else(|| invalid("missing `NAME` tag"))?
+            .data
+            .split(|&b| b == 0)
+            .next()
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .unwrap_or_default();
+        let function_type = fixed(TYPE, 1)?
+            .map(|mut r| r.u8().map(FunctionType::from_raw))
+            .transpose()?;
+        let hash = fixed(HASH, 32)?.map(|mut r| r.array()).transpose()?;
+        let bitcode_size = fixed(BITCODE_SIZE, 8)?
+            .ok_or_else(|| invalid("missing `MDSZ` tag"))?
+            .u64()?;
+        let mut offsets_reader =
+            fixed(OFFSETS, 24)?.ok_or_else(|| invalid("missing `OFFT` tag"))?;
+        let offsets = FunctionOffsets {
+            public_metadata: offsets_reader.u64()?,
+            private_metadata: offsets_reader.u64()?,
+            bitcode: offsets_reader.u64()?,
+        };
+        let version = fixed(VERSION, 8)?
+            .map(|mut r| {
+                Ok::<_, Error>(FunctionVersion {
+                    air: (r.u16()?, r.u16()?),
+                    language: (r.u16()?, r.u16()?),
+                })
+            })
+            .transpose()?;
+
+        Ok(Function {
+            bitcode: range(bitcode, offsets.bitcode, bitcode_size, "bitcode")?,
+            name,
+            function_type,
+            hash,
+            offsets,
+            version,
+            tags,
+            public_metadata: &[],
+            private_metadata: &[],
+        })
+    }
+
+    /// The function named `name`.
+    pub fn function(&self, name: &str) -> Option<&Function<'a>> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    /// [`Function::bitcode_module()`] of every function, in order.
+    pub fn bitcode_modules(&self) -> Result<Vec<BitcodeModule<'a>>, Error> {
+        self.functions
+            .iter()
+            .enumerate()
+            .map(|(i, f)| f.bitcode_module().map_err(in_function(i)))
+            .collect()
+    }
+
+    pub fn public_metadata(&self) -> &'a [u8] {
+        self.public_metadata
+    }
+
+    pub fn private_metadata(&self) -> &'a [u8] {
+        self.private_metadata
+    }
+
+    /// The bitcode section holding the bitcode of every function.
+    pub fn bitcode(&self) -> &'a [u8] {
+        self.bitcode
+    }
+}
+
+/// `name` when it is not `taken`, otherwise the first of `name_1`, `name_2`, ... that is not.
+///
+/// [`MetalLibWriter`] rejects duplicate function names, because the bitcode of a function refers
+/// to the name it was converted with.  Shaders that share an entry point name are renamed before
+/// converting instead, so that their bitcode agrees with the merged function list:
+///
+/// ```no_run
+/// # use saxaboom::{metallib::{self, MetalLib, MetalLibWriter}, MetalIrConverter};
+/// # let converter = MetalIrConverter::discover()?;
+/// # let shaders: [&[u8]; 0] = [];
+/// let mut names = Vec::new();
+/// let mut metallibs = Vec::new();
+/// for dxil in shaders {
+///     let name = metallib::unique_name("main", |n| names.iter().any(|taken| taken == n));
+///     let mut compiler = converter.create_compiler();
+///     compiler.set_entry_point_name(&std::ffi::CString::new(name.as_str())?);
+///     let dxil = converter.create_object_from_dxil(dxil);
+///     let object = compiler.alloc_compile_and_link(c"main", &dxil)?;
+///     metallibs.push(object.metal_lib_binary()?.byte_code());
+///     names.push(name);
+/// }
+/// let metallibs = metallibs.iter().map(Vec::as_slice).collect::<Vec<_>>();
+/// std::fs::write("merged.metallib", metallib::merge(&metallibs)?)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn unique_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(name) {
+        return name.to_owned();
+    }
+    (1..)
+        .map(|n| format!("{name}_{n}"))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_default()
+}
+
+/// Writes a `metallib` with the functions of one or more parsed libraries, in the order they were
+/// added.
+///
+/// ```no_run
+/// use saxaboom::metallib::{MetalLib, MetalLibWriter};
+///
+/// let vertex = std::fs::read("vertex.metallib")?;
+/// let fragment = std::fs::read("fragment.metallib")?;
+/// let mut writer = MetalLibWriter::new();
+/// writer.add(&MetalLib::parse(&vertex)?)?;
+/// writer.add(&MetalLib::parse(&fragment)?)?;
+/// std::fs::write("merged.metallib", writer.write())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MetalLibWriter<'a> {
+    header: Option<Header>,
+    functions: Vec<Function<'a>>,
+}
+
+impl<'a> MetalLibWriter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every function of `library`.  The header of the first library is used for the merged
+    /// library; later libraries must target the same platform and operating system.  Fails with
+    /// [`Error::DuplicateFunction`] for names that were already added, see [`unique_name()`], and
+    /// for names that are too long for a [`NAME`] tag.
+    ///
+    /// Adds nothing when this fails.
+    pub fn add(&mut self, library: &MetalLib<'a>) -> Result<&mut Self, Error> {
+        if let Some(header) = &self.header {
+            let target = |h: &Header| (h.platform, h.operating_system);
+            if target(header) != target(&library.header) {
+                return Err(invalid(format!(
+                    "cannot merge a library for platform {:#x} and operating system {:#x} into one for platform {:#x} and operating system {:#x}",
+                    library.header.platform,
+                    library.header.operating_system,
+                    header.platform,
+                    header.operating_system
+                )));
+            }
+        }
+
+        for (i, function) in library.functions.iter().enumerate() {
+            let name = &function.name;
+            let added = self.functions.iter().chain(&library.functions[..i]);
+            if added.map(|f| &f.name).any(|n| n == name) {
+                return Err(Error::DuplicateFunction { name: name.clone() });
+            }
+            // Rewritten into a `NAME` tag, whose size is a `u16` including the NUL terminator
+            if name.len() >= usize::from(u16::MAX) {
+                return Err(invalid(format!(
+                    "function name of {} bytes does not fit in a NAME tag",
+                    name.len()
+                )));
+            }
+        }
+
+        self.header.get_or_insert(library.header);
+        self.functions.extend(library.functions.iter().cloned());
+        Ok(self)
+    }
+
+    /// Names of the functions added so far.
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions.iter().map(|f| f.name.as_str())
+    }
+
+    /// Lays out the header, the function list with rewritten [`NAME`] and [`OFFSETS`] tags, and
+    /// the metadata and bitcode of every function.  Other tags are copied as-is.
+    pub fn write(&self) -> Vec<u8> {
+        let mut function_list = (self.functions.len() as u32).to_le_bytes().to_vec();
+        let mut public_metadata = Vec::new();
+        let mut private_metadata = Vec::new();
+        let mut bitcode = Vec::new();
+
+        for function in &self.functions {
+            let offsets = [
+                public_metadata.len() as u64,
+                private_metadata.len() as u64,
+                bitcode.len() as u64,
+            ];
+            public_metadata.extend(function.public_metadata);
+            private_metadata.extend(function.private_metadata);
+            bitcode.extend(function.bitcode);
+
+            let start = function_list.len();
+            // Size, filled in below
+            function_list.extend([0; 4]);
+            // Only the first of duplicate tags is read by the parser, and rewritten
+            let first = |fourcc| function.tags.iter().position(|t| t.fourcc == fourcc);
+            let (name_tag, offsets_tag) = (first(NAME), first(OFFSETS));
+            for (i, tag) in function.tags.iter().enumerate() {
+                let data = if Some(i) == name_tag {
+                    [function.name.as_bytes(), &[0]].concat()
+                } else if Some(i) == offsets_tag {
+                    offsets.iter().flat_map(|o| o.to_le_bytes()).collect()
+                } else {
+                    tag.data.to_vec()
+                };
+                function_list.extend(tag.fourcc);
+                function_list.extend((data.len() as u16).to_le_bytes());
+                function_list.extend(data);
+            }
+            function_list.extend(END);
+            let size = (function_list.len() - start) as u32;
+            function_list[start..start + 4].copy_from_slice(&size.to_le_bytes());
+        }
+
+        let mut header = self.header.unwrap_or(Header {
+            platform: 0,
+            file_version: (1, 0),
+            library_type: LibraryType::Executable,
+            operating_system: 0,
+            operating_system_version: (0, 0),
+            file_size: 0,
+            function_list: Section::default(),
+            public_metadata: Section::default(),
+            private_metadata: Section::default(),
+            bitcode: Section::default(),
+        });
+        let mut offset = HEADER_SIZE;
+        let mut section = |data: &[u8]| {
+            let section = Section {
+                offset,
+                size: data.len() as u64,
+            };
+            offset += section.size;
+            section
+        };
+        header.function_list = section(&function_list);
+        header.public_metadata = section(&public_metadata);
+        header.private_metadata = section(&private_metadata);
+        header.bitcode = section(&bitcode);
+        header.file_size = offset;
+
+        let mut out = Vec::with_capacity(offset as usize);
+        header.write(&mut out);
+        for data in [function_list, public_metadata, private_metadata, bitcode] {
+            out.extend(data);
+        }
+        out
+    }
+}
+
+/// Merges the `metallib`s in `libraries` into one, see [`MetalLibWriter`].
+pub fn merge(libraries: &[&[u8]]) -> Result<Vec<u8>, Error> {
+    let mut writer = MetalLibWriter::new();
+    for bytes in libraries {
+        writer.add(&MetalLib::parse(bytes)?)?;
+    }
+    Ok(writer.write())
+}
+
+/// SHA-256 of `bitcode`, as stored in the [`HASH`] tag of a function.
+pub fn bitcode_hash(bitcode: &[u8]) -> [u8; 32] {
+    sha256(bitcode)
+}
+
+/// A problem found by [`validate()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Offset from the start of the file of the field or data that is wrong.
+    pub offset: u64,
+    /// Index of the function in the function list, for problems with a single function.
+    pub function: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    /// Formats as `function 1 at offset 120: missing `NAME` tag`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(function) = self.function {
+            write!(f, "function {function} ")?;
+        }
+        write!(f, "at offset {}: {}", self.offset, self.message)
+    }
+}
+
+/// Checks the header magic, the declared file size, the location of every section, every entry
+/// in the function list and the [`HASH`] tag of every function against its bitcode.
+///
+/// Unlike [`MetalLib::parse()`], which stops at the first problem, this carries on where it can
+/// and returns every problem found.  An empty list means that the `metallib` is well-formed as
+/// far as this crate knows; Metal may still reject its bitcode.
+pub fn validate(bytes: &[u8]) -> Vec<Diagnostic> {
+    let diagnostic = |offset: u64, function: Option<usize>, message: String| Diagnostic {
+        offset,
+        function,
+        message,
+    };
+    let len = bytes.len() as u64;
+    if !bytes.starts_with(MAGIC) {
+        return vec![diagnostic(0, None, "missing `MTLB` magic".to_owned())];
+    }
+    let header = match Header::read(&mut Reader::new(bytes)) {
+        Ok(header) => header,
+        Err(_) => {
+            return vec![diagnostic(
+                len,
+                None,
+                format!("header truncated to {len} of {HEADER_SIZE} bytes"),
+            )]
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    // Offset of the `file_size` field, followed by the sections
+    const FILE_SIZE_OFFSET: u64 = 16;
+    if header.file_size != len {
+        diagnostics.push(diagnostic(
+            FILE_SIZE_OFFSET,
+            None,
+            format!(
+                "declared file size of {} bytes, but the file is {len} bytes",
+                header.file_size
+            ),
+        ));
+    }
+
+    let sections = [
+        ("function list", header.function_list),
+        ("public metadata", header.public_metadata),
+        ("private metadata", header.private_metadata),
+        ("bitcode", header.bitcode),
+    ];
+    // Data of every section that starts after the header, cut off at the end of the file so
+    // that functions that lie within the file can still be checked
+    let mut data = [None; 4];
+    let file_end = len.min(header.file_size);
+    for (i, (name, section)) in sections.iter().enumerate() {
+        let field = FILE_SIZE_OFFSET + 8 + 16 * i as u64;
+        if section.offset < HEADER_SIZE {
+            diagnostics.push(diagnostic(
+                field,
+                None,
+                format!(
+                    "{name} section at offset {} overlaps the header",
+                    section.offset
+                ),
+            ));
+            continue;
+        }
+        let end = section.offset.saturating_add(section.size);
+        if end > file_end {
+            diagnostics.push(diagnostic(
+                field,
+                None,
+                format!(
+                    "{name} section ({} bytes at offset {}) extends past the end of the file",
+                    section.size, section.offset
+                ),
+            ));
+        }
+        data[i] = range(
+            bytes,
+            section.offset,
+            end.min(file_end).saturating_sub(section.offset),
+            name,
+        )
+        .ok();
+    }
+    for (i, (name, section)) in sections.iter().enumerate() {
+        for (other, other_section) in &sections[i + 1..] {
+            let overlaps = section.size > 0
+                && other_section.size > 0
+                && section.offset < other_section.offset.saturating_add(other_section.size)
+                && other_section.offset < section.offset.saturating_add(section.size);
+            if overlaps {
+                diagnostics.push(diagnostic(
+                    section.offset,
+                    None,
+                    format!("{name} section overlaps the {other} section"),
+                ));
+            }
+        }
+    }
+
+    let [Some(function_list), public_metadata, private_metadata, bitcode] = data else {
+        return diagnostics;
+    };
+    let mut reader = Reader::new(function_list);
+    let Ok(count) = reader.u32() else {
+        diagnostics.push(diagnostic(
+            header.function_list.offset,
+            None,
+            "function list is too small to hold the number of functions".to_owned(),
+        ));
+        return diagnostics;
+    };
+    // Name of every function parsed so far, with its index
+    let mut names = Vec::<(usize, String)>::new();
+    for i in 0..count as usize {
+        let start = reader.offset;
+        let offset = header.function_list.offset + start as u64;
+        let entry = reader
+            .u32()
+            .ok()
+            .filter(|&size| size >= 4)
+            .and_then(|size| {
+                reader.offset = start;
+                reader.bytes(size as usize).ok()
+            });
+        let Some(entry) = entry else {
+            diagnostics.push(diagnostic(
+                offset,
+                Some(i),
+                format!(
+                    "entry does not fit in the function list, which declares {count} functions"
+                ),
+            ));
+            break;
+        };
+        let mut entry = Reader::new(&entry[4..]);
+        let function = match MetalLib::parse_function(&mut entry, bitcode.unwrap_or_default()) {
+            Ok(function) => function,
+            Err(e) => {
+                diagnostics.push(diagnostic(offset, Some(i), reason(e)));
+                continue;
+            }
+        };
+
+        let mut report = |message| diagnostics.push(diagnostic(offset, Some(i), message));
+        for (name, section, metadata_offset) in [
+            (
+                "public metadata",
+                public_metadata,
+                function.offsets.public_metadata,
+            ),
+            (
+                "private metadata",
+                private_metadata,
+                function.offsets.private_metadata,
+            ),
+        ] {
+            if section.is_some_and(|section| metadata_offset > section.len() as u64) {
+                report(format!(
+                    "{name} offset {metadata_offset} is past the end of the {name} section"
+                ));
+            }
+        }
+        if let Some((j, _)) = names.iter().find(|(_, name)| *name == function.name) {
+            report(format!(
+                "function name `{}` is also used by function {j}",
+                function.name
+            ));
+        }
+        if let Some(hash) = function.hash {
+            let actual = bitcode_hash(function.bitcode);
+            if hash != actual {
+                report(format!(
+                    "`HASH` tag {} does not match the SHA-256 of the bitcode, {}",
+                    hex(&hash),
+                    hex(&actual)
+                ));
+            }
+        }
+        if let Err(e) = function.bitcode_module() {
+            diagnostics.push(diagnostic(
+                header.bitcode.offset + function.offsets.bitcode,
+                Some(i),
+                reason(e),
+            ));
+        }
+        names.push((i, function.name));
+    }
+    diagnostics
+}
+
+/// The reason of [`Error::InvalidMetalLib`], without the `Invalid metallib: ` prefix.
+fn reason(error: Error) -> String {
+    match error {
+        Error::InvalidMetalLib { reason } => reason,
+        e => e.to_string(),
+    }
+}
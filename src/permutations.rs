@@ -0,0 +1,291 @@
+//! Compiling every combination of a shader's feature switches, see
+//! [`IRCompiler::alloc_compile_and_link_permutations()`].
+//!
+//! ```no_run
+//! use saxaboom::{
+//!     permutations::{Permutations, Switch},
+//!     MetalIrConverter,
+//! };
+//!
+//! let permutations = Permutations::new(vec![
+//!     Switch::bool("ALPHA_TEST"),
+//!     Switch::enumeration("LIGHTING", ["unlit", "lambert", "pbr"]),
+//! ])
+//! // Unlit materials are never alpha tested
+//! .valid(|key| !(key.get("LIGHTING") == Some("unlit") && key.get("ALPHA_TEST") == Some("1")));
+//!
+//! let converter = MetalIrConverter::discover()?;
+//! // Every permutation is converted with the same compiler settings
+//! let compiler = converter.create_compiler();
+//! let table = compiler.alloc_compile_and_link_permutations(c"main", &permutations, |key| {
+//!     // Such as `material.ALPHA_TEST=0.LIGHTING=pbr.dxil`
+//!     format!("material.{}.dxil", key.to_string().replace(' ', ".")).into()
+//! })?;
+//!
+//! let key = permutations.key(&[("LIGHTING", "pbr")]).unwrap();
+//! let metallib = &table.get(&key).unwrap().metallib;
+//! # Ok::<(), saxaboom::Error>(())
+//! ```
+use std::{collections::BTreeMap, ffi::CStr, fmt, fs, path::PathBuf};
+
+#[cfg(feature = "dxc")]
+use crate::dxc::{Dxc, Hlsl};
+use crate::{
+    dedup::OutputIndex, supports_json_reflection, Error, IRCompiler, IRObject, IRShaderReflection,
+};
+
+/// Feature switch with two or more values, of which every permutation selects one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Switch {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl Switch {
+    /// Switch that is either `0` or `1`, to be tested with `#if`.
+    pub fn bool(name: impl Into<String>) -> Self {
+        Self::enumeration(name, ["0", "1"])
+    }
+
+    pub fn enumeration(
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// The value of every switch, in the order of [`Permutations::new()`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PermutationKey {
+    values: Vec<(String, String)>,
+}
+
+impl PermutationKey {
+    /// Value of the switch `name`, or [`None`] when there is no such switch.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Switch names and their values, to be passed as preprocessor defines.
+    pub fn defines(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+impl fmt::Display for PermutationKey {
+    /// Formats as `ALPHA_TEST=0 LIGHTING=pbr`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+type Valid = dyn Fn(&PermutationKey) -> bool;
+
+/// Set of feature switches, of which every valid combination of values is compiled.
+pub struct Permutations {
+    switches: Vec<Switch>,
+    valid: Option<Box<Valid>>,
+}
+
+impl Permutations {
+    pub fn new(switches: Vec<Switch>) -> Self {
+        Self {
+            switches,
+            valid: None,
+        }
+    }
+
+    /// Skips the combinations for which `valid` returns `false`.
+    pub fn valid(mut self, valid: impl Fn(&PermutationKey) -> bool + 'static) -> Self {
+        self.valid = Some(Box::new(valid));
+        self
+    }
+
+    pub fn switches(&self) -> &[Switch] {
+        &self.switches
+    }
+
+    /// Every valid combination, where the last switch changes fastest.
+    pub fn keys(&self) -> Vec<PermutationKey> {
+        let mut keys = vec![PermutationKey { values: Vec::new() }];
+        for switch in &self.switches {
+            keys = keys
+                .into_iter()
+                .flat_map(|key| {
+                    switch.values.iter().map(move |value| {
+                        let mut key = key.clone();
+                        key.values.push((switch.name.clone(), value.clone()));
+                        key
+                    })
+                })
+                .collect();
+        }
+        keys.retain(|key| self.is_valid(key));
+        keys
+    }
+
+    /// Key with the given switch values, and the first value of every other switch.  [`None`]
+    /// when a switch or value does not exist, or the combination is not valid.
+    pub fn key(&self, values: &[(&str, &str)]) -> Option<PermutationKey> {
+        if values
+            .iter()
+            .any(|(name, _)| !self.switches.iter().any(|s| s.name == *name))
+        {
+            return None;
+        }
+        let values = self
+            .switches
+            .iter()
+            .map(|switch| {
+                let value = match values.iter().find(|(name, _)| *name == switch.name) {
+                    Some((_, value)) => switch.values.iter().find(|v| v == value)?,
+                    None => switch.values.first()?,
+                };
+                Some((switch.name.clone(), value.clone()))
+            })
+            .collect::<Option<_>>()?;
+        let key = PermutationKey { values };
+        self.is_valid(&key).then_some(key)
+    }
+
+    fn is_valid(&self, key: &PermutationKey) -> bool {
+        self.valid.as_ref().map_or(true, |valid| valid(key))
+    }
+}
+
+/// `metallib` and reflection shared by one or more permutations, see [`PermutationTable`].
+pub struct PermutationOutput {
+    pub metallib: Vec<u8>,
+    pub reflection: IRShaderReflection,
+    /// Permutations that produced exactly this output, in the order of [`Permutations::keys()`].
+    pub keys: Vec<PermutationKey>,
+}
+
+/// Returned by [`IRCompiler::alloc_compile_and_link_permutations()`].
+pub struct PermutationTable {
+    /// Distinct outputs, in order of the first permutation that produced them.
+    pub outputs: Vec<PermutationOutput>,
+    /// Index into [`Self::outputs`] for every permutation.
+    by_key: BTreeMap<PermutationKey, usize>,
+}
+
+impl PermutationTable {
+    /// Index into [`Self::outputs`] of the output of `key`, [`None`] for keys that were not
+    /// compiled.
+    pub fn output_index(&self, key: &PermutationKey) -> Option<usize> {
+        self.by_key.get(key).copied()
+    }
+
+    pub fn get(&self, key: &PermutationKey) -> Option<&PermutationOutput> {
+        self.output_index(key).map(|index| &self.outputs[index])
+    }
+
+    /// Every compiled permutation with the index of its output, ordered by key.
+    pub fn entries(&self) -> impl Iterator<Item = (&PermutationKey, usize)> {
+        self.by_key.iter().map(|(key, index)| (key, *index))
+    }
+}
+
+impl IRCompiler {
+    /// Converts `entry_point` from the DXIL file at `dxil_path(key)` for every key in
+    /// [`Permutations::keys()`], with the current settings of this compiler.  Permutations that
+    /// produce the same `metallib` (and reflection JSON, when the loaded library supports it)
+    /// share a single [`PermutationOutput`].
+    ///
+    /// Fails at the first permutation that fails, with [`Error::Permutation`] naming it.
+    pub fn alloc_compile_and_link_permutations(
+        &self,
+        entry_point: &CStr,
+        permutations: &Permutations,
+        dxil_path: impl Fn(&PermutationKey) -> PathBuf,
+    ) -> Result<PermutationTable, Error> {
+        self.compile_permutations(permutations, |key| {
+            let path = dxil_path(key);
+            let dxil = fs::read(&path).map_err(|source| Error::Read {
+                path: path.clone(),
+                source,
+            })?;
+            let mut input = IRObject::from_dxil(self.funcs.clone(), &dxil);
+            input.set_name(path.display().to_string());
+            self.alloc_compile_and_link(entry_point, &input)
+        })
+    }
+
+    /// Compiles `hlsl` with the [`PermutationKey::defines()`] of every key in
+    /// [`Permutations::keys()`] added, and converts the result like
+    /// [`IRCompiler::alloc_compile_and_link_permutations()`].
+    #[cfg(feature = "dxc")]
+    pub fn alloc_compile_hlsl_and_link_permutations(
+        &self,
+        dxc: &Dxc,
+        hlsl: &Hlsl,
+        permutations: &Permutations,
+    ) -> Result<PermutationTable, Error> {
+        self.compile_permutations(permutations, |key| {
+            let hlsl = key
+                .defines()
+                .fold(hlsl.clone(), |hlsl, (name, value)| hlsl.define(name, value));
+            self.alloc_compile_hlsl_and_link(dxc, &hlsl)
+        })
+    }
+
+    fn compile_permutations(
+        &self,
+        permutations: &Permutations,
+        mut compile: impl FnMut(&PermutationKey) -> Result<IRObject, Error>,
+    ) -> Result<PermutationTable, Error> {
+        let json_reflection = supports_json_reflection(&*self.funcs);
+        let mut index = OutputIndex::default();
+        let mut outputs = Vec::<PermutationOutput>::new();
+        let mut by_key = BTreeMap::new();
+
+        for key in permutations.keys() {
+            let with_key = |error| Error::Permutation {
+                key: key.to_string(),
+                error: Box::new(error),
+            };
+            let object = compile(&key).map_err(with_key)?;
+            let metallib = object.metal_lib_binary().map_err(with_key)?.byte_code();
+            let reflection = object.reflection().map_err(with_key)?;
+            let reflection_key = if json_reflection {
+                Some(reflection.json().map_err(with_key)?)
+            } else {
+                None
+            };
+
+            let existing = index.existing(&metallib, reflection_key.as_deref(), outputs.len());
+            let output = match existing {
+                Some(output) => {
+                    outputs[output].keys.push(key.clone());
+                    output
+                }
+                None => {
+                    outputs.push(PermutationOutput {
+                        metallib,
+                        reflection,
+                        keys: vec![key.clone()],
+                    });
+                    outputs.len() - 1
+                }
+            };
+            by_key.insert(key, output);
+        }
+
+        Ok(PermutationTable { outputs, by_key })
+    }
+}
@@ -0,0 +1,331 @@
+//! Where a converted shader came from: every object compiled by an [`IRCompiler`] carries a
+//! [`Provenance`] record with the hash of its DXIL input, its entry point, every setting applied to
+//! the compiler, the hash of its root signature and the converter library and `saxaboom` versions
+//! that produced it.  See [`IRObject::provenance()`].
+//!
+//! The record is stored in versioned sidecar files next to the `metallib`, like the
+//! [reflection sidecars](crate::reflection), and read by `saxaboom_runtime::provenance` without
+//! loading the converter library:
+//!
+//! ```no_run
+//! # fn example(object: &saxaboom::IRObject) -> Result<(), saxaboom::Error> {
+//! let path = std::path::Path::new("shaders/blur.metallib");
+//! std::fs::write(path, object.metal_lib_binary()?.byte_code()).unwrap();
+//! // Writes `shaders/blur.provenance.bin` and `shaders/blur.provenance.json`
+//! if let Some(provenance) = object.provenance() {
+//!     provenance.write_sidecars(path)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    cell::OnceCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, OnceLock},
+};
+
+#[cfg(doc)]
+use crate::MetalIrConverter;
+use crate::{
+    encode::{hex, json_string, sha256, Encoder},
+    replay::{Call, RootSignatureJson},
+    Error, IRCompiler, IRObject,
+};
+
+/// Starts binary sidecar files, see [`Provenance::to_sidecar()`].
+pub const SIDECAR_MAGIC: [u8; 4] = *b"SXPV";
+/// Version written to sidecar files.  Bumped whenever the encoding of [`Provenance`] changes, so
+/// that older readers reject newer sidecars.
+pub const SIDECAR_VERSION: u32 = 1;
+/// Extension of binary sidecar files, replacing `metallib`.
+pub const BINARY_EXTENSION: &str = "provenance.bin";
+/// Extension of JSON sidecar files, replacing `metallib`.
+pub const JSON_EXTENSION: &str = "provenance.json";
+/// Version of this crate, with the release of the converter headers that it was generated from
+/// as build metadata, such as `0.2.0+irconverter-2.0`.
+pub const SAXABOOM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The file a converter was loaded from, shared by the [`MetalIrConverter`] and everything it
+/// creates so that the file is hashed at most once.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConverterLibrary {
+    pub(crate) path: Option<PathBuf>,
+    hash: Arc<OnceLock<Option<[u8; 32]>>>,
+}
+
+impl ConverterLibrary {
+    pub(crate) fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            hash: Arc::default(),
+        }
+    }
+
+    /// SHA-256 of the library file, [`None`] without a path or when it cannot be read.
+    pub(crate) fn hash(&self) -> Option<[u8; 32]> {
+        *self.hash.get_or_init(|| {
+            let data = fs::read(self.path.as_ref()?).ok()?;
+            Some(sha256(&data))
+        })
+    }
+}
+
+/// How a compiled [`IRObject`] was produced, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    /// SHA-256 of the DXIL input, as passed to [`MetalIrConverter::create_object_from_dxil()`].
+    /// For [`IRCompiler::alloc_combine_compile_and_link()`], of the hashes of the intersection
+    /// and any-hit inputs.
+    pub input_hash: [u8; 32],
+    /// For [`IRCompiler::alloc_combine_compile_and_link()`], the intersection and any-hit entry
+    /// points joined by `+`.
+    pub entry_point: String,
+    /// Name of every setting applied to the compiler, such as `set_minimum_gpu_family`, with its
+    /// value, in the order in which they were first applied.  Applying a setting again replaces
+    /// its value.
+    pub settings: Vec<(String, String)>,
+    /// SHA-256 of the JSON of the global root signature, see [`crate::IRRootSignature::json()`].
+    /// [`None`] when no root signature was set, or when the loaded library cannot serialize it.
+    pub root_signature_hash: Option<[u8; 32]>,
+    /// See [`MetalIrConverter::library_path()`].
+    pub converter_path: Option<String>,
+    /// The converter library does not report its version, this SHA-256 of the library file
+    /// identifies it instead, see [`MetalIrConverter::library_hash()`].
+    pub converter_hash: Option<[u8; 32]>,
+    /// [`SAXABOOM_VERSION`] of the `saxaboom` that converted the shader.
+    pub saxaboom_version: String,
+}
+
+impl Provenance {
+    /// Encodes every field in declaration order: hashes as 32 bytes, strings and lists are
+    /// prefixed with their length as a `u32`, and [`Option`]s with a `0` or `1` byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut e = Encoder::default();
+        e.bytes.extend(self.input_hash);
+        e.str(&self.entry_point).len(self.settings.len());
+        for (name, value) in &self.settings {
+            e.str(name).str(value);
+        }
+        let opt_hash = |e: &mut Encoder, hash: Option<[u8; 32]>| {
+            e.bool(hash.is_some());
+            e.bytes.extend(hash.iter().flatten());
+        };
+        opt_hash(&mut e, self.root_signature_hash);
+        e.opt_str(self.converter_path.as_deref());
+        opt_hash(&mut e, self.converter_hash);
+        e.str(&self.saxaboom_version);
+        e.bytes
+    }
+
+    /// Binary sidecar file: [`SIDECAR_MAGIC`], [`SIDECAR_VERSION`] as a `u32`, and
+    /// [`Self::to_bytes()`].
+    pub fn to_sidecar(&self) -> Vec<u8> {
+        let mut e = Encoder::default();
+        e.bytes.extend(SIDECAR_MAGIC);
+        e.u32(SIDECAR_VERSION);
+        e.bytes.extend(self.to_bytes());
+        e.bytes
+    }
+
+    /// JSON sidecar file, with the fields of this struct and a `version`.  Hashes are stored as
+    /// lowercase hexadecimal strings, and settings as objects with a `name` and a `value`.
+    pub fn to_json(&self) -> String {
+        let opt = |value: Option<String>| value.unwrap_or_else(|| "null".to_owned());
+        let hash = |hash: &[u8; 32]| format!("\"{}\"", hex(hash));
+
+        let settings = self
+            .settings
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    r#"{{"name": {}, "value": {}}}"#,
+                    json_string(name),
+                    json_string(value)
+                )
+            })
+            .collect::<Vec<_>>();
+        let fields = [
+            ("version", SIDECAR_VERSION.to_string()),
+            ("input_hash", hash(&self.input_hash)),
+            ("entry_point", json_string(&self.entry_point)),
+            ("settings", format!("[{}]", settings.join(", "))),
+            (
+                "root_signature_hash",
+                opt(self.root_signature_hash.as_ref().map(hash)),
+            ),
+            (
+                "converter_path",
+                opt(self.converter_path.as_deref().map(json_string)),
+            ),
+            (
+                "converter_hash",
+                opt(self.converter_hash.as_ref().map(hash)),
+            ),
+            ("saxaboom_version", json_string(&self.saxaboom_version)),
+        ];
+        let fields = fields
+            .iter()
+            .map(|(key, value)| format!("  \"{key}\": {value}"))
+            .collect::<Vec<_>>();
+        format!("{{\n{}\n}}\n", fields.join(",\n"))
+    }
+
+    /// Writes [`Self::to_sidecar()`] and [`Self::to_json()`] next to `metallib_path`, with the
+    /// extension replaced by [`BINARY_EXTENSION`] and [`JSON_EXTENSION`].
+    pub fn write_sidecars(&self, metallib_path: &Path) -> Result<(), Error> {
+        let write = |extension, contents: &[u8]| {
+            let path = metallib_path.with_extension(extension);
+            fs::write(&path, contents).map_err(|source| Error::Write { path, source })
+        };
+        write(BINARY_EXTENSION, &self.to_sidecar())?;
+        write(JSON_EXTENSION, self.to_json().as_bytes())
+    }
+}
+
+/// DXIL that an [`IRObject`] was created from, which the converter reads without copying it.
+/// Shared with the objects compiled from it and with recordings, and only hashed when a
+/// [`Provenance`] is requested.
+#[derive(Debug, Default)]
+pub(crate) struct Dxil {
+    pub(crate) bytes: Arc<[u8]>,
+    hash: OnceCell<[u8; 32]>,
+}
+
+impl Dxil {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.into(),
+            hash: OnceCell::new(),
+        }
+    }
+
+    fn hash(&self) -> [u8; 32] {
+        *self.hash.get_or_init(|| sha256(&self.bytes))
+    }
+}
+
+/// A setting tracked for [`IRCompiler::settings()`].  Root signatures are kept as their shared
+/// JSON, so that they are only serialized when needed.
+#[derive(Clone, Debug)]
+pub(crate) enum Setting {
+    Call(Call),
+    GlobalRootSignature(Rc<RootSignatureJson>),
+    LocalRootSignature(Rc<RootSignatureJson>),
+}
+
+impl Setting {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Call(call) => call.name(),
+            Self::GlobalRootSignature(_) => "set_global_root_signature",
+            Self::LocalRootSignature(_) => "set_local_root_signature",
+        }
+    }
+
+    pub(crate) fn call(&self) -> Call {
+        let json = |json: &RootSignatureJson| json.get().map(str::to_owned);
+        match self {
+            Self::Call(call) => call.clone(),
+            Self::GlobalRootSignature(root_signature) => {
+                Call::SetGlobalRootSignature(json(root_signature))
+            }
+            Self::LocalRootSignature(root_signature) => {
+                Call::SetLocalRootSignature(json(root_signature))
+            }
+        }
+    }
+}
+
+/// What a compiled [`IRObject`] keeps to build its [`Provenance`] on request, so that nothing is
+/// hashed or formatted unless needed.  Keeps the DXIL inputs alive.
+#[derive(Debug)]
+pub(crate) struct Origin {
+    /// One, or the intersection and any-hit inputs.
+    inputs: Vec<Rc<Dxil>>,
+    entry_point: String,
+    settings: Vec<Setting>,
+    library: ConverterLibrary,
+}
+
+impl Origin {
+    fn provenance(&self) -> Provenance {
+        let input_hash = match &*self.inputs {
+            [input] => input.hash(),
+            inputs => sha256(&inputs.iter().flat_map(|i| i.hash()).collect::<Vec<_>>()),
+        };
+        let root_signature_hash = self.settings.iter().find_map(|setting| match setting {
+            Setting::GlobalRootSignature(json) => json.get().map(|j| sha256(j.as_bytes())),
+            _ => None,
+        });
+        Provenance {
+            input_hash,
+            entry_point: self.entry_point.clone(),
+            settings: self
+                .settings
+                .iter()
+                .filter_map(|setting| {
+                    let call = setting.call();
+                    Some((call.name().to_owned(), call.setting()?))
+                })
+                .collect(),
+            root_signature_hash,
+            converter_path: self
+                .library
+                .path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            converter_hash: self.library.hash(),
+            saxaboom_version: SAXABOOM_VERSION.to_owned(),
+        }
+    }
+}
+
+impl IRObject {
+    /// How this object was compiled, [`None`] for objects that were not returned by
+    /// [`IRCompiler::alloc_compile_and_link()`] or
+    /// [`IRCompiler::alloc_combine_compile_and_link()`].
+    ///
+    /// Hashes the inputs of this object, and the converter library file the first time it is
+    /// called for a [`MetalIrConverter`].
+    pub fn provenance(&self) -> Option<Provenance> {
+        self.origin.as_ref().map(|origin| origin.provenance())
+    }
+}
+
+impl IRCompiler {
+    /// Every setting applied to this compiler, in the order in which they were first applied.
+    /// Applying a setting again replaces the earlier call.
+    pub fn settings(&self) -> Vec<Call> {
+        self.settings.iter().map(Setting::call).collect()
+    }
+
+    /// Tracks a setting for [`Self::settings()`], and records it.
+    pub(crate) fn apply(&mut self, call: Call) {
+        self.apply_setting(Setting::Call(call));
+    }
+
+    pub(crate) fn apply_setting(&mut self, setting: Setting) {
+        self.record(|| setting.call());
+        match self
+            .settings
+            .iter_mut()
+            .find(|s| s.name() == setting.name())
+        {
+            Some(existing) => *existing = setting,
+            None => self.settings.push(setting),
+        }
+    }
+
+    /// The [`Origin`] of an object compiled from `inputs` at `entry_point` with the current
+    /// settings.
+    pub(crate) fn origin(&self, inputs: Vec<Rc<Dxil>>, entry_point: String) -> Box<Origin> {
+        Box::new(Origin {
+            inputs,
+            entry_point,
+            settings: self.settings.clone(),
+            library: self.library.clone(),
+        })
+    }
+}
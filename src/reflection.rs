@@ -0,0 +1,317 @@
+//! Owned copy of the parts of [`IRShaderReflection`] that a renderer needs at runtime, which
+//! outlives the converter and can be stored next to the `metallib`, see [`crate::bundle`].
+//!
+//! The binary encoding produced by [`ReflectionSnapshot::to_bytes()`] is read by
+//! `saxaboom_runtime::reflection`, without loading the converter library.  So are the versioned
+//! sidecar files written next to a `metallib` by [`ReflectionSnapshot::write_sidecars()`]:
+//!
+//! ```no_run
+//! # fn example(object: &saxaboom::IRObject) -> Result<(), saxaboom::Error> {
+//! let path = std::path::Path::new("shaders/blur.metallib");
+//! std::fs::write(path, object.metal_lib_binary()?.byte_code()).unwrap();
+//! // Writes `shaders/blur.reflection.bin` and `shaders/blur.reflection.json`
+//! object.reflection()?.snapshot().write_sidecars(path)?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{ffi::CStr, fs, path::Path};
+
+use crate::{
+    encode::{json_string, Encoder},
+    ffi, Error, FunctionConstant, IRShaderReflection,
+};
+
+/// Starts binary sidecar files, see [`ReflectionSnapshot::to_sidecar()`].
+pub const SIDECAR_MAGIC: [u8; 4] = *b"SXRF";
+/// Version written to sidecar files.  Bumped whenever the encoding of [`ReflectionSnapshot`]
+/// changes, so that older readers reject newer sidecars.
+pub const SIDECAR_VERSION: u32 = 1;
+/// Extension of binary sidecar files, replacing `metallib`.
+pub const BINARY_EXTENSION: &str = "reflection.bin";
+/// Extension of JSON sidecar files, replacing `metallib`.
+pub const JSON_EXTENSION: &str = "reflection.json";
+
+/// Owned counterpart of [`ffi::IRResourceLocation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub name: Option<String>,
+    pub resource_type: ffi::IRResourceType,
+    pub space: u32,
+    pub slot: u32,
+    pub top_level_offset: u32,
+    pub size_bytes: u64,
+}
+
+/// Owned counterpart of [`ffi::IRVertexInputInfo_1_0`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexInput {
+    pub name: String,
+    pub attribute_index: u8,
+}
+
+/// Owned counterpart of [`ffi::IRVSInfo_1_0`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexInfo {
+    pub instance_id_index: i32,
+    pub vertex_id_index: i32,
+    pub vertex_output_size_in_bytes: u32,
+    pub needs_draw_params: bool,
+    pub inputs: Vec<VertexInput>,
+}
+
+/// Counterpart of [`ffi::IRFSInfo_1_0`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FragmentInfo {
+    pub num_render_targets: i32,
+    pub rt_index_int: u8,
+    pub discards: bool,
+}
+
+/// Returned by [`IRShaderReflection::snapshot()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReflectionSnapshot {
+    pub entry_point: Option<String>,
+    pub needs_function_constants: bool,
+    pub function_constants: Vec<FunctionConstant>,
+    pub resources: Vec<Resource>,
+    /// Of compute, mesh and amplification shaders.
+    pub threadgroup_size: Option<[u32; 3]>,
+    pub vertex: Option<VertexInfo>,
+    pub fragment: Option<FragmentInfo>,
+}
+
+impl ReflectionSnapshot {
+    /// Encodes every field in declaration order: strings and lists are prefixed with their
+    /// length as a `u32`, [`Option`]s with a `0` or `1` byte, and enums are stored as their raw
+    /// `u32` value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut e = Encoder::default();
+        e.opt_str(self.entry_point.as_deref())
+            .bool(self.needs_function_constants)
+            .len(self.function_constants.len());
+        for constant in &self.function_constants {
+            e.str(&constant.name).u32(constant.r#type as u32);
+        }
+        e.len(self.resources.len());
+        for resource in &self.resources {
+            e.opt_str(resource.name.as_deref())
+                .u32(resource.resource_type as u32)
+                .u32(resource.space)
+                .u32(resource.slot)
+                .u32(resource.top_level_offset)
+                .u64(resource.size_bytes);
+        }
+        e.bool(self.threadgroup_size.is_some());
+        if let Some([x, y, z]) = self.threadgroup_size {
+            e.u32(x).u32(y).u32(z);
+        }
+        e.bool(self.vertex.is_some());
+        if let Some(vertex) = &self.vertex {
+            e.i32(vertex.instance_id_index)
+                .i32(vertex.vertex_id_index)
+                .u32(vertex.vertex_output_size_in_bytes)
+                .bool(vertex.needs_draw_params)
+                .len(vertex.inputs.len());
+            for input in &vertex.inputs {
+                e.str(&input.name).u8(input.attribute_index);
+            }
+        }
+        e.bool(self.fragment.is_some());
+        if let Some(fragment) = &self.fragment {
+            e.i32(fragment.num_render_targets)
+                .u8(fragment.rt_index_int)
+                .bool(fragment.discards);
+        }
+        e.bytes
+    }
+
+    /// Binary sidecar file: [`SIDECAR_MAGIC`], [`SIDECAR_VERSION`] as a `u32`, and
+    /// [`Self::to_bytes()`].
+    pub fn to_sidecar(&self) -> Vec<u8> {
+        let mut e = Encoder::default();
+        e.bytes.extend(SIDECAR_MAGIC);
+        e.u32(SIDECAR_VERSION);
+        e.bytes.extend(self.to_bytes());
+        e.bytes
+    }
+
+    /// JSON sidecar file, with the fields of this struct and a `version`.  Enums are stored by
+    /// the name of their [`ffi`] variant, such as `"CBV"`.
+    pub fn to_json(&self) -> String {
+        let opt = |value: Option<String>| value.unwrap_or_else(|| "null".to_owned());
+        let list = |items: Vec<String>| format!("[{}]", items.join(", "));
+
+        let function_constants = self
+            .function_constants
+            .iter()
+            .map(|c| {
+                format!(
+                    r#"{{"name": {}, "type": "{:?}"}}"#,
+                    json_string(&c.name),
+                    c.r#type
+                )
+            })
+            .collect();
+        let resources = self
+            .resources
+            .iter()
+            .map(|r| {
+                format!(
+                    r#"{{"name": {}, "type": "{:?}", "space": {}, "slot": {}, "top_level_offset": {}, "size_bytes": {}}}"#,
+                    opt(r.name.as_deref().map(json_string)),
+                    r.resource_type,
+                    r.space,
+                    r.slot,
+                    r.top_level_offset,
+                    r.size_bytes
+                )
+            })
+            .collect();
+        let vertex = self.vertex.as_ref().map(|v| {
+            let inputs = v
+                .inputs
+                .iter()
+                .map(|i| {
+                    format!(
+                        r#"{{"name": {}, "attribute_index": {}}}"#,
+                        json_string(&i.name),
+                        i.attribute_index
+                    )
+                })
+                .collect();
+            format!(
+                r#"{{"instance_id_index": {}, "vertex_id_index": {}, "vertex_output_size_in_bytes": {}, "needs_draw_params": {}, "inputs": {}}}"#,
+                v.instance_id_index,
+                v.vertex_id_index,
+                v.vertex_output_size_in_bytes,
+                v.needs_draw_params,
+                list(inputs)
+            )
+        });
+        let fragment = self.fragment.map(|f| {
+            format!(
+                r#"{{"num_render_targets": {}, "rt_index_int": {}, "discards": {}}}"#,
+                f.num_render_targets, f.rt_index_int, f.discards
+            )
+        });
+
+        let fields = [
+            ("version", SIDECAR_VERSION.to_string()),
+            (
+                "entry_point",
+                opt(self.entry_point.as_deref().map(json_string)),
+            ),
+            (
+                "needs_function_constants",
+                self.needs_function_constants.to_string(),
+            ),
+            ("function_constants", list(function_constants)),
+            ("resources", list(resources)),
+            (
+                "threadgroup_size",
+                opt(self
+                    .threadgroup_size
+                    .map(|size| list(size.iter().map(u32::to_string).collect()))),
+            ),
+            ("vertex", opt(vertex)),
+            ("fragment", opt(fragment)),
+        ];
+        let fields = fields
+            .iter()
+            .map(|(key, value)| format!("  \"{key}\": {value}"))
+            .collect::<Vec<_>>();
+        format!("{{\n{}\n}}\n", fields.join(",\n"))
+    }
+
+    /// Writes [`Self::to_sidecar()`] and [`Self::to_json()`] next to `metallib_path`, with the
+    /// extension replaced by [`BINARY_EXTENSION`] and [`JSON_EXTENSION`].
+    pub fn write_sidecars(&self, metallib_path: &Path) -> Result<(), Error> {
+        let write = |extension, contents: &[u8]| {
+            let path = metallib_path.with_extension(extension);
+            fs::write(&path, contents).map_err(|source| Error::Write { path, source })
+        };
+        write(BINARY_EXTENSION, &self.to_sidecar())?;
+        write(JSON_EXTENSION, self.to_json().as_bytes())
+    }
+}
+
+impl IRShaderReflection {
+    /// Copies everything in [`ReflectionSnapshot`] out of this reflection.
+    pub fn snapshot(&self) -> ReflectionSnapshot {
+        let version = ffi::IRReflectionVersion::_1_0;
+        let threadgroup_size = self
+            .compute_info(version)
+            .map(|info| unsafe { info.u_1.info_1_0 }.tg_size)
+            .or_else(|| {
+                self.mesh_info(version)
+                    .map(|info| unsafe { info.u_1.info_1_0 }.num_threads)
+            })
+            .or_else(|| {
+                self.amplification_info(version)
+                    .map(|info| unsafe { info.u_1.info_1_0 }.num_threads)
+            });
+        let vertex = self.vertex_info(version).map(|info| {
+            let info = unsafe { info.u_1.info_1_0 };
+            let inputs = if info.vertex_inputs.is_null() {
+                &[][..]
+            } else {
+                unsafe { std::slice::from_raw_parts(info.vertex_inputs, info.num_vertex_inputs) }
+            };
+            VertexInfo {
+                instance_id_index: info.instance_id_index,
+                vertex_id_index: info.vertex_id_index,
+                vertex_output_size_in_bytes: info.vertex_output_size_in_bytes,
+                needs_draw_params: info.needs_draw_params,
+                inputs: inputs
+                    .iter()
+                    .map(|input| VertexInput {
+                        name: unsafe { c_str(input.name) }.unwrap_or_default(),
+                        attribute_index: input.attributeIndex,
+                    })
+                    .collect(),
+            }
+        });
+        let fragment = self.fragment_info(version).map(|info| {
+            let info = unsafe { info.u_1.info_1_0 };
+            FragmentInfo {
+                num_render_targets: info.num_render_targets,
+                rt_index_int: info.rt_index_int,
+                discards: info.discards,
+            }
+        });
+
+        ReflectionSnapshot {
+            entry_point: self
+                .entry_point_function_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            needs_function_constants: self.needs_function_constants(),
+            function_constants: self.function_constants(),
+            // Names point into `self`
+            resources: self
+                .resource_locations()
+                .iter()
+                .map(|location| Resource {
+                    name: unsafe { c_str(location.resourceName) },
+                    resource_type: location.resourceType,
+                    space: location.space,
+                    slot: location.slot,
+                    top_level_offset: location.topLevelOffset,
+                    size_bytes: location.sizeBytes,
+                })
+                .collect(),
+            threadgroup_size,
+            vertex,
+            fragment,
+        }
+    }
+}
+
+/// # Safety
+/// `name` must be null or point to a live string.
+unsafe fn c_str(name: *const std::ffi::c_char) -> Option<String> {
+    (!name.is_null()).then(|| {
+        unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
@@ -0,0 +1,1113 @@
+//! Recording every call made on an [`IRCompiler`] into a self-contained [`Recording`], and
+//! replaying it against any loaded converter library.  A recording reproduces a converter crash or
+//! miscompilation without the application that triggered it, such as for a bug report to Apple.
+//!
+//! ```no_run
+//! # fn example(converter: &saxaboom::MetalIrConverter, dxil: &[u8]) -> Result<(), saxaboom::Error> {
+//! use std::path::Path;
+//!
+//! use saxaboom::{ffi, replay::{self, Recording}};
+//!
+//! let mut compiler = converter.create_compiler();
+//! compiler.start_recording();
+//! compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+//! let object = converter.create_object_from_dxil(dxil);
+//! let _ = compiler.alloc_compile_and_link(c"main", &object);
+//! let recording = compiler.take_recording().expect("recording was started");
+//! recording.write(Path::new("miscompile.sxrp"))?;
+//!
+//! // Possibly on another machine, with another release of the converter
+//! let recording = Recording::read(Path::new("miscompile.sxrp"))?;
+//! let report = replay::replay(converter, &recording);
+//! print!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Root signatures and input layouts are recorded as JSON, which requires a library that can
+//! serialize them (see [`crate::backend::OPTIONAL_FUNCTIONS`]).  Calls that could not be recorded
+//! completely are [skipped](Outcome::Skipped) when replaying.
+use std::{
+    cell::OnceCell,
+    ffi::{CStr, CString},
+    fmt, fs,
+    path::Path,
+    ptr::NonNull,
+    sync::Arc,
+};
+
+use crate::{
+    backend::ConverterBackend,
+    encode::{self, Decoder, Encoder},
+    ffi, require, Error, IRCompiler, IRObject, IRRootSignature, MetalIrConverter,
+};
+
+/// Starts recording files, see [`Recording::to_bytes()`].
+pub const MAGIC: [u8; 4] = *b"SXRP";
+/// Version written by [`Recording::to_bytes()`].  Bumped whenever the encoding of [`Call`]
+/// changes, older versions are not read.
+pub const VERSION: u32 = 1;
+
+/// Arguments of [`IRCompiler::set_ray_tracing_pipeline_arguments()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RayTracingPipelineArguments {
+    pub max_attribute_size_in_bytes: u32,
+    pub raytracing_pipeline_flags: ffi::IRRaytracingPipelineFlags,
+    pub closest_hit_intrinsics_mask: u64,
+    pub miss_intrinsics_mask: u64,
+    pub any_hit_intrinsics_mask: u64,
+    pub callable_args: u64,
+    pub max_recursive_depth: i32,
+    pub ray_generation_compilation_mode: ffi::IRRayGenerationCompilationMode,
+    pub intersection_function_compilation_mode: ffi::IRIntersectionFunctionCompilationMode,
+}
+
+/// What a compilation or synthesis produced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// The `metallib` and the [`ReflectionSnapshot::to_json()`] of a compiled object, each
+    /// [`None`] when the object did not provide it.
+    ///
+    /// [`ReflectionSnapshot::to_json()`]: crate::reflection::ReflectionSnapshot::to_json()
+    Compiled {
+        metallib: Option<Vec<u8>>,
+        reflection: Option<String>,
+    },
+    /// Compilation failed with this error.
+    Failed {
+        code: ffi::IRErrorCode,
+        message: String,
+    },
+    /// The synthesized `metallib`, [`None`] when synthesizing failed.
+    Synthesized(Option<Vec<u8>>),
+}
+
+impl Output {
+    pub(crate) fn compiled(result: &Result<IRObject, Error>) -> Self {
+        match result {
+            Ok(object) => Self::Compiled {
+                metallib: object.metal_lib_binary().ok().map(|b| b.byte_code()),
+                reflection: object.reflection().ok().map(|r| r.snapshot().to_json()),
+            },
+            Err(Error::Compilation { error, .. }) => Self::Failed {
+                code: error.code(),
+                message: error.payload().to_string_lossy().into_owned(),
+            },
+            Err(error) => Self::Failed {
+                code: ffi::IRErrorCode::Unknown,
+                message: error.to_string(),
+            },
+        }
+    }
+
+    /// How `actual` differs from this output, [`None`] when it is the same.
+    fn difference(&self, actual: &Self) -> Option<String> {
+        if self == actual {
+            return None;
+        }
+        Some(match (self, actual) {
+            (
+                Self::Compiled {
+                    metallib,
+                    reflection,
+                },
+                Self::Compiled {
+                    metallib: actual_metallib,
+                    reflection: actual_reflection,
+                },
+            ) => {
+                let mut differences = Vec::new();
+                if metallib != actual_metallib {
+                    differences.push(metallib_difference(
+                        metallib.as_deref(),
+                        actual_metallib.as_deref(),
+                    ));
+                }
+                if reflection != actual_reflection {
+                    differences.push("reflection differs".to_owned());
+                }
+                differences.join(", ")
+            }
+            (Self::Synthesized(metallib), Self::Synthesized(actual_metallib)) => {
+                metallib_difference(metallib.as_deref(), actual_metallib.as_deref())
+            }
+            _ => format!("{actual} instead of {self}"),
+        })
+    }
+}
+
+fn metallib_difference(expected: Option<&[u8]>, actual: Option<&[u8]>) -> String {
+    match (expected, actual) {
+        (Some(expected), Some(actual)) if expected.len() == actual.len() => {
+            "metallib differs".to_owned()
+        }
+        _ => {
+            let size = |metallib: Option<&[u8]>| {
+                metallib.map_or("missing".to_owned(), |m| format!("{} bytes", m.len()))
+            };
+            format!("metallib is {} instead of {}", size(actual), size(expected))
+        }
+    }
+}
+
+fn describe(metallib: Option<&[u8]>) -> String {
+    match metallib {
+        Some(metallib) => format!("of {} bytes", metallib.len()),
+        None => "missing".to_owned(),
+    }
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compiled { metallib, .. } => {
+                write!(f, "compiled metallib {}", describe(metallib.as_deref()))
+            }
+            Self::Failed { code, message } => write!(f, "error {code:?}: {message}"),
+            Self::Synthesized(metallib) => {
+                write!(f, "synthesized metallib {}", describe(metallib.as_deref()))
+            }
+        }
+    }
+}
+
+/// A call on [`IRCompiler`], with its arguments and, for compilations and synthesis, its
+/// recorded [`Output`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Call {
+    SetValidationFlags(ffi::IRCompilerValidationFlags),
+    SetStageInGenerationMode(ffi::IRStageInCodeGenerationMode),
+    /// The [`IRRootSignature::json()`], [`None`] when it was not available.
+    SetGlobalRootSignature(Option<String>),
+    /// The [`IRRootSignature::json()`], [`None`] when it was not available.
+    SetLocalRootSignature(Option<String>),
+    SetHitgroupType(ffi::IRHitGroupType),
+    SetRayTracingPipelineArguments(RayTracingPipelineArguments),
+    SetCompatibilityFlags(ffi::IRCompatibilityFlags),
+    SetInputTopology(ffi::IRInputTopology),
+    EnableGeometryAndTessellationEmulation(bool),
+    SetDualSourceBlendingConfiguration(ffi::IRDualSourceBlendingConfiguration),
+    SetDepthFeedbackConfiguration(ffi::IRDepthFeedbackConfiguration),
+    SetIntRtMask(u8),
+    SetEntryPointName(String),
+    SetMinimumGpuFamily(ffi::IRGPUFamily),
+    IgnoreRootSignature(bool),
+    IgnoreDebugInformation(bool),
+    SetMinimumDeploymentTarget(ffi::IROperatingSystem, String),
+    /// `dxil` is empty when the input was not created with
+    /// [`MetalIrConverter::create_object_from_dxil()`].
+    CompileAndLink {
+        entry_point: String,
+        dxil: Arc<[u8]>,
+        output: Output,
+    },
+    CombineCompileAndLink {
+        intersection_function_entry_point: String,
+        intersection_function_dxil: Arc<[u8]>,
+        any_hit_function_entry_point: String,
+        any_hit_function_dxil: Arc<[u8]>,
+        output: Output,
+    },
+    /// The vertex shader reflection and input layout as JSON, each [`None`] when the library
+    /// could not serialize it.
+    SynthesizeStageInFunction {
+        vertex_shader_reflection: Option<String>,
+        layout: Option<String>,
+        output: Output,
+    },
+    SynthesizeIndirectRayDispatchFunction {
+        output: Output,
+    },
+    SynthesizeIndirectIntersectionFunction {
+        output: Output,
+    },
+}
+
+impl Call {
+    /// Name of the [`IRCompiler`] method that made this call.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SetValidationFlags(_) => "set_validation_flags",
+            Self::SetStageInGenerationMode(_) => "set_stage_in_generation_mode",
+            Self::SetGlobalRootSignature(_) => "set_global_root_signature",
+            Self::SetLocalRootSignature(_) => "set_local_root_signature",
+            Self::SetHitgroupType(_) => "set_hitgroup_type",
+            Self::SetRayTracingPipelineArguments(_) => "set_ray_tracing_pipeline_arguments",
+            Self::SetCompatibilityFlags(_) => "set_compatibility_flags",
+            Self::SetInputTopology(_) => "set_input_topology",
+            Self::EnableGeometryAndTessellationEmulation(_) => {
+                "enable_geometry_and_tessellation_emulation"
+            }
+            Self::SetDualSourceBlendingConfiguration(_) => "set_dual_source_blending_configuration",
+            Self::SetDepthFeedbackConfiguration(_) => "set_depth_feedback_configuration",
+            Self::SetIntRtMask(_) => "set_int_rt_mask",
+            Self::SetEntryPointName(_) => "set_entry_point_name",
+            Self::SetMinimumGpuFamily(_) => "set_minimum_gpu_family",
+            Self::IgnoreRootSignature(_) => "ignore_root_signature",
+            Self::IgnoreDebugInformation(_) => "ignore_debug_information",
+            Self::SetMinimumDeploymentTarget(..) => "set_minimum_deployment_target",
+            Self::CompileAndLink { .. } => "alloc_compile_and_link",
+            Self::CombineCompileAndLink { .. } => "alloc_combine_compile_and_link",
+            Self::SynthesizeStageInFunction { .. } => "synthesize_stage_in_function",
+            Self::SynthesizeIndirectRayDispatchFunction { .. } => {
+                "synthesize_indirect_ray_dispatch_function"
+            }
+            Self::SynthesizeIndirectIntersectionFunction { .. } => {
+                "synthesize_indirect_intersection_function"
+            }
+        }
+    }
+
+    /// The recorded output of compilations and synthesis.
+    pub fn output(&self) -> Option<&Output> {
+        match self {
+            Self::CompileAndLink { output, .. }
+            | Self::CombineCompileAndLink { output, .. }
+            | Self::SynthesizeStageInFunction { output, .. }
+            | Self::SynthesizeIndirectRayDispatchFunction { output }
+            | Self::SynthesizeIndirectIntersectionFunction { output } => Some(output),
+            _ => None,
+        }
+    }
+
+    /// The value of a setting, as stored in [`crate::provenance::Provenance::settings`].  Root
+    /// signatures are described by the SHA-256 of their JSON.  [`None`] for compilations and
+    /// synthesis.
+    pub(crate) fn setting(&self) -> Option<String> {
+        let root_signature = |json: &Option<String>| match json {
+            Some(json) => encode::hex(&encode::sha256(json.as_bytes())),
+            None => "unknown".to_owned(),
+        };
+        Some(match self {
+            Self::SetValidationFlags(flags) => format!("{:#x}", flags.0),
+            Self::SetStageInGenerationMode(mode) => format!("{mode:?}"),
+            Self::SetGlobalRootSignature(json) | Self::SetLocalRootSignature(json) => {
+                root_signature(json)
+            }
+            Self::SetHitgroupType(hit_group_type) => format!("{hit_group_type:?}"),
+            Self::SetRayTracingPipelineArguments(args) => format!("{args:?}"),
+            Self::SetCompatibilityFlags(flags) => format!("{:#x}", flags.0),
+            Self::SetInputTopology(topology) => format!("{topology:?}"),
+            Self::EnableGeometryAndTessellationEmulation(enable) => enable.to_string(),
+            Self::SetDualSourceBlendingConfiguration(configuration) => {
+                format!("{configuration:?}")
+            }
+            Self::SetDepthFeedbackConfiguration(configuration) => format!("{configuration:?}"),
+            Self::SetIntRtMask(mask) => format!("{mask:#x}"),
+            Self::SetEntryPointName(name) => name.clone(),
+            Self::SetMinimumGpuFamily(family) => format!("{family:?}"),
+            Self::IgnoreRootSignature(ignore) | Self::IgnoreDebugInformation(ignore) => {
+                ignore.to_string()
+            }
+            Self::SetMinimumDeploymentTarget(operating_system, version) => {
+                format!("{operating_system:?} {version}")
+            }
+            Self::CompileAndLink { .. }
+            | Self::CombineCompileAndLink { .. }
+            | Self::SynthesizeStageInFunction { .. }
+            | Self::SynthesizeIndirectRayDispatchFunction { .. }
+            | Self::SynthesizeIndirectIntersectionFunction { .. } => return None,
+        })
+    }
+}
+
+/// `#[non_exhaustive]` [`ffi`] enums that are stored as their raw value.
+trait RawEnum: Sized {
+    const NAME: &'static str;
+    fn from_raw(raw: u32) -> Option<Self>;
+}
+
+macro_rules! raw_enums {
+    ($($ty:ident { $($variant:ident),* $(,)? })*) => {
+        $(impl RawEnum for ffi::$ty {
+            const NAME: &'static str = stringify!($ty);
+
+            fn from_raw(raw: u32) -> Option<Self> {
+                [$(Self::$variant),*].into_iter().find(|v| *v as u32 == raw)
+            }
+        })*
+    };
+}
+
+raw_enums! {
+    IRStageInCodeGenerationMode { UseMetalVertexFetch, UseSeparateStageInFunction }
+    IRHitGroupType { Triangles, ProceduralPrimitive }
+    IRInputTopology { Undefined, Point, Line, Triangle, Patch }
+    IRDualSourceBlendingConfiguration { DecideAtRuntime, ForceEnabled, ForceDisabled }
+    IRDepthFeedbackConfiguration { DecideAtRuntime, ForceEnabled, ForceDisabled }
+    IRGPUFamily { Apple6, Apple7, Apple8, Apple9, Metal3 }
+    IROperatingSystem { macOS, iOS, tvOS, iOSSimulator }
+    IRRayGenerationCompilationMode { Kernel, VisibleFunction }
+    IRIntersectionFunctionCompilationMode { VisibleFunction, IntersectionFunction }
+    IRErrorCode {
+        NoError,
+        ShaderRequiresRootSignature,
+        UnrecognizedRootSignatureDescriptor,
+        UnrecognizedParameterTypeInRootSignature,
+        ResourceNotReferencedByRootSignature,
+        ShaderIncompatibleWithDualSourceBlending,
+        UnsupportedWaveSize,
+        UnsupportedInstruction,
+        CompilationError,
+        FailedToSynthesizeStageInFunction,
+        FailedToSynthesizeStreamOutFunction,
+        FailedToSynthesizeIndirectIntersectionFunction,
+        UnableToVerifyModule,
+        UnableToLinkModule,
+        UnrecognizedDXILHeader,
+        InvalidRaytracingAttribute,
+        NullHullShaderInputOutputMismatch,
+        InvalidRaytracingUserAttributeSize,
+        IncorrectHitgroupType,
+        Unknown,
+    }
+}
+
+fn raw_enum<T: RawEnum>(d: &mut Decoder<'_>) -> Result<T, String> {
+    let raw = d.u32()?;
+    T::from_raw(raw).ok_or_else(|| format!("unknown {} {raw}", T::NAME))
+}
+
+fn encode_opt_data(e: &mut Encoder, value: Option<&[u8]>) {
+    e.bool(value.is_some());
+    if let Some(value) = value {
+        e.data(value);
+    }
+}
+
+fn decode_opt_data(d: &mut Decoder<'_>) -> Result<Option<Vec<u8>>, String> {
+    Ok(d.bool()?.then(|| d.data()).transpose()?.map(<[u8]>::to_vec))
+}
+
+fn decode_opt_string(d: &mut Decoder<'_>) -> Result<Option<String>, String> {
+    Ok(d.opt_str()?.map(str::to_owned))
+}
+
+fn encode_output(e: &mut Encoder, output: &Output) {
+    match output {
+        Output::Compiled {
+            metallib,
+            reflection,
+        } => {
+            e.u8(0);
+            encode_opt_data(e, metallib.as_deref());
+            e.opt_str(reflection.as_deref());
+        }
+        Output::Failed { code, message } => {
+            e.u8(1).u32(*code as u32).str(message);
+        }
+        Output::Synthesized(metallib) => {
+            e.u8(2);
+            encode_opt_data(e, metallib.as_deref());
+        }
+    }
+}
+
+fn decode_output(d: &mut Decoder<'_>) -> Result<Output, String> {
+    Ok(match d.u8()? {
+        0 => Output::Compiled {
+            metallib: decode_opt_data(d)?,
+            reflection: decode_opt_string(d)?,
+        },
+        1 => Output::Failed {
+            code: raw_enum(d)?,
+            message: d.str()?.to_owned(),
+        },
+        2 => Output::Synthesized(decode_opt_data(d)?),
+        tag => return Err(format!("unknown output {tag}")),
+    })
+}
+
+/// Calls made on an [`IRCompiler`] between [`IRCompiler::start_recording()`] and
+/// [`IRCompiler::take_recording()`], see the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    pub calls: Vec<Call>,
+}
+
+impl Recording {
+    /// [`MAGIC`], [`VERSION`] as a `u32`, and the number of calls followed by every call: its
+    /// index in [`Call`] as a `u8` and its fields in declaration order.  Data and strings are
+    /// prefixed with their length as a `u32`, [`Option`]s with a `0` or `1` byte, and enums are
+    /// stored as their raw `u32` value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut e = Encoder::default();
+        e.bytes.extend(MAGIC);
+        e.u32(VERSION).len(self.calls.len());
+        for call in &self.calls {
+            match call {
+                Call::SetValidationFlags(flags) => {
+                    e.u8(0).i32(flags.0);
+                }
+                Call::SetStageInGenerationMode(mode) => {
+                    e.u8(1).u32(*mode as u32);
+                }
+                Call::SetGlobalRootSignature(json) => {
+                    e.u8(2).opt_str(json.as_deref());
+                }
+                Call::SetLocalRootSignature(json) => {
+                    e.u8(3).opt_str(json.as_deref());
+                }
+                Call::SetHitgroupType(hit_group_type) => {
+                    e.u8(4).u32(*hit_group_type as u32);
+                }
+                Call::SetRayTracingPipelineArguments(args) => {
+                    e.u8(5)
+                        .u32(args.max_attribute_size_in_bytes)
+                        .u32(args.raytracing_pipeline_flags.0)
+                        .u64(args.closest_hit_intrinsics_mask)
+                        .u64(args.miss_intrinsics_mask)
+                        .u64(args.any_hit_intrinsics_mask)
+                        .u64(args.callable_args)
+                        .i32(args.max_recursive_depth)
+                        .u32(args.ray_generation_compilation_mode as u32)
+                        .u32(args.intersection_function_compilation_mode as u32);
+                }
+                Call::SetCompatibilityFlags(flags) => {
+                    e.u8(6).u32(flags.0);
+                }
+                Call::SetInputTopology(topology) => {
+                    e.u8(7).u32(*topology as u32);
+                }
+                Call::EnableGeometryAndTessellationEmulation(enable) => {
+                    e.u8(8).bool(*enable);
+                }
+                Call::SetDualSourceBlendingConfiguration(configuration) => {
+                    e.u8(9).u32(*configuration as u32);
+                }
+                Call::SetDepthFeedbackConfiguration(configuration) => {
+                    e.u8(10).u32(*configuration as u32);
+                }
+                Call::SetIntRtMask(mask) => {
+                    e.u8(11).u8(*mask);
+                }
+                Call::SetEntryPointName(name) => {
+                    e.u8(12).str(name);
+                }
+                Call::SetMinimumGpuFamily(family) => {
+                    e.u8(13).u32(*family as u32);
+                }
+                Call::IgnoreRootSignature(ignore) => {
+                    e.u8(14).bool(*ignore);
+                }
+                Call::IgnoreDebugInformation(ignore) => {
+                    e.u8(15).bool(*ignore);
+                }
+                Call::SetMinimumDeploymentTarget(operating_system, version) => {
+                    e.u8(16).u32(*operating_system as u32).str(version);
+                }
+                Call::CompileAndLink {
+                    entry_point,
+                    dxil,
+                    output,
+                } => {
+                    e.u8(17).str(entry_point).data(dxil);
+                    encode_output(&mut e, output);
+                }
+                Call::CombineCompileAndLink {
+                    intersection_function_entry_point,
+                    intersection_function_dxil,
+                    any_hit_function_entry_point,
+                    any_hit_function_dxil,
+                    output,
+                } => {
+                    e.u8(18)
+                        .str(intersection_function_entry_point)
+                        .data(intersection_function_dxil)
+                        .str(any_hit_function_entry_point)
+                        .data(any_hit_function_dxil);
+                    encode_output(&mut e, output);
+                }
+                Call::SynthesizeStageInFunction {
+                    vertex_shader_reflection,
+                    layout,
+                    output,
+                } => {
+                    e.u8(19)
+                        .opt_str(vertex_shader_reflection.as_deref())
+                        .opt_str(layout.as_deref());
+                    encode_output(&mut e, output);
+                }
+                Call::SynthesizeIndirectRayDispatchFunction { output } => {
+                    e.u8(20);
+                    encode_output(&mut e, output);
+                }
+                Call::SynthesizeIndirectIntersectionFunction { output } => {
+                    e.u8(21);
+                    encode_output(&mut e, output);
+                }
+            }
+        }
+        e.bytes
+    }
+
+    /// Reads a recording written by [`Self::to_bytes()`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode(bytes).map_err(|reason| Error::InvalidRecording { reason })
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let Some(bytes) = bytes.strip_prefix(&MAGIC) else {
+            return Err("missing `SXRP` header".to_owned());
+        };
+        let mut d = Decoder::new(bytes);
+        let version = d.u32()?;
+        if version != VERSION {
+            return Err(format!(
+                "version {version} is not supported, only version {VERSION} is"
+            ));
+        }
+        let calls = (0..d.len()?)
+            .map(|_| {
+                Ok(match d.u8()? {
+                    0 => Call::SetValidationFlags(ffi::IRCompilerValidationFlags(d.i32()?)),
+                    1 => Call::SetStageInGenerationMode(raw_enum(&mut d)?),
+                    2 => Call::SetGlobalRootSignature(decode_opt_string(&mut d)?),
+                    3 => Call::SetLocalRootSignature(decode_opt_string(&mut d)?),
+                    4 => Call::SetHitgroupType(raw_enum(&mut d)?),
+                    5 => Call::SetRayTracingPipelineArguments(RayTracingPipelineArguments {
+                        max_attribute_size_in_bytes: d.u32()?,
+                        raytracing_pipeline_flags: ffi::IRRaytracingPipelineFlags(d.u32()?),
+                        closest_hit_intrinsics_mask: d.u64()?,
+                        miss_intrinsics_mask: d.u64()?,
+                        any_hit_intrinsics_mask: d.u64()?,
+                        callable_args: d.u64()?,
+                        max_recursive_depth: d.i32()?,
+                        ray_generation_compilation_mode: raw_enum(&mut d)?,
+                        intersection_function_compilation_mode: raw_enum(&mut d)?,
+                    }),
+                    6 => Call::SetCompatibilityFlags(ffi::IRCompatibilityFlags(d.u32()?)),
+                    7 => Call::SetInputTopology(raw_enum(&mut d)?),
+                    8 => Call::EnableGeometryAndTessellationEmulation(d.bool()?),
+                    9 => Call::SetDualSourceBlendingConfiguration(raw_enum(&mut d)?),
+                    10 => Call::SetDepthFeedbackConfiguration(raw_enum(&mut d)?),
+                    11 => Call::SetIntRtMask(d.u8()?),
+                    12 => Call::SetEntryPointName(d.str()?.to_owned()),
+                    13 => Call::SetMinimumGpuFamily(raw_enum(&mut d)?),
+                    14 => Call::IgnoreRootSignature(d.bool()?),
+                    15 => Call::IgnoreDebugInformation(d.bool()?),
+                    16 => Call::SetMinimumDeploymentTarget(raw_enum(&mut d)?, d.str()?.to_owned()),
+                    17 => Call::CompileAndLink {
+                        entry_point: d.str()?.to_owned(),
+                        dxil: d.data()?.into(),
+                        output: decode_output(&mut d)?,
+                    },
+                    18 => Call::CombineCompileAndLink {
+                        intersection_function_entry_point: d.str()?.to_owned(),
+                        intersection_function_dxil: d.data()?.into(),
+                        any_hit_function_entry_point: d.str()?.to_owned(),
+                        any_hit_function_dxil: d.data()?.into(),
+                        output: decode_output(&mut d)?,
+                    },
+                    19 => Call::SynthesizeStageInFunction {
+                        vertex_shader_reflection: decode_opt_string(&mut d)?,
+                        layout: decode_opt_string(&mut d)?,
+                        output: decode_output(&mut d)?,
+                    },
+                    20 => Call::SynthesizeIndirectRayDispatchFunction {
+                        output: decode_output(&mut d)?,
+                    },
+                    21 => Call::SynthesizeIndirectIntersectionFunction {
+                        output: decode_output(&mut d)?,
+                    },
+                    tag => return Err(format!("unknown call {tag}")),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        if !d.is_empty() {
+            return Err("trailing data after the last call".to_owned());
+        }
+        Ok(Self { calls })
+    }
+
+    /// Writes [`Self::to_bytes()`] to `path`.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_bytes()).map_err(|source| Error::Write {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Reads a recording written by [`Self::write()`].
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let bytes = fs::read(path).map_err(|source| Error::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// What happened to a [`Call`] when it was replayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// A setting was applied.
+    Applied,
+    /// The output is identical to the recorded output.
+    Matches,
+    /// The output differs from the recorded output.
+    Differs(Output),
+    /// The call was not made, because it was not recorded completely or is not supported by the
+    /// loaded library.
+    Skipped(String),
+}
+
+/// Returned by [`replay()`], with the [`Outcome`] of every call in the recording.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Pairs of the replayed call and its outcome, in recording order.
+    pub calls: Vec<(Call, Outcome)>,
+}
+
+impl ReplayReport {
+    /// Calls whose output differs from the recording.
+    pub fn differences(&self) -> impl Iterator<Item = (&Call, &Output)> {
+        self.calls
+            .iter()
+            .filter_map(|(call, outcome)| match outcome {
+                Outcome::Differs(actual) => Some((call, actual)),
+                _ => None,
+            })
+    }
+
+    /// Whether every call was replayed, and produced the recorded output.
+    pub fn reproduced(&self) -> bool {
+        self.calls
+            .iter()
+            .all(|(_, outcome)| matches!(outcome, Outcome::Applied | Outcome::Matches))
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    /// One line per call.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (call, outcome)) in self.calls.iter().enumerate() {
+            write!(f, "{i}: {}", call.name())?;
+            if let Call::CompileAndLink { entry_point, .. } = call {
+                write!(f, " `{entry_point}`")?;
+            }
+            match outcome {
+                Outcome::Applied => writeln!(f, ": applied")?,
+                Outcome::Matches => writeln!(f, ": matches")?,
+                Outcome::Differs(actual) => {
+                    let expected = call.output().expect("only outputs are compared");
+                    let difference = expected.difference(actual).unwrap_or_default();
+                    writeln!(f, ": differs, {difference}")?
+                }
+                Outcome::Skipped(reason) => writeln!(f, ": skipped, {reason}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Re-executes every call of `recording` on a new compiler created by `converter`, and compares
+/// the outputs of compilations and synthesis with the recorded ones.
+pub fn replay(converter: &MetalIrConverter, recording: &Recording) -> ReplayReport {
+    let mut compiler = converter.create_compiler();
+    // The compiler refers to the root signatures that are set on it until it is destroyed
+    let mut root_signatures = Vec::new();
+    let calls = recording
+        .calls
+        .iter()
+        .map(|call| {
+            let outcome = replay_call(converter, &mut compiler, &mut root_signatures, call)
+                .unwrap_or_else(Outcome::Skipped);
+            (call.clone(), outcome)
+        })
+        .collect();
+    ReplayReport { calls }
+}
+
+fn replay_call(
+    converter: &MetalIrConverter,
+    compiler: &mut IRCompiler,
+    root_signatures: &mut Vec<IRRootSignature>,
+    call: &Call,
+) -> Result<Outcome, String> {
+    let string = |value: &str| CString::new(value).map_err(|e| e.to_string());
+    let mut root_signature = |json: &Option<String>| {
+        let json = json
+            .as_deref()
+            .ok_or("the root signature was not recorded as JSON")?;
+        let root_signature = converter
+            .create_root_signature_from_json(&string(json)?)
+            .map_err(|e| e.to_string())?;
+        root_signatures.push(root_signature);
+        Ok::<_, String>(root_signatures.len() - 1)
+    };
+    let compare = |expected: &Output, actual: Output| {
+        Ok(if expected.difference(&actual).is_none() {
+            Outcome::Matches
+        } else {
+            Outcome::Differs(actual)
+        })
+    };
+
+    match call {
+        Call::SetValidationFlags(flags) => compiler.set_validation_flags(*flags),
+        Call::SetStageInGenerationMode(mode) => compiler.set_stage_in_generation_mode(*mode),
+        Call::SetGlobalRootSignature(json) => {
+            let index = root_signature(json)?;
+            compiler.set_global_root_signature(&root_signatures[index])
+        }
+        Call::SetLocalRootSignature(json) => {
+            let index = root_signature(json)?;
+            compiler.set_local_root_signature(&root_signatures[index])
+        }
+        Call::SetHitgroupType(hit_group_type) => compiler.set_hitgroup_type(*hit_group_type),
+        Call::SetRayTracingPipelineArguments(args) => compiler.set_ray_tracing_pipeline_arguments(
+            args.max_attribute_size_in_bytes,
+            args.raytracing_pipeline_flags,
+            args.closest_hit_intrinsics_mask,
+            args.miss_intrinsics_mask,
+            args.any_hit_intrinsics_mask,
+            args.callable_args,
+            args.max_recursive_depth,
+            args.ray_generation_compilation_mode,
+            args.intersection_function_compilation_mode,
+        ),
+        Call::SetCompatibilityFlags(flags) => compiler.set_compatibility_flags(*flags),
+        Call::SetInputTopology(topology) => compiler
+            .set_input_topology(*topology)
+            .map_err(|e| e.to_string())?,
+        Call::EnableGeometryAndTessellationEmulation(enable) => compiler
+            .enable_geometry_and_tessellation_emulation(*enable)
+            .map_err(|e| e.to_string())?,
+        Call::SetDualSourceBlendingConfiguration(configuration) => {
+            compiler.set_dual_source_blending_configuration(*configuration)
+        }
+        Call::SetDepthFeedbackConfiguration(configuration) => compiler
+            .set_depth_feedback_configuration(*configuration)
+            .map_err(|e| e.to_string())?,
+        Call::SetIntRtMask(mask) => compiler.set_int_rt_mask(*mask).map_err(|e| e.to_string())?,
+        Call::SetEntryPointName(name) => compiler.set_entry_point_name(&string(name)?),
+        Call::SetMinimumGpuFamily(family) => compiler.set_minimum_gpu_family(*family),
+        Call::IgnoreRootSignature(ignore) => compiler.ignore_root_signature(*ignore),
+        Call::IgnoreDebugInformation(ignore) => compiler
+            .ignore_debug_information(*ignore)
+            .map_err(|e| e.to_string())?,
+        Call::SetMinimumDeploymentTarget(operating_system, version) => {
+            compiler.set_minimum_deployment_target(*operating_system, &string(version)?)
+        }
+        Call::CompileAndLink {
+            entry_point,
+            dxil,
+            output,
+        } => {
+            let input = converter.create_object_from_dxil(dxil);
+            let result = compiler.alloc_compile_and_link(&string(entry_point)?, &input);
+            return compare(output, Output::compiled(&result));
+        }
+        Call::CombineCompileAndLink {
+            intersection_function_entry_point,
+            intersection_function_dxil,
+            any_hit_function_entry_point,
+            any_hit_function_dxil,
+            output,
+        } => {
+            let intersection = converter.create_object_from_dxil(intersection_function_dxil);
+            let any_hit = converter.create_object_from_dxil(any_hit_function_dxil);
+            let result = compiler.alloc_combine_compile_and_link(
+                &string(intersection_function_entry_point)?,
+                &intersection,
+                &string(any_hit_function_entry_point)?,
+                &any_hit,
+            );
+            return compare(output, Output::compiled(&result));
+        }
+        Call::SynthesizeStageInFunction {
+            vertex_shader_reflection,
+            layout,
+            output,
+        } => {
+            let (Some(reflection), Some(layout)) = (vertex_shader_reflection, layout) else {
+                return Err(
+                    "the vertex shader reflection or input layout was not recorded as JSON"
+                        .to_owned(),
+                );
+            };
+            let reflection = converter
+                .create_shader_reflection_from_json(&string(reflection)?)
+                .map_err(|e| e.to_string())?;
+            let layout = InputLayout::from_json(&*converter.funcs, &string(layout)?)
+                .map_err(|e| e.to_string())?;
+            let binary = converter.create_metal_lib_binary();
+            let synthesized =
+                compiler.synthesize_stage_in_function(&reflection, layout.versioned(), &binary);
+            return compare(
+                output,
+                Output::Synthesized(synthesized.then(|| binary.byte_code())),
+            );
+        }
+        Call::SynthesizeIndirectRayDispatchFunction { output } => {
+            let binary = compiler
+                .synthesize_indirect_ray_dispatch_function()
+                .map_err(|e| e.to_string())?;
+            return compare(output, Output::Synthesized(binary.map(|b| b.byte_code())));
+        }
+        Call::SynthesizeIndirectIntersectionFunction { output } => {
+            let binary = compiler
+                .synthesize_indirect_intersection_function()
+                .map_err(|e| e.to_string())?;
+            return compare(output, Output::Synthesized(binary.map(|b| b.byte_code())));
+        }
+    }
+    Ok(Outcome::Applied)
+}
+
+/// Input layout parsed by the library from JSON.
+struct InputLayout<'a> {
+    me: NonNull<ffi::IRInputLayoutDescriptor1>,
+    funcs: &'a dyn ConverterBackend,
+}
+
+impl<'a> InputLayout<'a> {
+    fn from_json(funcs: &'a dyn ConverterBackend, json: &CStr) -> Result<Self, Error> {
+        require(funcs, "IRInputLayoutDescriptor1CreateFromJSON")?;
+        require(funcs, "IRInputLayoutDescriptor1Release")?;
+        let me =
+            NonNull::new(unsafe { funcs.IRInputLayoutDescriptor1CreateFromJSON(json.as_ptr()) })
+                .ok_or(Error::Serialization {
+                    operation: "parse input layout JSON",
+                    context: crate::error::Context::default(),
+                })?;
+        Ok(Self { me, funcs })
+    }
+
+    fn versioned(&self) -> ffi::IRVersionedInputLayoutDescriptor {
+        ffi::IRVersionedInputLayoutDescriptor {
+            version: ffi::IRInputLayoutDescriptorVersion::_1,
+            u_1: ffi::IRVersionedInputLayoutDescriptor_u {
+                desc_1_0: unsafe { *self.me.as_ptr() },
+            },
+        }
+    }
+}
+
+impl Drop for InputLayout<'_> {
+    fn drop(&mut self) {
+        unsafe { self.funcs.IRInputLayoutDescriptor1Release(self.me.as_ptr()) }
+    }
+}
+
+/// JSON of `layout`, [`None`] when the library cannot serialize it.
+pub(crate) fn layout_json(
+    funcs: &dyn ConverterBackend,
+    layout: &ffi::IRVersionedInputLayoutDescriptor,
+) -> Option<String> {
+    if layout.version != ffi::IRInputLayoutDescriptorVersion::_1
+        || !funcs.is_loaded("IRInputLayoutDescriptor1CopyJSONString")
+        || !funcs.is_loaded("IRInputLayoutDescriptor1ReleaseString")
+    {
+        return None;
+    }
+    let mut desc = unsafe { layout.u_1.desc_1_0 };
+    unsafe {
+        let json = funcs.IRInputLayoutDescriptor1CopyJSONString(&mut desc);
+        if json.is_null() {
+            return None;
+        }
+        let string = CStr::from_ptr(json).to_string_lossy().into_owned();
+        funcs.IRInputLayoutDescriptor1ReleaseString(json);
+        Some(string)
+    }
+}
+
+/// JSON of `desc`, [`None`] when the library cannot serialize it.
+/// The JSON of an [`IRRootSignature`], for recordings and [`crate::provenance`].  Root signatures
+/// created from a descriptor keep a copy of it, which is only serialized when the JSON is first
+/// needed.
+pub(crate) struct RootSignatureJson {
+    json: OnceCell<Option<String>>,
+    descriptor: Option<OwnedRootSignatureDescriptor>,
+    funcs: Arc<dyn ConverterBackend>,
+}
+
+impl fmt::Debug for RootSignatureJson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RootSignatureJson")
+            .field("json", &self.json.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RootSignatureJson {
+    pub(crate) fn from_json(funcs: Arc<dyn ConverterBackend>, json: String) -> Self {
+        Self {
+            json: OnceCell::from(Some(json)),
+            descriptor: None,
+            funcs,
+        }
+    }
+
+    /// # Safety
+    /// Every pointer in `desc` must be valid, as for
+    /// [`MetalIrConverter::create_root_signature_from_descriptor()`].
+    pub(crate) unsafe fn from_descriptor(
+        funcs: Arc<dyn ConverterBackend>,
+        desc: &ffi::IRVersionedRootSignatureDescriptor,
+    ) -> Self {
+        Self {
+            json: OnceCell::new(),
+            descriptor: Some(unsafe { OwnedRootSignatureDescriptor::new(desc) }),
+            funcs,
+        }
+    }
+
+    /// Serializes the descriptor on first use, [`None`] when the loaded library cannot.
+    pub(crate) fn get(&self) -> Option<&str> {
+        self.json
+            .get_or_init(|| root_signature_json(&*self.funcs, &self.descriptor.as_ref()?.get()))
+            .as_deref()
+    }
+}
+
+/// Copy of an [`ffi::IRVersionedRootSignatureDescriptor`] and of everything it points to.  The
+/// parameters point into `ranges`, whose buffers do not move with this struct.
+struct OwnedRootSignatureDescriptor {
+    version: ffi::IRRootSignatureVersion,
+    flags: ffi::IRRootSignatureFlags,
+    parameters: Vec<ffi::IRRootParameter>,
+    ranges: Vec<Vec<ffi::IRDescriptorRange>>,
+    parameters_1_1: Vec<ffi::IRRootParameter1>,
+    ranges_1_1: Vec<Vec<ffi::IRDescriptorRange1>>,
+    static_samplers: Vec<ffi::IRStaticSamplerDescriptor>,
+}
+
+/// `len` values at `ptr`, which may be null when `len` is `0`.
+///
+/// # Safety
+/// `ptr` must be valid for `len` reads.
+unsafe fn copy_array<T: Copy>(ptr: *const T, len: u32) -> Vec<T> {
+    if ptr.is_null() || len == 0 {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(ptr, len as usize) }.to_vec()
+    }
+}
+
+impl OwnedRootSignatureDescriptor {
+    /// # Safety
+    /// Every pointer in `desc` must be valid.
+    unsafe fn new(desc: &ffi::IRVersionedRootSignatureDescriptor) -> Self {
+        let mut owned = Self {
+            version: desc.version,
+            flags: ffi::IRRootSignatureFlags::None,
+            parameters: Vec::new(),
+            ranges: Vec::new(),
+            parameters_1_1: Vec::new(),
+            ranges_1_1: Vec::new(),
+            static_samplers: Vec::new(),
+        };
+        match desc.version {
+            ffi::IRRootSignatureVersion::_1 => unsafe {
+                let desc = desc.u_1.desc_1_0;
+                owned.flags = desc.Flags;
+                owned.parameters = copy_array(desc.pParameters, desc.NumParameters);
+                owned.static_samplers = copy_array(desc.pStaticSamplers, desc.NumStaticSamplers);
+                for parameter in &mut owned.parameters {
+                    if parameter.ParameterType == ffi::IRRootParameterType::DescriptorTable {
+                        let table = &mut parameter.u_1.DescriptorTable;
+                        let ranges = copy_array(table.pDescriptorRanges, table.NumDescriptorRanges);
+                        table.pDescriptorRanges = ranges.as_ptr();
+                        owned.ranges.push(ranges);
+                    }
+                }
+            },
+            ffi::IRRootSignatureVersion::_1_1 => unsafe {
+                let desc = desc.u_1.desc_1_1;
+                owned.flags = desc.Flags;
+                owned.parameters_1_1 = copy_array(desc.pParameters, desc.NumParameters);
+                owned.static_samplers = copy_array(desc.pStaticSamplers, desc.NumStaticSamplers);
+                for parameter in &mut owned.parameters_1_1 {
+                    if parameter.ParameterType == ffi::IRRootParameterType::DescriptorTable {
+                        let table = &mut parameter.u_1.DescriptorTable;
+                        let ranges = copy_array(table.pDescriptorRanges, table.NumDescriptorRanges);
+                        table.pDescriptorRanges = ranges.as_ptr().cast_mut();
+                        owned.ranges_1_1.push(ranges);
+                    }
+                }
+            },
+        }
+        owned
+    }
+
+    /// A descriptor pointing into `self`.
+    fn get(&self) -> ffi::IRVersionedRootSignatureDescriptor {
+        let count = |len: usize| u32::try_from(len).expect("copied from a u32 count");
+        ffi::IRVersionedRootSignatureDescriptor {
+            version: self.version,
+            u_1: match self.version {
+                ffi::IRRootSignatureVersion::_1 => ffi::IRVersionedRootSignatureDescriptor_u {
+                    desc_1_0: ffi::IRRootSignatureDescriptor {
+                        NumParameters: count(self.parameters.len()),
+                        pParameters: self.parameters.as_ptr(),
+                        NumStaticSamplers: count(self.static_samplers.len()),
+                        pStaticSamplers: self.static_samplers.as_ptr(),
+                        Flags: self.flags,
+                    },
+                },
+                ffi::IRRootSignatureVersion::_1_1 => ffi::IRVersionedRootSignatureDescriptor_u {
+                    desc_1_1: ffi::IRRootSignatureDescriptor1 {
+                        NumParameters: count(self.parameters_1_1.len()),
+                        pParameters: self.parameters_1_1.as_ptr().cast_mut(),
+                        NumStaticSamplers: count(self.static_samplers.len()),
+                        pStaticSamplers: self.static_samplers.as_ptr().cast_mut(),
+                        Flags: self.flags,
+                    },
+                },
+            },
+        }
+    }
+}
+
+fn root_signature_json(
+    funcs: &dyn ConverterBackend,
+    desc: &ffi::IRVersionedRootSignatureDescriptor,
+) -> Option<String> {
+    if !funcs.is_loaded("IRVersionedRootSignatureDescriptorCopyJSONString")
+        || !funcs.is_loaded("IRVersionedRootSignatureDescriptorReleaseString")
+    {
+        return None;
+    }
+    unsafe {
+        // The library does not modify the descriptor
+        let json = funcs
+            .IRVersionedRootSignatureDescriptorCopyJSONString(std::ptr::from_ref(desc).cast_mut());
+        if json.is_null() {
+            return None;
+        }
+        let string = CStr::from_ptr(json).to_string_lossy().into_owned();
+        funcs.IRVersionedRootSignatureDescriptorReleaseString(json);
+        Some(string)
+    }
+}
+
+impl IRCompiler {
+    /// Records every following call on this compiler, until [`Self::take_recording()`].  The
+    /// recording starts with the [`Self::settings()`] applied so far, so that it replays the same
+    /// way no matter when recording started.  Discards the calls recorded so far when already
+    /// recording.
+    pub fn start_recording(&mut self) {
+        *self.recording.get_mut() = Some(Recording {
+            calls: self.settings(),
+        });
+    }
+
+    /// Stops recording and returns the calls recorded since [`Self::start_recording()`], or
+    /// [`None`] when not recording.
+    pub fn take_recording(&mut self) -> Option<Recording> {
+        self.recording.get_mut().take()
+    }
+
+    /// Appends `call()` to the recording, if any.
+    pub(crate) fn record(&self, call: impl FnOnce() -> Call) {
+        if self.recording.borrow().is_some() {
+            let call = call();
+            if let Some(recording) = self.recording.borrow_mut().as_mut() {
+                recording.calls.push(call);
+            }
+        }
+    }
+}
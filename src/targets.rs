@@ -0,0 +1,201 @@
+//! Converting one entry point for several operating systems and GPU families, see
+//! [`IRCompiler::alloc_compile_and_link_for_targets()`] and
+//! [`IRCompiler::find_lowest_gpu_family()`].
+use std::{
+    ffi::{CStr, CString},
+    fmt,
+};
+
+use crate::{
+    dedup::OutputIndex, ffi, supports_json_reflection, Error, IRCompiler, IRObject,
+    IRShaderReflection,
+};
+
+/// Every GPU family from lowest to highest, in the order tried by
+/// [`IRCompiler::find_lowest_gpu_family()`].
+pub const GPU_FAMILIES: [ffi::IRGPUFamily; 5] = [
+    ffi::IRGPUFamily::Apple6,
+    ffi::IRGPUFamily::Apple7,
+    ffi::IRGPUFamily::Apple8,
+    ffi::IRGPUFamily::Apple9,
+    ffi::IRGPUFamily::Metal3,
+];
+
+/// Minimum deployment target and GPU family to convert for, see
+/// [`IRCompiler::set_minimum_deployment_target()`] and [`IRCompiler::set_minimum_gpu_family()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Target {
+    pub operating_system: ffi::IROperatingSystem,
+    /// Such as `14.0.0`.
+    pub version: CString,
+    pub gpu_family: ffi::IRGPUFamily,
+}
+
+impl Target {
+    pub fn new(
+        operating_system: ffi::IROperatingSystem,
+        version: &CStr,
+        gpu_family: ffi::IRGPUFamily,
+    ) -> Self {
+        Self {
+            operating_system,
+            version: version.to_owned(),
+            gpu_family,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    /// Formats as `macOS 14.0.0 on Apple7`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} on {:?}",
+            self.operating_system,
+            self.version.to_string_lossy(),
+            self.gpu_family
+        )
+    }
+}
+
+/// `metallib` and reflection shared by one or more targets, see [`TargetOutputs`].
+pub struct TargetOutput {
+    pub metallib: Vec<u8>,
+    pub reflection: IRShaderReflection,
+    /// Indices into the targets passed to [`IRCompiler::alloc_compile_and_link_for_targets()`]
+    /// that produced exactly this output, in ascending order.
+    pub targets: Vec<usize>,
+}
+
+/// Returned by [`IRCompiler::alloc_compile_and_link_for_targets()`].
+pub struct TargetOutputs {
+    /// Distinct outputs, in order of the first target that produced them.
+    pub outputs: Vec<TargetOutput>,
+    /// Index into [`Self::outputs`] for every target.
+    by_target: Vec<usize>,
+}
+
+impl TargetOutputs {
+    /// Output of the target at `index` in the targets that were converted for.
+    ///
+    /// # Panics
+    /// When `index` is out of bounds.
+    pub fn for_target(&self, index: usize) -> &TargetOutput {
+        &self.outputs[self.by_target[index]]
+    }
+}
+
+/// Returned by [`IRCompiler::find_lowest_gpu_family()`].
+pub struct LowestGpuFamily {
+    /// The lowest family that the entry point compiles for, with its compiled object.  [`None`]
+    /// when even the highest family lacks support.
+    pub found: Option<(ffi::IRGPUFamily, IRObject)>,
+    /// Why the entry point did not compile for each lower family, from lowest to highest.  These
+    /// errors have an [`ffi::IRErrorCode::UnsupportedInstruction`] or
+    /// [`ffi::IRErrorCode::UnsupportedWaveSize`] code.
+    pub unsupported: Vec<(ffi::IRGPUFamily, Error)>,
+}
+
+impl LowestGpuFamily {
+    pub fn family(&self) -> Option<ffi::IRGPUFamily> {
+        self.found.as_ref().map(|(family, _)| *family)
+    }
+}
+
+impl IRCompiler {
+    /// Converts `entry_point` once for every target, and returns one `metallib` and reflection
+    /// per target.  Targets that produce the same `metallib` (and reflection JSON, when the
+    /// loaded library supports it) share a single [`TargetOutput`].
+    ///
+    /// Because the deployment target and GPU family are state of the compiler, they remain set
+    /// to the last target afterwards.  Fails at the first target that fails, with
+    /// [`Error::Target`] naming that target.
+    pub fn alloc_compile_and_link_for_targets(
+        &mut self,
+        entry_point: &CStr,
+        input: &IRObject,
+        targets: &[Target],
+    ) -> Result<TargetOutputs, Error> {
+        let json_reflection = supports_json_reflection(&*self.funcs);
+        let mut index = OutputIndex::default();
+        let mut outputs = Vec::<TargetOutput>::new();
+        let mut by_target = Vec::with_capacity(targets.len());
+
+        for (i, target) in targets.iter().enumerate() {
+            let with_target = |error| Error::Target {
+                target: target.clone(),
+                error: Box::new(error),
+            };
+            self.set_minimum_deployment_target(target.operating_system, &target.version);
+            self.set_minimum_gpu_family(target.gpu_family);
+            let object = self
+                .alloc_compile_and_link(entry_point, input)
+                .map_err(with_target)?;
+            let metallib = object.metal_lib_binary().map_err(with_target)?.byte_code();
+            let reflection = object.reflection().map_err(with_target)?;
+            let key = if json_reflection {
+                Some(reflection.json().map_err(with_target)?)
+            } else {
+                None
+            };
+
+            match index.existing(&metallib, key.as_deref(), outputs.len()) {
+                Some(index) => {
+                    outputs[index].targets.push(i);
+                    by_target.push(index);
+                }
+                None => {
+                    by_target.push(outputs.len());
+                    outputs.push(TargetOutput {
+                        metallib,
+                        reflection,
+                        targets: vec![i],
+                    });
+                }
+            }
+        }
+
+        Ok(TargetOutputs { outputs, by_target })
+    }
+
+    /// Compiles `entry_point` for every family in [`GPU_FAMILIES`] from lowest to highest, until
+    /// it compiles without an [`ffi::IRErrorCode::UnsupportedInstruction`] or
+    /// [`ffi::IRErrorCode::UnsupportedWaveSize`] error.
+    ///
+    /// Any other error does not depend on the GPU family and is returned right away.  The minimum
+    /// GPU family of the compiler remains set to the last family that was tried.
+    pub fn find_lowest_gpu_family(
+        &mut self,
+        entry_point: &CStr,
+        input: &IRObject,
+    ) -> Result<LowestGpuFamily, Error> {
+        let mut unsupported = Vec::new();
+        for family in GPU_FAMILIES {
+            self.set_minimum_gpu_family(family);
+            match self.alloc_compile_and_link(entry_point, input) {
+                Ok(object) => {
+                    return Ok(LowestGpuFamily {
+                        found: Some((family, object)),
+                        unsupported,
+                    })
+                }
+                Err(e)
+                    if e.ir_error().is_some_and(|e| {
+                        matches!(
+                            e.code(),
+                            ffi::IRErrorCode::UnsupportedInstruction
+                                | ffi::IRErrorCode::UnsupportedWaveSize
+                        )
+                    }) =>
+                {
+                    unsupported.push((family, e))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(LowestGpuFamily {
+            found: None,
+            unsupported,
+        })
+    }
+}
@@ -0,0 +1,1288 @@
+//! In-memory [`ConverterBackend`] with scripted outputs and errors, to test code built on top of
+//! [`MetalIrConverter`] without the `metal_irconverter` dynamic library (for example on Linux).
+//!
+//! ```
+//! use saxaboom::{
+//!     ffi,
+//!     testing::{FakeConverter, FakeError, FakeShader},
+//!     MetalIrConverter,
+//! };
+//!
+//! let fake = FakeConverter::default();
+//! let mut shader = FakeShader::new(ffi::IRShaderStage::Compute, b"metallib".to_vec());
+//! shader.compute_info = Some(ffi::IRCSInfo_1_0 { tg_size: [64, 1, 1] });
+//! fake.on_entry_point("main", Ok(shader)).on_entry_point(
+//!     "broken",
+//!     Err(FakeError::new(ffi::IRErrorCode::CompilationError, "scripted failure")),
+//! );
+//!
+//! let converter = MetalIrConverter::from_backend(fake.clone());
+//! let compiler = converter.create_compiler();
+//! let dxil = converter.create_object_from_dxil(&[0u8; 4]);
+//!
+//! let object = compiler.alloc_compile_and_link(c"main", &dxil).unwrap();
+//! assert_eq!(object.metal_lib_binary().unwrap().byte_code(), b"metallib");
+//! assert!(compiler.alloc_compile_and_link(c"broken", &dxil).is_err());
+//! assert_eq!(fake.compiled_entry_points(), ["main", "broken"]);
+//! ```
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use crate::{encode::json_string, ffi, ConverterBackend};
+#[cfg(doc)]
+use crate::{IRCompiler, MetalIrConverter};
+
+/// Error returned by [`FakeConverter`] in place of an `IRError` from the real library.
+#[derive(Clone, Debug)]
+pub struct FakeError {
+    pub code: ffi::IRErrorCode,
+    pub message: String,
+}
+
+impl FakeError {
+    pub fn new(code: ffi::IRErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Resource location reported by a fake root signature or reflection, see
+/// [`crate::IRRootSignature::resource_locations()`] and
+/// [`crate::IRShaderReflection::resource_locations()`].
+#[derive(Clone, Debug)]
+pub struct FakeResource {
+    pub resource_type: ffi::IRResourceType,
+    pub space: u32,
+    pub slot: u32,
+    pub top_level_offset: u32,
+    pub size_bytes: u64,
+    pub name: Option<String>,
+}
+
+/// Vertex shader reflection, the owned counterpart of [`ffi::IRVSInfo_1_0`].
+#[derive(Clone, Debug, Default)]
+pub struct FakeVertexInfo {
+    pub instance_id_index: i32,
+    pub vertex_id_index: i32,
+    pub vertex_output_size_in_bytes: u32,
+    pub needs_draw_params: bool,
+    /// Pairs of semantic name and attribute index.
+    pub vertex_inputs: Vec<(String, u8)>,
+}
+
+/// Scripted result of a successful compilation.
+///
+/// Every `*_info` field that is [`None`] makes the matching reflection query return [`None`].
+/// Hull and geometry reflection contain borrowed pointers and are never reported.
+#[derive(Clone, Debug)]
+pub struct FakeShader {
+    pub stage: ffi::IRShaderStage,
+    pub metallib: Vec<u8>,
+    pub compute_info: Option<ffi::IRCSInfo_1_0>,
+    pub vertex_info: Option<FakeVertexInfo>,
+    pub fragment_info: Option<ffi::IRFSInfo_1_0>,
+    pub domain_info: Option<ffi::IRDSInfo_1_0>,
+    pub mesh_info: Option<ffi::IRMSInfo_1_0>,
+    pub amplification_info: Option<ffi::IRASInfo_1_0>,
+    pub raytracing_info: Option<ffi::IRRTInfo_1_0>,
+    /// Reported by [`crate::IRShaderReflection::entry_point_function_name()`].  When [`None`],
+    /// compiling fills in the requested entry point.
+    pub entry_point: Option<String>,
+    /// Pairs of name and type, the shader [needs function
+    /// constants][crate::IRShaderReflection::needs_function_constants()] when not empty.
+    pub function_constants: Vec<(String, ffi::IRFunctionConstantType)>,
+    /// Top-level argument buffer layout reported by the reflection.
+    pub resources: Vec<FakeResource>,
+    /// Returned verbatim by [`crate::IRShaderReflection::json()`].  Parsing this exact string
+    /// with [`MetalIrConverter::create_shader_reflection_from_json()`] yields this shader's
+    /// reflection again.
+    pub reflection_json: String,
+}
+
+impl FakeShader {
+    pub fn new(stage: ffi::IRShaderStage, metallib: Vec<u8>) -> Self {
+        Self {
+            stage,
+            metallib,
+            compute_info: None,
+            vertex_info: None,
+            fragment_info: None,
+            domain_info: None,
+            mesh_info: None,
+            amplification_info: None,
+            raytracing_info: None,
+            entry_point: None,
+            function_constants: Vec::new(),
+            resources: Vec::new(),
+            reflection_json: "{}".to_owned(),
+        }
+    }
+}
+
+/// Snapshot of the state set on a fake [`IRCompiler`], recorded for every compilation.
+#[derive(Clone, Debug, Default)]
+pub struct FakeCompilerSettings {
+    pub validation_flags: Option<ffi::IRCompilerValidationFlags>,
+    pub stage_in_generation_mode: Option<ffi::IRStageInCodeGenerationMode>,
+    pub has_global_root_signature: bool,
+    pub has_local_root_signature: bool,
+    pub hitgroup_type: Option<ffi::IRHitGroupType>,
+    pub compatibility_flags: Option<ffi::IRCompatibilityFlags>,
+    pub input_topology: Option<ffi::IRInputTopology>,
+    pub geometry_and_tessellation_emulation: Option<bool>,
+    pub dual_source_blending_configuration: Option<ffi::IRDualSourceBlendingConfiguration>,
+    pub depth_feedback_configuration: Option<ffi::IRDepthFeedbackConfiguration>,
+    pub int_rt_mask: Option<u8>,
+    pub entry_point_name: Option<String>,
+    pub minimum_gpu_family: Option<ffi::IRGPUFamily>,
+    pub ignore_root_signature: Option<bool>,
+    pub ignore_debug_information: Option<bool>,
+    pub minimum_deployment_target: Option<(ffi::IROperatingSystem, String)>,
+}
+
+/// A single call to [`IRCompiler::alloc_compile_and_link()`] or
+/// [`IRCompiler::alloc_combine_compile_and_link()`] observed by [`FakeConverter`].
+#[derive(Clone, Debug)]
+pub struct FakeCompilation {
+    pub entry_point: String,
+    pub dxil: Vec<u8>,
+    pub settings: FakeCompilerSettings,
+}
+
+type FakeResult<T> = Result<T, FakeError>;
+
+type FakeRule = Arc<dyn Fn(&FakeCompilation) -> Option<FakeResult<FakeShader>> + Send + Sync>;
+
+struct Script {
+    rules: Vec<FakeRule>,
+    by_dxil: Vec<(Vec<u8>, FakeResult<FakeShader>)>,
+    by_entry_point: HashMap<String, FakeResult<FakeShader>>,
+    root_signature: FakeResult<Vec<FakeResource>>,
+    synthesized_metallib: Option<Vec<u8>>,
+    fallback: Option<FakeResult<FakeShader>>,
+    compilations: Vec<FakeCompilation>,
+    missing_functions: HashSet<String>,
+}
+
+/// [`ConverterBackend`] that never touches the `metal_irconverter` library, and instead returns
+/// outputs and errors that were scripted up front.
+///
+/// Compilation results are looked up with the rules from [`FakeConverter::on_compilation()`]
+/// first, then by input DXIL (see [`FakeConverter::on_dxil()`]), then by entry point name (see
+/// [`FakeConverter::on_entry_point()`]) and finally the fallback set with
+/// [`FakeConverter::on_any()`].  Compiling anything that was not scripted fails with
+/// [`ffi::IRErrorCode::CompilationError`].
+///
+/// Clones share their script, so a clone can be kept around to inspect
+/// [`FakeConverter::compilations()`] after handing the backend to
+/// [`MetalIrConverter::from_backend()`].
+#[derive(Clone)]
+pub struct FakeConverter {
+    script: Arc<Mutex<Script>>,
+    live_handles: Arc<AtomicUsize>,
+}
+
+impl Default for FakeConverter {
+    fn default() -> Self {
+        Self {
+            script: Arc::new(Mutex::new(Script {
+                rules: Vec::new(),
+                by_dxil: Vec::new(),
+                by_entry_point: HashMap::new(),
+                root_signature: Ok(Vec::new()),
+                synthesized_metallib: None,
+                fallback: None,
+                compilations: Vec::new(),
+                missing_functions: HashSet::new(),
+            })),
+            live_handles: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl FakeConverter {
+    fn script(&self) -> MutexGuard<'_, Script> {
+        self.script
+            .lock()
+            .expect("FakeConverter script is poisoned")
+    }
+
+    /// Result of any compilation for which `rule` returns [`Some`], for example to fail below a
+    /// certain [`FakeCompilerSettings::minimum_gpu_family`].  Rules are tried in the order they
+    /// were added, before any other rule.
+    pub fn on_compilation(
+        &self,
+        rule: impl Fn(&FakeCompilation) -> Option<FakeResult<FakeShader>> + Send + Sync + 'static,
+    ) -> &Self {
+        self.script().rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Result of compiling any entry point from exactly this DXIL blob.
+    pub fn on_dxil(&self, dxil: impl Into<Vec<u8>>, result: FakeResult<FakeShader>) -> &Self {
+        self.script().by_dxil.push((dxil.into(), result));
+        self
+    }
+
+    /// Result of compiling `entry_point`, when no [`FakeConverter::on_dxil()`] rule matched.
+    pub fn on_entry_point(
+        &self,
+        entry_point: impl Into<String>,
+        result: FakeResult<FakeShader>,
+    ) -> &Self {
+        self.script()
+            .by_entry_point
+            .insert(entry_point.into(), result);
+        self
+    }
+
+    /// Result of compiling anything that no other rule matched.  Without it, such compilations
+    /// fail with [`ffi::IRErrorCode::CompilationError`].
+    pub fn on_any(&self, result: FakeResult<FakeShader>) -> &Self {
+        self.script().fallback = Some(result);
+        self
+    }
+
+    /// Result of every subsequent root signature creation.  Defaults to a root signature without
+    /// any resources.
+    pub fn on_root_signature(&self, result: FakeResult<Vec<FakeResource>>) -> &Self {
+        self.script().root_signature = result;
+        self
+    }
+
+    /// Bytes returned by the `synthesize_*_function()` calls on [`IRCompiler`].  These fail when
+    /// unset.
+    pub fn on_synthesize(&self, metallib: impl Into<Vec<u8>>) -> &Self {
+        self.script().synthesized_metallib = Some(metallib.into());
+        self
+    }
+
+    /// Pretend that the library does not export `function`, like an older `metal_irconverter`
+    /// release would.  This only affects [`ConverterBackend::is_loaded()`], the wrappers are
+    /// expected to never call such a function.
+    pub fn without_function(&self, function: &str) -> &Self {
+        self.script().missing_functions.insert(function.to_owned());
+        self
+    }
+
+    /// All compilations performed so far, in order.
+    pub fn compilations(&self) -> Vec<FakeCompilation> {
+        self.script().compilations.clone()
+    }
+
+    /// Number of objects (compilers, IR objects, errors, ...) that were created through this
+    /// backend but not destroyed yet.
+    pub fn live_handles(&self) -> usize {
+        self.live_handles.load(Ordering::Relaxed)
+    }
+
+    /// Entry points of [`FakeConverter::compilations()`].
+    pub fn compiled_entry_points(&self) -> Vec<String> {
+        self.script()
+            .compilations
+            .iter()
+            .map(|c| c.entry_point.clone())
+            .collect()
+    }
+
+    fn compile(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        entry_point: *const c_char,
+        input: *const ffi::IRObject,
+        error: *mut *mut ffi::IRError,
+    ) -> *mut ffi::IRObject {
+        let compiler = unsafe { &*compiler.cast::<FakeCompiler>() };
+        let entry_point = unsafe { CStr::from_ptr(entry_point) }
+            .to_string_lossy()
+            .into_owned();
+        let FakeObject::Dxil(dxil) = (unsafe { &*input.cast::<FakeObject>() }) else {
+            unsafe {
+                self.write_error(error, ffi::IRErrorCode::UnrecognizedDXILHeader, "not DXIL")
+            };
+            return std::ptr::null_mut();
+        };
+
+        let compilation = FakeCompilation {
+            entry_point: entry_point.clone(),
+            dxil: dxil.clone(),
+            settings: compiler.settings.clone(),
+        };
+        let mut script = self.script();
+        script.compilations.push(compilation.clone());
+        // Rules may call back into this converter, so run them without holding the lock
+        let rules = script.rules.clone();
+        drop(script);
+        let ruled = rules.iter().find_map(|rule| rule(&compilation));
+        let script = self.script();
+        let result = ruled
+            .as_ref()
+            .or_else(|| {
+                script
+                    .by_dxil
+                    .iter()
+                    .find(|(d, _)| d == dxil)
+                    .map(|(_, r)| r)
+            })
+            .or_else(|| script.by_entry_point.get(&entry_point))
+            .or(script.fallback.as_ref())
+            .cloned()
+            .unwrap_or_else(|| {
+                Err(FakeError::new(
+                    ffi::IRErrorCode::CompilationError,
+                    format!("No scripted result for entry point `{entry_point}`"),
+                ))
+            });
+        drop(script);
+
+        match result {
+            Ok(mut shader) => {
+                shader.entry_point.get_or_insert(entry_point);
+                self.create_handle(FakeObject::Compiled(Box::new(shader)))
+            }
+            Err(e) => {
+                unsafe { self.write_error(error, e.code, &e.message) };
+                std::ptr::null_mut()
+            }
+        }
+    }
+
+    /// Scripted shader whose [`FakeShader::reflection_json`] is `json`.
+    fn scripted_shader(&self, json: &str) -> Option<FakeShader> {
+        let script = self.script();
+        let by_entry_point = script.by_entry_point.iter().map(|(entry_point, result)| {
+            result.as_ref().ok().map(|shader| {
+                let mut shader = shader.clone();
+                shader
+                    .entry_point
+                    .get_or_insert_with(|| entry_point.clone());
+                shader
+            })
+        });
+        script
+            .by_dxil
+            .iter()
+            .map(|(_, result)| result.as_ref().ok().cloned())
+            .chain(by_entry_point)
+            .chain(script.fallback.iter().map(|r| r.as_ref().ok().cloned()))
+            .flatten()
+            .find(|shader| shader.reflection_json == json)
+    }
+
+    fn create_handle<T, H>(&self, value: T) -> *mut H {
+        self.live_handles.fetch_add(1, Ordering::Relaxed);
+        Box::into_raw(Box::new(value)).cast()
+    }
+
+    unsafe fn destroy_handle<T, H>(&self, handle: *mut H) {
+        if !handle.is_null() {
+            self.live_handles.fetch_sub(1, Ordering::Relaxed);
+            drop(unsafe { Box::from_raw(handle.cast::<T>()) })
+        }
+    }
+
+    unsafe fn write_error(
+        &self,
+        error: *mut *mut ffi::IRError,
+        code: ffi::IRErrorCode,
+        message: &str,
+    ) {
+        let payload = c_string(message);
+        if !error.is_null() {
+            unsafe { *error = self.create_handle(FakeIrError { code, payload }) };
+        }
+    }
+
+    fn synthesize(&self, binary: *mut ffi::IRMetalLibBinary) -> bool {
+        match &self.script().synthesized_metallib {
+            Some(metallib) => {
+                unsafe { *binary.cast::<Vec<u8>>() = metallib.clone() };
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+struct FakeIrError {
+    code: ffi::IRErrorCode,
+    payload: CString,
+}
+
+struct FakeCompiler {
+    settings: FakeCompilerSettings,
+}
+
+enum FakeObject {
+    Dxil(Vec<u8>),
+    Compiled(Box<FakeShader>),
+}
+
+struct FakeRootSignature {
+    resources: Vec<FakeResource>,
+    names: Vec<Option<CString>>,
+}
+
+/// The fake `IRMetalLibBinary` is a plain `Vec<u8>`, the fake `IRShaderReflection` is this.
+#[derive(Default)]
+struct FakeReflection {
+    shader: Option<Box<FakeShader>>,
+    /// NUL-terminated copies of the strings in `shader`, which outlive the queries returning them
+    entry_point: Option<CString>,
+    resource_names: Vec<Option<CString>>,
+}
+
+impl FakeReflection {
+    fn new(shader: Box<FakeShader>) -> Self {
+        Self {
+            entry_point: shader.entry_point.as_deref().map(c_string),
+            resource_names: resource_names(&shader.resources),
+            shader: Some(shader),
+        }
+    }
+}
+
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).expect("NUL bytes were removed")
+}
+
+fn resource_names(resources: &[FakeResource]) -> Vec<Option<CString>> {
+    resources
+        .iter()
+        .map(|r| r.name.as_deref().map(c_string))
+        .collect()
+}
+
+unsafe fn write_resource_locations(
+    resources: &[FakeResource],
+    names: &[Option<CString>],
+    resource_locations: *mut ffi::IRResourceLocation,
+) {
+    for (i, (resource, name)) in resources.iter().zip(names).enumerate() {
+        let location = ffi::IRResourceLocation {
+            resourceType: resource.resource_type,
+            space: resource.space,
+            slot: resource.slot,
+            topLevelOffset: resource.top_level_offset,
+            sizeBytes: resource.size_bytes,
+            resourceName: name.as_ref().map_or(std::ptr::null(), |n| n.as_ptr()),
+        };
+        unsafe { resource_locations.add(i).write(location) };
+    }
+}
+
+unsafe fn settings<'a>(compiler: *mut ffi::IRCompiler) -> &'a mut FakeCompilerSettings {
+    unsafe { &mut (*compiler.cast::<FakeCompiler>()).settings }
+}
+
+unsafe fn reflected<'a>(reflection: *const ffi::IRShaderReflection) -> Option<&'a FakeShader> {
+    unsafe { (*reflection.cast::<FakeReflection>()).shader.as_deref() }
+}
+
+#[allow(non_snake_case)]
+unsafe impl ConverterBackend for FakeConverter {
+    fn is_loaded(&self, function: &str) -> bool {
+        !self.script().missing_functions.contains(function)
+    }
+
+    unsafe fn IRErrorGetCode(&self, error: *const ffi::IRError) -> u32 {
+        unsafe { (*error.cast::<FakeIrError>()).code as u32 }
+    }
+
+    unsafe fn IRErrorGetPayload(&self, error: *const ffi::IRError) -> *const c_void {
+        unsafe { (*error.cast::<FakeIrError>()).payload.as_ptr().cast() }
+    }
+
+    unsafe fn IRErrorDestroy(&self, error: *mut ffi::IRError) {
+        unsafe { self.destroy_handle::<FakeIrError, _>(error) }
+    }
+
+    unsafe fn IRRootSignatureCreateFromDescriptor(
+        &self,
+        _descriptor: *const ffi::IRVersionedRootSignatureDescriptor,
+        error: *mut *mut ffi::IRError,
+    ) -> *mut ffi::IRRootSignature {
+        match self.script().root_signature.clone() {
+            Ok(resources) => {
+                let names = resource_names(&resources);
+                self.create_handle(FakeRootSignature { resources, names })
+            }
+            Err(e) => {
+                unsafe { self.write_error(error, e.code, &e.message) };
+                std::ptr::null_mut()
+            }
+        }
+    }
+
+    unsafe fn IRRootSignatureDestroy(&self, sig: *mut ffi::IRRootSignature) {
+        unsafe { self.destroy_handle::<FakeRootSignature, _>(sig) }
+    }
+
+    unsafe fn IRVersionedRootSignatureDescriptorCreateFromJSON(
+        &self,
+        serialized: *const c_char,
+    ) -> *mut ffi::IRVersionedRootSignatureDescriptor {
+        // The fake root signature only depends on the script, any non-empty JSON is accepted
+        if unsafe { CStr::from_ptr(serialized) }.is_empty() {
+            return std::ptr::null_mut();
+        }
+        self.create_handle(ffi::IRVersionedRootSignatureDescriptor {
+            version: ffi::IRRootSignatureVersion::_1_1,
+            u_1: ffi::IRVersionedRootSignatureDescriptor_u {
+                desc_1_1: ffi::IRRootSignatureDescriptor1 {
+                    NumParameters: 0,
+                    pParameters: std::ptr::null_mut(),
+                    NumStaticSamplers: 0,
+                    pStaticSamplers: std::ptr::null_mut(),
+                    Flags: ffi::IRRootSignatureFlags::None,
+                },
+            },
+        })
+    }
+
+    unsafe fn IRVersionedRootSignatureDescriptorRelease(
+        &self,
+        root_signature_descriptor: *mut ffi::IRVersionedRootSignatureDescriptor,
+    ) {
+        unsafe {
+            self.destroy_handle::<ffi::IRVersionedRootSignatureDescriptor, _>(
+                root_signature_descriptor,
+            )
+        }
+    }
+
+    unsafe fn IRVersionedRootSignatureDescriptorCopyJSONString(
+        &self,
+        root_signature_descriptor: *mut ffi::IRVersionedRootSignatureDescriptor,
+    ) -> *const c_char {
+        // The number of descriptors of every range of the descriptor tables of version 1.1
+        let desc = unsafe { &*root_signature_descriptor };
+        let mut ranges = Vec::new();
+        if desc.version == ffi::IRRootSignatureVersion::_1_1 {
+            let desc = unsafe { desc.u_1.desc_1_1 };
+            for i in 0..desc.NumParameters as usize {
+                let parameter = unsafe { &*desc.pParameters.add(i) };
+                if parameter.ParameterType == ffi::IRRootParameterType::DescriptorTable {
+                    let table = unsafe { parameter.u_1.DescriptorTable };
+                    for j in 0..table.NumDescriptorRanges as usize {
+                        let range = unsafe { &*table.pDescriptorRanges.add(j) };
+                        ranges.push(range.NumDescriptors.to_string());
+                    }
+                }
+            }
+        }
+        let version = desc.version as u32;
+        c_string(&format!(
+            r#"{{"version": {version}, "ranges": [{}]}}"#,
+            ranges.join(", ")
+        ))
+        .into_raw()
+    }
+
+    unsafe fn IRVersionedRootSignatureDescriptorReleaseString(&self, serialized: *const c_char) {
+        drop(unsafe { CString::from_raw(serialized.cast_mut()) })
+    }
+
+    unsafe fn IRInputLayoutDescriptor1CopyJSONString(
+        &self,
+        input_layout_descriptor: *mut ffi::IRInputLayoutDescriptor1,
+    ) -> *const c_char {
+        let layout = unsafe { &*input_layout_descriptor };
+        let names = layout.semanticNames[..layout.numElements as usize]
+            .iter()
+            .map(|&name| json_string(&unsafe { CStr::from_ptr(name) }.to_string_lossy()))
+            .collect::<Vec<_>>();
+        c_string(&format!(r#"{{"semantic_names": [{}]}}"#, names.join(", "))).into_raw()
+    }
+
+    unsafe fn IRInputLayoutDescriptor1ReleaseString(&self, serialized: *const c_char) {
+        drop(unsafe { CString::from_raw(serialized.cast_mut()) })
+    }
+
+    unsafe fn IRInputLayoutDescriptor1CreateFromJSON(
+        &self,
+        serialized: *const c_char,
+    ) -> *mut ffi::IRInputLayoutDescriptor1 {
+        // Synthesizing ignores the layout, any non-empty JSON yields an empty one
+        if unsafe { CStr::from_ptr(serialized) }.is_empty() {
+            return std::ptr::null_mut();
+        }
+        self.create_handle(ffi::IRInputLayoutDescriptor1 {
+            semanticNames: [std::ptr::null(); 31],
+            inputElementDescs: [unsafe { std::mem::zeroed() }; 31],
+            numElements: 0,
+        })
+    }
+
+    unsafe fn IRInputLayoutDescriptor1Release(
+        &self,
+        input_layout_descriptor: *mut ffi::IRInputLayoutDescriptor1,
+    ) {
+        unsafe { self.destroy_handle::<ffi::IRInputLayoutDescriptor1, _>(input_layout_descriptor) }
+    }
+
+    unsafe fn IRObjectCreateFromDXIL(
+        &self,
+        bytecode: *const u8,
+        size: usize,
+        _bytecode_ownership: ffi::IRBytecodeOwnership,
+    ) -> *mut ffi::IRObject {
+        let bytecode = unsafe { std::slice::from_raw_parts(bytecode, size) };
+        self.create_handle(FakeObject::Dxil(bytecode.to_vec()))
+    }
+
+    unsafe fn IRObjectDestroy(&self, object: *mut ffi::IRObject) {
+        unsafe { self.destroy_handle::<FakeObject, _>(object) }
+    }
+
+    unsafe fn IRObjectGetType(&self, object: *const ffi::IRObject) -> ffi::IRObjectType {
+        match unsafe { &*object.cast::<FakeObject>() } {
+            FakeObject::Dxil(_) => ffi::IRObjectType::DXILBytecode,
+            FakeObject::Compiled(_) => ffi::IRObjectType::MetalIRObject,
+        }
+    }
+
+    unsafe fn IRObjectGetMetalIRShaderStage(
+        &self,
+        object: *const ffi::IRObject,
+    ) -> ffi::IRShaderStage {
+        match unsafe { &*object.cast::<FakeObject>() } {
+            FakeObject::Dxil(_) => ffi::IRShaderStage::Invalid,
+            FakeObject::Compiled(shader) => shader.stage,
+        }
+    }
+
+    unsafe fn IRCompilerSetValidationFlags(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        validation_flags: ffi::IRCompilerValidationFlags,
+    ) {
+        unsafe { settings(compiler).validation_flags = Some(validation_flags) }
+    }
+
+    unsafe fn IRCompilerCreate(&self) -> *mut ffi::IRCompiler {
+        self.create_handle(FakeCompiler {
+            settings: FakeCompilerSettings::default(),
+        })
+    }
+
+    unsafe fn IRCompilerDestroy(&self, compiler: *mut ffi::IRCompiler) {
+        unsafe { self.destroy_handle::<FakeCompiler, _>(compiler) }
+    }
+
+    unsafe fn IRCompilerAllocCompileAndLink(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        entry_point_name: *const c_char,
+        input: *const ffi::IRObject,
+        error: *mut *mut ffi::IRError,
+    ) -> *mut ffi::IRObject {
+        self.compile(compiler, entry_point_name, input, error)
+    }
+
+    unsafe fn IRCompilerAllocCombineCompileAndLink(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        intersection_function_entry_point_name: *const c_char,
+        intersection_function_bytecode: *const ffi::IRObject,
+        _any_hit_function_entry_point_name: *const c_char,
+        _any_hit_function_bytecode: *const ffi::IRObject,
+        error: *mut *mut ffi::IRError,
+    ) -> *mut ffi::IRObject {
+        self.compile(
+            compiler,
+            intersection_function_entry_point_name,
+            intersection_function_bytecode,
+            error,
+        )
+    }
+
+    unsafe fn IRObjectGetMetalLibBinary(
+        &self,
+        obj: *const ffi::IRObject,
+        stage: ffi::IRShaderStage,
+        lib: *mut ffi::IRMetalLibBinary,
+    ) -> bool {
+        match unsafe { &*obj.cast::<FakeObject>() } {
+            FakeObject::Compiled(shader) if shader.stage == stage => {
+                unsafe { *lib.cast::<Vec<u8>>() = shader.metallib.clone() };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    unsafe fn IRCompilerSetStageInGenerationMode(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        stage_in_code_generation_mode: ffi::IRStageInCodeGenerationMode,
+    ) {
+        unsafe { settings(compiler).stage_in_generation_mode = Some(stage_in_code_generation_mode) }
+    }
+
+    unsafe fn IRMetalLibSynthesizeStageInFunction(
+        &self,
+        _compiler: *const ffi::IRCompiler,
+        _vertex_shader_reflection: *const ffi::IRShaderReflection,
+        _layout: *const ffi::IRVersionedInputLayoutDescriptor,
+        binary: *mut ffi::IRMetalLibBinary,
+    ) -> bool {
+        self.synthesize(binary)
+    }
+
+    unsafe fn IRObjectGetReflection(
+        &self,
+        obj: *const ffi::IRObject,
+        stage: ffi::IRShaderStage,
+        reflection: *mut ffi::IRShaderReflection,
+    ) -> bool {
+        match unsafe { &*obj.cast::<FakeObject>() } {
+            FakeObject::Compiled(shader) if shader.stage == stage => {
+                unsafe {
+                    *reflection.cast::<FakeReflection>() = FakeReflection::new(shader.clone())
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    unsafe fn IRCompilerSetGlobalRootSignature(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        _root_signature: *const ffi::IRRootSignature,
+    ) {
+        unsafe { settings(compiler).has_global_root_signature = true }
+    }
+
+    unsafe fn IRCompilerSetLocalRootSignature(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        _root_signature: *const ffi::IRRootSignature,
+    ) {
+        unsafe { settings(compiler).has_local_root_signature = true }
+    }
+
+    unsafe fn IRCompilerSetHitgroupType(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        hit_group_type: ffi::IRHitGroupType,
+    ) {
+        unsafe { settings(compiler).hitgroup_type = Some(hit_group_type) }
+    }
+
+    unsafe fn IRObjectGatherRaytracingIntrinsics(
+        &self,
+        _input: *mut ffi::IRObject,
+        _entry_point: *const c_char,
+    ) -> u64 {
+        0
+    }
+
+    unsafe fn IRCompilerSetRayTracingPipelineArguments(
+        &self,
+        _compiler: *mut ffi::IRCompiler,
+        _max_attribute_size_in_bytes: u32,
+        _raytracing_pipeline_flags: ffi::IRRaytracingPipelineFlags,
+        _chs: u64,
+        _miss: u64,
+        _any_hit: u64,
+        _callable_args: u64,
+        _max_recursive_depth: c_int,
+        _ray_generation_compilation_mode: ffi::IRRayGenerationCompilationMode,
+        _intersection_function_compilation_mode: ffi::IRIntersectionFunctionCompilationMode,
+    ) {
+    }
+
+    unsafe fn IRCompilerSetCompatibilityFlags(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        flags: ffi::IRCompatibilityFlags,
+    ) {
+        unsafe { settings(compiler).compatibility_flags = Some(flags) }
+    }
+
+    unsafe fn IRCompilerSetInputTopology(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        input_topology: ffi::IRInputTopology,
+    ) {
+        unsafe { settings(compiler).input_topology = Some(input_topology) }
+    }
+
+    unsafe fn IRCompilerEnableGeometryAndTessellationEmulation(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        enable: bool,
+    ) {
+        unsafe { settings(compiler).geometry_and_tessellation_emulation = Some(enable) }
+    }
+
+    unsafe fn IRCompilerSetDualSourceBlendingConfiguration(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        configuration: ffi::IRDualSourceBlendingConfiguration,
+    ) {
+        unsafe { settings(compiler).dual_source_blending_configuration = Some(configuration) }
+    }
+
+    unsafe fn IRCompilerSetDepthFeedbackConfiguration(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        configuration: ffi::IRDepthFeedbackConfiguration,
+    ) {
+        unsafe { settings(compiler).depth_feedback_configuration = Some(configuration) }
+    }
+
+    unsafe fn IRCompilerSetIntRTMask(&self, compiler: *mut ffi::IRCompiler, int_rt_mask: u8) {
+        unsafe { settings(compiler).int_rt_mask = Some(int_rt_mask) }
+    }
+
+    unsafe fn IRMetalLibSynthesizeIndirectRayDispatchFunction(
+        &self,
+        _compiler: *const ffi::IRCompiler,
+        binary: *mut ffi::IRMetalLibBinary,
+    ) -> bool {
+        self.synthesize(binary)
+    }
+
+    unsafe fn IRMetalLibSynthesizeIndirectIntersectionFunction(
+        &self,
+        _compiler: *const ffi::IRCompiler,
+        binary: *mut ffi::IRMetalLibBinary,
+    ) -> bool {
+        self.synthesize(binary)
+    }
+
+    unsafe fn IRCompilerSetEntryPointName(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        new_name: *const c_char,
+    ) {
+        let new_name = unsafe { CStr::from_ptr(new_name) }.to_string_lossy();
+        unsafe { settings(compiler).entry_point_name = Some(new_name.into_owned()) }
+    }
+
+    unsafe fn IRCompilerSetMinimumGPUFamily(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        family: ffi::IRGPUFamily,
+    ) {
+        unsafe { settings(compiler).minimum_gpu_family = Some(family) }
+    }
+
+    unsafe fn IRCompilerIgnoreRootSignature(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        ignore_embedded_root_signature: bool,
+    ) {
+        unsafe { settings(compiler).ignore_root_signature = Some(ignore_embedded_root_signature) }
+    }
+
+    unsafe fn IRCompilerIgnoreDebugInformation(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        ignore_debug_information: bool,
+    ) {
+        unsafe { settings(compiler).ignore_debug_information = Some(ignore_debug_information) }
+    }
+
+    unsafe fn IRCompilerSetMinimumDeploymentTarget(
+        &self,
+        compiler: *mut ffi::IRCompiler,
+        operating_system: ffi::IROperatingSystem,
+        version: *const c_char,
+    ) {
+        let version = unsafe { CStr::from_ptr(version) }.to_string_lossy();
+        unsafe {
+            settings(compiler).minimum_deployment_target =
+                Some((operating_system, version.into_owned()))
+        }
+    }
+
+    unsafe fn IRMetalLibBinaryCreate(&self) -> *mut ffi::IRMetalLibBinary {
+        self.create_handle(Vec::<u8>::new())
+    }
+
+    unsafe fn IRMetalLibBinaryDestroy(&self, lib: *mut ffi::IRMetalLibBinary) {
+        unsafe { self.destroy_handle::<Vec<u8>, _>(lib) }
+    }
+
+    unsafe fn IRMetalLibGetBytecode(
+        &self,
+        lib: *const ffi::IRMetalLibBinary,
+        out_bytecode: *mut u8,
+    ) -> usize {
+        let bytes = unsafe { &*lib.cast::<Vec<u8>>() };
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), out_bytecode, bytes.len()) };
+        bytes.len()
+    }
+
+    unsafe fn IRMetalLibGetBytecodeSize(&self, lib: *const ffi::IRMetalLibBinary) -> usize {
+        unsafe { (*lib.cast::<Vec<u8>>()).len() }
+    }
+
+    unsafe fn IRShaderReflectionCreate(&self) -> *mut ffi::IRShaderReflection {
+        self.create_handle(FakeReflection::default())
+    }
+
+    unsafe fn IRShaderReflectionDestroy(&self, reflection: *mut ffi::IRShaderReflection) {
+        unsafe { self.destroy_handle::<FakeReflection, _>(reflection) }
+    }
+
+    unsafe fn IRShaderReflectionCopyJSONString(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+    ) -> *const c_char {
+        let json = (unsafe { reflected(reflection) }).map_or("{}", |s| &s.reflection_json);
+        c_string(json).into_raw()
+    }
+
+    unsafe fn IRShaderReflectionReleaseString(&self, serialized: *const c_char) {
+        drop(unsafe { CString::from_raw(serialized.cast_mut()) })
+    }
+
+    unsafe fn IRShaderReflectionCreateFromJSON(
+        &self,
+        json: *const c_char,
+    ) -> *mut ffi::IRShaderReflection {
+        let json = unsafe { CStr::from_ptr(json) }.to_string_lossy();
+        if json.trim().is_empty() {
+            return std::ptr::null_mut();
+        }
+        // JSON of a scripted shader parses back into that shader, any other JSON survives the
+        // round trip but reports no info
+        let shader = self.scripted_shader(&json).unwrap_or_else(|| {
+            let mut shader = FakeShader::new(ffi::IRShaderStage::Invalid, Vec::new());
+            shader.reflection_json = json.into_owned();
+            shader
+        });
+        self.create_handle(FakeReflection::new(Box::new(shader)))
+    }
+
+    unsafe fn IRShaderReflectionCopyComputeInfo(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        version: ffi::IRReflectionVersion,
+        csinfo: *mut ffi::IRVersionedCSInfo,
+    ) -> bool {
+        let Some(info_1_0) = (unsafe { reflected(reflection) }).and_then(|s| s.compute_info) else {
+            return false;
+        };
+        let u_1 = ffi::IRVersionedCSInfo_u { info_1_0 };
+        unsafe { csinfo.write(ffi::IRVersionedCSInfo { version, u_1 }) };
+        true
+    }
+
+    unsafe fn IRShaderReflectionCopyVertexInfo(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        version: ffi::IRReflectionVersion,
+        vsinfo: *mut ffi::IRVersionedVSInfo,
+    ) -> bool {
+        let Some(info) = (unsafe { reflected(reflection) }).and_then(|s| s.vertex_info.as_ref())
+        else {
+            return false;
+        };
+        // Names and the input array are owned by the copied info, and freed again in
+        // `IRShaderReflectionReleaseVertexInfo()`
+        let vertex_inputs = info
+            .vertex_inputs
+            .iter()
+            .map(|(name, attribute_index)| ffi::IRVertexInputInfo_1_0 {
+                name: CString::new(name.as_str())
+                    .expect("Vertex input name contains NUL")
+                    .into_raw(),
+                attributeIndex: *attribute_index,
+            })
+            .collect::<Box<[_]>>();
+        let num_vertex_inputs = vertex_inputs.len();
+        let info_1_0 = ffi::IRVSInfo_1_0 {
+            instance_id_index: info.instance_id_index,
+            vertex_id_index: info.vertex_id_index,
+            vertex_output_size_in_bytes: info.vertex_output_size_in_bytes,
+            needs_draw_params: info.needs_draw_params,
+            vertex_inputs: Box::into_raw(vertex_inputs).cast(),
+            num_vertex_inputs,
+        };
+        let u_1 = ffi::IRVersionedVSInfo_u { info_1_0 };
+        unsafe { vsinfo.write(ffi::IRVersionedVSInfo { version, u_1 }) };
+        true
+    }
+
+    unsafe fn IRShaderReflectionCopyFragmentInfo(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        version: ffi::IRReflectionVersion,
+        fsinfo: *mut ffi::IRVersionedFSInfo,
+    ) -> bool {
+        let Some(info_1_0) = (unsafe { reflected(reflection) }).and_then(|s| s.fragment_info)
+        else {
+            return false;
+        };
+        let u_1 = ffi::IRVersionedFSInfo_u { info_1_0 };
+        unsafe { fsinfo.write(ffi::IRVersionedFSInfo { version, u_1 }) };
+        true
+    }
+
+    unsafe fn IRShaderReflectionCopyGeometryInfo(
+        &self,
+        _reflection: *const ffi::IRShaderReflection,
+        _version: ffi::IRReflectionVersion,
+        _gsinfo: *mut ffi::IRVersionedGSInfo,
+    ) -> bool {
+        false
+    }
+
+    unsafe fn IRShaderReflectionCopyHullInfo(
+        &self,
+        _reflection: *const ffi::IRShaderReflection,
+        _version: ffi::IRReflectionVersion,
+        _hsinfo: *mut ffi::IRVersionedHSInfo,
+    ) -> bool {
+        false
+    }
+
+    unsafe fn IRShaderReflectionCopyDomainInfo(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        version: ffi::IRReflectionVersion,
+        dsinfo: *mut ffi::IRVersionedDSInfo,
+    ) -> bool {
+        let Some(info_1_0) = (unsafe { reflected(reflection) }).and_then(|s| s.domain_info) else {
+            return false;
+        };
+        let u_1 = ffi::IRVersionedDSInfo_u { info_1_0 };
+        unsafe { dsinfo.write(ffi::IRVersionedDSInfo { version, u_1 }) };
+        true
+    }
+
+    unsafe fn IRShaderReflectionCopyMeshInfo(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        version: ffi::IRReflectionVersion,
+        msinfo: *mut ffi::IRVersionedMSInfo,
+    ) -> bool {
+        let Some(info_1_0) = (unsafe { reflected(reflection) }).and_then(|s| s.mesh_info) else {
+            return false;
+        };
+        let u_1 = ffi::IRVersionedMSInfo_u { info_1_0 };
+        unsafe { msinfo.write(ffi::IRVersionedMSInfo { version, u_1 }) };
+        true
+    }
+
+    unsafe fn IRShaderReflectionCopyAmplificationInfo(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        version: ffi::IRReflectionVersion,
+        asinfo: *mut ffi::IRVersionedASInfo,
+    ) -> bool {
+        let Some(info_1_0) = (unsafe { reflected(reflection) }).and_then(|s| s.amplification_info)
+        else {
+            return false;
+        };
+        let u_1 = ffi::IRVersionedASInfo_u { info_1_0 };
+        unsafe { asinfo.write(ffi::IRVersionedASInfo { version, u_1 }) };
+        true
+    }
+
+    unsafe fn IRShaderReflectionCopyRaytracingInfo(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        version: ffi::IRReflectionVersion,
+        rtinfo: *mut ffi::IRVersionedRTInfo,
+    ) -> bool {
+        let Some(info_1_0) = (unsafe { reflected(reflection) }).and_then(|s| s.raytracing_info)
+        else {
+            return false;
+        };
+        let u_1 = ffi::IRVersionedRTInfo_u { info_1_0 };
+        unsafe { rtinfo.write(ffi::IRVersionedRTInfo { version, u_1 }) };
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseComputeInfo(
+        &self,
+        _csinfo: *mut ffi::IRVersionedCSInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseVertexInfo(
+        &self,
+        vsinfo: *mut ffi::IRVersionedVSInfo,
+    ) -> bool {
+        let info = unsafe { (*vsinfo).u_1.info_1_0 };
+        let vertex_inputs = unsafe {
+            Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                info.vertex_inputs,
+                info.num_vertex_inputs,
+            ))
+        };
+        for input in vertex_inputs.iter() {
+            drop(unsafe { CString::from_raw(input.name.cast_mut()) });
+        }
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseFragmentInfo(
+        &self,
+        _fsinfo: *mut ffi::IRVersionedFSInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseGeometryInfo(
+        &self,
+        _gsinfo: *mut ffi::IRVersionedGSInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseHullInfo(
+        &self,
+        _hsinfo: *mut ffi::IRVersionedHSInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseDomainInfo(
+        &self,
+        _dsinfo: *mut ffi::IRVersionedDSInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseMeshInfo(
+        &self,
+        _msinfo: *mut ffi::IRVersionedMSInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseAmplificationInfo(
+        &self,
+        _asinfo: *mut ffi::IRVersionedASInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionReleaseRaytracingInfo(
+        &self,
+        _rtinfo: *mut ffi::IRVersionedRTInfo,
+    ) -> bool {
+        true
+    }
+
+    unsafe fn IRShaderReflectionGetEntryPointFunctionName(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+    ) -> *const c_char {
+        let reflection = unsafe { &*reflection.cast::<FakeReflection>() };
+        reflection
+            .entry_point
+            .as_ref()
+            .map_or(std::ptr::null(), |n| n.as_ptr())
+    }
+
+    unsafe fn IRShaderReflectionNeedsFunctionConstants(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+    ) -> bool {
+        (unsafe { reflected(reflection) }).is_some_and(|s| !s.function_constants.is_empty())
+    }
+
+    unsafe fn IRShaderReflectionGetFunctionConstantCount(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+    ) -> usize {
+        (unsafe { reflected(reflection) }).map_or(0, |s| s.function_constants.len())
+    }
+
+    unsafe fn IRShaderReflectionCopyFunctionConstants(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        function_constants: *mut ffi::IRFunctionConstant,
+    ) {
+        let Some(shader) = (unsafe { reflected(reflection) }) else {
+            return;
+        };
+        for (i, (name, type_)) in shader.function_constants.iter().enumerate() {
+            // Freed by `IRShaderReflectionReleaseFunctionConstants()`
+            let constant = ffi::IRFunctionConstant {
+                name: c_string(name).into_raw(),
+                type_: *type_,
+            };
+            unsafe { function_constants.add(i).write(constant) };
+        }
+    }
+
+    unsafe fn IRShaderReflectionReleaseFunctionConstants(
+        &self,
+        function_constants: *mut ffi::IRFunctionConstant,
+        function_constant_count: usize,
+    ) {
+        for i in 0..function_constant_count {
+            let name = unsafe { (*function_constants.add(i)).name };
+            drop(unsafe { CString::from_raw(name.cast_mut()) })
+        }
+    }
+
+    unsafe fn IRShaderReflectionGetResourceCount(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+    ) -> usize {
+        (unsafe { reflected(reflection) }).map_or(0, |s| s.resources.len())
+    }
+
+    unsafe fn IRShaderReflectionGetResourceLocations(
+        &self,
+        reflection: *const ffi::IRShaderReflection,
+        resource_locations: *mut ffi::IRResourceLocation,
+    ) {
+        let reflection = unsafe { &*reflection.cast::<FakeReflection>() };
+        if let Some(shader) = &reflection.shader {
+            unsafe {
+                write_resource_locations(
+                    &shader.resources,
+                    &reflection.resource_names,
+                    resource_locations,
+                )
+            }
+        }
+    }
+
+    unsafe fn IRRootSignatureGetResourceCount(
+        &self,
+        root_signature: *const ffi::IRRootSignature,
+    ) -> usize {
+        unsafe {
+            (*root_signature.cast::<FakeRootSignature>())
+                .resources
+                .len()
+        }
+    }
+
+    unsafe fn IRRootSignatureGetResourceLocations(
+        &self,
+        root_signature: *const ffi::IRRootSignature,
+        resource_locations: *mut ffi::IRResourceLocation,
+    ) {
+        let root_signature = unsafe { &*root_signature.cast::<FakeRootSignature>() };
+        unsafe {
+            write_resource_locations(
+                &root_signature.resources,
+                &root_signature.names,
+                resource_locations,
+            )
+        }
+    }
+}
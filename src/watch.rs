@@ -0,0 +1,344 @@
+//! Recompiles DXIL files whenever they change on disk, for hot-reloading shaders during
+//! development.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use saxaboom::{
+//!     ffi,
+//!     watch::{WatchEvent, Watched, Watcher, WatcherConfig},
+//!     MetalIrConverter,
+//! };
+//!
+//! let converter = MetalIrConverter::discover().unwrap();
+//! let watcher = Watcher::spawn(
+//!     converter,
+//!     // Every `.dxil` file in `shaders/`, which may be rewritten by a DXC watcher
+//!     vec![Watched::new("shaders", c"main")
+//!         .configure(|compiler| compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7))],
+//!     WatcherConfig::default(),
+//! );
+//!
+//! loop {
+//!     match watcher.events().recv_timeout(Duration::from_millis(16)) {
+//!         Ok(WatchEvent::Reloaded { path, shader }) => { /* Recreate pipelines from shader.metallib */ }
+//!         // The previous metallib of `path` stays current
+//!         Ok(WatchEvent::Failed { path, error, .. }) => eprintln!("{}: {error}", path.display()),
+//!         Err(_) => {}
+//!     }
+//! }
+//! ```
+//!
+//! Changes are detected by polling the modification time and size of every watched file.
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{ffi, Error, IRCompiler, MetalIrConverter};
+
+type Configure = dyn Fn(&mut IRCompiler) + Send + Sync;
+
+/// A DXIL file, or a directory of which every `.dxil` file is watched, see [`Watcher::spawn()`].
+#[derive(Clone)]
+pub struct Watched {
+    pub path: PathBuf,
+    pub entry_point: CString,
+    configure: Option<Arc<Configure>>,
+}
+
+impl Watched {
+    pub fn new(path: impl Into<PathBuf>, entry_point: &CStr) -> Self {
+        Self {
+            path: path.into(),
+            entry_point: entry_point.to_owned(),
+            configure: None,
+        }
+    }
+
+    /// Settings to apply to the fresh [`IRCompiler`] of every recompilation, such as a root
+    /// signature or [`IRCompiler::set_minimum_gpu_family()`].
+    pub fn configure(
+        mut self,
+        configure: impl Fn(&mut IRCompiler) + Send + Sync + 'static,
+    ) -> Self {
+        self.configure = Some(Arc::new(configure));
+        self
+    }
+}
+
+/// Output of a successful (re)compilation.
+#[derive(Clone, Debug)]
+pub struct CompiledShader {
+    pub stage: ffi::IRShaderStage,
+    pub metallib: Vec<u8>,
+    /// [`None`] when the loaded library cannot serialize reflection, see
+    /// [`MetalIrConverter::supports_json_reflection()`].
+    pub reflection_json: Option<String>,
+}
+
+/// Delivered on [`Watcher::events()`] after every (re)compilation.
+#[derive(Debug)]
+pub enum WatchEvent {
+    Reloaded {
+        path: PathBuf,
+        shader: Arc<CompiledShader>,
+    },
+    /// [`Watcher::current()`] keeps returning `previous`.
+    Failed {
+        path: PathBuf,
+        error: Error,
+        previous: Option<Arc<CompiledShader>>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WatcherConfig {
+    /// How often to check the watched files for changes.
+    pub poll_interval: Duration,
+    /// How long a file must remain unchanged before it is recompiled, so that a file that is
+    /// still being written is compiled once.
+    pub debounce: Duration,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(50),
+            debounce: Duration::from_millis(150),
+        }
+    }
+}
+
+type Current = Arc<Mutex<HashMap<PathBuf, Arc<CompiledShader>>>>;
+
+/// Background thread that compiles every watched file once, and again after every change.
+/// Stops when dropped.
+pub struct Watcher {
+    events: mpsc::Receiver<WatchEvent>,
+    current: Current,
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+    /// Of watchers created with [`Watcher::manual()`].
+    poller: Option<Mutex<Poller>>,
+}
+
+impl Watcher {
+    pub fn spawn(
+        converter: MetalIrConverter,
+        watched: Vec<Watched>,
+        config: WatcherConfig,
+    ) -> Self {
+        let (sender, events) = mpsc::channel();
+        let current = Current::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let current = current.clone();
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("saxaboom-watch".to_owned())
+                .spawn(move || {
+                    let mut poller = Poller::new(converter, watched, config, sender, current);
+                    while !stop.load(Ordering::Relaxed) && poller.poll(Instant::now()) {
+                        thread::sleep(config.poll_interval);
+                    }
+                })
+                .expect("Failed to spawn the saxaboom-watch thread")
+        };
+        Self {
+            events,
+            current,
+            stop,
+            thread: Some(thread),
+            poller: None,
+        }
+    }
+
+    /// A watcher without a background thread, that only looks for changes when [`Self::poll()`]
+    /// is called, such as once per frame.  [`WatcherConfig::poll_interval`] is unused.
+    pub fn manual(
+        converter: MetalIrConverter,
+        watched: Vec<Watched>,
+        config: WatcherConfig,
+    ) -> Self {
+        let (sender, events) = mpsc::channel();
+        let current = Current::default();
+        let poller = Poller::new(converter, watched, config, sender, current.clone());
+        Self {
+            events,
+            current,
+            stop: Arc::default(),
+            thread: None,
+            poller: Some(Mutex::new(poller)),
+        }
+    }
+
+    /// Looks for changes as if the time is `now`, and compiles the files whose last change is at
+    /// least [`WatcherConfig::debounce`] earlier.  Their events are available from
+    /// [`Self::events()`] when this returns.
+    ///
+    /// Does nothing for watchers created with [`Self::spawn()`], which poll on their own thread.
+    pub fn poll(&self, now: Instant) {
+        if let Some(poller) = &self.poller {
+            lock(poller).poll(now);
+        }
+    }
+
+    pub fn events(&self) -> &mpsc::Receiver<WatchEvent> {
+        &self.events
+    }
+
+    /// Latest successful compilation of the DXIL file at `path`, as reported in
+    /// [`WatchEvent::Reloaded`].
+    pub fn current(&self, path: &Path) -> Option<Arc<CompiledShader>> {
+        lock(&self.current).get(path).cloned()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            // A panic on the watcher thread has already been reported
+            let _ = thread.join();
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // The map stays consistent even if a thread panicked while holding it
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+struct Poller {
+    converter: MetalIrConverter,
+    watched: Vec<Watched>,
+    config: WatcherConfig,
+    sender: mpsc::Sender<WatchEvent>,
+    current: Current,
+    /// Modification time and size of every file when last polled.
+    seen: HashMap<PathBuf, (Option<SystemTime>, u64)>,
+    /// Files that changed, with the time of their last change unless they should be compiled
+    /// without debouncing.
+    pending: HashMap<PathBuf, Option<Instant>>,
+    first_poll: bool,
+}
+
+impl Poller {
+    fn new(
+        converter: MetalIrConverter,
+        watched: Vec<Watched>,
+        config: WatcherConfig,
+        sender: mpsc::Sender<WatchEvent>,
+        current: Current,
+    ) -> Self {
+        Self {
+            converter,
+            watched,
+            config,
+            sender,
+            current,
+            seen: HashMap::new(),
+            pending: HashMap::new(),
+            first_poll: true,
+        }
+    }
+
+    /// Returns `false` once nobody is listening anymore.
+    fn poll(&mut self, now: Instant) -> bool {
+        let mut files = HashMap::new();
+        for (i, watched) in self.watched.iter().enumerate() {
+            if watched.path.is_dir() {
+                let Ok(entries) = fs::read_dir(&watched.path) else {
+                    continue;
+                };
+                for path in entries.flatten().map(|entry| entry.path()) {
+                    if path.extension().is_some_and(|e| e == "dxil") {
+                        files.entry(path).or_insert(i);
+                    }
+                }
+            } else {
+                files.entry(watched.path.clone()).or_insert(i);
+            }
+        }
+
+        self.seen.retain(|path, _| files.contains_key(path));
+        self.pending.retain(|path, _| files.contains_key(path));
+        for path in files.keys() {
+            let Ok(metadata) = fs::metadata(path) else {
+                continue;
+            };
+            let stamp = (metadata.modified().ok(), metadata.len());
+            if self.seen.insert(path.clone(), stamp) != Some(stamp) {
+                // Files that exist when the watcher starts are compiled right away
+                let changed = (!self.first_poll).then_some(now);
+                self.pending.insert(path.clone(), changed);
+            }
+        }
+
+        let mut ready = self
+            .pending
+            .iter()
+            .filter(|(_, changed)| {
+                changed.map_or(true, |c| {
+                    now.saturating_duration_since(c) >= self.config.debounce
+                })
+            })
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        ready.sort();
+        for path in ready {
+            self.pending.remove(&path);
+            let watched = &self.watched[files[&path]];
+            let event = match self.compile(watched, &path) {
+                Ok(shader) => {
+                    let shader = Arc::new(shader);
+                    lock(&self.current).insert(path.clone(), shader.clone());
+                    WatchEvent::Reloaded { path, shader }
+                }
+                Err(error) => WatchEvent::Failed {
+                    previous: lock(&self.current).get(&path).cloned(),
+                    path,
+                    error,
+                },
+            };
+            if self.sender.send(event).is_err() {
+                return false;
+            }
+        }
+        self.first_poll = false;
+        true
+    }
+
+    fn compile(&self, watched: &Watched, path: &Path) -> Result<CompiledShader, Error> {
+        let dxil = fs::read(path).map_err(|source| Error::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        let mut compiler = self.converter.create_compiler();
+        if let Some(configure) = &watched.configure {
+            configure(&mut compiler);
+        }
+        let mut object = self.converter.create_object_from_dxil(&dxil);
+        object.set_name(path.display().to_string());
+        let object = compiler.alloc_compile_and_link(&watched.entry_point, &object)?;
+        let reflection_json = if self.converter.supports_json_reflection() {
+            Some(object.reflection()?.json()?)
+        } else {
+            None
+        };
+        Ok(CompiledShader {
+            stage: object.metal_ir_shader_stage(),
+            metallib: object.metal_lib_binary()?.byte_code(),
+            reflection_json,
+        })
+    }
+}
@@ -0,0 +1,294 @@
+//! Writes bundles and reflection snapshots with [`saxaboom::bundle`] and
+//! [`saxaboom::reflection`], and reads them back with the readers of `saxaboom-runtime`.
+#![cfg(feature = "testing")]
+use std::{fs, path::PathBuf};
+
+use saxaboom::{
+    bundle::BundleWriter,
+    ffi,
+    permutations::{Permutations, Switch},
+    reflection::ReflectionSnapshot,
+    testing::{FakeConverter, FakeResource, FakeShader, FakeVertexInfo},
+    Error, MetalIrConverter,
+};
+use saxaboom_runtime::{bundle, reflection};
+
+fn vertex_shader() -> FakeShader {
+    let mut shader = FakeShader::new(ffi::IRShaderStage::Vertex, b"vertex metallib".to_vec());
+    shader.vertex_info = Some(FakeVertexInfo {
+        instance_id_index: -1,
+        vertex_id_index: 2,
+        vertex_output_size_in_bytes: 32,
+        needs_draw_params: true,
+        vertex_inputs: vec![("POSITION0".to_owned(), 0), ("TEXCOORD0".to_owned(), 1)],
+    });
+    shader.function_constants = vec![("USE_FOG".to_owned(), ffi::IRFunctionConstantType::Bool)];
+    shader.resources = vec![
+        FakeResource {
+            resource_type: ffi::IRResourceType::CBV,
+            space: 0,
+            slot: 1,
+            top_level_offset: 8,
+            size_bytes: 8,
+            name: Some("Constants".to_owned()),
+        },
+        FakeResource {
+            resource_type: ffi::IRResourceType::Sampler,
+            space: 2,
+            slot: 0,
+            top_level_offset: 16,
+            size_bytes: 24,
+            name: None,
+        },
+    ];
+    shader
+}
+
+fn snapshot(shader: FakeShader) -> ReflectionSnapshot {
+    let fake = FakeConverter::default();
+    fake.on_any(Ok(shader));
+    let converter = MetalIrConverter::from_backend(fake);
+    let object = converter.create_object_from_dxil(b"DXBC");
+    let compiled = converter
+        .create_compiler()
+        .alloc_compile_and_link(c"main", &object)
+        .unwrap();
+    compiled.reflection().unwrap().snapshot()
+}
+
+#[test]
+fn reflection_round_trips() {
+    let snapshot = snapshot(vertex_shader());
+    assert_eq!(snapshot.entry_point.as_deref(), Some("main"));
+    assert!(snapshot.needs_function_constants);
+    assert_eq!(snapshot.threadgroup_size, None);
+    assert_eq!(snapshot.resources[0].name.as_deref(), Some("Constants"));
+    assert_eq!(
+        snapshot.vertex.as_ref().unwrap().inputs[1].name,
+        "TEXCOORD0"
+    );
+
+    let read = reflection::ReflectionSnapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+    assert_eq!(read.entry_point.as_deref(), Some("main"));
+    assert!(read.needs_function_constants);
+    assert_eq!(
+        read.function_constants,
+        [reflection::FunctionConstant {
+            name: "USE_FOG".to_owned(),
+            r#type: reflection::FunctionConstantType::Bool,
+        }]
+    );
+    assert_eq!(
+        read.resources,
+        [
+            reflection::Resource {
+                name: Some("Constants".to_owned()),
+                resource_type: reflection::ResourceType::Cbv,
+                space: 0,
+                slot: 1,
+                top_level_offset: 8,
+                size_bytes: 8,
+            },
+            reflection::Resource {
+                name: None,
+                resource_type: reflection::ResourceType::Sampler,
+                space: 2,
+                slot: 0,
+                top_level_offset: 16,
+                size_bytes: 24,
+            },
+        ]
+    );
+    assert_eq!(read.threadgroup_size, None);
+    assert_eq!(
+        read.vertex,
+        Some(reflection::VertexInfo {
+            instance_id_index: -1,
+            vertex_id_index: 2,
+            vertex_output_size_in_bytes: 32,
+            needs_draw_params: true,
+            inputs: vec![
+                reflection::VertexInput {
+                    name: "POSITION0".to_owned(),
+                    attribute_index: 0,
+                },
+                reflection::VertexInput {
+                    name: "TEXCOORD0".to_owned(),
+                    attribute_index: 1,
+                },
+            ],
+        })
+    );
+    assert_eq!(read.fragment, None);
+
+    let mut compute = FakeShader::new(ffi::IRShaderStage::Compute, Vec::new());
+    compute.compute_info = Some(ffi::IRCSInfo_1_0 { tg_size: [8, 8, 1] });
+    let bytes = self::snapshot(compute).to_bytes();
+    let read = reflection::ReflectionSnapshot::from_bytes(&bytes).unwrap();
+    assert_eq!(read.threadgroup_size, Some([8, 8, 1]));
+    assert_eq!(read.vertex, None);
+
+    for len in 0..bytes.len() {
+        assert!(reflection::ReflectionSnapshot::from_bytes(&bytes[..len]).is_err());
+    }
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(reflection::ReflectionSnapshot::from_bytes(&trailing).is_err());
+}
+
+fn permutation_table() -> saxaboom::permutations::PermutationTable {
+    let permutations = Permutations::new(vec![Switch::bool("ALPHA_TEST")]);
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("bundle_permutations");
+    fs::create_dir_all(&dir).unwrap();
+    for key in permutations.keys() {
+        fs::write(dir.join(format!("{key}.dxil")), key.to_string()).unwrap();
+    }
+
+    let fake = FakeConverter::default();
+    fake.on_compilation(|compilation| {
+        let mut shader = FakeShader::new(ffi::IRShaderStage::Fragment, compilation.dxil.clone());
+        shader.fragment_info = Some(ffi::IRFSInfo_1_0 {
+            num_render_targets: 1,
+            rt_index_int: 0,
+            discards: compilation.dxil.ends_with(b"1"),
+        });
+        Some(Ok(shader))
+    });
+    let converter = MetalIrConverter::from_backend(fake);
+    converter
+        .create_compiler()
+        .alloc_compile_and_link_permutations(c"main", &permutations, |key| {
+            dir.join(format!("{key}.dxil"))
+        })
+        .unwrap()
+}
+
+fn bundle_bytes() -> Vec<u8> {
+    let mut writer = BundleWriter::new();
+    writer
+        .add_root_signature("global", b"{\"RootSignature\": {}}".to_vec())
+        .unwrap()
+        .add_permutations("material", &permutation_table(), Some("global"))
+        .unwrap()
+        .add_shader(
+            "fullscreen",
+            None,
+            b"fullscreen metallib".to_vec(),
+            &snapshot(vertex_shader()),
+            None,
+        )
+        .unwrap();
+    writer.write()
+}
+
+#[test]
+fn bundle_round_trips() {
+    let bytes = bundle_bytes();
+    let bundle = bundle::Bundle::parse(&bytes).unwrap();
+    assert_eq!(bundle.version, bundle::VERSION);
+
+    let shaders = bundle
+        .shaders
+        .iter()
+        .map(|s| (s.name, s.permutation))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        shaders,
+        [
+            ("fullscreen", ""),
+            ("material", "ALPHA_TEST=0"),
+            ("material", "ALPHA_TEST=1"),
+        ]
+    );
+
+    let fullscreen = bundle.get("fullscreen", "").unwrap();
+    assert_eq!(fullscreen.metallib, b"fullscreen metallib");
+    assert_eq!(fullscreen.root_signature, None);
+    let reflection = fullscreen.reflection().unwrap();
+    assert_eq!(reflection.vertex.unwrap().inputs.len(), 2);
+
+    let discarding = bundle.get("material", "ALPHA_TEST=1").unwrap();
+    assert_eq!(discarding.metallib, b"ALPHA_TEST=1");
+    assert!(discarding.reflection().unwrap().fragment.unwrap().discards);
+    let root_signature = discarding.root_signature.unwrap();
+    assert_eq!(root_signature.name, "global");
+    assert_eq!(root_signature.serialized, b"{\"RootSignature\": {}}");
+    assert_eq!(bundle.root_signature("global"), Some(&root_signature));
+
+    assert_eq!(bundle.permutations("material").len(), 2);
+    assert!(bundle.permutations("missing").is_empty());
+    assert!(bundle.get("material", "").is_none());
+
+    // Every item is borrowed from the bundle and aligned
+    for shader in &bundle.shaders {
+        let offset = shader.metallib.as_ptr() as usize - bytes.as_ptr() as usize;
+        assert!(offset + shader.metallib.len() <= bytes.len());
+        assert_eq!(offset % saxaboom::bundle::ALIGNMENT, 0);
+    }
+}
+
+#[test]
+fn bundle_rejects_invalid() {
+    let bytes = bundle_bytes();
+
+    let mut newer = bytes.clone();
+    newer[4..8].copy_from_slice(&(bundle::VERSION + 1).to_le_bytes());
+    let error = bundle::Bundle::parse(&newer).unwrap_err();
+    assert_eq!(
+        error,
+        saxaboom_runtime::FormatError::UnsupportedVersion {
+            format: "bundle",
+            version: bundle::VERSION + 1,
+            supported: bundle::VERSION,
+        }
+    );
+    assert!(error.to_string().contains("newer tools"));
+
+    assert!(matches!(
+        bundle::Bundle::parse(b"MTLB"),
+        Err(saxaboom_runtime::FormatError::Invalid { .. })
+    ));
+    for len in 0..bytes.len() {
+        if let Ok(bundle) = bundle::Bundle::parse(&bytes[..len]) {
+            panic!("parsed {len} bytes into {} shaders", bundle.shaders.len());
+        }
+    }
+    for i in 0..bytes.len() {
+        let mut corrupt = bytes.clone();
+        corrupt[i] ^= 0xff;
+        if let Ok(bundle) = bundle::Bundle::parse(&corrupt) {
+            for shader in &bundle.shaders {
+                let _ = shader.reflection();
+            }
+        }
+    }
+}
+
+#[test]
+fn writer_rejects_invalid() {
+    let reflection = ReflectionSnapshot::default();
+    let mut writer = BundleWriter::new();
+    writer
+        .add_root_signature("global", Vec::new())
+        .unwrap()
+        .add_shader("blit", None, Vec::new(), &reflection, Some("global"))
+        .unwrap();
+
+    let reason = |result: Result<&mut BundleWriter, Error>| match result {
+        Err(Error::Bundle { reason }) => reason,
+        Err(e) => panic!("unexpected error {e:?}"),
+        Ok(_) => panic!("added an invalid item"),
+    };
+    assert_eq!(
+        reason(writer.add_root_signature("global", Vec::new())),
+        "duplicate root signature `global`"
+    );
+    assert_eq!(
+        reason(writer.add_shader("blit", None, Vec::new(), &reflection, None)),
+        "duplicate shader `blit` with permutation ``"
+    );
+    assert_eq!(
+        reason(writer.add_shader("copy", None, Vec::new(), &reflection, Some("local"))),
+        "unknown root signature `local`"
+    );
+}
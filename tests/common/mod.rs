@@ -0,0 +1,70 @@
+//! Helpers shared by the tests that convert with a [`FakeConverter`].
+// Every test crate compiles this module, but not every test uses every helper
+#![allow(dead_code)]
+use saxaboom::{
+    ffi,
+    metallib::{self, Function, FunctionOffsets, FunctionType, MetalLib, MetalLibWriter, Tag},
+    testing::{FakeCompilation, FakeConverter, FakeError, FakeShader},
+    MetalIrConverter,
+};
+
+/// Converts every compilation with `convert`, and returns a clone of the fake to inspect its
+/// [`FakeConverter::compilations()`].
+pub fn converter(
+    convert: impl Fn(&FakeCompilation) -> Result<FakeShader, FakeError> + Send + Sync + 'static,
+) -> (FakeConverter, MetalIrConverter) {
+    let fake = FakeConverter::default();
+    fake.on_compilation(move |compilation| Some(convert(compilation)));
+    (fake.clone(), MetalIrConverter::from_backend(fake))
+}
+
+/// Compilation failure for an instruction that `metal_irconverter` does not support.
+pub fn unsupported(message: &str) -> FakeError {
+    FakeError::new(ffi::IRErrorCode::UnsupportedInstruction, message)
+}
+
+/// `metallib` with an empty function of every name and type, written by [`MetalLibWriter`].
+pub fn metallib(functions: &[(&str, FunctionType)]) -> Vec<u8> {
+    let empty = MetalLibWriter::new().write();
+    let mut library = MetalLib::parse(&empty).unwrap();
+    let types = functions
+        .iter()
+        .map(|(_, ty)| [ty.to_raw()])
+        .collect::<Vec<_>>();
+    library.functions = functions
+        .iter()
+        .zip(&types)
+        .map(|((name, _), ty)| Function {
+            name: (*name).to_owned(),
+            function_type: None,
+            hash: None,
+            offsets: FunctionOffsets::default(),
+            version: None,
+            tags: vec![
+                // Both rewritten by the writer
+                Tag {
+                    fourcc: metallib::NAME,
+                    data: b"\0",
+                },
+                Tag {
+                    fourcc: metallib::OFFSETS,
+                    data: &[0; 24],
+                },
+                Tag {
+                    fourcc: metallib::TYPE,
+                    data: ty,
+                },
+                Tag {
+                    fourcc: metallib::BITCODE_SIZE,
+                    data: &[0; 8],
+                },
+            ],
+            bitcode: &[],
+            public_metadata: &[],
+            private_metadata: &[],
+        })
+        .collect();
+    let mut writer = MetalLibWriter::new();
+    writer.add(&library).unwrap();
+    writer.write()
+}
@@ -0,0 +1,10 @@
+// Source of `compute.metallib`, regenerate it on macOS with:
+//
+//     cargo test -p saxaboom --features dxc --test metallib -- --ignored regenerate_converter_output
+RWStructuredBuffer<float> output : register(u0);
+
+[numthreads(64, 1, 1)]
+void main(uint3 id : SV_DispatchThreadID)
+{
+    output[id.x] = output[id.x] * 2.0f;
+}
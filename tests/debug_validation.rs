@@ -0,0 +1,58 @@
+//! Validates converted `metallib`s with [`IRMetalLibBinary::set_debug_validation()`], which
+//! applies to the whole process and therefore has a test binary of its own.
+#![cfg(all(feature = "testing", debug_assertions))]
+use saxaboom::{
+    ffi,
+    metallib::MetalLibWriter,
+    testing::{FakeConverter, FakeShader},
+    Error, IRMetalLibBinary, MetalIrConverter,
+};
+
+#[test]
+fn validates_every_compile() {
+    let fake = FakeConverter::default();
+    fake.on_compilation(|compilation| {
+        Some(Ok(FakeShader::new(
+            ffi::IRShaderStage::Compute,
+            compilation.dxil.clone(),
+        )))
+    });
+    let converter = MetalIrConverter::from_backend(fake);
+    let compiler = converter.create_compiler();
+    let compile = |metallib: &[u8]| {
+        let mut object = converter.create_object_from_dxil(metallib);
+        object.set_name("blur.dxil");
+        compiler
+            .alloc_compile_and_link(c"main", &object)
+            .unwrap()
+            .metal_lib_binary()
+    };
+    let valid = MetalLibWriter::new().write();
+
+    // Disabled by default
+    assert!(compile(b"MTLB").is_ok());
+
+    IRMetalLibBinary::set_debug_validation(true);
+    assert_eq!(compile(&valid).unwrap().byte_code(), valid);
+    let Err(Error::InvalidMetalLibOutput {
+        context,
+        diagnostics,
+    }) = compile(b"MTLB")
+    else {
+        panic!("converted an invalid metallib");
+    };
+    assert_eq!(context.input.as_deref(), Some("blur.dxil"));
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        Error::InvalidMetalLibOutput {
+            context,
+            diagnostics
+        }
+        .to_string(),
+        "Converted metallib of entry point `main` (Compute) from `blur.dxil` is invalid: at offset 4: header truncated to 4 of 88 bytes"
+    );
+
+    IRMetalLibBinary::set_debug_validation(false);
+    let binary = compile(b"MTLB").unwrap();
+    assert_eq!(binary.validate().len(), 1);
+}
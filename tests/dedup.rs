@@ -0,0 +1,140 @@
+//! Deduplicates converter output with [`saxaboom::dedup::BlobStore`].
+#![cfg(feature = "testing")]
+use std::{fs, path::PathBuf};
+
+use saxaboom::{
+    dedup::{BlobId, BlobStore, INDEX_FILE},
+    ffi,
+    reflection::ReflectionSnapshot,
+    testing::FakeShader,
+    Error, MetalIrConverter,
+};
+
+mod common;
+
+/// Converts `dxil` of the form `<metallib>:<thread group width>` into a compute shader.
+fn converter() -> MetalIrConverter {
+    let (_, converter) = common::converter(|compilation| {
+        let dxil = std::str::from_utf8(&compilation.dxil).unwrap();
+        let (metallib, width) = dxil.split_once(':').unwrap();
+        let mut shader = FakeShader::new(ffi::IRShaderStage::Compute, metallib.into());
+        shader.compute_info = Some(ffi::IRCSInfo_1_0 {
+            tg_size: [width.parse().unwrap(), 1, 1],
+        });
+        Ok(shader)
+    });
+    converter
+}
+
+fn store() -> BlobStore {
+    let converter = converter();
+    let compiler = converter.create_compiler();
+    let mut store = BlobStore::new();
+    for (name, dxil) in [
+        ("blur_h", "blur:64"),
+        ("blur_v", "blur:64"),
+        ("blur_wide", "blur:128"),
+        ("copy", "copy:64"),
+        ("copy_alias", "copy:64"),
+        ("copy_alias2", "copy:64"),
+    ] {
+        let object = converter.create_object_from_dxil(dxil.as_bytes());
+        let compiled = compiler.alloc_compile_and_link(c"main", &object).unwrap();
+        store.insert_object(name, &compiled).unwrap();
+    }
+    store
+}
+
+#[test]
+fn shares_identical_output() {
+    let store = store();
+    assert_eq!(store.blobs().count(), 3);
+    assert_eq!(store.duplicates(), 3);
+    assert_eq!(store.saved_bytes(), "blur".len() + 2 * "copy".len());
+
+    assert_eq!(store.id("blur_h"), store.id("blur_v"));
+    assert_eq!(store.id("copy"), store.id("copy_alias2"));
+    // Same `metallib`, different reflection
+    assert_ne!(store.id("blur_h"), store.id("blur_wide"));
+    assert_eq!(store.get("blur_wide").unwrap().metallib, b"blur");
+    assert_eq!(
+        store.get("blur_wide").unwrap().reflection.threadgroup_size,
+        Some([128, 1, 1])
+    );
+    assert!(store.get("missing").is_none());
+
+    let names = store.names().map(|(name, _)| name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "blur_h",
+            "blur_v",
+            "blur_wide",
+            "copy",
+            "copy_alias",
+            "copy_alias2"
+        ]
+    );
+    for (name, id) in store.names() {
+        let blob = store.blob(&id).unwrap();
+        assert_eq!(BlobId::of(&blob.metallib, &blob.reflection), id, "{name}");
+    }
+}
+
+#[test]
+fn ids_are_stable() {
+    let id = BlobId::of(&[], &ReflectionSnapshot::default());
+    assert_eq!(
+        id.to_string(),
+        "c90232586b801f9558a76f2f963eccd831d9fe6775e4c8f1446b2331aa2132f2"
+    );
+    assert_eq!(format!("{id:?}"), format!("BlobId({id})"));
+
+    assert_ne!(id, BlobId::of(&[0], &ReflectionSnapshot::default()));
+}
+
+#[test]
+fn rejects_duplicate_names() {
+    let mut store = BlobStore::new();
+    store
+        .insert("blit", b"a".to_vec(), ReflectionSnapshot::default())
+        .unwrap();
+    let Err(Error::DuplicateShader { name }) =
+        store.insert("blit", b"b".to_vec(), ReflectionSnapshot::default())
+    else {
+        panic!("inserted `blit` twice");
+    };
+    assert_eq!(name, "blit");
+    assert_eq!(store.get("blit").unwrap().metallib, b"a");
+    assert_eq!(store.duplicates(), 0);
+}
+
+#[test]
+fn writes_each_blob_once() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("dedup");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let store = store();
+    store.write_dir(&dir).unwrap();
+
+    let files = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    // A `metallib` and two sidecars per blob
+    assert_eq!(files.len(), 3 * 3 + 1);
+    let blur = store.id("blur_h").unwrap();
+    assert_eq!(
+        fs::read(dir.join(format!("{blur}.metallib"))).unwrap(),
+        b"blur"
+    );
+    assert!(dir.join(format!("{blur}.reflection.json")).exists());
+
+    let index = fs::read_to_string(dir.join(INDEX_FILE)).unwrap();
+    assert!(index.starts_with("{\n  \"blur_h\": \""));
+    assert!(index.contains(&format!("\"blur_v\": \"{blur}\"")));
+    assert_eq!(index.lines().count(), 6 + 2);
+
+    BlobStore::new().write_dir(&dir).unwrap();
+    assert_eq!(fs::read_to_string(dir.join(INDEX_FILE)).unwrap(), "{}\n");
+}
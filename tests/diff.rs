@@ -0,0 +1,197 @@
+//! Compares the output of two converters with [`saxaboom::diff`].
+#![cfg(feature = "testing")]
+use saxaboom::{
+    diff::{self, Difference, Shader},
+    ffi,
+    metallib::FunctionType,
+    testing::{FakeConverter, FakeResource, FakeShader},
+    Error, MetalIrConverter,
+};
+
+mod common;
+
+fn texture(slot: u32) -> FakeResource {
+    FakeResource {
+        resource_type: ffi::IRResourceType::SRV,
+        space: 0,
+        slot,
+        top_level_offset: 8 * slot,
+        size_bytes: 8,
+        name: Some("input".to_owned()),
+    }
+}
+
+/// Converts compute shaders whose DXIL is their name.  The newer release moves the texture of
+/// `blur` and halves its thread group size, adds a function to its `metallib`, and supports
+/// `wave_ops`.
+fn converter(newer: bool) -> (FakeConverter, MetalIrConverter) {
+    common::converter(move |compilation| {
+        let name = String::from_utf8_lossy(&compilation.dxil).into_owned();
+        if name == "wave_ops" && !newer {
+            return Err(common::unsupported("wave intrinsics"));
+        }
+        let blur = name == "blur" && newer;
+        let functions: &[_] = if blur {
+            &[
+                ("main", FunctionType::Kernel),
+                ("main.helper", FunctionType::Visible),
+            ]
+        } else {
+            &[("main", FunctionType::Kernel)]
+        };
+        let mut shader = FakeShader::new(ffi::IRShaderStage::Compute, common::metallib(functions));
+        shader.compute_info = Some(ffi::IRCSInfo_1_0 {
+            tg_size: [if blur { 32 } else { 64 }, 1, 1],
+        });
+        shader.resources = vec![texture(if blur { 1 } else { 0 })];
+        shader.reflection_json = format!(
+            "{{\n  \"Name\": \"{name}\",\n  \"Slot\": {}\n}}",
+            u32::from(blur)
+        );
+        Ok(shader)
+    })
+}
+
+fn corpus() -> Vec<Shader> {
+    ["blur", "copy", "wave_ops"]
+        .into_iter()
+        .map(|name| Shader::new(name, name.as_bytes().to_vec(), c"main"))
+        .collect()
+}
+
+#[test]
+fn reports_differences() {
+    let ((_, old), (_, new)) = (converter(false), converter(true));
+    let report = diff::diff(&old, &new, &corpus(), |_, compiler| {
+        compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+        Ok::<_, Error>(())
+    })
+    .unwrap();
+    assert!(!report.is_identical());
+    let changed = report
+        .changed()
+        .map(|s| s.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(changed, ["blur", "wave_ops"]);
+
+    let blur = &report.shaders[0];
+    let new = blur.new.as_ref().unwrap();
+    assert_eq!(new.threadgroup_size, Some([32, 1, 1]));
+    assert_eq!(new.resources[0].slot, 1);
+    assert_eq!(
+        new.functions,
+        [
+            ("main".to_owned(), Some(FunctionType::Kernel)),
+            ("main.helper".to_owned(), Some(FunctionType::Visible)),
+        ]
+    );
+    assert_eq!(blur.differences.len(), 4);
+    assert!(report.shaders[1].differences.is_empty());
+    assert_eq!(
+        report.shaders[2].differences,
+        [Difference::Conversion {
+            old: Err(r#"Compilation of entry point `main` from `wave_ops` failed: IRError UnsupportedInstruction: "wave intrinsics""#.to_owned()),
+            new: Ok(()),
+        }]
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "`blur` `main`:
+  reflection JSON, from line 3:
+    - \"Slot\": 0
+    + \"Slot\": 1
+  resources:
+    - SRV `input` in space 0 slot 0 at offset 0 (8 bytes)
+    + SRV `input` in space 0 slot 1 at offset 8 (8 bytes)
+  thread group size:
+    - Some([64, 1, 1])
+    + Some([32, 1, 1])
+  metallib functions:
+    + `main.helper` (Visible)
+`wave_ops` `main`:
+  conversion:
+    - Compilation of entry point `main` from `wave_ops` failed: IRError UnsupportedInstruction: \"wave intrinsics\"
+    + succeeded
+2 of 3 shaders differ
+"
+    );
+}
+
+#[test]
+fn identical_converters() {
+    let ((_, old), (_, new)) = (converter(true), converter(true));
+    let report = diff::diff(&old, &new, &corpus(), |_, _| Ok::<_, Error>(())).unwrap();
+    assert!(report.is_identical());
+    assert_eq!(report.failed().count(), 0);
+    assert_eq!(report.to_string(), "0 of 3 shaders differ\n");
+}
+
+#[test]
+fn reports_shaders_failing_with_both() {
+    let ((_, old), (_, new)) = (converter(false), converter(false));
+    let report = diff::diff(&old, &new, &corpus(), |_, _| Ok::<_, Error>(())).unwrap();
+    assert_eq!(report.changed().count(), 0);
+    let failed = report.failed().map(|s| s.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(failed, ["wave_ops"]);
+    assert!(!report.is_identical());
+    assert_eq!(
+        report.to_string(),
+        "`wave_ops` `main`:
+  failed with both converters:
+    Compilation of entry point `main` from `wave_ops` failed: IRError UnsupportedInstruction: \"wave intrinsics\"
+0 of 3 shaders differ, 1 failed with both converters
+"
+    );
+}
+
+#[test]
+fn without_json_reflection() {
+    let ((old_fake, old), (_, new)) = (converter(false), converter(true));
+    old_fake.without_function("IRShaderReflectionCopyJSONString");
+    let report = diff::diff(&old, &new, &corpus()[..2], |_, _| Ok::<_, Error>(())).unwrap();
+    let blur = &report.shaders[0];
+    assert_eq!(blur.old.as_ref().unwrap().reflection_json, None);
+    assert!(blur.new.as_ref().unwrap().reflection_json.is_some());
+    // Everything else is still compared
+    assert_eq!(blur.differences.len(), 3);
+    assert!(!blur
+        .differences
+        .iter()
+        .any(|d| matches!(d, Difference::ReflectionJson { .. })));
+    assert!(report.shaders[1].differences.is_empty());
+}
+
+#[test]
+fn configures_both_converters() {
+    let old = FakeConverter::default();
+    let new = FakeConverter::default();
+    let (old_converter, new_converter) = (
+        MetalIrConverter::from_backend(old.clone()),
+        MetalIrConverter::from_backend(new.clone()),
+    );
+    let report = diff::diff(
+        &old_converter,
+        &new_converter,
+        &corpus(),
+        |converter, compiler| {
+            let root_signature = converter.create_root_signature_from_json(c"{}")?;
+            compiler.set_global_root_signature(&root_signature);
+            Ok::<_, Error>(root_signature)
+        },
+    )
+    .unwrap();
+    assert_eq!(report.shaders.len(), 3);
+    for fake in [old, new] {
+        assert_eq!(fake.compiled_entry_points(), ["main", "main", "main"]);
+        assert!(fake
+            .compilations()
+            .iter()
+            .all(|c| c.settings.has_global_root_signature));
+    }
+
+    let failed = diff::diff(&old_converter, &new_converter, &corpus(), |_, _| {
+        Err::<(), _>("no root signature")
+    });
+    assert_eq!(failed, Err("no root signature"));
+}
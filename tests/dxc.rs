@@ -0,0 +1,316 @@
+//! Compiles HLSL with [`saxaboom::dxc::Dxc`] against the `dxcompiler_stub` library, which is
+//! loaded exactly like DXC's `libdxcompiler`, and converts the result with a
+//! [`saxaboom::testing::FakeConverter`].
+#![cfg(all(feature = "dxc", feature = "testing"))]
+use std::{
+    cell::RefCell,
+    fs,
+    path::PathBuf,
+    rc::Rc,
+    sync::{Mutex, MutexGuard},
+};
+
+use saxaboom::{
+    dxc::{Dxc, DxcError, Hlsl},
+    ffi,
+    permutations::{Permutations, Switch},
+    testing::{FakeConverter, FakeError, FakeShader},
+    Error, MetalIrConverter,
+};
+
+const SOURCE: &str = "[numthreads(8, 8, 1)]\nvoid main() {}\n";
+
+/// The stub counts its objects per process, tests that load it take this lock.
+static STUB: Mutex<()> = Mutex::new(());
+
+struct Stub {
+    dxc: Dxc,
+    lib: libloading::Library,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Stub {
+    fn load() -> Self {
+        let _guard = STUB.lock().unwrap_or_else(|e| e.into_inner());
+        // Cargo places the `cdylib` of the `dxcompiler_stub` dev-dependency next to the test
+        // executable.
+        let path: PathBuf = std::env::current_exe()
+            .unwrap()
+            .with_file_name(libloading::library_filename("dxcompiler"));
+        let dxc = Dxc::new(&path).unwrap();
+        let lib = unsafe { libloading::Library::new(&path) }.unwrap();
+        Self { dxc, lib, _guard }
+    }
+
+    fn live_objects(&self) -> usize {
+        unsafe {
+            self.lib
+                .get::<unsafe extern "C" fn() -> usize>(b"saxaboom_dxc_stub_live_objects\0")
+                .unwrap()()
+        }
+    }
+}
+
+fn compile(dxc: &Dxc, hlsl: &Hlsl) -> String {
+    String::from_utf8(dxc.compile(hlsl).unwrap()).unwrap()
+}
+
+#[test]
+fn passes_arguments() {
+    let stub = Stub::load();
+    let hlsl = Hlsl::new(SOURCE, c"main", "cs_6_6")
+        .name("blur.hlsl")
+        .define("RADIUS", "4")
+        .define_flag("FAST")
+        .arg("-O3");
+    assert_eq!(
+        compile(&stub.dxc, &hlsl),
+        format!("DXBC\nblur.hlsl -E main -T cs_6_6 -D RADIUS=4 -D FAST -O3\n{SOURCE}")
+    );
+    assert_eq!(hlsl.entry_point(), c"main");
+
+    let nul = Hlsl::new(SOURCE, c"main", "cs_6_6").define("A", "\0");
+    assert!(matches!(
+        stub.dxc.compile(&nul),
+        Err(Error::Dxc(DxcError::Argument { argument })) if argument == "A=\0"
+    ));
+}
+
+#[test]
+fn include_handler() {
+    let stub = Stub::load();
+    let requested = Rc::new(RefCell::new(Vec::new()));
+    let hlsl = Hlsl::new(
+        format!("#include \"common.hlsl\"\n{SOURCE}"),
+        c"main",
+        "cs_6_6",
+    )
+    .include_handler({
+        let requested = requested.clone();
+        move |path| {
+            requested.borrow_mut().push(path.to_owned());
+            (path == "common.hlsl").then(|| "float weight();".to_owned())
+        }
+    });
+    assert!(compile(&stub.dxc, &hlsl).ends_with(&format!("\nfloat weight();\n{SOURCE}")));
+    assert_eq!(*requested.borrow(), ["common.hlsl"]);
+
+    let missing =
+        Hlsl::new("#include \"missing.hlsl\"\n", c"main", "cs_6_6").include_handler(|_| None);
+    let error = stub.dxc.compile(&missing).unwrap_err();
+    let Error::Dxc(DxcError::Compilation { name, diagnostics }) = &error else {
+        panic!("unexpected error {error:?}");
+    };
+    assert_eq!(name, "shader.hlsl");
+    assert_eq!(
+        diagnostics,
+        "shader.hlsl:1:10: fatal error: 'missing.hlsl' file not found"
+    );
+    assert_eq!(
+        error.to_string(),
+        "Compiling `shader.hlsl` with DXC failed:\nshader.hlsl:1:10: fatal error: 'missing.hlsl' file not found"
+    );
+
+    // Panics are reported as missing files instead of unwinding through DXC
+    let panicking = Hlsl::new("#include \"panic.hlsl\"\n", c"main", "cs_6_6")
+        .include_handler(|_| panic!("include handler panicked"));
+    assert!(matches!(
+        stub.dxc.compile(&panicking),
+        Err(Error::Dxc(DxcError::Compilation { .. }))
+    ));
+}
+
+#[test]
+fn reads_files() {
+    let stub = Stub::load();
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("dxc_reads_files");
+    fs::create_dir_all(&dir).unwrap();
+    let common = dir.join("common.hlsl");
+    fs::write(&common, "float weight();\n").unwrap();
+    let path = dir.join("blur.hlsl");
+    fs::write(
+        &path,
+        format!("#include \"{}\"\n{SOURCE}", common.display()),
+    )
+    .unwrap();
+
+    let hlsl = Hlsl::from_file(&path, c"main", "cs_6_6").unwrap();
+    let dxil = compile(&stub.dxc, &hlsl);
+    assert!(dxil.starts_with(&format!("DXBC\n{} -E main", path.display())));
+    assert!(dxil.ends_with(&format!("\nfloat weight();\n{SOURCE}")));
+
+    assert!(matches!(
+        Hlsl::from_file(dir.join("missing.hlsl"), c"main", "cs_6_6"),
+        Err(Error::Read { .. })
+    ));
+}
+
+#[test]
+fn converts_hlsl() {
+    let stub = Stub::load();
+    let fake = FakeConverter::default();
+    fake.on_compilation(|compilation| {
+        assert!(compilation.dxil.starts_with(b"DXBC\n"));
+        Some(if compilation.dxil.windows(6).any(|w| w == b"-D BAD") {
+            Err(FakeError::new(
+                ffi::IRErrorCode::UnsupportedInstruction,
+                "unsupported instruction",
+            ))
+        } else {
+            Ok(FakeShader::new(
+                ffi::IRShaderStage::Compute,
+                b"MTLB".to_vec(),
+            ))
+        })
+    });
+    let converter = MetalIrConverter::from_backend(fake.clone());
+    let compiler = converter.create_compiler();
+
+    let hlsl = Hlsl::new(SOURCE, c"main", "cs_6_6").name("blur.hlsl");
+    let object = compiler
+        .alloc_compile_hlsl_and_link(&stub.dxc, &hlsl)
+        .unwrap();
+    assert_eq!(object.metal_lib_binary().unwrap().byte_code(), b"MTLB");
+    assert_eq!(fake.compiled_entry_points(), ["main"]);
+
+    // Conversion errors name the HLSL file
+    let Err(error) = compiler.alloc_compile_hlsl_and_link(&stub.dxc, &hlsl.define_flag("BAD"))
+    else {
+        panic!("BAD should fail to convert");
+    };
+    assert!(matches!(error, Error::Compilation { .. }));
+    assert_eq!(error.context().unwrap().input.as_deref(), Some("blur.hlsl"));
+
+    // HLSL errors never reach the converter
+    let broken = Hlsl::new("#error broken\n", c"main", "cs_6_6");
+    let Err(error) = compiler.alloc_compile_hlsl_and_link(&stub.dxc, &broken) else {
+        panic!("#error should fail to compile");
+    };
+    assert!(
+        matches!(&error, Error::Dxc(DxcError::Compilation { diagnostics, .. }) if diagnostics == "shader.hlsl:1:2: error: broken")
+    );
+    assert_eq!(fake.compilations().len(), 2);
+}
+
+#[test]
+fn converts_permutations() {
+    let stub = Stub::load();
+    let fake = FakeConverter::default();
+    fake.on_any(Ok(FakeShader::new(
+        ffi::IRShaderStage::Fragment,
+        b"MTLB".to_vec(),
+    )));
+    let converter = MetalIrConverter::from_backend(fake);
+    let permutations = Permutations::new(vec![
+        Switch::bool("ALPHA_TEST"),
+        Switch::enumeration("LIGHTING", ["unlit", "pbr"]),
+    ]);
+    let hlsl = Hlsl::new(SOURCE, c"main", "ps_6_6").define("QUALITY", "high");
+    let table = converter
+        .create_compiler()
+        .alloc_compile_hlsl_and_link_permutations(&stub.dxc, &hlsl, &permutations)
+        .unwrap();
+
+    assert_eq!(table.outputs.len(), 1);
+    assert_eq!(table.entries().count(), 4);
+
+    let fake = FakeConverter::default();
+    fake.on_compilation(|compilation| {
+        let dxil = String::from_utf8(compilation.dxil.clone()).unwrap();
+        let arguments = dxil.lines().nth(1).unwrap().to_owned();
+        Some(Ok(FakeShader::new(
+            ffi::IRShaderStage::Fragment,
+            arguments.into_bytes(),
+        )))
+    });
+    let converter = MetalIrConverter::from_backend(fake);
+    let table = converter
+        .create_compiler()
+        .alloc_compile_hlsl_and_link_permutations(&stub.dxc, &hlsl, &permutations)
+        .unwrap();
+    let key = permutations
+        .key(&[("ALPHA_TEST", "1"), ("LIGHTING", "pbr")])
+        .unwrap();
+    assert_eq!(
+        table.get(&key).unwrap().metallib,
+        b"shader.hlsl -E main -T ps_6_6 -D QUALITY=high -D ALPHA_TEST=1 -D LIGHTING=pbr"
+    );
+    assert_eq!(table.outputs.len(), 4);
+}
+
+#[test]
+fn releases_objects() {
+    let stub = Stub::load();
+    let before = stub.live_objects();
+    let dxc = Dxc::new(
+        std::env::current_exe()
+            .unwrap()
+            .with_file_name(libloading::library_filename("dxcompiler")),
+    )
+    .unwrap();
+    compile(
+        &dxc,
+        &Hlsl::new(format!("#include \"a.hlsl\"\n{SOURCE}"), c"main", "cs_6_6")
+            .include_handler(|_| Some(String::new())),
+    );
+    dxc.compile(&Hlsl::new("#error broken\n", c"main", "cs_6_6"))
+        .unwrap_err();
+    drop(dxc);
+    assert_eq!(stub.live_objects(), before);
+}
+
+#[test]
+fn discover() {
+    let _stub = Stub::load();
+    let dir = std::env::current_exe().unwrap().with_file_name("");
+    std::env::set_var(saxaboom::dxc::ENV_VAR, &dir);
+    let dxc = Dxc::discover().unwrap();
+    std::env::remove_var(saxaboom::dxc::ENV_VAR);
+    assert_eq!(
+        dxc.library_path(),
+        Some(
+            dir.join(libloading::library_filename("dxcompiler"))
+                .as_path()
+        )
+    );
+}
+
+/// Against the real `dxcompiler` library, which checks that the vtables match its layout on this
+/// platform.  Run with `SAXABOOM_DXCOMPILER_PATH` pointing to it and `--ignored`.
+#[test]
+#[ignore = "needs the real dxcompiler library in SAXABOOM_DXCOMPILER_PATH"]
+fn real_dxcompiler() {
+    assert!(
+        std::env::var_os(saxaboom::dxc::ENV_VAR).is_some(),
+        "`{}` is not set",
+        saxaboom::dxc::ENV_VAR
+    );
+    let dxc = Dxc::discover().unwrap();
+    let hlsl = Hlsl::new(
+        "#include \"common.hlsl\"\nRWBuffer<float> output;\n[numthreads(8, 1, 1)]\nvoid main(uint id : SV_DispatchThreadID) { output[id] = weight(); }\n",
+        c"main",
+        "cs_6_6",
+    )
+    .include_handler(|path| {
+        path.ends_with("common.hlsl")
+            .then(|| "float weight() { return 0.5; }".to_owned())
+    });
+    let dxil = dxc.compile(&hlsl).unwrap();
+    let container = saxaboom::dxil::Container::parse(&dxil).unwrap();
+    assert!(
+        container.parts.iter().any(|p| &p.fourcc == b"DXIL"),
+        "{container:?}"
+    );
+
+    let error = dxc
+        .compile(&Hlsl::new(
+            "void main() { undeclared(); }\n",
+            c"main",
+            "cs_6_6",
+        ))
+        .unwrap_err();
+    let Error::Dxc(DxcError::Compilation { diagnostics, .. }) = &error else {
+        panic!("unexpected error {error:?}");
+    };
+    assert!(diagnostics.contains("undeclared"), "{diagnostics}");
+}
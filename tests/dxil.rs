@@ -0,0 +1,181 @@
+//! Parses hand-assembled DXBC containers with [`saxaboom::dxil`].
+use saxaboom::dxil::{self, Container, ResourceBinding, ResourceType, ShaderKind};
+
+fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn container(parts: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut offset = 32 + 4 * parts.len();
+    let mut offsets = Vec::new();
+    let mut data = Vec::new();
+    for (fourcc, part) in parts {
+        offsets.push(offset as u32);
+        data.extend_from_slice(fourcc);
+        data.extend(u32s(&[part.len() as u32]));
+        data.extend_from_slice(part);
+        offset += 8 + part.len();
+    }
+
+    let mut bytes = b"DXBC".to_vec();
+    bytes.extend([0xab; 16]);
+    bytes.extend([1, 0, 0, 0]);
+    bytes.extend(u32s(&[offset as u32, parts.len() as u32]));
+    bytes.extend(u32s(&offsets));
+    bytes.extend(data);
+    bytes
+}
+
+/// Shader model 6.6 program of the given kind, with a 4 byte bitcode blob.
+fn program(kind: u32) -> Vec<u8> {
+    let mut part = u32s(&[kind << 16 | 6 << 4 | 6, 10]);
+    part.extend(b"DXIL");
+    part.extend(u32s(&[0x106, 16, 4]));
+    part.extend(b"BC\xc0\xde");
+    part
+}
+
+#[test]
+fn parts_and_program() {
+    let bytes = container(&[(dxil::DXIL, program(5)), (dxil::ROOT_SIGNATURE, vec![0; 8])]);
+    let container = Container::parse(&bytes).unwrap();
+    assert_eq!(container.hash, [0xab; 16]);
+    assert_eq!(container.version, (1, 0));
+    let names = container.parts.iter().map(|p| p.name()).collect::<Vec<_>>();
+    assert_eq!(names, ["DXIL", "RTS0"]);
+    assert_eq!(container.part(dxil::ROOT_SIGNATURE), Some(&[0; 8][..]));
+
+    let program = container.program().unwrap().unwrap();
+    assert_eq!(program.kind, ShaderKind::Compute);
+    assert_eq!(program.shader_model, (6, 6));
+    assert_eq!(program.dxil_version, (1, 6));
+    assert_eq!(program.bitcode, b"BC\xc0\xde");
+    assert_eq!(container.shader_kind().unwrap(), Some(ShaderKind::Compute));
+    assert!(container
+        .signature(dxil::INPUT_SIGNATURE)
+        .unwrap()
+        .is_none());
+    assert!(container.pipeline_state().unwrap().is_none());
+}
+
+#[test]
+fn signature() {
+    let mut part = u32s(&[2, 8]);
+    // Stream, name, index, system value, component type, register, mask, min precision
+    part.extend(u32s(&[0, 72, 0, 0, 3, 0, 0x0f, 0]));
+    part.extend(u32s(&[0, 81, 0, 6, 1, 1, 0x01, 0]));
+    part.extend(b"TEXCOORD\0SV_VertexID\0");
+    let bytes = container(&[(dxil::INPUT_SIGNATURE, part)]);
+
+    let elements = Container::parse(&bytes)
+        .unwrap()
+        .signature(dxil::INPUT_SIGNATURE)
+        .unwrap()
+        .unwrap();
+    assert_eq!(elements.len(), 2);
+    assert_eq!(elements[0].semantic_name, "TEXCOORD");
+    assert_eq!(elements[0].component_type, 3);
+    assert_eq!(elements[0].mask, 0x0f);
+    assert_eq!(elements[1].semantic_name, "SV_VertexID");
+    assert_eq!(elements[1].system_value, 6);
+    assert_eq!(elements[1].register, 1);
+}
+
+#[test]
+fn pipeline_state() {
+    // `PSVRuntimeInfo3`
+    let mut info = vec![0; 52];
+    info[16..24].copy_from_slice(&u32s(&[32, 64]));
+    info[24] = 5;
+    info[36..48].copy_from_slice(&u32s(&[8, 8, 1]));
+    info[48..52].copy_from_slice(&u32s(&[0]));
+
+    let mut part = u32s(&[info.len() as u32]);
+    part.extend(info);
+    // Two `PSVResourceBindInfo1`
+    part.extend(u32s(&[2, 24]));
+    part.extend(u32s(&[2, 0, 0, 0, 0, 0]));
+    part.extend(u32s(&[3, 1, 0, u32::MAX, 0, 0]));
+    part.extend(u32s(&[8]));
+    part.extend(b"main\0\0\0\0");
+    let bytes = container(&[(dxil::PIPELINE_STATE, part)]);
+
+    let container = Container::parse(&bytes).unwrap();
+    let psv = container.pipeline_state().unwrap().unwrap();
+    assert_eq!(psv.stage, Some(ShaderKind::Compute));
+    assert_eq!(psv.entry_point.as_deref(), Some("main"));
+    assert_eq!(psv.num_threads, Some([8, 8, 1]));
+    assert_eq!(psv.wave_lane_counts, (32, 64));
+    assert_eq!(
+        psv.resources,
+        [
+            ResourceBinding {
+                resource_type: ResourceType::CBV,
+                space: 0,
+                lower_bound: 0,
+                upper_bound: 0,
+            },
+            ResourceBinding {
+                resource_type: ResourceType::SRVTyped,
+                space: 1,
+                lower_bound: 0,
+                upper_bound: u32::MAX,
+            },
+        ]
+    );
+    // Falls back to the pipeline state without a program
+    assert_eq!(container.shader_kind().unwrap(), Some(ShaderKind::Compute));
+}
+
+#[test]
+fn invalid() {
+    assert!(matches!(
+        Container::parse(b"DXBC"),
+        Err(saxaboom::Error::InvalidDxil { .. })
+    ));
+
+    let mut bytes = container(&[(dxil::DXIL, program(0))]);
+    // Claim more bytes than there are
+    bytes[24] = 0xff;
+    let error = Container::parse(&bytes).unwrap_err();
+    assert!(error.to_string().contains("exceeds"), "{error}");
+
+    let mut bytes = container(&[(dxil::DXIL, program(0))]);
+    // Corrupt the bitcode header magic
+    bytes[52] = b'X';
+    let container = Container::parse(&bytes).unwrap();
+    assert!(container.program().is_err());
+}
+
+#[test]
+fn invalid_resource_bindings() {
+    let psv = |resource_count: u32, bind_info_size: u32| {
+        let mut part = u32s(&[24]);
+        part.extend([0; 24]);
+        part.extend(u32s(&[resource_count, bind_info_size]));
+        part.extend(u32s(&[2, 0, 0, 0]));
+        container(&[(dxil::PIPELINE_STATE, part)])
+    };
+    let error = |bytes: &[u8]| {
+        Container::parse(bytes)
+            .unwrap()
+            .pipeline_state()
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        Container::parse(&psv(1, 16))
+            .unwrap()
+            .pipeline_state()
+            .unwrap()
+            .unwrap()
+            .resources
+            .len(),
+        1
+    );
+    // Would otherwise reserve and read billions of bindings
+    assert!(error(&psv(u32::MAX, 16)).contains("out of bounds"));
+    assert!(error(&psv(2, 16)).contains("out of bounds"));
+    assert!(error(&psv(u32::MAX, 0)).contains("smaller than 16 bytes"));
+    assert!(error(&psv(1, 8)).contains("smaller than 16 bytes"));
+}
@@ -0,0 +1,625 @@
+//! Parses hand-assembled `metallib` files with [`saxaboom::metallib`], and merges them with
+//! [`saxaboom::metallib::MetalLibWriter`].
+use saxaboom::{
+    metallib::{
+        self, BitcodeWrapper, FunctionOffsets, FunctionType, FunctionVersion, LibraryType,
+        MetalLib, MetalLibWriter,
+    },
+    Error,
+};
+
+struct TestFunction {
+    name: &'static str,
+    function_type: u8,
+    bitcode: &'static [u8],
+    extra_tags: Vec<([u8; 4], Vec<u8>)>,
+}
+
+fn function(name: &'static str, function_type: u8, bitcode: &'static [u8]) -> TestFunction {
+    TestFunction {
+        name,
+        function_type,
+        bitcode,
+        extra_tags: Vec::new(),
+    }
+}
+
+fn tag(fourcc: [u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut tag = fourcc.to_vec();
+    tag.extend((data.len() as u16).to_le_bytes());
+    tag.extend(data);
+    tag
+}
+
+fn u64s(values: &[u64]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// `metallib` with 4 bytes of public and private metadata per function.
+fn metallib(functions: &[TestFunction]) -> Vec<u8> {
+    let mut function_list = (functions.len() as u32).to_le_bytes().to_vec();
+    let mut bitcode = Vec::<u8>::new();
+    for (i, f) in functions.iter().enumerate() {
+        let mut tags = tag(metallib::NAME, format!("{}\0", f.name).as_bytes());
+        tags.extend(tag(metallib::TYPE, &[f.function_type]));
+        tags.extend(tag(metallib::HASH, &metallib::bitcode_hash(f.bitcode)));
+        tags.extend(tag(
+            metallib::BITCODE_SIZE,
+            &u64s(&[f.bitcode.len() as u64]),
+        ));
+        tags.extend(tag(
+            metallib::OFFSETS,
+            &u64s(&[4 * i as u64, 4 * i as u64, bitcode.len() as u64]),
+        ));
+        tags.extend(tag(metallib::VERSION, &[2, 0, 6, 0, 3, 0, 1, 0]));
+        for (fourcc, data) in &f.extra_tags {
+            tags.extend(tag(*fourcc, data));
+        }
+        tags.extend(metallib::END);
+        function_list.extend((tags.len() as u32 + 4).to_le_bytes());
+        function_list.extend(tags);
+        bitcode.extend(f.bitcode);
+    }
+    let metadata_size = 4 * functions.len();
+
+    let function_list_offset = 88;
+    let public_offset = function_list_offset + function_list.len();
+    let private_offset = public_offset + metadata_size;
+    let bitcode_offset = private_offset + metadata_size;
+    let file_size = bitcode_offset + bitcode.len();
+
+    let mut bytes = b"MTLB".to_vec();
+    bytes.extend(0x8001_u16.to_le_bytes());
+    bytes.extend(2_u16.to_le_bytes());
+    bytes.extend(7_u16.to_le_bytes());
+    bytes.extend([0, 0x81]);
+    bytes.extend(14_u16.to_le_bytes());
+    bytes.extend(0_u16.to_le_bytes());
+    bytes.extend(u64s(&[
+        file_size as u64,
+        function_list_offset as u64,
+        function_list.len() as u64,
+        public_offset as u64,
+        metadata_size as u64,
+        private_offset as u64,
+        metadata_size as u64,
+        bitcode_offset as u64,
+        bitcode.len() as u64,
+    ]));
+    assert_eq!(bytes.len(), 88);
+    bytes.extend(function_list);
+    bytes.extend(vec![0xaa; metadata_size]);
+    bytes.extend(vec![0xbb; metadata_size]);
+    bytes.extend(bitcode);
+    bytes
+}
+
+fn library() -> Vec<u8> {
+    let mut vertex = function("vs_main", 0, b"\xde\xc0\x17\x0bvertex");
+    vertex.extra_tags.push((*b"LAYR", vec![1]));
+    metallib(&[
+        vertex,
+        function("cs_main", 2, b"\xde\xc0\x17\x0bkernel bitcode"),
+    ])
+}
+
+fn invalid_reason(bytes: &[u8]) -> String {
+    match MetalLib::parse(bytes) {
+        Err(Error::InvalidMetalLib { reason }) => reason,
+        Err(e) => panic!("unexpected error {e:?}"),
+        Ok(_) => panic!("parsed an invalid metallib"),
+    }
+}
+
+#[test]
+fn header_and_functions() {
+    let bytes = library();
+    let library = MetalLib::parse(&bytes).unwrap();
+    let header = library.header;
+    assert_eq!(header.platform, 0x8001);
+    assert_eq!(header.file_version, (2, 7));
+    assert_eq!(header.library_type, LibraryType::Executable);
+    assert_eq!(header.operating_system, 0x81);
+    assert_eq!(header.operating_system_version, (14, 0));
+    assert_eq!(header.file_size, bytes.len() as u64);
+    assert_eq!(header.function_list.offset, 88);
+    assert_eq!(library.public_metadata(), [0xaa; 8]);
+    assert_eq!(library.private_metadata(), [0xbb; 8]);
+    assert_eq!(
+        library.bitcode(),
+        b"\xde\xc0\x17\x0bvertex\xde\xc0\x17\x0bkernel bitcode"
+    );
+
+    let names = library
+        .functions
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["vs_main", "cs_main"]);
+
+    let vertex = &library.functions[0];
+    assert_eq!(vertex.function_type, Some(FunctionType::Vertex));
+    assert_eq!(
+        vertex.hash,
+        Some(metallib::bitcode_hash(b"\xde\xc0\x17\x0bvertex"))
+    );
+    assert_eq!(vertex.bitcode, b"\xde\xc0\x17\x0bvertex");
+    assert_eq!(vertex.tag(*b"LAYR"), Some(&[1][..]));
+    let tags = vertex.tags.iter().map(|t| t.name()).collect::<Vec<_>>();
+    assert_eq!(
+        tags,
+        ["NAME", "TYPE", "HASH", "MDSZ", "OFFT", "VERS", "LAYR"]
+    );
+
+    let kernel = library.function("cs_main").unwrap();
+    assert_eq!(kernel.function_type, Some(FunctionType::Kernel));
+    assert_ne!(kernel.hash, vertex.hash);
+    assert_eq!(
+        kernel.offsets,
+        FunctionOffsets {
+            public_metadata: 4,
+            private_metadata: 4,
+            bitcode: 10,
+        }
+    );
+    assert_eq!(
+        kernel.version,
+        Some(FunctionVersion {
+            air: (2, 6),
+            language: (3, 1),
+        })
+    );
+    assert_eq!(kernel.bitcode, b"\xde\xc0\x17\x0bkernel bitcode");
+    assert!(library.function("ps_main").is_none());
+}
+
+#[test]
+fn empty_library() {
+    let bytes = metallib(&[]);
+    let library = MetalLib::parse(&bytes).unwrap();
+    assert!(library.functions.is_empty());
+    assert!(library.bitcode().is_empty());
+}
+
+#[test]
+fn rejects_truncated() {
+    let bytes = library();
+    for len in 0..bytes.len() {
+        assert!(
+            MetalLib::parse(&bytes[..len]).is_err(),
+            "parsed {len} of {} bytes",
+            bytes.len()
+        );
+    }
+    assert_eq!(invalid_reason(b"DXBC"), "missing `MTLB` header");
+}
+
+#[test]
+fn rejects_malformed() {
+    // Bitcode of the second function starts past the bitcode section
+    let mut bytes = library();
+    let offsets = bytes
+        .windows(4)
+        .rposition(|w| w == metallib::OFFSETS)
+        .unwrap();
+    bytes[offsets + 6 + 16] = 0xff;
+    assert_eq!(
+        invalid_reason(&bytes),
+        "function 1: bitcode (18 bytes at offset 255) out of bounds"
+    );
+
+    // Offsets that overflow
+    bytes[offsets + 6 + 16..offsets + 6 + 24].copy_from_slice(&[0xff; 8]);
+    assert!(invalid_reason(&bytes).starts_with("function 1: bitcode"));
+
+    // Function list that extends past the end of the file
+    let mut bytes = library();
+    bytes[32] = 0xff;
+    assert!(invalid_reason(&bytes).starts_with("function list"));
+
+    // Fixed-size tags of the wrong size
+    let mut broken = function("broken", 2, b"");
+    broken.extra_tags.push((metallib::NAME, b"x\0".to_vec()));
+    let mut bytes = metallib(&[broken]);
+    let ty = bytes.windows(4).position(|w| w == metallib::TYPE).unwrap();
+    bytes[ty..ty + 4].copy_from_slice(b"XXXX");
+    let parsed = MetalLib::parse(&bytes).unwrap();
+    assert_eq!(parsed.functions[0].function_type, None);
+    assert_eq!(parsed.functions[0].name, "broken");
+    let hash = bytes.windows(4).position(|w| w == metallib::HASH).unwrap();
+    bytes[hash + 4] = 31;
+    assert!(invalid_reason(&bytes).starts_with("function 0: "));
+}
+
+#[test]
+fn never_panics_on_corruption() {
+    let bytes = library();
+    for i in 0..bytes.len() {
+        for value in [0x00, 0x7f, 0xff] {
+            let mut corrupt = bytes.clone();
+            corrupt[i] = value;
+            let _ = MetalLib::parse(&corrupt);
+        }
+    }
+}
+
+fn names(library: &MetalLib<'_>) -> Vec<String> {
+    library.functions.iter().map(|f| f.name.clone()).collect()
+}
+
+#[test]
+fn merge_round_trips() {
+    let first = library();
+    let second = metallib(&[function("ps_main", 1, b"\xde\xc0\x17\x0bfragment")]);
+    let merged = metallib::merge(&[&first, &second]).unwrap();
+
+    let library = MetalLib::parse(&merged).unwrap();
+    assert_eq!(names(&library), ["vs_main", "cs_main", "ps_main"]);
+    assert_eq!(library.header.platform, 0x8001);
+    assert_eq!(library.header.operating_system_version, (14, 0));
+    assert_eq!(library.header.file_size, merged.len() as u64);
+    assert_eq!(library.public_metadata(), [0xaa; 12]);
+    assert_eq!(library.private_metadata(), [0xbb; 12]);
+
+    let inputs = [
+        MetalLib::parse(&first).unwrap(),
+        MetalLib::parse(&second).unwrap(),
+    ];
+    for (merged, input) in library
+        .functions
+        .iter()
+        .zip(inputs.iter().flat_map(|l| &l.functions))
+    {
+        assert_eq!(merged.name, input.name);
+        assert_eq!(merged.function_type, input.function_type);
+        assert_eq!(merged.hash, input.hash);
+        assert_eq!(merged.version, input.version);
+        assert_eq!(merged.bitcode, input.bitcode);
+        assert_eq!(merged.public_metadata, input.public_metadata);
+        assert_eq!(merged.private_metadata, input.private_metadata);
+        let tags = |f: &metallib::Function<'_>| f.tags.iter().map(|t| t.name()).collect::<Vec<_>>();
+        assert_eq!(tags(merged), tags(input));
+    }
+    assert_eq!(
+        library.function("ps_main").unwrap().offsets,
+        FunctionOffsets {
+            public_metadata: 8,
+            private_metadata: 8,
+            bitcode: 28,
+        }
+    );
+    assert_eq!(
+        library.function("vs_main").unwrap().tag(*b"LAYR"),
+        Some(&[1][..])
+    );
+
+    // Writing a parsed library reproduces it
+    let mut writer = MetalLibWriter::new();
+    writer.add(&library).unwrap();
+    assert_eq!(writer.write(), merged);
+
+    let empty = MetalLibWriter::new().write();
+    assert!(MetalLib::parse(&empty).unwrap().functions.is_empty());
+}
+
+#[test]
+fn merge_rejects_duplicates() {
+    let first = library();
+    let second = metallib(&[
+        function("ps_main", 1, b""),
+        function("cs_main", 2, b"\xde\xc0\x17\x0bother kernel"),
+    ]);
+    let Err(Error::DuplicateFunction { name }) = metallib::merge(&[&first, &second]) else {
+        panic!("merged duplicate functions");
+    };
+    assert_eq!(name, "cs_main");
+
+    // Nothing of the failing library is added
+    let mut writer = MetalLibWriter::new();
+    writer.add(&MetalLib::parse(&first).unwrap()).unwrap();
+    assert!(writer.add(&MetalLib::parse(&second).unwrap()).is_err());
+    assert_eq!(
+        writer.function_names().collect::<Vec<_>>(),
+        ["vs_main", "cs_main"]
+    );
+
+    // Libraries for another platform
+    let mut other = library();
+    other[4] = 0x02;
+    assert!(writer.add(&MetalLib::parse(&other).unwrap()).is_err());
+}
+
+#[test]
+fn unique_names() {
+    let taken = ["main", "main_1"];
+    assert_eq!(
+        metallib::unique_name("other", |n| taken.contains(&n)),
+        "other"
+    );
+    assert_eq!(
+        metallib::unique_name("main", |n| taken.contains(&n)),
+        "main_2"
+    );
+}
+
+#[test]
+fn merge_rejects_long_names() {
+    // The longest name that fits in a `NAME` tag, including its NUL terminator
+    let name: &'static str = "f".repeat(usize::from(u16::MAX) - 1).leak();
+    let bytes = metallib(&[function(name, 2, b"")]);
+    let mut library = MetalLib::parse(&bytes).unwrap();
+    let mut writer = MetalLibWriter::new();
+    writer.add(&library).unwrap();
+    assert_eq!(
+        MetalLib::parse(&writer.write()).unwrap().functions[0].name,
+        name
+    );
+
+    // One more byte would truncate the size of its tag
+    library.functions[0].name.push('f');
+    let error = writer.add(&library).unwrap_err();
+    assert!(error.to_string().contains("does not fit"), "{error}");
+    assert_eq!(writer.function_names().count(), 1);
+}
+
+/// `module` in a bitcode wrapper header, with `padding` bytes between the header and the module.
+fn wrapped(module: &[u8], padding: usize) -> &'static [u8] {
+    let mut bitcode = Vec::new();
+    for value in [
+        metallib::BITCODE_WRAPPER_MAGIC,
+        0,
+        20 + padding as u32,
+        module.len() as u32,
+        0x0100_0017,
+    ] {
+        bitcode.extend(value.to_le_bytes());
+    }
+    bitcode.extend(vec![0; padding]);
+    bitcode.extend(module);
+    bitcode.leak()
+}
+
+#[test]
+fn extracts_bitcode_modules() {
+    let bytes = metallib(&[
+        function("vs_main", 0, wrapped(b"BC\xc0\xdevertex module", 0)),
+        function("ps_main", 1, wrapped(b"BC\xc0\xdefragment module", 12)),
+        function("cs_main", 2, b"BC\xc0\xdeunwrapped module"),
+    ]);
+    let library = MetalLib::parse(&bytes).unwrap();
+    let modules = library.bitcode_modules().unwrap();
+    let summary = modules
+        .iter()
+        .map(|m| (m.name.as_str(), m.function_type, m.module))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            (
+                "vs_main",
+                Some(FunctionType::Vertex),
+                &b"BC\xc0\xdevertex module"[..]
+            ),
+            (
+                "ps_main",
+                Some(FunctionType::Fragment),
+                b"BC\xc0\xdefragment module"
+            ),
+            (
+                "cs_main",
+                Some(FunctionType::Kernel),
+                b"BC\xc0\xdeunwrapped module"
+            ),
+        ]
+    );
+    assert_eq!(
+        modules[1].wrapper,
+        Some(BitcodeWrapper {
+            version: 0,
+            cpu_type: 0x0100_0017,
+        })
+    );
+    assert_eq!(modules[2].wrapper, None);
+
+    // The module is borrowed from the `metallib`
+    let module = library.functions[1].bitcode_module().unwrap().module;
+    let offset = module.as_ptr() as usize - bytes.as_ptr() as usize;
+    assert_eq!(&bytes[offset..offset + module.len()], module);
+}
+
+#[test]
+fn rejects_invalid_bitcode() {
+    // The test libraries above only have a wrapper magic
+    let bytes = library();
+    let library = MetalLib::parse(&bytes).unwrap();
+    let Err(Error::InvalidMetalLib { reason }) = library.bitcode_modules() else {
+        panic!("extracted bitcode without a wrapper header");
+    };
+    assert_eq!(
+        reason,
+        "function 0: truncated bitcode wrapper header of `vs_main`"
+    );
+
+    // Wrapped module extending past the function's bitcode
+    let mut bitcode = wrapped(b"BC\xc0\xdemodule", 0).to_vec();
+    bitcode.truncate(bitcode.len() - 1);
+    let bytes = metallib(&[
+        function("vs_main", 0, b"not bitcode"),
+        function("cs_main", 2, bitcode.leak()),
+    ]);
+    let library = MetalLib::parse(&bytes).unwrap();
+    let Err(Error::InvalidMetalLib { reason }) = library.functions[0].bitcode_module() else {
+        panic!("extracted invalid bitcode");
+    };
+    assert_eq!(reason, "bitcode of `vs_main` is not an LLVM bitcode module");
+    let Err(Error::InvalidMetalLib { reason }) = library.bitcode_modules() else {
+        panic!("extracted invalid bitcode");
+    };
+    assert_eq!(
+        reason,
+        "function 0: bitcode of `vs_main` is not an LLVM bitcode module"
+    );
+    let Err(Error::InvalidMetalLib { reason }) = library.functions[1].bitcode_module() else {
+        panic!("extracted a truncated module");
+    };
+    assert_eq!(
+        reason,
+        "wrapped bitcode module (10 bytes at offset 20) out of bounds"
+    );
+}
+
+fn valid_library() -> Vec<u8> {
+    metallib(&[
+        function("vs_main", 0, wrapped(b"BC\xc0\xdevertex module", 0)),
+        function("cs_main", 2, wrapped(b"BC\xc0\xdekernel module", 4)),
+    ])
+}
+
+fn messages(bytes: &[u8]) -> Vec<String> {
+    metallib::validate(bytes)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn validates_well_formed() {
+    let bytes = valid_library();
+    assert_eq!(metallib::validate(&bytes), []);
+    let fragment = metallib(&[function(
+        "ps_main",
+        1,
+        wrapped(b"BC\xc0\xdefragment module", 0),
+    )]);
+    let merged = metallib::merge(&[&bytes, &fragment]).unwrap();
+    assert_eq!(metallib::validate(&merged), []);
+    assert_eq!(metallib::validate(&metallib(&[])), []);
+}
+
+#[test]
+fn validation_diagnostics() {
+    let bytes = valid_library();
+    assert_eq!(messages(b"DXBC"), ["at offset 0: missing `MTLB` magic"]);
+    assert_eq!(
+        messages(&bytes[..40]),
+        ["at offset 40: header truncated to 40 of 88 bytes"]
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        messages(&trailing),
+        [format!(
+            "at offset 16: declared file size of {} bytes, but the file is {} bytes",
+            bytes.len(),
+            bytes.len() + 1
+        )]
+    );
+
+    // Flip the last byte of the kernel module
+    let mut corrupt = bytes.clone();
+    *corrupt.last_mut().unwrap() ^= 1;
+    let diagnostics = metallib::validate(&corrupt);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].function, Some(1));
+    let hex = |hash: [u8; 32]| hash.map(|b| format!("{b:02x}")).concat();
+    let stored = MetalLib::parse(&bytes).unwrap().functions[1].hash.unwrap();
+    let actual = metallib::bitcode_hash(MetalLib::parse(&corrupt).unwrap().functions[1].bitcode);
+    assert_eq!(
+        diagnostics[0].message,
+        format!(
+            "`HASH` tag {} does not match the SHA-256 of the bitcode, {}",
+            hex(stored),
+            hex(actual)
+        )
+    );
+
+    // Only the bitcode of the last function extends past the end of the file
+    let mut truncated = bytes.clone();
+    truncated.truncate(bytes.len() - 1);
+    assert_eq!(
+        messages(&truncated),
+        [
+            format!(
+                "at offset 16: declared file size of {} bytes, but the file is {} bytes",
+                bytes.len(),
+                bytes.len() - 1
+            ),
+            format!(
+                "at offset 72: bitcode section (78 bytes at offset {}) extends past the end of the file",
+                bytes.len() - 78
+            ),
+            "function 1 at offset 217: bitcode (41 bytes at offset 37) out of bounds".to_owned(),
+        ]
+    );
+
+    let bytes = metallib(&[
+        function("main", 2, wrapped(b"BC\xc0\xdefirst", 0)),
+        function("main", 2, b"\xde\xc0\x17\x0b"),
+    ]);
+    assert_eq!(
+        messages(&bytes),
+        [
+            "function 1 at offset 214: function name `main` is also used by function 0".to_owned(),
+            format!(
+                "function 1 at offset {}: truncated bitcode wrapper header of `main`",
+                // After the 29 bytes of the first function
+                MetalLib::parse(&bytes).unwrap().header.bitcode.offset + 29
+            ),
+        ]
+    );
+}
+
+#[test]
+fn validation_never_panics() {
+    let bytes = valid_library();
+    for len in 0..bytes.len() {
+        assert_ne!(metallib::validate(&bytes[..len]), [], "{len} bytes");
+    }
+    for i in 0..bytes.len() {
+        let mut corrupt = bytes.clone();
+        corrupt[i] ^= 0xff;
+        metallib::validate(&corrupt);
+    }
+}
+
+/// Real `metal_irconverter` output, see `tests/data/compute.hlsl`.
+const CONVERTER_OUTPUT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/compute.metallib");
+
+#[test]
+#[ignore = "`tests/data/compute.metallib` has to be produced on macOS by `regenerate_converter_output`"]
+fn converter_output() {
+    let bytes = std::fs::read(CONVERTER_OUTPUT).unwrap();
+    assert_eq!(metallib::validate(&bytes), []);
+    let library = MetalLib::parse(&bytes).unwrap();
+    let main = library.function("main").unwrap();
+    assert_eq!(main.function_type, Some(FunctionType::Kernel));
+    for module in library.bitcode_modules().unwrap() {
+        assert!(module.module.starts_with(b"BC\xc0\xde"), "{}", module.name);
+    }
+}
+
+#[test]
+#[ignore = "writes `tests/data/compute.metallib` with the installed converter and DXC"]
+#[cfg(all(target_os = "macos", feature = "dxc"))]
+fn regenerate_converter_output() {
+    use saxaboom::{
+        dxc::{Dxc, Hlsl},
+        MetalIrConverter,
+    };
+
+    let source = std::fs::read_to_string(CONVERTER_OUTPUT.replace(".metallib", ".hlsl")).unwrap();
+    let object = MetalIrConverter::discover()
+        .unwrap()
+        .create_compiler()
+        .alloc_compile_hlsl_and_link(
+            &Dxc::discover().unwrap(),
+            &Hlsl::new(source, c"main", "cs_6_0"),
+        )
+        .unwrap();
+    std::fs::write(
+        CONVERTER_OUTPUT,
+        object.metal_lib_binary().unwrap().byte_code(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,166 @@
+//! Expands and converts shader permutations with
+//! [`IRCompiler::alloc_compile_and_link_permutations()`].
+#![cfg(feature = "testing")]
+use std::{fs, path::PathBuf};
+
+use saxaboom::{
+    ffi,
+    permutations::{PermutationKey, Permutations, Switch},
+    testing::{FakeConverter, FakeShader},
+    Error, MetalIrConverter,
+};
+
+mod common;
+
+fn permutations() -> Permutations {
+    Permutations::new(vec![
+        Switch::bool("ALPHA_TEST"),
+        Switch::enumeration("LIGHTING", ["unlit", "lambert", "pbr"]),
+    ])
+    .valid(|key| !(key.get("LIGHTING") == Some("unlit") && key.get("ALPHA_TEST") == Some("1")))
+}
+
+/// Writes the key itself as the DXIL of every permutation.
+fn write_dxil(dir: &str, permutations: &Permutations) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(dir);
+    fs::create_dir_all(&dir).unwrap();
+    for key in permutations.keys() {
+        fs::write(dir.join(file_name(&key)), key.to_string()).unwrap();
+    }
+    dir
+}
+
+fn file_name(key: &PermutationKey) -> String {
+    format!("material.{}.dxil", key.to_string().replace(' ', "."))
+}
+
+#[test]
+fn expands_valid_keys() {
+    let permutations = permutations();
+    let keys = permutations
+        .keys()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        [
+            "ALPHA_TEST=0 LIGHTING=unlit",
+            "ALPHA_TEST=0 LIGHTING=lambert",
+            "ALPHA_TEST=0 LIGHTING=pbr",
+            "ALPHA_TEST=1 LIGHTING=lambert",
+            "ALPHA_TEST=1 LIGHTING=pbr",
+        ]
+    );
+
+    let key = permutations.key(&[("LIGHTING", "pbr")]).unwrap();
+    assert_eq!(key.to_string(), "ALPHA_TEST=0 LIGHTING=pbr");
+    assert_eq!(
+        key.defines().collect::<Vec<_>>(),
+        [("ALPHA_TEST", "0"), ("LIGHTING", "pbr")]
+    );
+    assert_eq!(permutations.key(&[("ALPHA_TEST", "1")]), None);
+    assert_eq!(permutations.key(&[("LIGHTING", "phong")]), None);
+    assert_eq!(permutations.key(&[("SHADOWS", "1")]), None);
+}
+
+#[test]
+fn deduplicates() {
+    let permutations = permutations();
+    let dir = write_dxil("permutations_deduplicates", &permutations);
+    // Alpha testing is compiled out of unlit and lambert materials
+    let (fake, converter) = common::converter(|compilation| {
+        let dxil = String::from_utf8(compilation.dxil.clone()).unwrap();
+        let metallib = if dxil.ends_with("pbr") {
+            dxil
+        } else {
+            "simple".to_owned()
+        };
+        Ok(FakeShader::new(
+            ffi::IRShaderStage::Fragment,
+            metallib.into_bytes(),
+        ))
+    });
+    let mut compiler = converter.create_compiler();
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+
+    let table = compiler
+        .alloc_compile_and_link_permutations(c"main", &permutations, |key| dir.join(file_name(key)))
+        .unwrap();
+
+    assert_eq!(table.outputs.len(), 3);
+    assert_eq!(table.outputs[0].metallib, b"simple");
+    assert_eq!(
+        table.outputs[0]
+            .keys
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        [
+            "ALPHA_TEST=0 LIGHTING=unlit",
+            "ALPHA_TEST=0 LIGHTING=lambert",
+            "ALPHA_TEST=1 LIGHTING=lambert",
+        ]
+    );
+    let pbr = permutations
+        .key(&[("ALPHA_TEST", "1"), ("LIGHTING", "pbr")])
+        .unwrap();
+    assert_eq!(table.output_index(&pbr), Some(2));
+    assert_eq!(
+        table.get(&pbr).unwrap().metallib,
+        b"ALPHA_TEST=1 LIGHTING=pbr"
+    );
+    assert_eq!(table.entries().count(), 5);
+
+    let compilations = fake.compilations();
+    assert_eq!(compilations.len(), 5);
+    assert!(compilations
+        .iter()
+        .all(|c| c.settings.minimum_gpu_family == Some(ffi::IRGPUFamily::Apple7)));
+}
+
+#[test]
+fn reports_failing_permutation() {
+    let permutations = permutations();
+    let dir = write_dxil("permutations_reports_failing_permutation", &permutations);
+    let fake = FakeConverter::default();
+    fake.on_dxil(
+        "ALPHA_TEST=1 LIGHTING=lambert",
+        Err(common::unsupported("discard is not supported")),
+    )
+    .on_any(Ok(FakeShader::new(
+        ffi::IRShaderStage::Fragment,
+        b"MTLB".to_vec(),
+    )));
+    let converter = MetalIrConverter::from_backend(fake);
+    let compiler = converter.create_compiler();
+
+    let Err(error) = compiler.alloc_compile_and_link_permutations(c"main", &permutations, |key| {
+        dir.join(file_name(key))
+    }) else {
+        panic!("ALPHA_TEST=1 LIGHTING=lambert should fail");
+    };
+    let Error::Permutation { key, .. } = &error else {
+        panic!("unexpected error {error:?}");
+    };
+    assert_eq!(key, "ALPHA_TEST=1 LIGHTING=lambert");
+    assert_eq!(
+        error.ir_error().unwrap().code(),
+        ffi::IRErrorCode::UnsupportedInstruction
+    );
+    let input = dir.join("material.ALPHA_TEST=1.LIGHTING=lambert.dxil");
+    assert_eq!(
+        error.context().unwrap().input.as_deref(),
+        Some(input.display().to_string().as_str())
+    );
+
+    let Err(error) = compiler
+        .alloc_compile_and_link_permutations(c"main", &permutations, |_| dir.join("missing.dxil"))
+    else {
+        panic!("missing DXIL should fail");
+    };
+    assert!(matches!(
+        &error,
+        Error::Permutation { error, .. } if matches!(**error, Error::Read { .. })
+    ));
+}
@@ -0,0 +1,311 @@
+//! Records the [`saxaboom::provenance::Provenance`] of compiled objects, and reads its sidecars back
+//! with the reader of `saxaboom-runtime`.
+#![cfg(feature = "testing")]
+use std::{fs, path::PathBuf};
+
+use saxaboom::{
+    ffi,
+    provenance::{self as sidecar, Provenance, SAXABOOM_VERSION},
+    replay::Call,
+    testing::{FakeConverter, FakeShader},
+    MetalIrConverter,
+};
+
+mod common;
+use saxaboom_runtime::{provenance, FormatError};
+use sha2::{Digest, Sha256};
+
+const ROOT_SIGNATURE: &str = r#"{"RootSignature": {}}"#;
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn converter() -> (FakeConverter, MetalIrConverter) {
+    common::converter(|_| {
+        Ok(FakeShader::new(
+            ffi::IRShaderStage::Compute,
+            b"metallib".to_vec(),
+        ))
+    })
+}
+
+fn provenance() -> Provenance {
+    let (_, converter) = converter();
+    let mut compiler = converter.create_compiler();
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+    compiler.set_compatibility_flags(ffi::IRCompatibilityFlags::BoundsCheck);
+    let root_signature = converter
+        .create_root_signature_from_json(&std::ffi::CString::new(ROOT_SIGNATURE).unwrap())
+        .unwrap();
+    compiler.set_global_root_signature(&root_signature);
+    // Replaces the first value, but keeps its position
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple9);
+    compiler.set_minimum_deployment_target(ffi::IROperatingSystem::macOS, c"14.0");
+
+    let dxil = converter.create_object_from_dxil(b"DXBC \"blur\"");
+    assert_eq!(dxil.provenance(), None);
+    compiler
+        .alloc_compile_and_link(c"main", &dxil)
+        .unwrap()
+        .provenance()
+        .unwrap()
+}
+
+#[test]
+fn records_compilations() {
+    let provenance = provenance();
+    assert_eq!(provenance.input_hash, sha256(b"DXBC \"blur\""));
+    assert_eq!(provenance.entry_point, "main");
+    let root_signature_hash = sha256(ROOT_SIGNATURE.as_bytes());
+    let hex = root_signature_hash
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    assert_eq!(
+        provenance.settings,
+        [
+            ("set_minimum_gpu_family", "Apple9"),
+            ("set_compatibility_flags", "0x1"),
+            ("set_global_root_signature", hex.as_str()),
+            ("set_minimum_deployment_target", "macOS 14.0"),
+        ]
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+    );
+    assert_eq!(provenance.root_signature_hash, Some(root_signature_hash));
+    assert_eq!(provenance.converter_path, None);
+    assert_eq!(provenance.converter_hash, None);
+    assert_eq!(provenance.saxaboom_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(SAXABOOM_VERSION, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn tracks_settings() {
+    let (_, converter) = converter();
+    let mut compiler = converter.create_compiler();
+    assert!(compiler.settings().is_empty());
+    compiler.ignore_debug_information(true).unwrap();
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+    compiler.ignore_debug_information(false).unwrap();
+    assert_eq!(
+        compiler.settings(),
+        [
+            Call::IgnoreDebugInformation(false),
+            Call::SetMinimumGpuFamily(ffi::IRGPUFamily::Apple7),
+        ]
+    );
+
+    // Objects compiled earlier keep the settings they were compiled with
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let before = compiler.alloc_compile_and_link(c"main", &dxil).unwrap();
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Metal3);
+    let after = compiler.alloc_compile_and_link(c"main", &dxil).unwrap();
+    let gpu_family = |object: &saxaboom::IRObject| object.provenance().unwrap().settings[1].clone();
+    assert_eq!(gpu_family(&before).1, "Apple7");
+    assert_eq!(gpu_family(&after).1, "Metal3");
+    assert_eq!(before.provenance().unwrap().root_signature_hash, None);
+
+    let combined = compiler
+        .alloc_combine_compile_and_link(c"intersect", &dxil, c"any_hit", &dxil)
+        .unwrap()
+        .provenance()
+        .unwrap();
+    assert_eq!(combined.entry_point, "intersect+any_hit");
+    assert_ne!(combined.input_hash, sha256(b"DXBC"));
+}
+
+#[test]
+fn identifies_the_converter_library() {
+    // Cargo places the `cdylib` of the `irconverter_stub` dev-dependency next to the test
+    // executable.
+    let path: PathBuf = std::env::current_exe()
+        .unwrap()
+        .with_file_name(libloading::library_filename("metalirconverter"));
+    let converter = MetalIrConverter::new(&path).unwrap();
+    let library_hash = sha256(&fs::read(&path).unwrap());
+    assert_eq!(converter.library_hash(), Some(library_hash));
+
+    let compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let provenance = compiler
+        .alloc_compile_and_link(c"main", &dxil)
+        .unwrap()
+        .provenance()
+        .unwrap();
+    assert_eq!(
+        provenance.converter_path.as_deref(),
+        Some(path.display().to_string().as_str())
+    );
+    assert_eq!(provenance.converter_hash, Some(library_hash));
+}
+
+/// A root signature with a descriptor table of `ranges`, whose arrays are freed on return.
+fn root_signature_with_ranges(
+    converter: &MetalIrConverter,
+    ranges: &[u32],
+) -> saxaboom::IRRootSignature {
+    let mut ranges = ranges
+        .iter()
+        .map(|&count| ffi::IRDescriptorRange1 {
+            RangeType: ffi::IRDescriptorRangeType::SRV,
+            NumDescriptors: count,
+            BaseShaderRegister: 0,
+            RegisterSpace: 0,
+            Flags: ffi::IRDescriptorRangeFlags::None,
+            OffsetInDescriptorsFromTableStart: 0,
+        })
+        .collect::<Vec<_>>();
+    let mut parameters = [ffi::IRRootParameter1 {
+        ParameterType: ffi::IRRootParameterType::DescriptorTable,
+        u_1: ffi::IRRootParameter1_u {
+            DescriptorTable: ffi::IRRootDescriptorTable1 {
+                NumDescriptorRanges: ranges.len() as u32,
+                pDescriptorRanges: ranges.as_mut_ptr(),
+            },
+        },
+        ShaderVisibility: ffi::IRShaderVisibility::All,
+    }];
+    converter
+        .create_root_signature_from_descriptor(&ffi::IRVersionedRootSignatureDescriptor {
+            version: ffi::IRRootSignatureVersion::_1_1,
+            u_1: ffi::IRVersionedRootSignatureDescriptor_u {
+                desc_1_1: ffi::IRRootSignatureDescriptor1 {
+                    Flags: ffi::IRRootSignatureFlags::None,
+                    NumParameters: parameters.len() as u32,
+                    pParameters: parameters.as_mut_ptr(),
+                    NumStaticSamplers: 0,
+                    pStaticSamplers: std::ptr::null_mut(),
+                },
+            },
+        })
+        .unwrap()
+}
+
+#[test]
+fn serializes_root_signatures_on_demand() {
+    let (fake, converter) = converter();
+    let root_signature = root_signature_with_ranges(&converter, &[4, 2]);
+    let mut compiler = converter.create_compiler();
+    compiler.set_global_root_signature(&root_signature);
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let object = compiler.alloc_compile_and_link(c"main", &dxil).unwrap();
+
+    // Serialized from a copy of the descriptor, once its JSON is first needed
+    let json = r#"{"version": 2, "ranges": [4, 2]}"#;
+    assert_eq!(
+        object.provenance().unwrap().root_signature_hash,
+        Some(sha256(json.as_bytes()))
+    );
+    assert_eq!(root_signature.json(), Some(json));
+    assert_eq!(
+        compiler.settings(),
+        [Call::SetGlobalRootSignature(Some(json.to_owned()))]
+    );
+
+    // Not at all when nothing needs it
+    let unused = root_signature_with_ranges(&converter, &[1]);
+    fake.without_function("IRVersionedRootSignatureDescriptorCopyJSONString");
+    assert_eq!(unused.json(), None);
+    assert_eq!(root_signature.json(), Some(json));
+}
+
+/// The runtime type of `provenance`.
+fn runtime(provenance: &Provenance) -> provenance::Provenance {
+    provenance::Provenance {
+        input_hash: provenance.input_hash,
+        entry_point: provenance.entry_point.clone(),
+        settings: provenance.settings.clone(),
+        root_signature_hash: provenance.root_signature_hash,
+        converter_path: provenance.converter_path.clone(),
+        converter_hash: provenance.converter_hash,
+        saxaboom_version: provenance.saxaboom_version.clone(),
+    }
+}
+
+#[test]
+fn sidecars_round_trip() {
+    let mut provenance = provenance();
+    provenance.converter_path = Some("C:\\Program Files\\Metal Shader Converter".to_owned());
+    provenance.converter_hash = Some([0xab; 32]);
+    let expected = runtime(&provenance);
+
+    let binary = provenance.to_sidecar();
+    assert!(binary.starts_with(&sidecar::SIDECAR_MAGIC));
+    let read = provenance::Provenance::from_sidecar(&binary).unwrap();
+    assert_eq!(read, expected);
+    assert_eq!(read.setting("set_minimum_gpu_family"), Some("Apple9"));
+    assert_eq!(read.setting("set_input_topology"), None);
+    assert_eq!(
+        provenance::Provenance::from_sidecar_json(&provenance.to_json()).unwrap(),
+        expected
+    );
+    assert_eq!(
+        provenance::Provenance::from_bytes(&provenance.to_bytes()).unwrap(),
+        expected
+    );
+
+    provenance.root_signature_hash = None;
+    provenance.converter_path = None;
+    provenance.converter_hash = None;
+    provenance.settings.clear();
+    let expected = runtime(&provenance);
+    assert_eq!(
+        provenance::Provenance::from_sidecar(&provenance.to_sidecar()).unwrap(),
+        expected
+    );
+    assert_eq!(
+        provenance::Provenance::from_sidecar_json(&provenance.to_json()).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn writes_next_to_metallib() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("provenance_sidecars");
+    fs::create_dir_all(&dir).unwrap();
+    let metallib = dir.join("blur.metallib");
+    provenance().write_sidecars(&metallib).unwrap();
+
+    let binary = fs::read(dir.join("blur.provenance.bin")).unwrap();
+    let json = fs::read_to_string(dir.join("blur.provenance.json")).unwrap();
+    assert_eq!(
+        metallib.with_extension(provenance::JSON_EXTENSION),
+        dir.join("blur.provenance.json")
+    );
+    assert_eq!(
+        provenance::Provenance::from_sidecar(&binary).unwrap(),
+        provenance::Provenance::from_sidecar_json(&json).unwrap()
+    );
+}
+
+#[test]
+fn rejects_invalid_sidecars() {
+    let provenance = provenance();
+    let newer = sidecar::SIDECAR_VERSION + 1;
+    let mut binary = provenance.to_sidecar();
+    binary[4..8].copy_from_slice(&newer.to_le_bytes());
+    assert_eq!(
+        provenance::Provenance::from_sidecar(&binary),
+        Err(FormatError::UnsupportedVersion {
+            format: "provenance sidecar",
+            version: newer,
+            supported: provenance::SIDECAR_VERSION,
+        })
+    );
+    let binary = provenance.to_sidecar();
+    for len in 0..binary.len() {
+        assert!(provenance::Provenance::from_sidecar(&binary[..len]).is_err());
+    }
+
+    let json = provenance.to_json();
+    let invalid_hash = json.replacen("\"input_hash\": \"", "\"input_hash\": \"+", 1);
+    assert!(matches!(
+        provenance::Provenance::from_sidecar_json(&invalid_hash),
+        Err(FormatError::Invalid { reason, .. }) if reason.starts_with("`input_hash` is not a SHA-256 hash")
+    ));
+    let newer_json = json.replacen("\"version\": 1", "\"version\": 2", 1);
+    assert!(matches!(
+        provenance::Provenance::from_sidecar_json(&newer_json),
+        Err(FormatError::UnsupportedVersion { version: 2, .. })
+    ));
+}
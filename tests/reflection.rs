@@ -0,0 +1,247 @@
+//! Writes reflection sidecars with [`saxaboom::reflection`] and reads them back with the reader
+//! of `saxaboom-runtime`.
+#![cfg(feature = "testing")]
+use std::{fs, path::PathBuf};
+
+use saxaboom::{
+    ffi,
+    reflection::{self as sidecar, ReflectionSnapshot},
+    testing::{FakeConverter, FakeResource, FakeShader, FakeVertexInfo},
+    MetalIrConverter,
+};
+use saxaboom_runtime::{reflection, FormatError};
+
+fn snapshot() -> ReflectionSnapshot {
+    let mut shader = FakeShader::new(ffi::IRShaderStage::Vertex, b"metallib".to_vec());
+    shader.entry_point = Some("vs_\"quoted\"\\main".to_owned());
+    shader.vertex_info = Some(FakeVertexInfo {
+        instance_id_index: 1,
+        vertex_id_index: -1,
+        vertex_output_size_in_bytes: 48,
+        needs_draw_params: false,
+        vertex_inputs: vec![("POSITION0".to_owned(), 0), ("NORMAL\n0 ü".to_owned(), 3)],
+    });
+    shader.function_constants = vec![
+        ("FOG".to_owned(), ffi::IRFunctionConstantType::Bool),
+        ("LIGHTS".to_owned(), ffi::IRFunctionConstantType::Int),
+    ];
+    shader.resources = vec![
+        FakeResource {
+            resource_type: ffi::IRResourceType::SRV,
+            space: 1,
+            slot: 2,
+            top_level_offset: 24,
+            size_bytes: u64::MAX,
+            name: Some("Albedo".to_owned()),
+        },
+        FakeResource {
+            resource_type: ffi::IRResourceType::Table,
+            space: 0,
+            slot: 0,
+            top_level_offset: 0,
+            size_bytes: 8,
+            name: None,
+        },
+    ];
+    shader.fragment_info = Some(ffi::IRFSInfo_1_0 {
+        num_render_targets: 2,
+        rt_index_int: 1,
+        discards: true,
+    });
+
+    let fake = FakeConverter::default();
+    fake.on_any(Ok(shader));
+    let converter = MetalIrConverter::from_backend(fake);
+    let object = converter.create_object_from_dxil(b"DXBC");
+    converter
+        .create_compiler()
+        .alloc_compile_and_link(c"main", &object)
+        .unwrap()
+        .reflection()
+        .unwrap()
+        .snapshot()
+}
+
+/// The runtime types of `snapshot()`.
+fn expected() -> reflection::ReflectionSnapshot {
+    reflection::ReflectionSnapshot {
+        entry_point: Some("vs_\"quoted\"\\main".to_owned()),
+        needs_function_constants: true,
+        function_constants: vec![
+            reflection::FunctionConstant {
+                name: "FOG".to_owned(),
+                r#type: reflection::FunctionConstantType::Bool,
+            },
+            reflection::FunctionConstant {
+                name: "LIGHTS".to_owned(),
+                r#type: reflection::FunctionConstantType::Int,
+            },
+        ],
+        resources: vec![
+            reflection::Resource {
+                name: Some("Albedo".to_owned()),
+                resource_type: reflection::ResourceType::Srv,
+                space: 1,
+                slot: 2,
+                top_level_offset: 24,
+                size_bytes: u64::MAX,
+            },
+            reflection::Resource {
+                name: None,
+                resource_type: reflection::ResourceType::Table,
+                space: 0,
+                slot: 0,
+                top_level_offset: 0,
+                size_bytes: 8,
+            },
+        ],
+        threadgroup_size: None,
+        vertex: Some(reflection::VertexInfo {
+            instance_id_index: 1,
+            vertex_id_index: -1,
+            vertex_output_size_in_bytes: 48,
+            needs_draw_params: false,
+            inputs: vec![
+                reflection::VertexInput {
+                    name: "POSITION0".to_owned(),
+                    attribute_index: 0,
+                },
+                reflection::VertexInput {
+                    name: "NORMAL\n0 ü".to_owned(),
+                    attribute_index: 3,
+                },
+            ],
+        }),
+        fragment: Some(reflection::FragmentInfo {
+            num_render_targets: 2,
+            rt_index_int: 1,
+            discards: true,
+        }),
+    }
+}
+
+#[test]
+fn sidecars_round_trip() {
+    let snapshot = snapshot();
+    let binary = snapshot.to_sidecar();
+    assert!(binary.starts_with(&sidecar::SIDECAR_MAGIC));
+    assert_eq!(
+        reflection::ReflectionSnapshot::from_sidecar(&binary).unwrap(),
+        expected()
+    );
+    let json = snapshot.to_json();
+    assert_eq!(
+        reflection::ReflectionSnapshot::from_sidecar_json(&json).unwrap(),
+        expected()
+    );
+
+    let mut compute = ReflectionSnapshot {
+        threadgroup_size: Some([32, 2, 1]),
+        ..Default::default()
+    };
+    let read = reflection::ReflectionSnapshot::from_sidecar_json(&compute.to_json()).unwrap();
+    assert_eq!(read.threadgroup_size, Some([32, 2, 1]));
+    assert_eq!(read.entry_point, None);
+    assert_eq!(read.vertex, None);
+    compute.threadgroup_size = None;
+    let read = reflection::ReflectionSnapshot::from_sidecar(&compute.to_sidecar()).unwrap();
+    assert_eq!(read, reflection::ReflectionSnapshot::default());
+}
+
+#[test]
+fn writes_next_to_metallib() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("reflection_sidecars");
+    fs::create_dir_all(&dir).unwrap();
+    let metallib = dir.join("blur.metallib");
+    snapshot().write_sidecars(&metallib).unwrap();
+
+    let binary = fs::read(dir.join("blur.reflection.bin")).unwrap();
+    let json = fs::read_to_string(dir.join("blur.reflection.json")).unwrap();
+    assert_eq!(
+        metallib.with_extension(reflection::BINARY_EXTENSION),
+        dir.join("blur.reflection.bin")
+    );
+    assert_eq!(
+        reflection::ReflectionSnapshot::from_sidecar(&binary).unwrap(),
+        reflection::ReflectionSnapshot::from_sidecar_json(&json).unwrap()
+    );
+
+    let missing = dir.join("missing").join("blur.metallib");
+    assert!(matches!(
+        snapshot().write_sidecars(&missing),
+        Err(saxaboom::Error::Write { .. })
+    ));
+}
+
+#[test]
+fn rejects_newer_versions() {
+    let snapshot = snapshot();
+    let newer = sidecar::SIDECAR_VERSION + 1;
+    let expected = FormatError::UnsupportedVersion {
+        format: "reflection sidecar",
+        version: newer,
+        supported: reflection::SIDECAR_VERSION,
+    };
+
+    let mut binary = snapshot.to_sidecar();
+    binary[4..8].copy_from_slice(&newer.to_le_bytes());
+    assert_eq!(
+        reflection::ReflectionSnapshot::from_sidecar(&binary),
+        Err(expected.clone())
+    );
+
+    let json = snapshot.to_json().replace(
+        &format!("\"version\": {}", sidecar::SIDECAR_VERSION),
+        &format!("\"version\": {newer}"),
+    );
+    let error = reflection::ReflectionSnapshot::from_sidecar_json(&json).unwrap_err();
+    assert_eq!(error, expected);
+    assert_eq!(
+        error.to_string(),
+        format!("Unsupported reflection sidecar version {newer}: this version of saxaboom-runtime only reads up to version 1, update it to read files written by newer tools")
+    );
+}
+
+#[test]
+fn rejects_malformed() {
+    let invalid = |result: Result<reflection::ReflectionSnapshot, FormatError>| match result {
+        Err(FormatError::Invalid { reason, .. }) => reason,
+        other => panic!("unexpected result {other:?}"),
+    };
+
+    let binary = snapshot().to_sidecar();
+    assert_eq!(
+        invalid(reflection::ReflectionSnapshot::from_sidecar(&binary[8..])),
+        "missing `SXRF` header"
+    );
+    for len in 0..binary.len() {
+        assert!(reflection::ReflectionSnapshot::from_sidecar(&binary[..len]).is_err());
+    }
+
+    let json = snapshot().to_json();
+    assert_eq!(
+        invalid(reflection::ReflectionSnapshot::from_sidecar_json(
+            &json.replace("\"attribute_index\": 3", "\"attribute_index\": 300")
+        )),
+        "`vertex.inputs[1].attribute_index` is missing or not an integer in range"
+    );
+    assert_eq!(
+        invalid(reflection::ReflectionSnapshot::from_sidecar_json(
+            &json.replace("\"SRV\"", "\"RTV\"")
+        )),
+        "unknown resource type `RTV`"
+    );
+    assert!(invalid(reflection::ReflectionSnapshot::from_sidecar_json(
+        &json[..json.len() / 2]
+    ))
+    .ends_with("of the JSON"));
+    assert!(reflection::ReflectionSnapshot::from_sidecar_json(
+        "[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]"
+    )
+    .is_err());
+    for i in 0..json.len() {
+        if json.is_char_boundary(i) {
+            let _ = reflection::ReflectionSnapshot::from_sidecar_json(&json[..i]);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Records [`saxaboom::IRCompiler`] calls and replays them with [`saxaboom::replay`].
+#![cfg(feature = "testing")]
+use saxaboom::{
+    ffi,
+    replay::{self, Call, Outcome, Output, Recording},
+    testing::{FakeConverter, FakeShader},
+    Error, IRInputLayoutDescriptor1, MetalIrConverter,
+};
+
+mod common;
+
+/// Compiles to a `metallib` naming the minimum GPU family, which fails for entry point `broken`.
+/// `release` stands in for the version of the converter library, and ends up in every output.
+fn converter(release: &'static str) -> (FakeConverter, MetalIrConverter) {
+    let (fake, converter) = common::converter(move |compilation| {
+        if compilation.entry_point == "broken" {
+            return Err(common::unsupported("wave intrinsics"));
+        }
+        let metallib = format!(
+            "{release} {:?} {}",
+            compilation.settings.minimum_gpu_family,
+            String::from_utf8_lossy(&compilation.dxil)
+        );
+        let mut shader = FakeShader::new(ffi::IRShaderStage::Vertex, metallib.into_bytes());
+        shader.reflection_json = r#"{"EntryPoint": "vs"}"#.to_owned();
+        Ok(shader)
+    });
+    fake.on_synthesize(format!("{release} stage in"));
+    (fake, converter)
+}
+
+fn layout() -> IRInputLayoutDescriptor1<'static> {
+    IRInputLayoutDescriptor1::new(
+        &[c"POSITION"],
+        &[ffi::IRInputElementDescriptor1 {
+            semanticIndex: 0,
+            format: ffi::IRFormat::R32G32B32Float,
+            inputSlot: 0,
+            alignedByteOffset: 0,
+            instanceDataStepRate: 0,
+            inputSlotClass: ffi::IRInputClassification::PerVertexData,
+        }],
+    )
+}
+
+fn record(converter: &MetalIrConverter) -> Recording {
+    let mut compiler = converter.create_compiler();
+    assert!(compiler.take_recording().is_none());
+
+    compiler.start_recording();
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+    compiler.set_minimum_deployment_target(ffi::IROperatingSystem::macOS, c"14.0");
+    let root_signature = converter
+        .create_root_signature_from_json(cr#"{"RootSignature": {}}"#)
+        .unwrap();
+    compiler.set_global_root_signature(&root_signature);
+
+    let dxil = converter.create_object_from_dxil(b"DXBC vs");
+    let object = compiler.alloc_compile_and_link(c"vs", &dxil).unwrap();
+    assert!(compiler.alloc_compile_and_link(c"broken", &dxil).is_err());
+    let binary = converter.create_metal_lib_binary();
+    assert!(compiler.synthesize_stage_in_function(
+        &object.reflection().unwrap(),
+        layout(),
+        &binary
+    ));
+    compiler.take_recording().unwrap()
+}
+
+#[test]
+fn records_calls() {
+    let (_, converter) = converter("2.0");
+    let recording = record(&converter);
+    let names = recording.calls.iter().map(Call::name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "set_minimum_gpu_family",
+            "set_minimum_deployment_target",
+            "set_global_root_signature",
+            "alloc_compile_and_link",
+            "alloc_compile_and_link",
+            "synthesize_stage_in_function",
+        ]
+    );
+    assert_eq!(
+        recording.calls[2],
+        Call::SetGlobalRootSignature(Some(r#"{"RootSignature": {}}"#.to_owned()))
+    );
+    let Call::CompileAndLink {
+        entry_point,
+        dxil,
+        output: Output::Compiled {
+            metallib,
+            reflection,
+        },
+    } = &recording.calls[3]
+    else {
+        panic!("unexpected call {:?}", recording.calls[3]);
+    };
+    assert_eq!(entry_point, "vs");
+    assert_eq!(&**dxil, b"DXBC vs");
+    assert_eq!(metallib.as_deref(), Some(&b"2.0 Some(Apple7) DXBC vs"[..]));
+    assert!(reflection
+        .as_ref()
+        .unwrap()
+        .contains("\"entry_point\": \"vs\""));
+    assert_eq!(
+        recording.calls[4].output(),
+        Some(&Output::Failed {
+            code: ffi::IRErrorCode::UnsupportedInstruction,
+            message: "wave intrinsics".to_owned()
+        })
+    );
+    assert_eq!(
+        recording.calls[5],
+        Call::SynthesizeStageInFunction {
+            vertex_shader_reflection: Some(r#"{"EntryPoint": "vs"}"#.to_owned()),
+            layout: Some(r#"{"semantic_names": ["POSITION"]}"#.to_owned()),
+            output: Output::Synthesized(Some(b"2.0 stage in".to_vec())),
+        }
+    );
+}
+
+#[test]
+fn replays_recordings() {
+    let (_, recorded_with) = converter("2.0");
+    let recording = Recording::from_bytes(&record(&recorded_with).to_bytes()).unwrap();
+
+    let (fake, same) = converter("2.0");
+    let report = replay::replay(&same, &recording);
+    assert!(report.reproduced(), "{report}");
+    assert_eq!(report.differences().count(), 0);
+    assert_eq!(fake.compiled_entry_points(), ["vs", "broken"]);
+    let settings = &fake.compilations()[0].settings;
+    assert_eq!(settings.minimum_gpu_family, Some(ffi::IRGPUFamily::Apple7));
+    assert!(settings.has_global_root_signature);
+    assert_eq!(
+        settings.minimum_deployment_target,
+        Some((ffi::IROperatingSystem::macOS, "14.0".to_owned()))
+    );
+
+    let (_, newer) = converter("2.1.1");
+    let report = replay::replay(&newer, &recording);
+    assert!(!report.reproduced());
+    let differences = report
+        .differences()
+        .map(|(call, _)| call.name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        differences,
+        ["alloc_compile_and_link", "synthesize_stage_in_function"]
+    );
+    assert_eq!(
+        report.to_string(),
+        "0: set_minimum_gpu_family: applied
+1: set_minimum_deployment_target: applied
+2: set_global_root_signature: applied
+3: alloc_compile_and_link `vs`: differs, metallib is 26 bytes instead of 24 bytes
+4: alloc_compile_and_link `broken`: matches
+5: synthesize_stage_in_function: differs, metallib is 14 bytes instead of 12 bytes
+"
+    );
+}
+
+#[test]
+fn records_earlier_settings() {
+    let (_, recorded_with) = converter("2.0");
+    let mut compiler = recorded_with.create_compiler();
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple6);
+    compiler.set_int_rt_mask(1).unwrap();
+    compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+
+    compiler.start_recording();
+    let dxil = recorded_with.create_object_from_dxil(b"DXBC vs");
+    compiler.alloc_compile_and_link(c"vs", &dxil).unwrap();
+    let recording = compiler.take_recording().unwrap();
+    assert_eq!(
+        recording.calls[..2],
+        [
+            Call::SetMinimumGpuFamily(ffi::IRGPUFamily::Apple7),
+            Call::SetIntRtMask(1),
+        ]
+    );
+    assert_eq!(recording.calls[2].name(), "alloc_compile_and_link");
+
+    let (fake, same) = converter("2.0");
+    let report = replay::replay(&same, &recording);
+    assert!(report.reproduced(), "{report}");
+    assert_eq!(
+        fake.compilations()[0].settings.minimum_gpu_family,
+        Some(ffi::IRGPUFamily::Apple7)
+    );
+}
+
+#[test]
+fn skips_incomplete_calls() {
+    let (fake, converter) = converter("2.0");
+    fake.without_function("IRVersionedRootSignatureDescriptorCopyJSONString")
+        .without_function("IRInputLayoutDescriptor1CopyJSONString");
+    let recording = record(&converter);
+    // Created from JSON, so it is still recorded
+    assert!(matches!(
+        &recording.calls[2],
+        Call::SetGlobalRootSignature(Some(_))
+    ));
+
+    let mut compiler = converter.create_compiler();
+    compiler.start_recording();
+    let root_signature = converter
+        .create_root_signature_from_descriptor(&ffi::IRVersionedRootSignatureDescriptor {
+            version: ffi::IRRootSignatureVersion::_1_1,
+            u_1: ffi::IRVersionedRootSignatureDescriptor_u {
+                desc_1_1: ffi::IRRootSignatureDescriptor1 {
+                    Flags: ffi::IRRootSignatureFlags::None,
+                    NumParameters: 0,
+                    pParameters: std::ptr::null_mut(),
+                    NumStaticSamplers: 0,
+                    pStaticSamplers: std::ptr::null_mut(),
+                },
+            },
+        })
+        .unwrap();
+    assert_eq!(root_signature.json(), None);
+    compiler.set_local_root_signature(&root_signature);
+    let mut calls = compiler.take_recording().unwrap().calls;
+    assert_eq!(calls, [Call::SetLocalRootSignature(None)]);
+    calls.push(recording.calls[5].clone());
+
+    let report = replay::replay(&converter, &Recording { calls });
+    assert!(!report.reproduced());
+    assert_eq!(
+        report.calls[0].1,
+        Outcome::Skipped("the root signature was not recorded as JSON".to_owned())
+    );
+    assert_eq!(
+        report.calls[1].1,
+        Outcome::Skipped(
+            "the vertex shader reflection or input layout was not recorded as JSON".to_owned()
+        )
+    );
+}
+
+#[test]
+fn rejects_invalid_recordings() {
+    let (_, converter) = converter("2.0");
+    let bytes = record(&converter).to_bytes();
+    assert!(bytes.starts_with(&replay::MAGIC));
+    let invalid = |bytes: &[u8]| match Recording::from_bytes(bytes) {
+        Err(Error::InvalidRecording { reason }) => reason,
+        other => panic!("unexpected result {other:?}"),
+    };
+
+    assert_eq!(invalid(&bytes[4..]), "missing `SXRP` header");
+    let mut newer = bytes.clone();
+    newer[4..8].copy_from_slice(&(replay::VERSION + 1).to_le_bytes());
+    assert_eq!(
+        invalid(&newer),
+        "version 2 is not supported, only version 1 is"
+    );
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(invalid(&trailing), "trailing data after the last call");
+    // The first call sets the GPU family
+    let mut family = bytes.clone();
+    family[13..17].copy_from_slice(&42u32.to_le_bytes());
+    assert_eq!(invalid(&family), "unknown IRGPUFamily 42");
+    for len in 0..bytes.len() {
+        assert!(Recording::from_bytes(&bytes[..len]).is_err());
+    }
+
+    let path = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("missing.sxrp");
+    assert!(matches!(Recording::read(&path), Err(Error::Read { .. })));
+}
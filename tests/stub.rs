@@ -0,0 +1,349 @@
+//! Exercises every wrapper in this crate against the `irconverter_stub` library, which is loaded
+//! through [`MetalIrConverter::new()`] exactly like Apple's `libmetalirconverter`.
+use std::{
+    ffi::{c_char, CStr, CString},
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+use saxaboom::{ffi, IRInputLayoutDescriptor1, MetalIrConverter};
+
+const STUB_METALLIB: &[u8] = b"MTLB saxaboom stub metallib";
+const STUB_SYNTHESIZED_METALLIB: &[u8] = b"MTLB saxaboom stub synthesized metallib";
+
+/// The stub keeps its state per process, tests that inspect or modify it take this lock.
+static STUB: Mutex<()> = Mutex::new(());
+
+struct Stub {
+    converter: MetalIrConverter,
+    lib: libloading::Library,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Stub {
+    fn load() -> Self {
+        let _guard = STUB.lock().unwrap_or_else(|e| e.into_inner());
+        // Cargo places the `cdylib` of the `irconverter_stub` dev-dependency next to the test
+        // executable.
+        let path: PathBuf = std::env::current_exe()
+            .unwrap()
+            .with_file_name(libloading::library_filename("metalirconverter"));
+        let converter = MetalIrConverter::new(&path).unwrap();
+        let lib = unsafe { libloading::Library::new(&path) }.unwrap();
+        unsafe {
+            lib.get::<unsafe extern "C" fn()>(b"saxaboom_stub_reset\0")
+                .unwrap()()
+        };
+        Self {
+            converter,
+            lib,
+            _guard,
+        }
+    }
+
+    fn live_objects(&self) -> usize {
+        unsafe {
+            self.lib
+                .get::<unsafe extern "C" fn() -> usize>(b"saxaboom_stub_live_objects\0")
+                .unwrap()()
+        }
+    }
+
+    fn fail_entry_point(&self, entry_point: &CStr, code: ffi::IRErrorCode, message: &CStr) {
+        unsafe {
+            self.lib
+                .get::<unsafe extern "C" fn(*const c_char, ffi::IRErrorCode, *const c_char)>(
+                    b"saxaboom_stub_fail_entry_point\0",
+                )
+                .unwrap()(entry_point.as_ptr(), code, message.as_ptr())
+        }
+    }
+
+    fn fail_root_signature(&self, code: ffi::IRErrorCode, message: &CStr) {
+        unsafe {
+            self.lib
+                .get::<unsafe extern "C" fn(ffi::IRErrorCode, *const c_char)>(
+                    b"saxaboom_stub_fail_root_signature\0",
+                )
+                .unwrap()(code, message.as_ptr())
+        }
+    }
+}
+
+fn empty_root_signature() -> ffi::IRVersionedRootSignatureDescriptor {
+    ffi::IRVersionedRootSignatureDescriptor {
+        version: ffi::IRRootSignatureVersion::_1_1,
+        u_1: ffi::IRVersionedRootSignatureDescriptor_u {
+            desc_1_1: ffi::IRRootSignatureDescriptor1 {
+                Flags: ffi::IRRootSignatureFlags::None,
+                NumParameters: 0,
+                pParameters: std::ptr::null_mut(),
+                NumStaticSamplers: 0,
+                pStaticSamplers: std::ptr::null_mut(),
+            },
+        },
+    }
+}
+
+#[test]
+fn missing_library() {
+    assert!(MetalIrConverter::new("/nonexistent/libmetalirconverter.so").is_err());
+}
+
+#[test]
+fn discover() {
+    let stub = Stub::load();
+    let path = stub.converter.library_path().unwrap();
+    let dir = path.parent().unwrap();
+    // Like the entries of `SAXABOOM_IRCONVERTER_PATH`, without changing the environment of this
+    // multi-threaded test binary
+    let converter = MetalIrConverter::discover_in([Path::new("/nonexistent"), dir]).unwrap();
+    assert_eq!(converter.library_path(), Some(path));
+    let converter = MetalIrConverter::discover_in([path]).unwrap();
+    assert_eq!(converter.library_path(), Some(path));
+    // Searched by the dynamic loader, in the directories that Cargo adds for tests, which may
+    // find a copy of the stub in another one
+    let converter = MetalIrConverter::new(path.file_name().unwrap()).unwrap();
+    let found = converter.library_path().unwrap();
+    assert!(found.is_absolute(), "{}", found.display());
+    assert_eq!(found.file_name(), path.file_name());
+    assert!(converter.library_hash().is_some());
+
+    let Err(saxaboom::Error::Discover(error)) = MetalIrConverter::discover_in(["/nonexistent"])
+    else {
+        panic!("discovered a library in `/nonexistent`");
+    };
+    assert_eq!(error.attempts.len(), 1);
+    assert_eq!(error.attempts[0].0, Path::new("/nonexistent"));
+}
+
+#[test]
+fn compile_and_reflect() {
+    let stub = Stub::load();
+    assert!(stub.converter.missing_optional_functions().is_empty());
+    assert!(stub.converter.supports_json_reflection());
+    let baseline = stub.live_objects();
+    {
+        let mut compiler = stub.converter.create_compiler();
+        let root_signature = stub
+            .converter
+            .create_root_signature_from_descriptor(&empty_root_signature())
+            .unwrap();
+        compiler.set_global_root_signature(&root_signature);
+        compiler.set_local_root_signature(&root_signature);
+        compiler.set_validation_flags(ffi::IRCompilerValidationFlags::All);
+        compiler.set_stage_in_generation_mode(
+            ffi::IRStageInCodeGenerationMode::UseSeparateStageInFunction,
+        );
+        compiler.set_hitgroup_type(ffi::IRHitGroupType::Triangles);
+        compiler.set_ray_tracing_pipeline_arguments(
+            32,
+            ffi::IRRaytracingPipelineFlags::None,
+            ffi::IRIntrinsicMaskClosestHitAll,
+            ffi::IRIntrinsicMaskMissShaderAll,
+            0,
+            ffi::IRIntrinsicMaskCallableShaderAll,
+            ffi::IRRayTracingUnlimitedRecursionDepth,
+            ffi::IRRayGenerationCompilationMode::Kernel,
+            ffi::IRIntersectionFunctionCompilationMode::VisibleFunction,
+        );
+        compiler.set_compatibility_flags(ffi::IRCompatibilityFlags::BoundsCheck);
+        compiler
+            .set_input_topology(ffi::IRInputTopology::Triangle)
+            .unwrap();
+        compiler
+            .enable_geometry_and_tessellation_emulation(false)
+            .unwrap();
+        compiler.set_dual_source_blending_configuration(
+            ffi::IRDualSourceBlendingConfiguration::DecideAtRuntime,
+        );
+        compiler
+            .set_depth_feedback_configuration(ffi::IRDepthFeedbackConfiguration::DecideAtRuntime)
+            .unwrap();
+        compiler.set_int_rt_mask(0).unwrap();
+        compiler.set_entry_point_name(c"renamed");
+        compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple7);
+        compiler.ignore_root_signature(false);
+        compiler.ignore_debug_information(true).unwrap();
+        compiler.set_minimum_deployment_target(ffi::IROperatingSystem::macOS, c"14.0.0");
+
+        let dxil = stub.converter.create_object_from_dxil(b"DXBC");
+        assert_eq!(dxil.r#type(), ffi::IRObjectType::DXILBytecode);
+        assert_eq!(dxil.gather_raytracing_intrinsics(c"main").unwrap(), 0);
+        assert!(matches!(
+            dxil.metal_lib_binary(),
+            Err(saxaboom::Error::MetalLib { .. })
+        ));
+        assert!(matches!(
+            dxil.reflection(),
+            Err(saxaboom::Error::Reflection { .. })
+        ));
+
+        let object = compiler.alloc_compile_and_link(c"main", &dxil).unwrap();
+        assert_eq!(object.r#type(), ffi::IRObjectType::MetalIRObject);
+        assert_eq!(object.metal_ir_shader_stage(), ffi::IRShaderStage::Compute);
+        assert_eq!(
+            object.metal_lib_binary().unwrap().byte_code(),
+            STUB_METALLIB
+        );
+
+        let reflection = object.reflection().unwrap();
+        let compute_info = reflection
+            .compute_info(ffi::IRReflectionVersion::_1_0)
+            .unwrap();
+        assert_eq!(unsafe { compute_info.u_1.info_1_0.tg_size }, [8, 8, 1]);
+        let version = ffi::IRReflectionVersion::_1_0;
+        assert!(reflection.vertex_info(version).is_none());
+        assert!(reflection.fragment_info(version).is_none());
+        assert!(reflection.geometry_info(version).is_none());
+        assert!(reflection.hull_info(version).is_none());
+        assert!(reflection.domain_info(version).is_none());
+        assert!(reflection.mesh_info(version).is_none());
+        assert!(reflection.amplification_info(version).is_none());
+        assert!(reflection.raytracing_info(version).is_none());
+        assert_eq!(reflection.entry_point_function_name(), Some(c"main"));
+        assert!(!reflection.needs_function_constants());
+        assert!(reflection.function_constants().is_empty());
+        let resources = reflection.resource_locations();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].resourceType, ffi::IRResourceType::CBV);
+        assert_eq!(resources[0].sizeBytes, 8);
+        assert_eq!(
+            unsafe { CStr::from_ptr(resources[0].resourceName) },
+            c"constants"
+        );
+
+        let json = reflection.json().unwrap();
+        let parsed = stub
+            .converter
+            .create_shader_reflection_from_json(&CString::new(json.clone()).unwrap())
+            .unwrap();
+        assert_eq!(parsed.json().unwrap(), json);
+        assert_eq!(parsed.resource_locations().len(), 1);
+        assert!(parsed.compute_info(version).is_some());
+
+        let combined = compiler
+            .alloc_combine_compile_and_link(c"intersection", &dxil, c"any_hit", &dxil)
+            .unwrap();
+        assert_eq!(
+            combined.metal_lib_binary().unwrap().byte_code(),
+            STUB_METALLIB
+        );
+
+        assert!(stub.live_objects() > baseline);
+    }
+    assert_eq!(stub.live_objects(), baseline);
+}
+
+#[test]
+fn synthesize() {
+    let stub = Stub::load();
+    let baseline = stub.live_objects();
+    {
+        let mut compiler = stub.converter.create_compiler();
+        let dxil = stub.converter.create_object_from_dxil(b"DXBC");
+        let object = compiler.alloc_compile_and_link(c"main", &dxil).unwrap();
+        let reflection = object.reflection().unwrap();
+        let binary = object.metal_lib_binary().unwrap();
+
+        let layout = IRInputLayoutDescriptor1::new(
+            &[c"POSITION"],
+            &[ffi::IRInputElementDescriptor1 {
+                semanticIndex: 0,
+                format: ffi::IRFormat::R32G32B32Float,
+                inputSlot: 0,
+                alignedByteOffset: 0,
+                instanceDataStepRate: 0,
+                inputSlotClass: ffi::IRInputClassification::PerVertexData,
+            }],
+        );
+        assert!(compiler.synthesize_stage_in_function(&reflection, layout, &binary));
+        assert_eq!(binary.byte_code(), STUB_SYNTHESIZED_METALLIB);
+
+        let ray_dispatch = compiler
+            .synthesize_indirect_ray_dispatch_function()
+            .unwrap()
+            .unwrap();
+        assert_eq!(ray_dispatch.byte_code(), STUB_SYNTHESIZED_METALLIB);
+        let intersection = compiler
+            .synthesize_indirect_intersection_function()
+            .unwrap()
+            .unwrap();
+        assert_eq!(intersection.byte_code(), STUB_SYNTHESIZED_METALLIB);
+    }
+    assert_eq!(stub.live_objects(), baseline);
+}
+
+#[test]
+fn compile_error() {
+    let stub = Stub::load();
+    stub.fail_entry_point(
+        c"broken",
+        ffi::IRErrorCode::UnsupportedInstruction,
+        c"stub failure",
+    );
+    let baseline = stub.live_objects();
+    {
+        let compiler = stub.converter.create_compiler();
+        let mut dxil = stub.converter.create_object_from_dxil(b"DXBC");
+        dxil.set_name("broken.dxil");
+        let error = compiler
+            .alloc_compile_and_link(c"broken", &dxil)
+            .err()
+            .unwrap();
+        let message = format!("{error:?}");
+        assert!(message.contains("UnsupportedInstruction"), "{message}");
+        assert!(message.contains("stub failure"), "{message}");
+
+        let ir_error = error.ir_error().unwrap();
+        assert_eq!(ir_error.code(), ffi::IRErrorCode::UnsupportedInstruction);
+        assert_eq!(ir_error.payload(), c"stub failure");
+        let context = error.context().unwrap();
+        assert_eq!(context.entry_point.as_deref(), Some("broken"));
+        assert_eq!(context.input.as_deref(), Some("broken.dxil"));
+        let message = error.to_string();
+        assert!(
+            message.contains("`broken`") && message.contains("`broken.dxil`"),
+            "{message}"
+        );
+
+        assert!(compiler
+            .alloc_combine_compile_and_link(c"broken", &dxil, c"any_hit", &dxil)
+            .is_err());
+    }
+    assert_eq!(stub.live_objects(), baseline);
+}
+
+#[test]
+fn root_signature() {
+    let stub = Stub::load();
+    let baseline = stub.live_objects();
+    {
+        let root_signature = stub
+            .converter
+            .create_root_signature_from_descriptor(&empty_root_signature())
+            .unwrap();
+        let locations = root_signature.resource_locations();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].resourceType, ffi::IRResourceType::CBV);
+        assert_eq!(locations[0].sizeBytes, 8);
+        let name = unsafe { CStr::from_ptr(locations[0].resourceName) };
+        assert_eq!(name, c"constants");
+
+        stub.fail_root_signature(
+            ffi::IRErrorCode::UnrecognizedRootSignatureDescriptor,
+            c"stub failure",
+        );
+        let error = stub
+            .converter
+            .create_root_signature_from_descriptor(&empty_root_signature())
+            .err()
+            .unwrap();
+        let message = format!("{error:?}");
+        assert!(
+            message.contains("UnrecognizedRootSignatureDescriptor"),
+            "{message}"
+        );
+    }
+    assert_eq!(stub.live_objects(), baseline);
+}
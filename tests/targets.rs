@@ -0,0 +1,214 @@
+//! Converts for several targets with [`IRCompiler::alloc_compile_and_link_for_targets()`] and
+//! searches the lowest GPU family with [`IRCompiler::find_lowest_gpu_family()`].
+#![cfg(feature = "testing")]
+use saxaboom::{
+    ffi,
+    testing::{FakeConverter, FakeError, FakeShader},
+    Error, MetalIrConverter, Target,
+};
+
+mod common;
+
+/// Produces one `metallib` for Apple GPU families and another for the Metal 3 family, and fails for tvOS.
+fn converter() -> (FakeConverter, MetalIrConverter) {
+    common::converter(|compilation| {
+        let settings = &compilation.settings;
+        if matches!(
+            settings.minimum_deployment_target,
+            Some((ffi::IROperatingSystem::tvOS, _))
+        ) {
+            return Err(common::unsupported("tvOS is not supported"));
+        }
+        let metallib = match settings.minimum_gpu_family {
+            Some(ffi::IRGPUFamily::Metal3) => b"metal3".to_vec(),
+            _ => b"apple".to_vec(),
+        };
+        Ok(FakeShader::new(ffi::IRShaderStage::Compute, metallib))
+    })
+}
+
+#[test]
+fn deduplicates() {
+    let (fake, converter) = converter();
+    let mut compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let targets = [
+        Target::new(
+            ffi::IROperatingSystem::macOS,
+            c"14.0.0",
+            ffi::IRGPUFamily::Metal3,
+        ),
+        Target::new(
+            ffi::IROperatingSystem::iOS,
+            c"17.0.0",
+            ffi::IRGPUFamily::Apple7,
+        ),
+        Target::new(
+            ffi::IROperatingSystem::iOSSimulator,
+            c"17.0.0",
+            ffi::IRGPUFamily::Apple7,
+        ),
+        Target::new(
+            ffi::IROperatingSystem::macOS,
+            c"14.0.0",
+            ffi::IRGPUFamily::Apple8,
+        ),
+    ];
+    let outputs = compiler
+        .alloc_compile_and_link_for_targets(c"main", &dxil, &targets)
+        .unwrap();
+
+    assert_eq!(outputs.outputs.len(), 2);
+    assert_eq!(outputs.outputs[0].metallib, b"metal3");
+    assert_eq!(outputs.outputs[0].targets, [0]);
+    assert_eq!(outputs.outputs[1].metallib, b"apple");
+    assert_eq!(outputs.outputs[1].targets, [1, 2, 3]);
+    assert_eq!(outputs.for_target(2).metallib, b"apple");
+    assert_eq!(
+        outputs.for_target(0).reflection.entry_point_function_name(),
+        Some(c"main")
+    );
+
+    let settings = fake
+        .compilations()
+        .into_iter()
+        .map(|c| c.settings)
+        .collect::<Vec<_>>();
+    assert_eq!(settings.len(), 4);
+    assert!(matches!(
+        &settings[2].minimum_deployment_target,
+        Some((ffi::IROperatingSystem::iOSSimulator, version)) if version == "17.0.0"
+    ));
+    assert_eq!(
+        settings[3].minimum_gpu_family,
+        Some(ffi::IRGPUFamily::Apple8)
+    );
+}
+
+#[test]
+fn reports_failing_target() {
+    let (_fake, converter) = converter();
+    let mut compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let targets = [
+        Target::new(
+            ffi::IROperatingSystem::iOS,
+            c"17.0.0",
+            ffi::IRGPUFamily::Apple7,
+        ),
+        Target::new(
+            ffi::IROperatingSystem::tvOS,
+            c"17.0.0",
+            ffi::IRGPUFamily::Apple7,
+        ),
+    ];
+    let Err(error) = compiler.alloc_compile_and_link_for_targets(c"main", &dxil, &targets) else {
+        panic!("tvOS should fail");
+    };
+
+    assert!(matches!(&error, Error::Target { target, .. } if *target == targets[1]));
+    assert_eq!(
+        error.ir_error().unwrap().code(),
+        ffi::IRErrorCode::UnsupportedInstruction
+    );
+    assert_eq!(
+        error.context().unwrap().entry_point.as_deref(),
+        Some("main")
+    );
+    assert!(
+        error
+            .to_string()
+            .ends_with("(targeting tvOS 17.0.0 on Apple7)"),
+        "{error}"
+    );
+}
+
+/// Requires wave size 64 support from Apple8, and an instruction from Apple9.
+fn family_converter(entry_point: &'static str) -> MetalIrConverter {
+    let fake = FakeConverter::default();
+    fake.on_compilation(move |compilation| {
+        if compilation.entry_point != entry_point {
+            return None;
+        }
+        Some(match compilation.settings.minimum_gpu_family {
+            Some(ffi::IRGPUFamily::Apple6 | ffi::IRGPUFamily::Apple7) => Err(FakeError::new(
+                ffi::IRErrorCode::UnsupportedWaveSize,
+                "wave size 64",
+            )),
+            Some(ffi::IRGPUFamily::Apple8) => Err(common::unsupported("barycentrics")),
+            _ => Ok(FakeShader::new(
+                ffi::IRShaderStage::Fragment,
+                b"apple9".to_vec(),
+            )),
+        })
+    });
+    fake.on_entry_point(
+        "broken",
+        Err(FakeError::new(ffi::IRErrorCode::CompilationError, "syntax")),
+    );
+    MetalIrConverter::from_backend(fake)
+}
+
+#[test]
+fn lowest_gpu_family() {
+    let converter = family_converter("main");
+    let mut compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let lowest = compiler.find_lowest_gpu_family(c"main", &dxil).unwrap();
+
+    assert_eq!(lowest.family(), Some(ffi::IRGPUFamily::Apple9));
+    let (_, object) = lowest.found.as_ref().unwrap();
+    assert_eq!(object.metal_lib_binary().unwrap().byte_code(), b"apple9");
+    let unsupported = lowest
+        .unsupported
+        .iter()
+        .map(|(family, e)| (*family, e.ir_error().unwrap().code()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        unsupported,
+        [
+            (
+                ffi::IRGPUFamily::Apple6,
+                ffi::IRErrorCode::UnsupportedWaveSize
+            ),
+            (
+                ffi::IRGPUFamily::Apple7,
+                ffi::IRErrorCode::UnsupportedWaveSize
+            ),
+            (
+                ffi::IRGPUFamily::Apple8,
+                ffi::IRErrorCode::UnsupportedInstruction
+            ),
+        ]
+    );
+}
+
+#[test]
+fn lowest_gpu_family_unsupported_everywhere() {
+    let fake = FakeConverter::default();
+    fake.on_any(Err(common::unsupported("ray queries")));
+    let converter = MetalIrConverter::from_backend(fake);
+    let mut compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let lowest = compiler.find_lowest_gpu_family(c"main", &dxil).unwrap();
+    assert!(lowest.found.is_none());
+    assert_eq!(
+        lowest.unsupported.len(),
+        saxaboom::targets::GPU_FAMILIES.len()
+    );
+}
+
+#[test]
+fn lowest_gpu_family_other_error() {
+    let converter = family_converter("main");
+    let mut compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    // Fails for every family, so the search stops right away
+    let Err(error) = compiler.find_lowest_gpu_family(c"broken", &dxil) else {
+        panic!("`broken` should fail");
+    };
+    assert_eq!(
+        error.ir_error().unwrap().code(),
+        ffi::IRErrorCode::CompilationError
+    );
+}
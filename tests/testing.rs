@@ -0,0 +1,75 @@
+//! Behavior of the wrappers when the backend lacks optional functions, simulated with
+//! [`FakeConverter::without_function()`].
+#![cfg(feature = "testing")]
+
+use saxaboom::{
+    ffi,
+    testing::{FakeConverter, FakeShader},
+    Error, FunctionConstant, MetalIrConverter, UnsupportedError,
+};
+
+#[test]
+fn unsupported_functions() {
+    let fake = FakeConverter::default();
+    fake.on_any(Ok(FakeShader::new(ffi::IRShaderStage::Compute, vec![])))
+        .without_function("IRCompilerSetIntRTMask")
+        .without_function("IRShaderReflectionCopyJSONString");
+    let converter = MetalIrConverter::from_backend(fake);
+
+    assert!(!converter.is_loaded("IRCompilerSetIntRTMask"));
+    assert!(!converter.supports_json_reflection());
+    assert_eq!(
+        converter.missing_optional_functions(),
+        ["IRCompilerSetIntRTMask", "IRShaderReflectionCopyJSONString"]
+    );
+
+    let mut compiler = converter.create_compiler();
+    assert!(matches!(
+        compiler.set_int_rt_mask(0),
+        Err(Error::Unsupported(UnsupportedError {
+            function: "IRCompilerSetIntRTMask"
+        }))
+    ));
+    assert!(compiler.ignore_debug_information(true).is_ok());
+
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let object = compiler.alloc_compile_and_link(c"main", &dxil).unwrap();
+    let error = object.reflection().unwrap().json().unwrap_err();
+    assert!(
+        matches!(error, Error::Unsupported(e) if e.function == "IRShaderReflectionCopyJSONString")
+    );
+}
+
+#[test]
+fn function_constants() {
+    let fake = FakeConverter::default();
+    let mut shader = FakeShader::new(ffi::IRShaderStage::Fragment, vec![]);
+    shader.function_constants = vec![
+        ("useFog".to_owned(), ffi::IRFunctionConstantType::Bool),
+        ("lightCount".to_owned(), ffi::IRFunctionConstantType::Int),
+    ];
+    fake.on_entry_point("ps_main", Ok(shader));
+    let converter = MetalIrConverter::from_backend(fake.clone());
+
+    let compiler = converter.create_compiler();
+    let dxil = converter.create_object_from_dxil(b"DXBC");
+    let object = compiler.alloc_compile_and_link(c"ps_main", &dxil).unwrap();
+    let reflection = object.reflection().unwrap();
+    assert_eq!(reflection.entry_point_function_name(), Some(c"ps_main"));
+    assert!(reflection.needs_function_constants());
+    assert_eq!(
+        reflection.function_constants(),
+        [
+            FunctionConstant {
+                name: "useFog".to_owned(),
+                r#type: ffi::IRFunctionConstantType::Bool,
+            },
+            FunctionConstant {
+                name: "lightCount".to_owned(),
+                r#type: ffi::IRFunctionConstantType::Int,
+            },
+        ]
+    );
+    drop((reflection, object, dxil, compiler));
+    assert_eq!(fake.live_handles(), 0);
+}
@@ -0,0 +1,168 @@
+//! Hot-reloads DXIL files with [`saxaboom::watch::Watcher`].
+#![cfg(feature = "testing")]
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::TryRecvError,
+    time::{Duration, Instant},
+};
+
+use saxaboom::{
+    ffi,
+    testing::{FakeConverter, FakeShader},
+    watch::{WatchEvent, Watched, Watcher, WatcherConfig},
+    MetalIrConverter,
+};
+
+mod common;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Compiles every DXIL file to a `metallib` holding the same bytes, and fails for files that start
+/// with `broken`.
+fn converter() -> (FakeConverter, MetalIrConverter) {
+    common::converter(|compilation| {
+        if compilation.dxil.starts_with(b"broken") {
+            Err(common::unsupported("broken shader"))
+        } else {
+            Ok(FakeShader::new(
+                ffi::IRShaderStage::Compute,
+                compilation.dxil.clone(),
+            ))
+        }
+    })
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn config() -> WatcherConfig {
+    WatcherConfig {
+        poll_interval: Duration::from_millis(10),
+        debounce: Duration::from_millis(100),
+    }
+}
+
+fn reloaded(watcher: &Watcher) -> (PathBuf, Vec<u8>) {
+    match watcher.events().recv_timeout(TIMEOUT).unwrap() {
+        WatchEvent::Reloaded { path, shader } => (path, shader.metallib.clone()),
+        WatchEvent::Failed { path, error, .. } => {
+            panic!("{} failed to compile: {error}", path.display())
+        }
+    }
+}
+
+#[test]
+fn keeps_previous_on_failure() {
+    let (_fake, converter) = converter();
+    let dir = temp_dir("watch_keeps_previous_on_failure");
+    let path = dir.join("shader.dxil");
+    fs::write(&path, b"first").unwrap();
+
+    let watcher = Watcher::spawn(converter, vec![Watched::new(&path, c"main")], config());
+    assert_eq!(reloaded(&watcher), (path.clone(), b"first".to_vec()));
+
+    fs::write(&path, b"broken shader").unwrap();
+    match watcher.events().recv_timeout(TIMEOUT).unwrap() {
+        WatchEvent::Failed {
+            path: failed,
+            error,
+            previous,
+        } => {
+            assert_eq!(failed, path);
+            assert_eq!(
+                error.ir_error().unwrap().code(),
+                ffi::IRErrorCode::UnsupportedInstruction
+            );
+            assert_eq!(previous.unwrap().metallib, b"first");
+        }
+        WatchEvent::Reloaded { .. } => panic!("a broken shader compiled"),
+    }
+    assert_eq!(watcher.current(&path).unwrap().metallib, b"first");
+
+    fs::write(&path, b"second one").unwrap();
+    assert_eq!(reloaded(&watcher), (path.clone(), b"second one".to_vec()));
+    assert_eq!(watcher.current(&path).unwrap().metallib, b"second one");
+}
+
+#[test]
+fn watches_directory() {
+    let (fake, converter) = converter();
+    let dir = temp_dir("watch_watches_directory");
+    fs::write(dir.join("a.dxil"), b"a").unwrap();
+    fs::write(dir.join("notes.txt"), b"not a shader").unwrap();
+
+    let watcher = Watcher::spawn(
+        converter,
+        vec![Watched::new(&dir, c"main")
+            .configure(|compiler| compiler.set_minimum_gpu_family(ffi::IRGPUFamily::Apple8))],
+        config(),
+    );
+    assert_eq!(reloaded(&watcher), (dir.join("a.dxil"), b"a".to_vec()));
+
+    fs::write(dir.join("b.dxil"), b"b").unwrap();
+    assert_eq!(reloaded(&watcher), (dir.join("b.dxil"), b"b".to_vec()));
+
+    let compilations = fake.compilations();
+    assert_eq!(compilations.len(), 2);
+    assert!(compilations
+        .iter()
+        .all(|c| c.settings.minimum_gpu_family == Some(ffi::IRGPUFamily::Apple8)));
+}
+
+#[test]
+fn debounces_rapid_changes() {
+    let (fake, converter) = converter();
+    let dir = temp_dir("watch_debounces_rapid_changes");
+    let path = dir.join("shader.dxil");
+    fs::write(&path, b"v").unwrap();
+
+    let debounce = Duration::from_millis(500);
+    let watcher = Watcher::manual(
+        converter,
+        vec![Watched::new(&path, c"main")],
+        WatcherConfig {
+            poll_interval: Duration::ZERO,
+            debounce,
+        },
+    );
+    let mut now = Instant::now();
+    watcher.poll(now);
+    assert_eq!(
+        watcher.events().try_recv().map(|_| ()),
+        Ok(()),
+        "existing files are compiled right away"
+    );
+
+    // Every write changes the size, so that each one is noticed regardless of the timestamp
+    // resolution of the file system
+    let mut contents = b"v".to_vec();
+    for _ in 0..5 {
+        contents.push(b'!');
+        fs::write(&path, &contents).unwrap();
+        now += Duration::from_millis(30);
+        watcher.poll(now);
+        assert_eq!(
+            watcher.events().try_recv().unwrap_err(),
+            TryRecvError::Empty
+        );
+    }
+    watcher.poll(now + debounce - Duration::from_millis(1));
+    assert_eq!(
+        watcher.events().try_recv().unwrap_err(),
+        TryRecvError::Empty
+    );
+
+    watcher.poll(now + debounce);
+    assert_eq!(reloaded(&watcher), (path.clone(), contents));
+    watcher.poll(now + 3 * debounce);
+    assert_eq!(
+        watcher.events().try_recv().unwrap_err(),
+        TryRecvError::Empty
+    );
+    assert_eq!(fake.compilations().len(), 2);
+}